@@ -1,31 +1,48 @@
-use actix_web::{
-    post,
-    web::{scope, Data, Json},
-    Error, Scope,
-};
+use actix_web::{post, web::Data, Error};
 use intmax2_interfaces::api::balance_prover::types::{
     ProveReceiveDepositRequest, ProveReceiveTransferRequest, ProveResponse, ProveSendRequest,
     ProveSingleWithdrawalRequest, ProveSpentRequest, ProveUpdateRequest,
 };
 
+use intmax2_interfaces::api::error::ErrorResponse;
+use server_common::{api_scope, encoding::Encoded, error::ApiError};
+
 use crate::api::balance_prover::BalanceProver;
 
+#[utoipa::path(
+    post,
+    path = "/prove-spent",
+    request_body = ProveSpentRequest,
+    responses(
+        (status = 200, body = ProveResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/prove-spent")]
 pub async fn prove_spent(
     state: Data<BalanceProver>,
-    request: Json<ProveSpentRequest>,
-) -> Result<Json<ProveResponse>, Error> {
+    request: Encoded<ProveSpentRequest>,
+) -> Result<Encoded<ProveResponse>, Error> {
     let proof = state
         .prove_spent(&request.spent_witness)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(ProveResponse { proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(ProveResponse { proof }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/prove-send",
+    request_body = ProveSendRequest,
+    responses(
+        (status = 200, body = ProveResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/prove-send")]
 pub async fn prove_send(
     state: Data<BalanceProver>,
-    request: Json<ProveSendRequest>,
-) -> Result<Json<ProveResponse>, Error> {
+    request: Encoded<ProveSendRequest>,
+) -> Result<Encoded<ProveResponse>, Error> {
     let proof = state
         .prove_send(
             request.pubkey,
@@ -34,26 +51,44 @@ pub async fn prove_send(
             &request.spent_proof,
             &request.prev_proof,
         )
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(ProveResponse { proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(ProveResponse { proof }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/prove-update",
+    request_body = ProveUpdateRequest,
+    responses(
+        (status = 200, body = ProveResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/prove-update")]
 pub async fn prove_update(
     state: Data<BalanceProver>,
-    request: Json<ProveUpdateRequest>,
-) -> Result<Json<ProveResponse>, Error> {
+    request: Encoded<ProveUpdateRequest>,
+) -> Result<Encoded<ProveResponse>, Error> {
     let proof = state
         .prove_update(request.pubkey, &request.update_witness, &request.prev_proof)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(ProveResponse { proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(ProveResponse { proof }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/prove-receive-transfer",
+    request_body = ProveReceiveTransferRequest,
+    responses(
+        (status = 200, body = ProveResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/prove-receive-transfer")]
 pub async fn prove_receive_transfer(
     state: Data<BalanceProver>,
-    request: Json<ProveReceiveTransferRequest>,
-) -> Result<Json<ProveResponse>, Error> {
+    request: Encoded<ProveReceiveTransferRequest>,
+) -> Result<Encoded<ProveResponse>, Error> {
     let request = request.into_inner();
     let proof = state
         .prove_receive_transfer(
@@ -61,15 +96,24 @@ pub async fn prove_receive_transfer(
             &request.receive_transfer_witness,
             &request.prev_proof,
         )
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(ProveResponse { proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(ProveResponse { proof }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/prove-receive-deposit",
+    request_body = ProveReceiveDepositRequest,
+    responses(
+        (status = 200, body = ProveResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/prove-receive-deposit")]
 pub async fn prove_receive_deposit(
     state: Data<BalanceProver>,
-    request: Json<ProveReceiveDepositRequest>,
-) -> Result<Json<ProveResponse>, Error> {
+    request: Encoded<ProveReceiveDepositRequest>,
+) -> Result<Encoded<ProveResponse>, Error> {
     let request = request.into_inner();
     let proof = state
         .prove_receive_deposit(
@@ -77,28 +121,67 @@ pub async fn prove_receive_deposit(
             &request.receive_deposit_witness,
             &request.prev_proof,
         )
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(ProveResponse { proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(ProveResponse { proof }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/prove-single-withdrawal",
+    request_body = ProveSingleWithdrawalRequest,
+    responses(
+        (status = 200, body = ProveResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/prove-single-withdrawal")]
 pub async fn prove_single_withdrawal(
     state: Data<BalanceProver>,
-    request: Json<ProveSingleWithdrawalRequest>,
-) -> Result<Json<ProveResponse>, Error> {
+    request: Encoded<ProveSingleWithdrawalRequest>,
+) -> Result<Encoded<ProveResponse>, Error> {
     let request = request.into_inner();
     let proof = state
         .prove_single_withdrawal(&request.withdrawal_witness)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(ProveResponse { proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(ProveResponse { proof }))
 }
 
-pub fn balance_prover_scope() -> Scope {
-    scope("/balance-prover")
-        .service(prove_spent)
-        .service(prove_send)
-        .service(prove_update)
-        .service(prove_receive_transfer)
-        .service(prove_receive_deposit)
-        .service(prove_single_withdrawal)
+api_scope!(
+    balance_prover_scope,
+    balance_prover_openapi,
+    "/balance-prover",
+    [
+        prove_spent,
+        prove_send,
+        prove_update,
+        prove_receive_transfer,
+        prove_receive_deposit,
+        prove_single_withdrawal,
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use server_common::openapi::assert_spec_matches_routes;
+
+    use super::{balance_prover_openapi, balance_prover_scope};
+
+    #[actix_web::test]
+    async fn test_openapi_spec_matches_routes() {
+        let app = test::init_service(App::new().service(balance_prover_scope())).await;
+        assert_spec_matches_routes(
+            &app,
+            &balance_prover_openapi(),
+            &[
+                ("POST", "/balance-prover/prove-receive-deposit"),
+                ("POST", "/balance-prover/prove-receive-transfer"),
+                ("POST", "/balance-prover/prove-send"),
+                ("POST", "/balance-prover/prove-single-withdrawal"),
+                ("POST", "/balance-prover/prove-spent"),
+                ("POST", "/balance-prover/prove-update"),
+            ],
+        )
+        .await;
+    }
 }
@@ -1,3 +1,6 @@
+use intmax2_interfaces::api::error::ErrorCode;
+use server_common::error::ApiError;
+
 #[derive(Debug, thiserror::Error)]
 pub enum BalanceProverError {
     #[error("ProveSpentError: {0}")]
@@ -13,3 +16,10 @@ pub enum BalanceProverError {
     #[error("ProveSingleWithdrawalError: {0}")]
     ProveSingleWithdrawalError(String),
 }
+
+impl From<BalanceProverError> for ApiError {
+    fn from(e: BalanceProverError) -> Self {
+        // the witnesses are given by the client, so a failure is caused by an invalid request
+        ApiError::new(ErrorCode::ProofGenerationFailed, e)
+    }
+}
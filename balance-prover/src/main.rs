@@ -1,12 +1,21 @@
 use actix_cors::Cors;
-use actix_web::{middleware::Logger, web::Data, App, HttpServer};
+use actix_web::{
+    middleware::Logger,
+    web::{self, Data},
+    App, HttpServer,
+};
 use balance_prover::{
-    api::{api::balance_prover_scope, balance_prover::BalanceProver},
+    api::{
+        api::{balance_prover_openapi, balance_prover_scope},
+        balance_prover::BalanceProver,
+    },
     Env,
 };
 use server_common::{
+    error::not_found,
     health_check::{health_check, set_name_and_version},
     logger::init_logger,
+    openapi::openapi_service,
 };
 use std::io::{self};
 
@@ -33,6 +42,8 @@ async fn main() -> std::io::Result<()> {
             .app_data(state.clone())
             .service(health_check)
             .service(balance_prover_scope())
+            .service(openapi_service(balance_prover_openapi()))
+            .default_service(web::to(not_found))
     })
     .bind(format!("0.0.0.0:{}", env.port))?
     .run()
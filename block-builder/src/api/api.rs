@@ -10,8 +10,20 @@ use intmax2_interfaces::api::block_builder::types::{
 use intmax2_zkp::common::block_builder::UserSignature;
 use serde_qs::actix::QsQuery;
 
+use intmax2_interfaces::api::error::ErrorResponse;
+use server_common::{api_scope, error::ApiError};
+
 use crate::api::state::State;
 
+#[utoipa::path(
+    get,
+    path = "/status",
+    params(GetBlockBuilderStatusQuery),
+    responses(
+        (status = 200, body = GetBlockBuilderStatusResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/status")]
 pub async fn get_status(
     state: Data<State>,
@@ -25,6 +37,15 @@ pub async fn get_status(
     Ok(Json(GetBlockBuilderStatusResponse { status }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/tx-request",
+    request_body = TxRequestRequest,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/tx-request")]
 pub async fn tx_request(
     state: Data<State>,
@@ -37,10 +58,19 @@ pub async fn tx_request(
         .await
         .send_tx_request(request.is_registration_block, request.pubkey, request.tx)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/query-proposal",
+    request_body = QueryProposalRequest,
+    responses(
+        (status = 200, body = QueryProposalResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/query-proposal")]
 pub async fn query_proposal(
     state: Data<State>,
@@ -52,10 +82,19 @@ pub async fn query_proposal(
         .read()
         .await
         .query_proposal(request.is_registration_block, request.pubkey, request.tx)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(QueryProposalResponse { block_proposal }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/post-signature",
+    request_body = PostSignatureRequest,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/post-signature")]
 pub async fn post_signature(
     state: Data<State>,
@@ -71,14 +110,37 @@ pub async fn post_signature(
         .write()
         .await
         .post_signature(request.is_registration_block, request.tx, user_signature)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(()))
 }
 
-pub fn block_builder_scope() -> actix_web::Scope {
-    actix_web::web::scope("/block-builder")
-        .service(get_status)
-        .service(tx_request)
-        .service(query_proposal)
-        .service(post_signature)
+api_scope!(
+    block_builder_scope,
+    block_builder_openapi,
+    "/block-builder",
+    [get_status, tx_request, query_proposal, post_signature,]
+);
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use server_common::openapi::assert_spec_matches_routes;
+
+    use super::{block_builder_openapi, block_builder_scope};
+
+    #[actix_web::test]
+    async fn test_openapi_spec_matches_routes() {
+        let app = test::init_service(App::new().service(block_builder_scope())).await;
+        assert_spec_matches_routes(
+            &app,
+            &block_builder_openapi(),
+            &[
+                ("GET", "/block-builder/status"),
+                ("POST", "/block-builder/post-signature"),
+                ("POST", "/block-builder/query-proposal"),
+                ("POST", "/block-builder/tx-request"),
+            ],
+        )
+        .await;
+    }
 }
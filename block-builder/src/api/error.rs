@@ -1,6 +1,7 @@
 use intmax2_client_sdk::external_api::contract::error::BlockchainError;
-use intmax2_interfaces::api::error::ServerError;
+use intmax2_interfaces::api::error::{ErrorCode, ServerError};
 use intmax2_zkp::ethereum_types::u256::U256;
+use server_common::error::ApiError;
 
 #[derive(Debug, thiserror::Error)]
 pub enum BlockBuilderError {
@@ -46,3 +47,34 @@ pub enum BlockBuilderError {
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
+
+impl From<BlockBuilderError> for ApiError {
+    fn from(e: BlockBuilderError) -> Self {
+        let code = match &e {
+            BlockBuilderError::BlockchainError(_) | BlockBuilderError::ServerError(_) => {
+                ErrorCode::Unavailable
+            }
+            BlockBuilderError::NotAcceptingTx => ErrorCode::NotAcceptingTx,
+            BlockBuilderError::BlockIsFull => ErrorCode::BlockIsFull,
+            BlockBuilderError::OnlyOneSenderAllowed => ErrorCode::OnlyOneSenderAllowed,
+            BlockBuilderError::ValidityProverIsNotSynced(onchain, validity_prover) => {
+                return ApiError::new(ErrorCode::ValidityProverNotSynced, &e).with_details(
+                    serde_json::json!({
+                        "onchainBlockNumber": onchain,
+                        "validityProverBlockNumber": validity_prover,
+                    }),
+                )
+            }
+            BlockBuilderError::AccountAlreadyRegistered(..) => ErrorCode::AccountAlreadyRegistered,
+            BlockBuilderError::AccountNotFound(_) => ErrorCode::AccountNotFound,
+            BlockBuilderError::BlockBuilderIsPausing => ErrorCode::BlockBuilderIsPausing,
+            BlockBuilderError::NotProposing => ErrorCode::NotProposing,
+            BlockBuilderError::TxRequestNotFound => ErrorCode::TxRequestNotFound,
+            BlockBuilderError::InvalidSignature(_) => ErrorCode::InvalidSignature,
+            BlockBuilderError::ShouldBePausing | BlockBuilderError::UnexpectedError(_) => {
+                ErrorCode::Internal
+            }
+        };
+        ApiError::new(code, e)
+    }
+}
@@ -1,15 +1,25 @@
 use std::io;
 
 use actix_cors::Cors;
-use actix_web::{middleware::Logger, web::Data, App, HttpServer};
+use actix_web::{
+    middleware::Logger,
+    web::{self, Data},
+    App, HttpServer,
+};
 use block_builder::{
-    api::{api::block_builder_scope, block_builder::BlockBuilder, state::State},
+    api::{
+        api::{block_builder_openapi, block_builder_scope},
+        block_builder::BlockBuilder,
+        state::State,
+    },
     Env,
 };
 use intmax2_client_sdk::external_api::contract::utils::get_address;
 use server_common::{
+    error::{json_config, not_found, qs_query_config},
     health_check::{health_check, set_name_and_version},
     logger::init_logger,
+    openapi::openapi_service,
 };
 
 #[actix_web::main]
@@ -53,8 +63,12 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .wrap(Logger::new("Request: %r | Status: %s | Duration: %Ts"))
             .app_data(state.clone())
+            .app_data(json_config())
+            .app_data(qs_query_config())
             .service(health_check)
             .service(block_builder_scope())
+            .service(openapi_service(block_builder_openapi()))
+            .default_service(web::to(not_found))
     })
     .bind(format!("0.0.0.0:{}", env.port))?
     .run()
@@ -21,6 +21,8 @@ pub enum Commands {
         amount: u128,
         #[clap(long)]
         token_index: u32,
+        #[clap(long)]
+        memo: Option<String>,
     },
     BatchTransfer {
         #[clap(long)]
@@ -42,6 +44,20 @@ pub enum Commands {
         #[clap(long)]
         token_id: Option<u128>,
     },
+    RecheckDeposit {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        deposit_uuid: String,
+    },
+    CancelDeposit {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        eth_private_key: H256,
+        #[clap(long)]
+        deposit_uuid: String,
+    },
     PostEmptyBlock,
     SyncWithdrawals {
         #[clap(long)]
@@ -65,9 +81,81 @@ pub enum Commands {
         #[clap(long)]
         eth_private_key: H256,
     },
+    Recover {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        dry_run: bool,
+    },
+    ExportBackup {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        path: String,
+    },
+    ImportBackup {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        path: String,
+    },
+    DeleteMyData {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        confirm: Option<String>,
+    },
+    Receipt {
+        #[clap(subcommand)]
+        command: ReceiptCommands,
+    },
+    Invoice {
+        #[clap(subcommand)]
+        command: InvoiceCommands,
+    },
     GenerateKey,
     GenerateFromEthKey {
         #[clap(long)]
         eth_private_key: H256,
     },
 }
+
+#[derive(Subcommand)]
+pub enum ReceiptCommands {
+    Create {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        tx_uuid: String,
+        #[clap(long)]
+        transfer_index: u32,
+        #[clap(long)]
+        path: String,
+    },
+    Verify {
+        #[clap(long)]
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum InvoiceCommands {
+    Create {
+        #[clap(long)]
+        recipient: String,
+        #[clap(long)]
+        token_index: u32,
+        #[clap(long)]
+        amount: u128,
+        #[clap(long)]
+        memo: Option<String>,
+        #[clap(long)]
+        expires_in: Option<u64>,
+    },
+    Pay {
+        #[clap(long)]
+        private_key: H256,
+        #[clap(long)]
+        uri: String,
+    },
+}
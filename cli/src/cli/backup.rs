@@ -0,0 +1,20 @@
+use colored::Colorize as _;
+use intmax2_zkp::common::signature::key_set::KeySet;
+
+use super::{client::get_client, error::CliError};
+
+pub async fn export_backup(key: KeySet, path: &str) -> Result<(), CliError> {
+    let client = get_client()?;
+    let backup = client.export_backup(key).await?;
+    std::fs::write(path, &backup)?;
+    println!("{}", format!("Backup is written to {}", path).green());
+    Ok(())
+}
+
+pub async fn import_backup(key: KeySet, path: &str) -> Result<(), CliError> {
+    let client = get_client()?;
+    let backup = std::fs::read(path)?;
+    client.import_backup(key, &backup).await?;
+    println!("{}", "Backup is imported".green());
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use intmax2_client_sdk::{
     client::{client::Client, config::ClientConfig},
     external_api::{
@@ -5,11 +7,17 @@ use intmax2_client_sdk::{
         block_builder::BlockBuilderClient,
         contract::{liquidity_contract::LiquidityContract, rollup_contract::RollupContract},
         store_vault_server::StoreVaultServerClient,
+        utils::{
+            retry::RetryPolicy,
+            transport::{HttpTransport, TransportConfig},
+        },
         validity_prover::ValidityProverClient,
         withdrawal_server::WithdrawalServerClient,
     },
 };
 
+use intmax2_interfaces::api::encoding::WireFormat;
+
 use crate::env_var::EnvVar;
 
 use super::error::CliError;
@@ -22,12 +30,25 @@ type W = WithdrawalServerClient;
 
 pub fn get_client() -> Result<Client<BB, S, V, B, W>, CliError> {
     let env = envy::from_env::<EnvVar>()?;
-    let block_builder = BB::new();
-    let store_vault_server = S::new(&env.store_vault_server_base_url);
+    let transport = get_transport(&env);
+    let block_builder = BB::with_transport(transport.clone());
+    let mut store_vault_server =
+        S::with_transport(&env.store_vault_server_base_url, transport.clone());
 
-    let validity_prover = V::new(&env.validity_prover_base_url);
-    let balance_prover = B::new(&env.balance_prover_base_url);
-    let withdrawal_server = W::new(&env.withdrawal_server_base_url);
+    // the provers ship large proofs and witnesses, which are much smaller in bincode
+    let prover_transport = if env.binary_wire_encoding {
+        transport.with_wire_format(WireFormat::Bincode)
+    } else {
+        transport.clone()
+    };
+    let validity_prover =
+        V::with_transport(&env.validity_prover_base_url, prover_transport.clone());
+    let balance_prover = B::with_transport(&env.balance_prover_base_url, prover_transport);
+    let mut withdrawal_server = W::with_transport(&env.withdrawal_server_base_url, transport);
+    if env.use_sessions {
+        store_vault_server = store_vault_server.with_sessions();
+        withdrawal_server = withdrawal_server.with_sessions();
+    }
 
     let liquidity_contract = LiquidityContract::new(
         &env.l1_rpc_url,
@@ -46,6 +67,9 @@ pub fn get_client() -> Result<Client<BB, S, V, B, W>, CliError> {
         tx_timeout: env.tx_timeout,
         block_builder_request_interval: env.block_builder_request_interval,
         block_builder_request_limit: env.block_builder_request_limit,
+        block_builder_query_wait_time: env.block_builder_query_wait_time,
+        block_builder_query_interval: env.block_builder_query_interval,
+        block_builder_query_limit: env.block_builder_query_limit,
     };
 
     let client = Client {
@@ -61,3 +85,16 @@ pub fn get_client() -> Result<Client<BB, S, V, B, W>, CliError> {
 
     Ok(client)
 }
+
+fn get_transport(env: &EnvVar) -> HttpTransport {
+    let mut retry_policy = RetryPolicy::default();
+    if let Some(max_retries) = env.http_max_retries {
+        retry_policy.max_retries = max_retries;
+    }
+    let config = TransportConfig {
+        default_timeout: env.http_timeout.map(Duration::from_secs),
+        retry_policy,
+        ..Default::default()
+    };
+    HttpTransport::new(config)
+}
@@ -0,0 +1,46 @@
+use colored::Colorize as _;
+use intmax2_zkp::common::signature::key_set::KeySet;
+
+use super::{client::get_client, error::CliError, get::format_timestamp};
+
+pub async fn request_data_deletion(key: KeySet) -> Result<(), CliError> {
+    let client = get_client()?;
+    let response = client.request_account_data_deletion(key).await?;
+    println!(
+        "{}",
+        "All data of the account will be deleted from the store vault. This cannot be undone, \
+         consider exporting a backup first."
+            .yellow()
+    );
+    println!("Confirmation token: {}", response.token);
+    println!("Expires at: {}", format_timestamp(response.expires_at));
+    println!("Run again with --confirm {} to delete", response.token);
+    Ok(())
+}
+
+pub async fn delete_my_data(key: KeySet, token: &str) -> Result<(), CliError> {
+    let client = get_client()?;
+    let response = client.delete_account_data(key, token).await?;
+    println!(
+        "{}",
+        format!(
+            "Deleted {} user data versions, {} records and {} sender proof sets",
+            response.deleted_user_data_versions,
+            response.deleted_entries,
+            response.deleted_sender_proof_sets
+        )
+        .green()
+    );
+    if response.skipped_sender_proof_sets > 0 {
+        println!(
+            "{}",
+            format!(
+                "{} sender proof sets are still needed by the recipients and are kept until they \
+                 expire",
+                response.skipped_sender_proof_sets
+            )
+            .yellow()
+        );
+    }
+    Ok(())
+}
@@ -1,14 +1,20 @@
 use ethers::types::{Address, H256, U256};
-use intmax2_client_sdk::external_api::contract::{
+use colored::Colorize as _;
+use intmax2_client_sdk::{
+    client::strategy::deposit::DepositRecheckResult,
+    external_api::contract::{
     erc1155_contract::ERC1155Contract,
     erc20_contract::ERC20Contract,
     erc721_contract::ERC721Contract,
     liquidity_contract::LiquidityContract,
     utils::{get_address, get_eth_balance},
+    },
 };
 use intmax2_interfaces::data::deposit_data::TokenType;
 use intmax2_zkp::common::signature::key_set::KeySet;
 
+use crate::env_var::EnvVar;
+
 use super::{
     client::get_client,
     error::CliError,
@@ -119,6 +125,44 @@ pub async fn deposit(
     Ok(())
 }
 
+pub async fn recheck_deposit(key: KeySet, deposit_uuid: &str) -> Result<(), CliError> {
+    let client = get_client()?;
+    let result = client.recheck_deposit(key, deposit_uuid).await?;
+    match result {
+        DepositRecheckResult::AlreadyProcessed => {
+            println!("Deposit is already included in the balance");
+        }
+        DepositRecheckResult::Resurrected { block_number } => {
+            println!(
+                "{}",
+                format!("Deposit is relayed at block {} and now included", block_number).green()
+            );
+        }
+        DepositRecheckResult::Pending => {
+            println!("{}", "Deposit is still pending".yellow());
+        }
+        DepositRecheckResult::Rejected(reason) => {
+            println!("{}", format!("Deposit is rejected: {}", reason).red());
+        }
+    }
+    Ok(())
+}
+
+pub async fn cancel_deposit(
+    key: KeySet,
+    eth_private_key: H256,
+    deposit_uuid: &str,
+) -> Result<(), CliError> {
+    let env = envy::from_env::<EnvVar>()?;
+    let from_block = env.liquidity_contract_deployed_block_number.unwrap_or(0);
+    let client = get_client()?;
+    client
+        .cancel_deposit(eth_private_key, key, deposit_uuid, from_block)
+        .await?;
+    println!("{}", "Deposit is cancelled".green());
+    Ok(())
+}
+
 async fn balance_check_and_approve(
     liquidity_contract: &LiquidityContract,
     eth_private_key: H256,
@@ -21,6 +21,9 @@ pub enum CliError {
     #[error("CSV deserialize error: {0}")]
     CSVDeserializeError(#[from] csv::Error),
 
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+
     #[error("Too many transfer: {0}")]
     TooManyTransfer(usize),
 
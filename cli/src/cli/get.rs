@@ -1,14 +1,16 @@
 use chrono::DateTime;
 use colored::{ColoredString, Colorize as _};
-use intmax2_client_sdk::client::history::HistoryEntry;
-use intmax2_interfaces::data::deposit_data::TokenType;
+use intmax2_client_sdk::client::history::{GenericTransfer, HistoryEntry};
+use intmax2_interfaces::{
+    data::deposit_data::TokenType,
+    utils::address::{IntmaxAddress, Network},
+};
 use intmax2_zkp::{
     common::{deposit::Deposit, signature::key_set::KeySet, trees::asset_tree::AssetLeaf},
-    ethereum_types::u32limb_trait::U32LimbTrait,
     utils::leafable::Leafable as _,
 };
 
-use crate::cli::client::get_client;
+use crate::{cli::client::get_client, env_var::EnvVar};
 
 use super::error::CliError;
 
@@ -66,15 +68,35 @@ pub async fn withdrawal_status(key: KeySet) -> Result<(), CliError> {
             withdrawal_info.status
         );
     }
+
+    let (user_data, _) = client.get_user_data_and_digest(key).await?;
+    let rejected = user_data
+        .rejected_transfers
+        .iter()
+        .filter(|r| !r.transfer.recipient.is_pubkey)
+        .collect::<Vec<_>>();
+    if !rejected.is_empty() {
+        println!("Rejected withdrawals:");
+        for r in rejected {
+            println!(
+                "uuid: {}, token_index: {}, amount: {}, reason: {}",
+                r.uuid,
+                r.transfer.token_index,
+                r.transfer.amount,
+                r.reason.bright_red()
+            );
+        }
+    }
     Ok(())
 }
 
 pub async fn history(key: KeySet) -> Result<(), CliError> {
+    let env = envy::from_env::<EnvVar>()?;
     let client = get_client()?;
     let history = client.fetch_history(key).await?;
     println!("History:");
     for entry in history {
-        print_history_entry(&entry)?;
+        print_history_entry(&entry, env.env.into())?;
         println!();
     }
     Ok(())
@@ -89,12 +111,28 @@ fn get_status_string(is_included: bool, is_rejected: bool) -> ColoredString {
     }
 }
 
-fn format_timestamp(timestamp: u64) -> String {
+pub(crate) fn format_timestamp(timestamp: u64) -> String {
     let naive = DateTime::from_timestamp(timestamp as i64, 0).unwrap();
     naive.format("%Y-%m-%d %H:%M:%S UTC").to_string()
 }
 
-fn print_history_entry(entry: &HistoryEntry) -> Result<(), CliError> {
+fn format_generic_transfer(transfer: &GenericTransfer, network: Network) -> String {
+    match transfer {
+        GenericTransfer::Transfer {
+            recipient,
+            token_index,
+            amount,
+        } => format!(
+            "Transfer(recipient: {}, token_index: {}, amount: {})",
+            IntmaxAddress::new(network, *recipient),
+            token_index,
+            amount
+        ),
+        GenericTransfer::Withdrawal { .. } => transfer.to_string(),
+    }
+}
+
+fn print_history_entry(entry: &HistoryEntry, network: Network) -> Result<(), CliError> {
     match entry {
         HistoryEntry::Deposit {
             token_type,
@@ -107,6 +145,7 @@ fn print_history_entry(entry: &HistoryEntry) -> Result<(), CliError> {
             pubkey_salt_hash,
             is_included,
             is_rejected,
+            rejection_reason,
             meta,
         } => {
             let status = get_status_string(*is_included, *is_rejected);
@@ -153,6 +192,9 @@ fn print_history_entry(entry: &HistoryEntry) -> Result<(), CliError> {
                 deposit_hash.map_or("N/A".to_string(), |h| h.to_string())
             );
             println!("  {}", status);
+            if let Some(reason) = rejection_reason {
+                println!("  Reason: {}", reason.to_string().bright_red());
+            }
         }
         HistoryEntry::Receive {
             amount,
@@ -160,6 +202,8 @@ fn print_history_entry(entry: &HistoryEntry) -> Result<(), CliError> {
             from,
             is_included,
             is_rejected,
+            rejection_reason,
+            memo,
             meta,
         } => {
             let status = get_status_string(*is_included, *is_rejected);
@@ -176,10 +220,19 @@ fn print_history_entry(entry: &HistoryEntry) -> Result<(), CliError> {
                 meta.block_number
                     .map_or("N/A".to_string(), |b| b.to_string())
             );
-            println!("  From: {}", from.to_hex().yellow());
+            println!(
+                "  From: {}",
+                IntmaxAddress::new(network, *from).to_string().yellow()
+            );
             println!("  Token Index: {}", token_index.to_string().white());
             println!("  Amount: {}", amount.to_string().bright_green());
+            if let Some(memo) = memo {
+                println!("  Memo: {}", memo.white());
+            }
             println!("  {}", status);
+            if let Some(reason) = rejection_reason {
+                println!("  Reason: {}", reason.bright_red());
+            }
         }
         HistoryEntry::Send {
             transfers,
@@ -199,7 +252,11 @@ fn print_history_entry(entry: &HistoryEntry) -> Result<(), CliError> {
             );
             println!("  Transfers:");
             for (i, t) in transfers.iter().enumerate() {
-                println!("    {}: {}", i + 1, t.to_string().white());
+                println!(
+                    "    {}: {}",
+                    i + 1,
+                    format_generic_transfer(t, network).white()
+                );
             }
             println!("  {}", status);
         }
@@ -0,0 +1,57 @@
+use colored::Colorize as _;
+use intmax2_interfaces::utils::invoice::{Invoice, InvoiceToken};
+use intmax2_zkp::{
+    common::signature::key_set::KeySet, ethereum_types::u32limb_trait::U32LimbTrait,
+};
+
+use crate::{cli::utils::convert_u256, env_var::EnvVar};
+
+use super::{
+    client::get_client,
+    error::CliError,
+    send::{get_block_builder_url, parse_generic_address},
+};
+
+/// Print the URI of an invoice for `amount` of `token_index` to `recipient`, valid for
+/// `expires_in` seconds if given.
+pub fn create_invoice(
+    recipient: &str,
+    token_index: u32,
+    amount: u128,
+    memo: Option<String>,
+    expires_in: Option<u64>,
+) -> Result<(), CliError> {
+    let env = envy::from_env::<EnvVar>()?;
+    let network = env.env.into();
+    let recipient = parse_generic_address(recipient, network)?;
+    let invoice = Invoice {
+        recipient,
+        token: InvoiceToken::Index(token_index),
+        amount: convert_u256(amount.into()),
+        memo: memo.filter(|memo| !memo.is_empty()),
+        expiry: expires_in.map(|secs| chrono::Utc::now().timestamp() as u64 + secs),
+    };
+    invoice
+        .validate()
+        .map_err(|e| CliError::ParseError(e.to_string()))?;
+    println!("{}", invoice.to_uri(network));
+    Ok(())
+}
+
+pub async fn pay_invoice(key: KeySet, uri: &str) -> Result<(), CliError> {
+    let env = envy::from_env::<EnvVar>()?;
+    let invoice = Invoice::from_uri(uri, env.env.into())
+        .map_err(|e| CliError::ParseError(format!("Failed to parse invoice: {}", e)))?;
+    let client = get_client()?;
+    let block_builder_url = get_block_builder_url(&env).await?;
+
+    log::info!("Paying invoice");
+    let result = client
+        .pay_invoice(&block_builder_url, key, &invoice)
+        .await?;
+    println!("{}", "Invoice is paid".green());
+    println!("  Recipient: {}", invoice.recipient);
+    println!("  Amount: {}", invoice.amount);
+    println!("  Tx Tree Root: {}", result.tx_tree_root.to_hex());
+    Ok(())
+}
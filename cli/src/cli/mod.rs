@@ -1,8 +1,13 @@
+pub mod backup;
 pub mod claim;
+pub mod delete;
 pub mod client;
 pub mod deposit;
 pub mod error;
 pub mod get;
+pub mod invoice;
+pub mod receipt;
+pub mod recover;
 pub mod send;
 pub mod sync;
 pub mod utils;
@@ -0,0 +1,40 @@
+use colored::Colorize as _;
+use intmax2_client_sdk::client::receipt::{verify_receipt, PaymentReceipt};
+use intmax2_zkp::{
+    common::signature::key_set::KeySet, ethereum_types::u32limb_trait::U32LimbTrait,
+};
+
+use super::{client::get_client, error::CliError};
+
+pub async fn create_receipt(
+    key: KeySet,
+    tx_uuid: &str,
+    transfer_index: u32,
+    path: &str,
+) -> Result<(), CliError> {
+    let client = get_client()?;
+    let receipt = client
+        .create_payment_receipt(key, tx_uuid, transfer_index)
+        .await?;
+    let json = serde_json::to_string_pretty(&receipt)
+        .map_err(|e| CliError::UnexpectedError(format!("Failed to serialize receipt: {}", e)))?;
+    std::fs::write(path, json)?;
+    println!("{}", format!("Receipt is written to {}", path).green());
+    Ok(())
+}
+
+pub async fn verify_receipt_file(path: &str) -> Result<(), CliError> {
+    let client = get_client()?;
+    let json = std::fs::read_to_string(path)?;
+    let receipt: PaymentReceipt = serde_json::from_str(&json)
+        .map_err(|e| CliError::ParseError(format!("Failed to parse receipt: {}", e)))?;
+    verify_receipt(&client.validity_prover, &receipt).await?;
+    println!("{}", "Receipt is valid".green());
+    println!("  Sender: {}", receipt.sender.to_hex());
+    println!("  Recipient: {}", receipt.transfer.recipient);
+    println!("  Token Index: {}", receipt.transfer.token_index);
+    println!("  Amount: {}", receipt.transfer.amount);
+    println!("  Block: {}", receipt.block_number);
+    println!("  Tx Tree Root: {}", receipt.tx_tree_root);
+    Ok(())
+}
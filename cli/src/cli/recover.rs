@@ -0,0 +1,39 @@
+use colored::Colorize as _;
+use intmax2_zkp::common::signature::key_set::KeySet;
+
+use super::{client::get_client, error::CliError};
+
+pub async fn recover(key: KeySet, dry_run: bool) -> Result<(), CliError> {
+    let client = get_client()?;
+    let result = client.recover_account(key, dry_run).await?;
+
+    println!("Recovered deposits: {}", result.num_deposits);
+    println!("Recovered transfers: {}", result.num_transfers);
+    println!("Recovered txs: {}", result.num_txs);
+    println!(
+        "Already requested withdrawals: {}",
+        result.num_requested_withdrawals
+    );
+    for uuid in &result.skipped_transfer_uuids {
+        println!("{}", format!("Skipped invalid transfer: {}", uuid).yellow());
+    }
+
+    let mut balances = result
+        .user_data
+        .balances()
+        .0
+        .into_iter()
+        .collect::<Vec<_>>();
+    balances.sort_by_key(|(i, _leaf)| *i);
+    println!("Balances:");
+    for (i, leaf) in balances.iter() {
+        println!("\t Token #{}: {}", i, leaf.amount);
+    }
+
+    if result.saved {
+        println!("{}", "Recovered user data is saved".green());
+    } else {
+        println!("Dry run: recovered user data is not saved");
+    }
+    Ok(())
+}
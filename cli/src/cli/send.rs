@@ -1,14 +1,12 @@
-use anyhow::{bail, ensure};
 use intmax2_client_sdk::external_api::indexer::IndexerClient;
-use intmax2_interfaces::api::indexer::interface::IndexerClientInterface;
+use intmax2_interfaces::{
+    api::indexer::interface::IndexerClientInterface, utils::address::Network,
+};
 use intmax2_zkp::{
     common::{
         generic_address::GenericAddress, salt::Salt, signature::key_set::KeySet, transfer::Transfer,
     },
     constants::NUM_TRANSFERS_IN_TX,
-    ethereum_types::{
-        address::Address as IAddress, u256::U256 as IU256, u32limb_trait::U32LimbTrait,
-    },
 };
 use serde::Deserialize;
 
@@ -25,6 +23,8 @@ pub struct TransferInput {
     pub recipient: String,
     pub amount: u128,
     pub token_index: u32,
+    #[serde(default)]
+    pub memo: Option<String>,
 }
 
 pub async fn transfer(key: KeySet, transfer_inputs: &[TransferInput]) -> Result<(), CliError> {
@@ -32,12 +32,12 @@ pub async fn transfer(key: KeySet, transfer_inputs: &[TransferInput]) -> Result<
     if transfer_inputs.len() > NUM_TRANSFERS_IN_TX {
         return Err(CliError::TooManyTransfer(transfer_inputs.len()));
     }
+    let env = envy::from_env::<EnvVar>()?;
 
     let transfers = transfer_inputs
         .iter()
         .map(|input| {
-            let recipient = parse_generic_address(&input.recipient)
-                .map_err(|e| CliError::ParseError(format!("Failed to parse recipient: {}", e)))?;
+            let recipient = parse_generic_address(&input.recipient, env.env.into())?;
             let amount = convert_u256(input.amount.into());
             let token_index = input.token_index;
             let salt = Salt::rand(&mut rng);
@@ -50,7 +50,11 @@ pub async fn transfer(key: KeySet, transfer_inputs: &[TransferInput]) -> Result<
         })
         .collect::<Result<Vec<_>, CliError>>()?;
 
-    let env = envy::from_env::<EnvVar>()?;
+    let memos = transfer_inputs
+        .iter()
+        .map(|input| input.memo.clone().filter(|memo| !memo.is_empty()))
+        .collect::<Vec<_>>();
+
     let client = get_client()?;
 
     let pending_info = client.sync(key).await?;
@@ -63,23 +67,10 @@ pub async fn transfer(key: KeySet, transfer_inputs: &[TransferInput]) -> Result<
         pending_info.pending_transfers.len()
     );
 
-    // override block builder base url if it is set in the env
-    let block_builder_url = if let Some(block_builder_base_url) = env.block_builder_base_url {
-        block_builder_base_url.to_string()
-    } else {
-        // get block builder info
-        let indexer = IndexerClient::new(&env.indexer_base_url.to_string());
-        let block_builder_info = indexer.get_block_builder_info().await?;
-        if block_builder_info.is_empty() {
-            return Err(CliError::UnexpectedError(
-                "Block builder info is empty".to_string(),
-            ));
-        }
-        block_builder_info.first().unwrap().url.clone()
-    };
+    let block_builder_url = get_block_builder_url(&env).await?;
 
     let memo = client
-        .send_tx_request(&block_builder_url, key, transfers)
+        .send_tx_request(&block_builder_url, key, transfers, memos)
         .await?;
 
     let is_registration_block = memo.is_registration_block;
@@ -121,16 +112,28 @@ pub async fn transfer(key: KeySet, transfer_inputs: &[TransferInput]) -> Result<
     Ok(())
 }
 
-fn parse_generic_address(address: &str) -> anyhow::Result<GenericAddress> {
-    ensure!(address.starts_with("0x"), "Invalid prefix");
-    let bytes = hex::decode(&address[2..])?;
-    if bytes.len() == 20 {
-        let address = IAddress::from_bytes_be(&bytes);
-        Ok(GenericAddress::from_address(address))
-    } else if bytes.len() == 32 {
-        let pubkey = IU256::from_bytes_be(&bytes);
-        Ok(GenericAddress::from_pubkey(pubkey))
-    } else {
-        bail!("Invalid length");
+pub(crate) async fn get_block_builder_url(env: &EnvVar) -> Result<String, CliError> {
+    // override block builder base url if it is set in the env
+    if let Some(block_builder_base_url) = &env.block_builder_base_url {
+        return Ok(block_builder_base_url.to_string());
     }
+    // get block builder info
+    let indexer = IndexerClient::new(&env.indexer_base_url.to_string());
+    let block_builder_info = indexer.get_block_builder_info().await?;
+    if block_builder_info.is_empty() {
+        return Err(CliError::UnexpectedError(
+            "Block builder info is empty".to_string(),
+        ));
+    }
+    Ok(block_builder_info.first().unwrap().url.clone())
+}
+
+/// Parse a recipient given as an intmax address of `network`, a hex pubkey, or a hex ethereum
+/// address.
+pub(crate) fn parse_generic_address(
+    address: &str,
+    network: Network,
+) -> Result<GenericAddress, CliError> {
+    intmax2_interfaces::utils::address::parse_generic_address(address, network)
+        .map_err(|e| CliError::ParseError(format!("Failed to parse recipient: {}", e)))
 }
@@ -1,4 +1,5 @@
 use ethers::types::Address;
+use intmax2_interfaces::utils::address::Network;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
@@ -29,12 +30,28 @@ pub struct EnvVar {
     pub l2_chain_id: u64,
     pub rollup_contract_address: Address,
     pub rollup_contract_deployed_block_number: u64,
+    // L1 block from which deposit events are searched when cancelling a deposit
+    pub liquidity_contract_deployed_block_number: Option<u64>,
 
     // optional block builder base url
     pub block_builder_base_url: Option<String>,
 
     // optional token mapping base url
     pub token_mapping_base_url: Option<String>,
+
+    // optional http settings. The timeout is in seconds and not set by default.
+    pub http_timeout: Option<u64>,
+    pub http_max_retries: Option<u32>,
+
+    // use bincode instead of JSON for the balance prover and the validity prover. Requires
+    // servers that support bincode.
+    #[serde(default)]
+    pub binary_wire_encoding: bool,
+
+    // authenticate the requests to the store vault server and the withdrawal server by session
+    // tokens instead of signing each of them
+    #[serde(default)]
+    pub use_sessions: bool,
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
@@ -45,3 +62,14 @@ pub enum EnvType {
     Staging,
     Prod,
 }
+
+impl From<EnvType> for Network {
+    fn from(env: EnvType) -> Self {
+        match env {
+            EnvType::Local => Network::Local,
+            EnvType::Dev => Network::Dev,
+            EnvType::Staging => Network::Staging,
+            EnvType::Prod => Network::Prod,
+        }
+    }
+}
@@ -2,18 +2,25 @@ use clap::Parser;
 use colored::Colorize as _;
 use ethers::types::H256;
 use intmax2_cli::{
-    args::{Args, Commands},
+    args::{Args, Commands, InvoiceCommands, ReceiptCommands},
     cli::{
+        backup::{export_backup, import_backup},
         claim::claim_withdrawals,
-        deposit::deposit,
+        delete::{delete_my_data, request_data_deletion},
+        deposit::{cancel_deposit, deposit, recheck_deposit},
         error::CliError,
         get::{balance, history, withdrawal_status},
+        invoice::{create_invoice, pay_invoice},
+        receipt::{create_receipt, verify_receipt_file},
+        recover::recover,
         send::{transfer, TransferInput},
         sync::sync_withdrawals,
         utils::post_empty_block,
     },
+    env_var::EnvVar,
     format::{format_token_info, privkey_to_keyset},
 };
+use intmax2_interfaces::utils::address::IntmaxAddress;
 use intmax2_zkp::{
     common::signature::key_set::KeySet,
     ethereum_types::{u256::U256 as IU256, u32limb_trait::U32LimbTrait},
@@ -55,12 +62,14 @@ async fn main_process(command: Commands) -> Result<(), CliError> {
             to,
             amount,
             token_index,
+            memo,
         } => {
             let key = privkey_to_keyset(private_key);
             let transfer_input = TransferInput {
                 recipient: to,
                 amount,
                 token_index,
+                memo,
             };
             transfer(key, &[transfer_input]).await?;
         }
@@ -103,6 +112,21 @@ async fn main_process(command: Commands) -> Result<(), CliError> {
             )
             .await?;
         }
+        Commands::RecheckDeposit {
+            private_key,
+            deposit_uuid,
+        } => {
+            let key = privkey_to_keyset(private_key);
+            recheck_deposit(key, &deposit_uuid).await?;
+        }
+        Commands::CancelDeposit {
+            private_key,
+            eth_private_key,
+            deposit_uuid,
+        } => {
+            let key = privkey_to_keyset(private_key);
+            cancel_deposit(key, eth_private_key, &deposit_uuid).await?;
+        }
         Commands::SyncWithdrawals { private_key } => {
             let key = privkey_to_keyset(private_key);
             sync_withdrawals(key).await?;
@@ -129,26 +153,88 @@ async fn main_process(command: Commands) -> Result<(), CliError> {
             let key = privkey_to_keyset(private_key);
             claim_withdrawals(key, eth_private_key).await?;
         }
+        Commands::Recover {
+            private_key,
+            dry_run,
+        } => {
+            let key = privkey_to_keyset(private_key);
+            recover(key, dry_run).await?;
+        }
+        Commands::ExportBackup { private_key, path } => {
+            let key = privkey_to_keyset(private_key);
+            export_backup(key, &path).await?;
+        }
+        Commands::ImportBackup { private_key, path } => {
+            let key = privkey_to_keyset(private_key);
+            import_backup(key, &path).await?;
+        }
+        Commands::DeleteMyData {
+            private_key,
+            confirm,
+        } => {
+            let key = privkey_to_keyset(private_key);
+            match confirm {
+                Some(token) => delete_my_data(key, &token).await?,
+                None => request_data_deletion(key).await?,
+            }
+        }
+        Commands::Receipt { command } => match command {
+            ReceiptCommands::Create {
+                private_key,
+                tx_uuid,
+                transfer_index,
+                path,
+            } => {
+                let key = privkey_to_keyset(private_key);
+                create_receipt(key, &tx_uuid, transfer_index, &path).await?;
+            }
+            ReceiptCommands::Verify { path } => {
+                verify_receipt_file(&path).await?;
+            }
+        },
+        Commands::Invoice { command } => match command {
+            InvoiceCommands::Create {
+                recipient,
+                token_index,
+                amount,
+                memo,
+                expires_in,
+            } => {
+                create_invoice(&recipient, token_index, amount, memo, expires_in)?;
+            }
+            InvoiceCommands::Pay { private_key, uri } => {
+                let key = privkey_to_keyset(private_key);
+                pay_invoice(key, &uri).await?;
+            }
+        },
         Commands::GenerateKey => {
             let mut rng = rand::thread_rng();
             let key = KeySet::rand(&mut rng);
-            let private_key = BigUint::from(key.privkey);
-            let private_key: IU256 = private_key.try_into().unwrap();
-            println!("Private key: {}", private_key.to_hex());
-            println!("Public key: {}", key.pubkey.to_hex());
+            print_key(key);
         }
         Commands::GenerateFromEthKey { eth_private_key } => {
             let provisional = BigUint::from_bytes_be(eth_private_key.as_bytes());
             let key = KeySet::generate_from_provisional(provisional.into());
-            let private_key = BigUint::from(key.privkey);
-            let private_key: IU256 = private_key.try_into().unwrap();
-            println!("Private key: {}", private_key.to_hex());
-            println!("Public key: {}", key.pubkey.to_hex());
+            print_key(key);
         }
     }
     Ok(())
 }
 
+fn print_key(key: KeySet) {
+    let private_key = BigUint::from(key.privkey);
+    let private_key: IU256 = private_key.try_into().unwrap();
+    println!("Private key: {}", private_key.to_hex());
+    println!("Public key: {}", key.pubkey.to_hex());
+    // the address is shown only if the network is known from the env
+    if let Ok(env) = envy::from_env::<EnvVar>() {
+        println!(
+            "Address: {}",
+            IntmaxAddress::new(env.env.into(), key.pubkey)
+        );
+    }
+}
+
 fn generate_key(private_key: Option<H256>) -> KeySet {
     match private_key {
         Some(private_key) => privkey_to_keyset(private_key),
@@ -0,0 +1,86 @@
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        store_vault_server::{
+            interface::{DataType, StoreVaultClientInterface},
+            types::{DeleteMyDataResponse, RequestDataDeletionResponse},
+        },
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    data::tx_data::TxData,
+};
+use intmax2_zkp::{common::signature::key_set::KeySet, ethereum_types::u256::U256};
+use num_bigint::BigUint;
+
+use super::{client::Client, error::ClientError};
+
+// the batch size limit of the store vault server
+const MAX_SENDER_PROOF_SETS_PER_REQUEST: usize = 1000;
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Request a token to confirm the deletion of the account's data by `delete_account_data`.
+    pub async fn request_account_data_deletion(
+        &self,
+        key: KeySet,
+    ) -> Result<RequestDataDeletionResponse, ClientError> {
+        let response = self.store_vault_server.request_data_deletion(key).await?;
+        Ok(response)
+    }
+
+    /// Delete the user data, all records and the sender proof sets of the txs sent by the
+    /// account from the store vault. The sender proof sets are deleted only once all of their
+    /// transfers are acknowledged by the recipients, and the others are left to the retention of
+    /// the server. The deletion cannot be undone, so export a backup first if the data may be
+    /// needed later.
+    pub async fn delete_account_data(
+        &self,
+        key: KeySet,
+        token: &str,
+    ) -> Result<DeleteMyDataResponse, ClientError> {
+        // the sender proof sets are keyed by the ephemeral keys kept in the tx data
+        let txs = self
+            .fetch_decrypted(DataType::Tx, key, |data| TxData::decrypt(data, key))
+            .await?;
+        let mut ephemeral_keys: Vec<U256> = Vec::new();
+        for (_, tx_data) in txs.iter() {
+            if !ephemeral_keys.contains(&tx_data.sender_proof_set_ephemeral_key) {
+                ephemeral_keys.push(tx_data.sender_proof_set_ephemeral_key);
+            }
+        }
+        let sender_proof_set_keys = ephemeral_keys
+            .into_iter()
+            .map(|ephemeral_privkey| KeySet::new(BigUint::from(ephemeral_privkey).into()))
+            .collect::<Vec<_>>();
+        // The token is consumed by each request, so a new one is issued for every further chunk
+        // of the sender proof sets. The user data and records are already gone by then.
+        let mut chunks = sender_proof_set_keys.chunks(MAX_SENDER_PROOF_SETS_PER_REQUEST);
+        let first_chunk = chunks.next().unwrap_or_default();
+        let mut response = self
+            .store_vault_server
+            .delete_my_data(key, token, first_chunk)
+            .await?;
+        for chunk in chunks {
+            let token = self
+                .store_vault_server
+                .request_data_deletion(key)
+                .await?
+                .token;
+            let chunk_response = self
+                .store_vault_server
+                .delete_my_data(key, &token, chunk)
+                .await?;
+            response.deleted_sender_proof_sets += chunk_response.deleted_sender_proof_sets;
+            response.skipped_sender_proof_sets += chunk_response.skipped_sender_proof_sets;
+        }
+        Ok(response)
+    }
+}
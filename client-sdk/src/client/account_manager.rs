@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+
+use futures::{stream, StreamExt as _};
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        store_vault_server::interface::StoreVaultClientInterface,
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    data::deposit_data::TokenType,
+};
+use intmax2_zkp::{
+    common::{generic_address::GenericAddress, signature::key_set::KeySet, transfer::Transfer},
+    constants::NUM_TRANSFERS_IN_TX,
+    ethereum_types::{address::Address, u256::U256},
+};
+
+use crate::external_api::{
+    cached_validity_prover::CachedValidityProver, contract::error::BlockchainError,
+};
+
+use super::{
+    client::{Client, TxResult},
+    error::ClientError,
+    strategy::strategy::PendingInfo,
+    sync::{error::SyncError, utils::generate_salt},
+};
+
+#[derive(Debug, Clone)]
+pub struct AccountManagerConfig {
+    /// Maximum number of accounts processed at the same time
+    pub concurrency: usize,
+}
+
+/// Total balance of a token over the managed accounts.
+#[derive(Debug, Clone)]
+pub struct TokenBalanceSummary {
+    pub token_index: u32,
+    pub total: U256,
+    /// Pubkeys and balances of the accounts holding the token
+    pub accounts: Vec<(U256, U256)>,
+    /// Pubkeys of the accounts whose balance of the token is insufficient
+    pub insufficient_accounts: Vec<U256>,
+}
+
+/// Holds the keys of many accounts and runs the client operations over all of them, sharing a
+/// single client, its connection pool and the validity prover cache.
+pub struct AccountManager<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    pub client: Client<BB, S, CachedValidityProver<V>, B, W>,
+    pub config: AccountManagerConfig,
+    accounts: BTreeMap<U256, KeySet>,
+}
+
+impl<BB, S, V, B, W> AccountManager<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    pub fn new(client: Client<BB, S, V, B, W>, config: AccountManagerConfig) -> Self {
+        let client = Client {
+            config: client.config,
+            block_builder: client.block_builder,
+            store_vault_server: client.store_vault_server,
+            validity_prover: CachedValidityProver::new(client.validity_prover),
+            balance_prover: client.balance_prover,
+            withdrawal_server: client.withdrawal_server,
+            liquidity_contract: client.liquidity_contract,
+            rollup_contract: client.rollup_contract,
+        };
+        Self {
+            client,
+            config,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_account(&mut self, key: KeySet) {
+        self.accounts.insert(key.pubkey, key);
+    }
+
+    pub fn remove_account(&mut self, pubkey: U256) -> Option<KeySet> {
+        self.accounts.remove(&pubkey)
+    }
+
+    pub fn accounts(&self) -> Vec<KeySet> {
+        self.accounts.values().cloned().collect()
+    }
+
+    pub fn get_account(&self, pubkey: U256) -> Option<KeySet> {
+        self.accounts.get(&pubkey).cloned()
+    }
+
+    /// Sync the balance proofs of all accounts. A failure of an account does not stop the others.
+    pub async fn sync_all(&self) -> Vec<(U256, Result<PendingInfo, SyncError>)> {
+        stream::iter(self.accounts.values().cloned())
+            .map(|key| async move { (key.pubkey, self.client.sync(key).await) })
+            .buffer_unordered(self.concurrency())
+            .collect()
+            .await
+    }
+
+    /// Sync the withdrawals of all accounts. A failure of an account does not stop the others.
+    pub async fn sync_withdrawals_all(&self) -> Vec<(U256, Result<(), SyncError>)> {
+        stream::iter(self.accounts.values().cloned())
+            .map(|key| async move { (key.pubkey, self.client.sync_withdrawals(key).await) })
+            .buffer_unordered(self.concurrency())
+            .collect()
+            .await
+    }
+
+    /// Returns the token type, address and id of the token index. The token lookups of the
+    /// liquidity contract are cached and shared by all accounts, including the ones of the deposit
+    /// sync, so each token is fetched only once.
+    pub async fn get_token_info(
+        &self,
+        token_index: u32,
+    ) -> Result<(TokenType, Address, U256), BlockchainError> {
+        self.client
+            .liquidity_contract
+            .get_token_info(token_index)
+            .await
+    }
+
+    /// Aggregate the balances of all accounts per token, as of their last sync.
+    pub async fn aggregate_balances(
+        &self,
+    ) -> Result<BTreeMap<u32, TokenBalanceSummary>, ClientError> {
+        let results: Vec<_> = stream::iter(self.accounts.values().cloned())
+            .map(|key| async move {
+                let (user_data, _) = self.client.get_user_data_and_digest(key).await?;
+                Ok::<_, ClientError>((key.pubkey, user_data.balances()))
+            })
+            .buffer_unordered(self.concurrency())
+            .collect()
+            .await;
+
+        let mut summaries = BTreeMap::new();
+        for result in results {
+            let (pubkey, balances) = result?;
+            for (token_index, leaf) in balances.0 {
+                let summary = summaries
+                    .entry(token_index)
+                    .or_insert_with(|| TokenBalanceSummary {
+                        token_index,
+                        total: U256::default(),
+                        accounts: Vec::new(),
+                        insufficient_accounts: Vec::new(),
+                    });
+                if leaf.is_insufficient {
+                    summary.insufficient_accounts.push(pubkey);
+                    continue;
+                }
+                if leaf.amount == U256::default() {
+                    continue;
+                }
+                summary.total = summary.total + leaf.amount;
+                summary.accounts.push((pubkey, leaf.amount));
+            }
+        }
+        for summary in summaries.values_mut() {
+            summary.accounts.sort_by_key(|(pubkey, _)| *pubkey);
+            summary.insufficient_accounts.sort();
+        }
+        Ok(summaries)
+    }
+
+    /// Send the whole balance of every managed account other than the treasury to the treasury.
+    ///
+    /// Each account sends a single tx per call, so an account holding more than
+    /// `NUM_TRANSFERS_IN_TX` tokens is fully swept after multiple calls. Accounts without any
+    /// balance are skipped and reported as `None`.
+    pub async fn sweep(
+        &self,
+        block_builder_url: &str,
+        treasury: U256,
+    ) -> Vec<(U256, Result<Option<TxResult>, ClientError>)> {
+        stream::iter(
+            self.accounts
+                .values()
+                .filter(|key| key.pubkey != treasury)
+                .cloned(),
+        )
+        .map(|key| async move {
+            let result = self.sweep_account(block_builder_url, key, treasury).await;
+            (key.pubkey, result)
+        })
+        .buffer_unordered(self.concurrency())
+        .collect()
+        .await
+    }
+
+    async fn sweep_account(
+        &self,
+        block_builder_url: &str,
+        key: KeySet,
+        treasury: U256,
+    ) -> Result<Option<TxResult>, ClientError> {
+        self.client.sync(key).await?;
+        let (user_data, _) = self.client.get_user_data_and_digest(key).await?;
+        let mut balances = user_data
+            .balances()
+            .0
+            .into_iter()
+            .filter(|(_, leaf)| !leaf.is_insufficient && leaf.amount != U256::default())
+            .collect::<Vec<_>>();
+        balances.sort_by_key(|(token_index, _)| *token_index);
+        let transfers = balances
+            .into_iter()
+            .take(NUM_TRANSFERS_IN_TX)
+            .map(|(token_index, leaf)| Transfer {
+                recipient: GenericAddress::from_pubkey(treasury),
+                amount: leaf.amount,
+                token_index,
+                salt: generate_salt(),
+            })
+            .collect::<Vec<_>>();
+        if transfers.is_empty() {
+            return Ok(None);
+        }
+
+        let memo = self
+            .client
+            .send_tx_request(block_builder_url, key, transfers, vec![])
+            .await?;
+        let proposal = self
+            .client
+            .wait_for_proposal(block_builder_url, key, memo.is_registration_block, memo.tx)
+            .await?;
+        let result = self
+            .client
+            .finalize_tx(block_builder_url, key, &memo, &proposal)
+            .await?;
+        Ok(Some(result))
+    }
+
+    fn concurrency(&self) -> usize {
+        self.config.concurrency.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::{
+        api::store_vault_server::interface::DataType,
+        data::{
+            deposit_data::{DepositData, TokenType},
+            user_data::UserData,
+        },
+    };
+    use intmax2_zkp::{
+        common::{
+            deposit::get_pubkey_salt_hash,
+            signature::key_set::KeySet,
+            trees::{asset_tree::AssetLeaf, transfer_tree::TransferTree},
+            tx::Tx,
+        },
+        constants::TRANSFER_TREE_HEIGHT,
+        ethereum_types::{address::Address, u256::U256, u32limb_trait::U32LimbTrait as _},
+    };
+
+    use crate::client::{
+        sync::utils::generate_salt,
+        test_utils::{
+            mock_client, MockBalanceProver, MockBlockBuilder, MockStoreVault, MockValidityProver,
+            MockWithdrawalServer,
+        },
+    };
+
+    use super::{AccountManager, AccountManagerConfig};
+
+    fn amount(value: u32) -> U256 {
+        U256::from_u32_slice(&[0, 0, 0, 0, 0, 0, 0, value])
+    }
+
+    fn save_balances(store_vault: &MockStoreVault, key: KeySet, leaves: &[(u32, AssetLeaf)]) {
+        let mut user_data = UserData::new(key.pubkey);
+        for (token_index, leaf) in leaves {
+            user_data
+                .full_private_state
+                .asset_tree
+                .update(*token_index as u64, *leaf);
+        }
+        store_vault
+            .user_data
+            .borrow_mut()
+            .insert(key.pubkey, user_data.encrypt(key.pubkey));
+    }
+
+    fn manager(
+        store_vault: MockStoreVault,
+    ) -> AccountManager<
+        MockBlockBuilder,
+        MockStoreVault,
+        MockValidityProver,
+        MockBalanceProver,
+        MockWithdrawalServer,
+    > {
+        let client = mock_client(store_vault, MockValidityProver::default());
+        AccountManager::new(client, AccountManagerConfig { concurrency: 2 })
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_balances() {
+        let mut rng = rand::thread_rng();
+        let key1 = KeySet::rand(&mut rng);
+        let key2 = KeySet::rand(&mut rng);
+        let store_vault = MockStoreVault::default();
+        let leaf = |value: u32| AssetLeaf {
+            is_insufficient: false,
+            amount: amount(value),
+        };
+        save_balances(&store_vault, key1, &[(0, leaf(10)), (1, leaf(3))]);
+        save_balances(
+            &store_vault,
+            key2,
+            &[
+                (0, leaf(5)),
+                (
+                    1,
+                    AssetLeaf {
+                        is_insufficient: true,
+                        amount: amount(0),
+                    },
+                ),
+            ],
+        );
+        let mut manager = manager(store_vault);
+        manager.add_account(key1);
+        manager.add_account(key2);
+
+        let summaries = manager.aggregate_balances().await.unwrap();
+        let eth = &summaries[&0];
+        assert_eq!(eth.total, amount(15));
+        assert_eq!(eth.accounts.len(), 2);
+        assert!(eth.insufficient_accounts.is_empty());
+        let token = &summaries[&1];
+        assert_eq!(token.total, amount(3));
+        assert_eq!(token.accounts, vec![(key1.pubkey, amount(3))]);
+        assert_eq!(token.insufficient_accounts, vec![key2.pubkey]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_isolates_failures() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let broken_key = KeySet::rand(&mut rng);
+        let store_vault = MockStoreVault::default();
+        // user data that cannot be decrypted
+        store_vault
+            .user_data
+            .borrow_mut()
+            .insert(broken_key.pubkey, vec![0; 64]);
+        let mut manager = manager(store_vault);
+        manager.add_account(key);
+        manager.add_account(broken_key);
+
+        let results = manager.sync_all().await;
+        assert_eq!(results.len(), 2);
+        for (pubkey, result) in results {
+            assert_eq!(result.is_ok(), pubkey == key.pubkey);
+        }
+        assert!(manager
+            .client
+            .store_vault_server
+            .user_data
+            .borrow()
+            .contains_key(&key.pubkey));
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_shares_token_lookups() {
+        let mut rng = rand::thread_rng();
+        let key1 = KeySet::rand(&mut rng);
+        let key2 = KeySet::rand(&mut rng);
+        let now = chrono::Utc::now().timestamp() as u64;
+        let store_vault = MockStoreVault::default();
+        let token_address = Address::rand(&mut rng);
+        for (key, uuid) in [(key1, "deposit1"), (key2, "deposit2")] {
+            let deposit_salt = generate_salt();
+            let deposit_data = DepositData {
+                deposit_salt,
+                depositor: Address::rand(&mut rng),
+                pubkey_salt_hash: get_pubkey_salt_hash(key.pubkey, deposit_salt),
+                amount: amount(1),
+                is_eligible: true,
+                token_type: TokenType::ERC20,
+                token_address,
+                token_id: U256::default(),
+                token_index: None,
+            };
+            store_vault.push_entry(
+                DataType::Deposit,
+                key.pubkey,
+                uuid,
+                now - 10,
+                deposit_data.encrypt(key.pubkey),
+            );
+        }
+        let mut manager = manager(store_vault);
+        manager.add_account(key1);
+        manager.add_account(key2);
+
+        // The rpc is unreachable, so both syncs resolve the token index from the entry cached
+        // once for the shared client.
+        manager.client.liquidity_contract.cache_token(
+            3,
+            TokenType::ERC20,
+            token_address,
+            U256::default(),
+        );
+        let results = manager.sync_all().await;
+        assert_eq!(results.len(), 2);
+        for (_, result) in results {
+            let pending = result.unwrap();
+            assert_eq!(pending.pending_deposits.len(), 1);
+            assert_eq!(pending.pending_deposits[0].1.token_index, Some(3));
+        }
+        assert_eq!(
+            manager.get_token_info(3).await.unwrap(),
+            (TokenType::ERC20, token_address, U256::default())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_proposal_gives_up_after_limit() {
+        let mut manager = manager(MockStoreVault::default());
+        manager.client.config.block_builder_query_wait_time = 0;
+        manager.client.config.block_builder_query_interval = 0;
+        manager.client.config.block_builder_query_limit = 2;
+        *manager.client.block_builder.proposal_delay.borrow_mut() = 10;
+
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let tx = Tx {
+            transfer_tree_root: TransferTree::new(TRANSFER_TREE_HEIGHT).get_root(),
+            nonce: 0,
+        };
+        assert!(manager
+            .client
+            .wait_for_proposal("http://block-builder", key, false, tx)
+            .await
+            .is_err());
+        // the first query and `block_builder_query_limit` retries
+        assert_eq!(*manager.client.block_builder.proposal_delay.borrow(), 7);
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashSet;
+
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        error::ErrorCode,
+        store_vault_server::{
+            interface::{DataType, SaveDataEntry, StoreVaultClientInterface},
+            types::DataWithMetaData,
+        },
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    data::{
+        deposit_data::DepositData, error::DataError, meta_data::MetaData,
+        processed_uuids::ProcessedUuids, sender_proof_set::SenderProofSet,
+        transfer_data::TransferData, tx_data::TxData, wallet_backup::WalletBackup,
+    },
+};
+use intmax2_zkp::{common::signature::key_set::KeySet, ethereum_types::u256::U256};
+use num_bigint::BigUint;
+
+use super::{client::Client, error::ClientError};
+
+// Number of entries saved in a single request on import
+const IMPORT_BATCH_SIZE: usize = 32;
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Export the user data, all records and the sender proof sets of the account as a single
+    /// archive encrypted for the account's pubkey. Entries already garbage collected by the store
+    /// vault are not included.
+    pub async fn export_backup(&self, key: KeySet) -> Result<Vec<u8>, ClientError> {
+        let (user_data, _) = self.get_user_data_and_digest(key).await?;
+
+        let deposits = self
+            .fetch_decrypted(DataType::Deposit, key, |data| {
+                DepositData::decrypt(data, key)
+            })
+            .await?;
+        let transfers = self
+            .fetch_decrypted(DataType::Transfer, key, |data| {
+                TransferData::decrypt(data, key)
+            })
+            .await?;
+        let txs = self
+            .fetch_decrypted(DataType::Tx, key, |data| TxData::decrypt(data, key))
+            .await?;
+        let withdrawals = self
+            .fetch_decrypted(DataType::Withdrawal, key, |data| {
+                TransferData::decrypt(data, key)
+            })
+            .await?;
+
+        let mut seen = HashSet::new();
+        let ephemeral_keys: Vec<U256> = transfers
+            .iter()
+            .chain(withdrawals.iter())
+            .map(|(_, data)| data.sender_proof_set_ephemeral_key)
+            .chain(
+                txs.iter()
+                    .map(|(_, data)| data.sender_proof_set_ephemeral_key),
+            )
+            .filter(|ephemeral_key| seen.insert(*ephemeral_key))
+            .collect();
+        let mut sender_proof_sets = Vec::new();
+        for ephemeral_privkey in ephemeral_keys {
+            let ephemeral_key = KeySet::new(BigUint::from(ephemeral_privkey).into());
+            let encrypted = match self
+                .store_vault_server
+                .get_sender_proof_set(ephemeral_key)
+                .await
+            {
+                Ok(encrypted) => encrypted,
+                Err(e) => {
+                    log::error!("failed to get sender proof set: {}", e);
+                    continue;
+                }
+            };
+            match SenderProofSet::decrypt(&encrypted, ephemeral_key) {
+                Ok(sender_proof_set) => {
+                    sender_proof_sets.push((ephemeral_privkey, sender_proof_set));
+                }
+                Err(e) => {
+                    log::error!("failed to decrypt sender proof set: {}", e);
+                }
+            }
+        }
+
+        let backup = WalletBackup {
+            pubkey: key.pubkey,
+            created_at: chrono::Utc::now().timestamp() as u64,
+            user_data,
+            deposits,
+            transfers,
+            txs,
+            withdrawals,
+            sender_proof_sets,
+        };
+        Ok(backup.encrypt(key.pubkey))
+    }
+
+    /// Re-seed a store vault server that has no user data for the account from an archive
+    /// produced by `export_backup`.
+    ///
+    /// The store vault assigns new uuids and timestamps to the records, so the processed uuids
+    /// and lpts of the user data are rewritten accordingly.
+    pub async fn import_backup(&self, key: KeySet, encrypted: &[u8]) -> Result<(), ClientError> {
+        let backup = WalletBackup::decrypt(encrypted, key)
+            .map_err(|e| ClientError::BackupError(e.to_string()))?;
+        // a backup can be imported into an account whose data has been deleted
+        match self.store_vault_server.get_user_data(key).await {
+            Ok(None) => {}
+            Err(e) if e.code() == Some(ErrorCode::AccountDeleted) => {}
+            Ok(Some(_)) => {
+                return Err(ClientError::BackupError(
+                    "user data already exists in the store vault".to_string(),
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        }
+        let import_start = chrono::Utc::now().timestamp() as u64;
+
+        for (ephemeral_privkey, sender_proof_set) in backup.sender_proof_sets.iter() {
+            let ephemeral_key = KeySet::new(BigUint::from(*ephemeral_privkey).into());
+            self.store_vault_server
+                .save_sender_proof_set(
+                    ephemeral_key,
+                    &sender_proof_set.encrypt(ephemeral_key.pubkey),
+                    // the transfers were acknowledged to the previous server
+                    None,
+                )
+                .await?;
+        }
+
+        let mut user_data = backup.user_data.clone();
+        let deposits = backup
+            .deposits
+            .iter()
+            .map(|(meta, data)| (meta, data.encrypt(key.pubkey)))
+            .collect::<Vec<_>>();
+        user_data.processed_deposit_uuids = self
+            .restore_records(
+                key,
+                DataType::Deposit,
+                &deposits,
+                &backup.user_data.processed_deposit_uuids,
+                backup.user_data.deposit_lpt,
+                import_start,
+            )
+            .await?;
+        let transfers = backup
+            .transfers
+            .iter()
+            .map(|(meta, data)| (meta, data.encrypt(key.pubkey)))
+            .collect::<Vec<_>>();
+        user_data.processed_transfer_uuids = self
+            .restore_records(
+                key,
+                DataType::Transfer,
+                &transfers,
+                &backup.user_data.processed_transfer_uuids,
+                backup.user_data.transfer_lpt,
+                import_start,
+            )
+            .await?;
+        let txs = backup
+            .txs
+            .iter()
+            .map(|(meta, data)| (meta, data.encrypt(key.pubkey)))
+            .collect::<Vec<_>>();
+        user_data.processed_tx_uuids = self
+            .restore_records(
+                key,
+                DataType::Tx,
+                &txs,
+                &backup.user_data.processed_tx_uuids,
+                backup.user_data.tx_lpt,
+                import_start,
+            )
+            .await?;
+        let withdrawals = backup
+            .withdrawals
+            .iter()
+            .map(|(meta, data)| (meta, data.encrypt(key.pubkey)))
+            .collect::<Vec<_>>();
+        user_data.processed_withdrawal_uuids = self
+            .restore_records(
+                key,
+                DataType::Withdrawal,
+                &withdrawals,
+                &backup.user_data.processed_withdrawal_uuids,
+                backup.user_data.withdrawal_lpt,
+                import_start,
+            )
+            .await?;
+
+        // All restored records have timestamps not older than import_start
+        let lpt = import_start.saturating_sub(1);
+        user_data.deposit_lpt = lpt;
+        user_data.transfer_lpt = lpt;
+        user_data.tx_lpt = lpt;
+        user_data.withdrawal_lpt = lpt;
+
+        self.store_vault_server
+            .save_user_data(key, None, &user_data.encrypt(key.pubkey))
+            .await?;
+        Ok(())
+    }
+
+    pub(super) async fn fetch_decrypted<T, F>(
+        &self,
+        data_type: DataType,
+        key: KeySet,
+        decrypt: F,
+    ) -> Result<Vec<(MetaData, T)>, ClientError>
+    where
+        F: Fn(&[u8]) -> Result<T, DataError>,
+    {
+        let data_with_meta = self
+            .store_vault_server
+            .get_data_all_after(data_type, key, 0)
+            .await?;
+        let mut records = Vec::new();
+        for DataWithMetaData { meta, data } in data_with_meta {
+            match decrypt(&data) {
+                Ok(data) => records.push((meta, data)),
+                Err(e) => {
+                    log::error!("failed to decrypt {} data {}: {}", data_type, meta.uuid, e);
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Save the records and return the processed uuids rewritten to the new uuids. The skipped
+    /// uuids are carried over as well.
+    async fn restore_records(
+        &self,
+        key: KeySet,
+        data_type: DataType,
+        records: &[(&MetaData, Vec<u8>)],
+        processed: &ProcessedUuids,
+        lpt: u64,
+        import_start: u64,
+    ) -> Result<ProcessedUuids, ClientError> {
+        let mut processed_new_uuids = HashSet::new();
+        let mut skipped_new_uuids = HashSet::new();
+        for chunk in records.chunks(IMPORT_BATCH_SIZE) {
+            let entries = chunk
+                .iter()
+                .map(|(_, encrypted_data)| SaveDataEntry {
+                    data_type,
+                    pubkey: key.pubkey,
+                    encrypted_data: encrypted_data.clone(),
+                    pow_nonce: None,
+                })
+                .collect::<Vec<_>>();
+            let uuids = self
+                .store_vault_server
+                .save_data_batch(key, &entries)
+                .await?;
+            for ((meta, _), uuid) in chunk.iter().zip(uuids) {
+                if processed.is_skipped(&meta.uuid) {
+                    skipped_new_uuids.insert(uuid);
+                } else if processed.is_processed(meta, lpt) {
+                    processed_new_uuids.insert(uuid);
+                }
+            }
+        }
+
+        // the timestamps are assigned by the store vault server
+        let saved = self
+            .store_vault_server
+            .get_data_all_after(data_type, key, import_start)
+            .await?;
+        let mut new_processed = ProcessedUuids::new();
+        for DataWithMetaData { meta, .. } in saved {
+            if skipped_new_uuids.contains(&meta.uuid) {
+                new_processed.skip(&meta);
+            } else if processed_new_uuids.contains(&meta.uuid) {
+                new_processed.insert(&meta);
+            }
+        }
+        Ok(new_processed)
+    }
+}
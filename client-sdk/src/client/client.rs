@@ -1,3 +1,4 @@
+use ethers::types::H256;
 use intmax2_interfaces::{
     api::{
         balance_prover::interface::BalanceProverClientInterface,
@@ -10,7 +11,7 @@ use intmax2_interfaces::{
         deposit_data::{DepositData, TokenType},
         proof_compression::{CompressedBalanceProof, CompressedSpentProof},
         sender_proof_set::SenderProofSet,
-        transfer_data::TransferData,
+        transfer_data::{validate_memo, TransferData},
         tx_data::TxData,
     },
 };
@@ -28,7 +29,7 @@ use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    client::sync::utils::generate_salt,
+    client::{strategy::deposit::fetch_deposit_by_uuid, sync::utils::generate_salt},
     external_api::{
         contract::{liquidity_contract::LiquidityContract, rollup_contract::RollupContract},
         utils::time::sleep_for,
@@ -69,6 +70,9 @@ pub struct TxRequestMemo {
     pub transfers: Vec<Transfer>,
     pub spent_witness: SpentWitness,
     pub sender_proof_set_ephemeral_key: U256,
+    /// Memos of the transfers, in the same order as the transfers
+    #[serde(default)]
+    pub transfer_memos: Vec<Option<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -131,6 +135,7 @@ where
             data_type: DataType::Deposit,
             pubkey,
             encrypted_data: deposit_data.encrypt(pubkey),
+            pow_nonce: None,
         };
         let ephemeral_key = KeySet::rand(&mut rand::thread_rng());
         let uuids = self
@@ -151,12 +156,82 @@ where
         Ok(result)
     }
 
-    /// Send a transaction request to the block builder
+    /// Cancel a deposit that will never be relayed to the rollup, refunding it to the depositor.
+    /// `signer_private_key` must be the key of the depositor, and `from_block` is the L1 block
+    /// from which the deposit event is searched.
+    pub async fn cancel_deposit(
+        &self,
+        signer_private_key: H256,
+        key: KeySet,
+        deposit_uuid: &str,
+        from_block: u64,
+    ) -> Result<(), ClientError> {
+        let (_, mut deposit_data) = fetch_deposit_by_uuid(
+            &self.store_vault_server,
+            &self.liquidity_contract,
+            key,
+            deposit_uuid,
+        )
+        .await?
+        .ok_or(ClientError::DepositCancelError(format!(
+            "deposit {} not found",
+            deposit_uuid
+        )))?;
+        let event = self
+            .liquidity_contract
+            .get_deposited_event(
+                deposit_data.depositor,
+                deposit_data.pubkey_salt_hash,
+                from_block,
+            )
+            .await?
+            .ok_or(ClientError::DepositCancelError(
+                "deposit event not found".to_string(),
+            ))?;
+        // The deposit is cancelled with the token index and amount of the event, since the token
+        // of a rejected deposit may be unknown to the backup.
+        deposit_data.token_index = Some(event.token_index);
+        deposit_data.amount = event.amount;
+
+        // eligibility is determined by the contract on deposit, so it may differ from the backup
+        let onchain_hash = self
+            .liquidity_contract
+            .get_deposit_data_hash(event.deposit_id)
+            .await?;
+        if deposit_data.deposit_hash() != Some(onchain_hash) {
+            deposit_data.is_eligible = !deposit_data.is_eligible;
+            if deposit_data.deposit_hash() != Some(onchain_hash) {
+                return Err(ClientError::DepositCancelError(
+                    "deposit does not match the contract".to_string(),
+                ));
+            }
+        }
+        if self
+            .validity_prover
+            .get_deposit_info(onchain_hash)
+            .await?
+            .is_some()
+        {
+            return Err(ClientError::DepositCancelError(
+                "deposit is already relayed".to_string(),
+            ));
+        }
+        self.liquidity_contract
+            .cancel_deposit(signer_private_key, event.deposit_id, &deposit_data)
+            .await?;
+        Ok(())
+    }
+
+    /// Send a transaction request to the block builder.
+    ///
+    /// `transfer_memos` is either empty or has a memo for each transfer, which is delivered
+    /// encrypted to the recipient on `finalize_tx`.
     pub async fn send_tx_request(
         &self,
         block_builder_url: &str,
         key: KeySet,
         transfers: Vec<Transfer>,
+        transfer_memos: Vec<Option<String>>,
     ) -> Result<TxRequestMemo, ClientError> {
         // input validation
         if transfers.is_empty() {
@@ -169,6 +244,14 @@ where
                 "transfers is too long".to_string(),
             ));
         }
+        if !transfer_memos.is_empty() && transfer_memos.len() != transfers.len() {
+            return Err(ClientError::TransferLenError(
+                "number of memos does not match the transfers".to_string(),
+            ));
+        }
+        for memo in &transfer_memos {
+            validate_memo(memo.as_deref()).map_err(|e| ClientError::InvalidMemo(e.to_string()))?;
+        }
 
         // sync balance proof
         self.sync(key).await?;
@@ -219,6 +302,7 @@ where
             .save_sender_proof_set(
                 ephemeral_key,
                 &sender_proof_set.encrypt(ephemeral_key.pubkey),
+                Some(transfers.len() as u32),
             )
             .await?;
         let sender_proof_set_ephemeral_key: U256 =
@@ -266,6 +350,7 @@ where
             transfers,
             spent_witness,
             sender_proof_set_ephemeral_key,
+            transfer_memos,
         };
         Ok(memo)
     }
@@ -284,6 +369,39 @@ where
         Ok(proposal)
     }
 
+    /// Query the proposal of the tx until the block builder builds the block. Waits
+    /// `block_builder_query_wait_time` seconds first, and then retries every
+    /// `block_builder_query_interval` seconds up to `block_builder_query_limit` times.
+    pub async fn wait_for_proposal(
+        &self,
+        block_builder_url: &str,
+        key: KeySet,
+        is_registration_block: bool,
+        tx: Tx,
+    ) -> Result<BlockProposal, ClientError> {
+        sleep_for(self.config.block_builder_query_wait_time).await;
+        let mut tries = 0;
+        loop {
+            let proposal = self
+                .query_proposal(block_builder_url, key, is_registration_block, tx)
+                .await?;
+            if let Some(proposal) = proposal {
+                return Ok(proposal);
+            }
+            if tries >= self.config.block_builder_query_limit {
+                return Err(ClientError::SendTxRequestError(
+                    "failed to get block proposal".to_string(),
+                ));
+            }
+            tries += 1;
+            log::info!(
+                "Failed to get proposal, retrying in {} seconds",
+                self.config.block_builder_query_interval
+            );
+            sleep_for(self.config.block_builder_query_interval).await;
+        }
+    }
+
     /// Verify the proposal, and send the signature to the block builder
     pub async fn finalize_tx(
         &self,
@@ -297,59 +415,7 @@ where
             .verify(memo.tx)
             .map_err(|e| ClientError::InvalidBlockProposal(format!("{}", e)))?;
 
-        let mut entries = vec![];
-
-        let tx_data = TxData {
-            tx_index: proposal.tx_index,
-            tx_merkle_proof: proposal.tx_merkle_proof.clone(),
-            tx_tree_root: proposal.tx_tree_root,
-            spent_witness: memo.spent_witness.clone(),
-            sender_proof_set_ephemeral_key: memo.sender_proof_set_ephemeral_key,
-        };
-
-        entries.push(SaveDataEntry {
-            data_type: DataType::Tx,
-            pubkey: key.pubkey,
-            encrypted_data: tx_data.encrypt(key.pubkey),
-        });
-
-        // save transfer data
-        let mut transfer_tree = TransferTree::new(TRANSFER_TREE_HEIGHT);
-        for transfer in &memo.transfers {
-            transfer_tree.push(*transfer);
-        }
-
-        for (i, transfer) in memo.transfers.iter().enumerate() {
-            let transfer_merkle_proof = transfer_tree.prove(i as u64);
-            let transfer_data = TransferData {
-                sender: key.pubkey,
-                transfer: *transfer,
-                transfer_index: i as u32,
-                transfer_merkle_proof,
-                sender_proof_set_ephemeral_key: memo.sender_proof_set_ephemeral_key,
-                sender_proof_set: None,
-                tx: memo.tx,
-                tx_index: proposal.tx_index,
-                tx_merkle_proof: proposal.tx_merkle_proof.clone(),
-                tx_tree_root: proposal.tx_tree_root,
-            };
-            let data_type = if transfer.recipient.is_pubkey {
-                DataType::Transfer
-            } else {
-                DataType::Withdrawal
-            };
-            let pubkey = if transfer.recipient.is_pubkey {
-                transfer.recipient.to_pubkey().unwrap()
-            } else {
-                key.pubkey
-            };
-            entries.push(SaveDataEntry {
-                data_type,
-                pubkey,
-                encrypted_data: transfer_data.encrypt(pubkey),
-            });
-        }
-
+        let entries = finalize_entries(key.pubkey, memo, proposal);
         let uuids = self
             .store_vault_server
             .save_data_batch(key, &entries)
@@ -367,35 +433,7 @@ where
             )
             .await?;
 
-        let transfer_uuids = uuids
-            .iter()
-            .zip(entries.iter())
-            .filter_map(|(uuid, entry)| {
-                if entry.data_type == DataType::Transfer {
-                    Some(uuid.clone())
-                } else {
-                    None
-                }
-            })
-            .collect();
-        let withdrawal_uuids = uuids
-            .iter()
-            .zip(entries.iter())
-            .filter_map(|(uuid, entry)| {
-                if entry.data_type == DataType::Withdrawal {
-                    Some(uuid.clone())
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        let result = TxResult {
-            tx_tree_root: proposal.tx_tree_root,
-            transfer_uuids,
-            withdrawal_uuids,
-        };
-
+        let result = tx_result(proposal, &uuids, &entries);
         Ok(result)
     }
 
@@ -411,3 +449,92 @@ where
         fetch_history(self, key).await
     }
 }
+
+/// Entries of the tx, transfer and withdrawal data to be saved when finalizing the tx
+pub(crate) fn finalize_entries(
+    pubkey: U256,
+    memo: &TxRequestMemo,
+    proposal: &BlockProposal,
+) -> Vec<SaveDataEntry> {
+    let mut entries = vec![];
+
+    let tx_data = TxData {
+        tx_index: proposal.tx_index,
+        tx_merkle_proof: proposal.tx_merkle_proof.clone(),
+        tx_tree_root: proposal.tx_tree_root,
+        spent_witness: memo.spent_witness.clone(),
+        sender_proof_set_ephemeral_key: memo.sender_proof_set_ephemeral_key,
+    };
+
+    entries.push(SaveDataEntry {
+        data_type: DataType::Tx,
+        pubkey,
+        encrypted_data: tx_data.encrypt(pubkey),
+        pow_nonce: None,
+    });
+
+    // save transfer data
+    let mut transfer_tree = TransferTree::new(TRANSFER_TREE_HEIGHT);
+    for transfer in &memo.transfers {
+        transfer_tree.push(*transfer);
+    }
+
+    for (i, transfer) in memo.transfers.iter().enumerate() {
+        let transfer_merkle_proof = transfer_tree.prove(i as u64);
+        let transfer_data = TransferData {
+            sender: pubkey,
+            transfer: *transfer,
+            transfer_index: i as u32,
+            transfer_merkle_proof,
+            sender_proof_set_ephemeral_key: memo.sender_proof_set_ephemeral_key,
+            sender_proof_set: None,
+            tx: memo.tx,
+            tx_index: proposal.tx_index,
+            tx_merkle_proof: proposal.tx_merkle_proof.clone(),
+            tx_tree_root: proposal.tx_tree_root,
+            memo: memo.transfer_memos.get(i).cloned().flatten(),
+        };
+        let data_type = if transfer.recipient.is_pubkey {
+            DataType::Transfer
+        } else {
+            DataType::Withdrawal
+        };
+        let recipient = if transfer.recipient.is_pubkey {
+            transfer.recipient.to_pubkey().unwrap()
+        } else {
+            pubkey
+        };
+        entries.push(SaveDataEntry {
+            data_type,
+            pubkey: recipient,
+            encrypted_data: transfer_data.encrypt(recipient),
+            pow_nonce: None,
+        });
+    }
+    entries
+}
+
+pub(crate) fn tx_result(
+    proposal: &BlockProposal,
+    uuids: &[String],
+    entries: &[SaveDataEntry],
+) -> TxResult {
+    let uuids_of = |data_type: DataType| {
+        uuids
+            .iter()
+            .zip(entries.iter())
+            .filter_map(|(uuid, entry)| {
+                if entry.data_type == data_type {
+                    Some(uuid.clone())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    };
+    TxResult {
+        tx_tree_root: proposal.tx_tree_root,
+        transfer_uuids: uuids_of(DataType::Transfer),
+        withdrawal_uuids: uuids_of(DataType::Withdrawal),
+    }
+}
@@ -7,6 +7,11 @@ pub struct ClientConfig {
     pub tx_timeout: u64,
     pub block_builder_request_interval: u64,
     pub block_builder_request_limit: u64,
+
+    /// Seconds to wait after sending a tx request before querying the proposal
+    pub block_builder_query_wait_time: u64,
+    pub block_builder_query_interval: u64,
+    pub block_builder_query_limit: u64,
 }
 
 impl Default for ClientConfig {
@@ -16,6 +21,9 @@ impl Default for ClientConfig {
             tx_timeout: 60,
             block_builder_request_interval: 5,
             block_builder_request_limit: 10,
+            block_builder_query_wait_time: 5,
+            block_builder_query_interval: 5,
+            block_builder_query_limit: 20,
         }
     }
 }
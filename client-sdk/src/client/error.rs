@@ -30,12 +30,36 @@ pub enum ClientError {
     #[error("Invalid transfer len: {0}")]
     TransferLenError(String),
 
+    #[error("Invalid memo: {0}")]
+    InvalidMemo(String),
+
     #[error("Cannot send tx by zero balance account")]
     CannotSendTxByZeroBalanceAccount,
 
     #[error("Invalid block proposal: {0}")]
     InvalidBlockProposal(String),
 
+    #[error("Deposit cancel error: {0}")]
+    DepositCancelError(String),
+
+    #[error("Invalid invoice: {0}")]
+    InvalidInvoice(String),
+
+    #[error("Invalid receipt: {0}")]
+    InvalidReceipt(String),
+
+    #[error("Threshold signature error: {0}")]
+    ThresholdSignatureError(String),
+
+    #[error("Backup error: {0}")]
+    BackupError(String),
+
+    #[error("Recovery error: {0}")]
+    RecoveryError(String),
+
+    #[error("User data version error: {0}")]
+    UserDataVersionError(String),
+
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
@@ -6,7 +6,10 @@ use intmax2_interfaces::{
         validity_prover::interface::ValidityProverClientInterface,
         withdrawal_server::interface::WithdrawalServerClientInterface,
     },
-    data::{deposit_data::TokenType, meta_data::MetaData, tx_data::TxData},
+    data::{
+        deposit_data::TokenType, meta_data::MetaData, processed_uuids::ProcessedUuids,
+        tx_data::TxData,
+    },
 };
 use intmax2_zkp::{
     common::signature::key_set::KeySet,
@@ -17,9 +20,15 @@ use serde::{Deserialize, Serialize};
 use super::{
     client::Client,
     error::ClientError,
-    strategy::{deposit::fetch_deposit_info, transfer::fetch_transfer_info, tx::fetch_tx_info},
+    strategy::{
+        deposit::{fetch_deposit_info, DepositRejectionReason},
+        transfer::fetch_transfer_info,
+        tx::fetch_tx_info,
+    },
 };
 
+const TRANSFER_TIMEOUT_REASON: &str = "tx is not included within the tx timeout";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum HistoryEntry {
@@ -34,6 +43,8 @@ pub enum HistoryEntry {
         pubkey_salt_hash: Bytes32,
         is_included: bool,
         is_rejected: bool,
+        #[serde(default)]
+        rejection_reason: Option<DepositRejectionReason>,
         meta: MetaData,
     },
     Receive {
@@ -42,6 +53,10 @@ pub enum HistoryEntry {
         from: U256,
         is_included: bool,
         is_rejected: bool,
+        #[serde(default)]
+        rejection_reason: Option<String>,
+        #[serde(default)]
+        memo: Option<String>,
         meta: MetaData,
     },
     Send {
@@ -117,12 +132,15 @@ pub async fn fetch_history<
         &client.validity_prover,
         &client.liquidity_contract,
         key,
-        0,   // set to 0 to get all deposits
-        &[], // no processed deposit uuids to get all deposits
+        0,                      // set to 0 to get all deposits
+        &ProcessedUuids::new(), // no processed deposit uuids to get all deposits
         client.config.deposit_timeout,
     )
     .await?;
     for (meta, settled) in all_deposit_info.settled {
+        // a deposit that timed out or had an unknown token at the time of the sync is skipped by
+        // the lpt, even if it has been relayed since then
+        let is_skipped = user_data.processed_deposit_uuids.is_skipped(&meta.uuid);
         history.push(HistoryEntry::Deposit {
             token_type: settled.token_type,
             token_address: settled.token_address,
@@ -132,8 +150,11 @@ pub async fn fetch_history<
             is_eligible: settled.is_eligible,
             depositor: settled.depositor,
             pubkey_salt_hash: settled.pubkey_salt_hash,
-            is_included: user_data.processed_deposit_uuids.contains(&meta.uuid),
-            is_rejected: false,
+            is_included: user_data
+                .processed_deposit_uuids
+                .is_included(&meta, user_data.deposit_lpt),
+            is_rejected: is_skipped,
+            rejection_reason: None,
             meta,
         });
     }
@@ -149,6 +170,7 @@ pub async fn fetch_history<
             pubkey_salt_hash: pending.pubkey_salt_hash,
             is_included: false,
             is_rejected: false,
+            rejection_reason: None,
             meta,
         });
     }
@@ -164,6 +186,23 @@ pub async fn fetch_history<
             depositor: timeout.depositor,
             is_included: false,
             is_rejected: true,
+            rejection_reason: Some(DepositRejectionReason::Timeout),
+            meta,
+        });
+    }
+    for (meta, unknown) in all_deposit_info.unknown_token {
+        history.push(HistoryEntry::Deposit {
+            token_type: unknown.token_type,
+            token_address: unknown.token_address,
+            token_id: unknown.token_id,
+            token_index: unknown.token_index,
+            amount: unknown.amount,
+            is_eligible: unknown.is_eligible,
+            pubkey_salt_hash: unknown.pubkey_salt_hash,
+            depositor: unknown.depositor,
+            is_included: false,
+            is_rejected: true,
+            rejection_reason: Some(DepositRejectionReason::UnknownToken),
             meta,
         });
     }
@@ -172,19 +211,34 @@ pub async fn fetch_history<
         &client.store_vault_server,
         &client.validity_prover,
         key,
-        0,   // set to 0 to get all transfers
-        &[], // no processed transfer uuids to get all transfers
+        0,                      // set to 0 to get all transfers
+        &ProcessedUuids::new(), // no processed transfer uuids to get all transfers
         client.config.tx_timeout,
     )
     .await?;
     for (meta, settled) in all_transfers_info.settled {
         let transfer = settled.transfer;
+        // a transfer with an invalid sender proof is passed by the lpt without being received
+        let rejected = user_data.rejected_transfer(&meta.uuid);
+        // a transfer that timed out at the time of the sync is skipped by the lpt, even if it has
+        // been settled since then
+        let is_skipped = user_data.processed_transfer_uuids.is_skipped(&meta.uuid);
+        let rejection_reason = match rejected {
+            Some(rejected) => Some(rejected.reason.clone()),
+            None if is_skipped => Some(TRANSFER_TIMEOUT_REASON.to_string()),
+            None => None,
+        };
         history.push(HistoryEntry::Receive {
             amount: transfer.amount,
             token_index: transfer.token_index,
-            from: transfer.recipient.data,
-            is_included: user_data.processed_transfer_uuids.contains(&meta.uuid),
-            is_rejected: false,
+            from: settled.sender,
+            memo: settled.memo.clone(),
+            is_included: rejected.is_none()
+                && user_data
+                    .processed_transfer_uuids
+                    .is_included(&meta, user_data.transfer_lpt),
+            is_rejected: rejection_reason.is_some(),
+            rejection_reason,
             meta: meta.clone(),
         });
     }
@@ -193,9 +247,11 @@ pub async fn fetch_history<
         history.push(HistoryEntry::Receive {
             amount: transfer.amount,
             token_index: transfer.token_index,
-            from: transfer.recipient.data,
+            from: pending.sender,
+            memo: pending.memo.clone(),
             is_included: false,
             is_rejected: false,
+            rejection_reason: None,
             meta: meta.clone(),
         });
     }
@@ -204,9 +260,11 @@ pub async fn fetch_history<
         history.push(HistoryEntry::Receive {
             amount: transfer.amount,
             token_index: transfer.token_index,
-            from: transfer.recipient.data,
+            from: timeout.sender,
+            memo: timeout.memo.clone(),
             is_included: false,
             is_rejected: true,
+            rejection_reason: Some(TRANSFER_TIMEOUT_REASON.to_string()),
             meta: meta.clone(),
         });
     }
@@ -215,15 +273,17 @@ pub async fn fetch_history<
         &client.store_vault_server,
         &client.validity_prover,
         key,
-        0,   // set to 0 to get all txs
-        &[], // no processed tx uuids to get all txs
+        0,                      // set to 0 to get all txs
+        &ProcessedUuids::new(), // no processed tx uuids to get all txs
         client.config.tx_timeout,
     )
     .await?;
     for (meta, settled) in all_tx_info.settled {
         history.push(HistoryEntry::Send {
             transfers: extract_generic_transfers(settled),
-            is_included: user_data.processed_tx_uuids.contains(&meta.uuid),
+            is_included: user_data
+                .processed_tx_uuids
+                .is_processed(&meta, user_data.tx_lpt),
             is_rejected: false,
             meta,
         });
@@ -0,0 +1,85 @@
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        store_vault_server::interface::StoreVaultClientInterface,
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    utils::invoice::{Invoice, InvoiceToken},
+};
+use intmax2_zkp::common::{signature::key_set::KeySet, transfer::Transfer};
+
+use super::{
+    client::{Client, TxResult},
+    error::ClientError,
+    sync::utils::generate_salt,
+};
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Resolve the token index of the invoice, looking it up in the liquidity contract if the
+    /// invoice specifies the token by its contract.
+    pub async fn resolve_invoice_token_index(&self, invoice: &Invoice) -> Result<u32, ClientError> {
+        match &invoice.token {
+            InvoiceToken::Index(token_index) => Ok(*token_index),
+            InvoiceToken::Contract {
+                token_type,
+                token_address,
+                token_id,
+            } => self
+                .liquidity_contract
+                .get_token_index(*token_type, *token_address, *token_id)
+                .await?
+                .ok_or(ClientError::InvalidInvoice(
+                    "token is not registered in the liquidity contract".to_string(),
+                )),
+        }
+    }
+
+    /// Pay the invoice by a single transfer, going through the whole send flow: sync, tx request,
+    /// waiting for the proposal, and finalization.
+    pub async fn pay_invoice(
+        &self,
+        block_builder_url: &str,
+        key: KeySet,
+        invoice: &Invoice,
+    ) -> Result<TxResult, ClientError> {
+        invoice
+            .validate()
+            .map_err(|e| ClientError::InvalidInvoice(e.to_string()))?;
+        if invoice.is_expired(chrono::Utc::now().timestamp() as u64) {
+            return Err(ClientError::InvalidInvoice(
+                "invoice is expired".to_string(),
+            ));
+        }
+        let token_index = self.resolve_invoice_token_index(invoice).await?;
+        let transfer = Transfer {
+            recipient: invoice.recipient,
+            token_index,
+            amount: invoice.amount,
+            salt: generate_salt(),
+        };
+
+        // `send_tx_request` syncs the balance proof first
+        let memo = self
+            .send_tx_request(
+                block_builder_url,
+                key,
+                vec![transfer],
+                vec![invoice.memo.clone()],
+            )
+            .await?;
+        let proposal = self
+            .wait_for_proposal(block_builder_url, key, memo.is_registration_block, memo.tx)
+            .await?;
+        self.finalize_tx(block_builder_url, key, &memo, &proposal)
+            .await
+    }
+}
@@ -1,8 +1,19 @@
+pub mod account_deletion;
+pub mod account_manager;
+pub mod backup;
 #[allow(clippy::module_inception)]
 pub mod client;
 pub mod config;
 pub mod error;
 pub mod history;
+pub mod invoice;
 pub mod key_from_eth;
+pub mod receipt;
+pub mod recover;
 pub mod strategy;
+pub mod subscription;
 pub mod sync;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod threshold;
+pub mod user_data_history;
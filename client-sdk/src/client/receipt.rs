@@ -0,0 +1,352 @@
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        store_vault_server::{
+            interface::{DataType, StoreVaultClientInterface},
+            types::DataWithMetaData,
+        },
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    data::tx_data::TxData,
+};
+use intmax2_zkp::{
+    common::{
+        signature::key_set::KeySet,
+        transfer::Transfer,
+        trees::{
+            sender_tree::SenderLeaf, transfer_tree::TransferMerkleProof, tx_tree::TxMerkleProof,
+        },
+        tx::Tx,
+    },
+    constants::NUM_TRANSFERS_IN_TX,
+    ethereum_types::{bytes32::Bytes32, u256::U256},
+    utils::poseidon_hash_out::PoseidonHashOut,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{client::Client, error::ClientError, sync::utils::generate_transfer_tree};
+
+/// Evidence that a transfer was included in a valid block, signed by its sender.
+///
+/// The receipt only contains the tx of the transfer and merkle proofs, whose siblings are hashes,
+/// so it does not reveal the other transfers of the tx or any other tx of the sender.
+/// Note that it does not prove that the sender had enough balance for the transfer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentReceipt {
+    pub sender: U256,
+    pub transfer: Transfer,
+    pub transfer_index: u32,
+    pub transfer_merkle_proof: TransferMerkleProof,
+    pub tx: Tx,
+    pub tx_index: u32,
+    pub tx_merkle_proof: TxMerkleProof,
+    pub tx_tree_root: Bytes32,
+    pub block_number: u32,
+    pub sender_leaf: SenderLeaf,
+}
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Create a receipt of the `transfer_index`-th transfer of the tx sent by the account, which
+    /// must be already included in a block.
+    pub async fn create_payment_receipt(
+        &self,
+        key: KeySet,
+        tx_uuid: &str,
+        transfer_index: u32,
+    ) -> Result<PaymentReceipt, ClientError> {
+        let data_with_meta = self
+            .store_vault_server
+            .get_data_batch(key, DataType::Tx, &[tx_uuid.to_string()])
+            .await?;
+        let DataWithMetaData { data, .. } =
+            data_with_meta
+                .into_iter()
+                .next()
+                .ok_or(ClientError::InvalidReceipt(format!(
+                    "tx {} not found",
+                    tx_uuid
+                )))?;
+        let tx_data =
+            TxData::decrypt(&data, key).map_err(|e| ClientError::InvalidReceipt(e.to_string()))?;
+
+        let transfers = &tx_data.spent_witness.transfers;
+        if transfer_index as usize >= transfers.len().min(NUM_TRANSFERS_IN_TX) {
+            return Err(ClientError::InvalidReceipt(format!(
+                "transfer index {} is out of range",
+                transfer_index
+            )));
+        }
+        let transfer = transfers[transfer_index as usize];
+        if transfer == Transfer::default() {
+            return Err(ClientError::InvalidReceipt(format!(
+                "transfer {} is a dummy transfer",
+                transfer_index
+            )));
+        }
+        let transfer_merkle_proof = generate_transfer_tree(transfers).prove(transfer_index as u64);
+
+        let block_number = self
+            .validity_prover
+            .get_block_number_by_tx_tree_root(tx_data.tx_tree_root)
+            .await?
+            .ok_or(ClientError::InvalidReceipt(
+                "tx is not included in a block yet".to_string(),
+            ))?;
+        let sender_leaf = self
+            .validity_prover
+            .get_sender_leaves(block_number)
+            .await?
+            .and_then(|leaves| leaves.get(tx_data.tx_index as usize).cloned())
+            .filter(|leaf| leaf.sender == key.pubkey)
+            .ok_or(ClientError::InvalidReceipt(
+                "sender leaf not found".to_string(),
+            ))?;
+
+        let receipt = PaymentReceipt {
+            sender: key.pubkey,
+            transfer,
+            transfer_index,
+            transfer_merkle_proof,
+            tx: tx_data.spent_witness.tx,
+            tx_index: tx_data.tx_index,
+            tx_merkle_proof: tx_data.tx_merkle_proof,
+            tx_tree_root: tx_data.tx_tree_root,
+            block_number,
+            sender_leaf,
+        };
+        // make sure that the receipt is acceptable before handing it out
+        verify_receipt(&self.validity_prover, &receipt).await?;
+        Ok(receipt)
+    }
+}
+
+/// Check the receipt against the block data of the validity prover.
+pub async fn verify_receipt<V: ValidityProverClientInterface>(
+    validity_prover: &V,
+    receipt: &PaymentReceipt,
+) -> Result<(), ClientError> {
+    // merkle proofs
+    receipt
+        .transfer_merkle_proof
+        .verify(
+            &receipt.transfer,
+            receipt.transfer_index as u64,
+            receipt.tx.transfer_tree_root,
+        )
+        .map_err(|_| ClientError::InvalidReceipt("invalid transfer merkle proof".to_string()))?;
+    let tx_tree_root: PoseidonHashOut = receipt
+        .tx_tree_root
+        .try_into()
+        .map_err(|_| ClientError::InvalidReceipt("invalid tx tree root".to_string()))?;
+    receipt
+        .tx_merkle_proof
+        .verify(&receipt.tx, receipt.tx_index as u64, tx_tree_root)
+        .map_err(|_| ClientError::InvalidReceipt("invalid tx merkle proof".to_string()))?;
+
+    // block data
+    let block_number = validity_prover
+        .get_block_number_by_tx_tree_root(receipt.tx_tree_root)
+        .await?;
+    if block_number != Some(receipt.block_number) {
+        return Err(ClientError::InvalidReceipt(format!(
+            "tx tree root is not included in block {}",
+            receipt.block_number
+        )));
+    }
+    let validity_pis = validity_prover
+        .get_validity_pis(receipt.block_number)
+        .await?
+        .ok_or(ClientError::InvalidReceipt(format!(
+            "validity public inputs not found for block {}",
+            receipt.block_number
+        )))?;
+    if !validity_pis.is_valid_block {
+        return Err(ClientError::InvalidReceipt(
+            "tx is included in an invalid block".to_string(),
+        ));
+    }
+    let sender_leaves = validity_prover
+        .get_sender_leaves(receipt.block_number)
+        .await?
+        .ok_or(ClientError::InvalidReceipt(format!(
+            "sender leaves not found for block {}",
+            receipt.block_number
+        )))?;
+    // the sender leaves are in the same order as the txs of the block
+    if receipt.sender_leaf.sender != receipt.sender
+        || sender_leaves.get(receipt.tx_index as usize) != Some(&receipt.sender_leaf)
+    {
+        return Err(ClientError::InvalidReceipt(
+            "sender leaf does not match the block".to_string(),
+        ));
+    }
+    if !receipt.sender_leaf.did_return_sig {
+        return Err(ClientError::InvalidReceipt(
+            "sender did not return signature".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::{
+        circuits::validity::validity_pis::ValidityPublicInputs,
+        common::{
+            signature::key_set::KeySet,
+            transfer::Transfer,
+            trees::{sender_tree::SenderLeaf, transfer_tree::TransferTree, tx_tree::TxTree},
+            tx::Tx,
+        },
+        constants::{TRANSFER_TREE_HEIGHT, TX_TREE_HEIGHT},
+        ethereum_types::u256::U256,
+    };
+
+    use crate::client::{error::ClientError, test_utils::MockValidityProver};
+
+    use super::{verify_receipt, PaymentReceipt};
+
+    const BLOCK_NUMBER: u32 = 7;
+
+    /// Receipt of the second transfer of the second tx of the block, and the validity prover
+    /// that knows the block.
+    fn receipt_and_validity_prover() -> (PaymentReceipt, MockValidityProver) {
+        let mut rng = rand::thread_rng();
+        let sender = KeySet::rand(&mut rng).pubkey;
+        let transfers = [Transfer::rand(&mut rng), Transfer::rand(&mut rng)];
+        let mut transfer_tree = TransferTree::new(TRANSFER_TREE_HEIGHT);
+        for transfer in transfers {
+            transfer_tree.push(transfer);
+        }
+        let tx = Tx {
+            transfer_tree_root: transfer_tree.get_root(),
+            nonce: 3,
+        };
+        let other_tx = Tx {
+            transfer_tree_root: TransferTree::new(TRANSFER_TREE_HEIGHT).get_root(),
+            nonce: 0,
+        };
+        let mut tx_tree = TxTree::new(TX_TREE_HEIGHT);
+        tx_tree.push(other_tx);
+        tx_tree.push(tx);
+        let tx_tree_root = tx_tree.get_root().into();
+
+        let sender_leaf = SenderLeaf {
+            sender,
+            did_return_sig: true,
+        };
+        let receipt = PaymentReceipt {
+            sender,
+            transfer: transfers[1],
+            transfer_index: 1,
+            transfer_merkle_proof: transfer_tree.prove(1),
+            tx,
+            tx_index: 1,
+            tx_merkle_proof: tx_tree.prove(1),
+            tx_tree_root,
+            block_number: BLOCK_NUMBER,
+            sender_leaf: sender_leaf.clone(),
+        };
+
+        let mut validity_pis = ValidityPublicInputs::genesis();
+        validity_pis.tx_tree_root = tx_tree_root;
+        validity_pis.is_valid_block = true;
+        let mut validity_prover = MockValidityProver::default();
+        validity_prover.block_number.set(BLOCK_NUMBER);
+        validity_prover
+            .tx_tree_roots
+            .borrow_mut()
+            .insert(tx_tree_root, BLOCK_NUMBER);
+        validity_prover
+            .validity_pis
+            .insert(BLOCK_NUMBER, validity_pis);
+        let other_leaf = SenderLeaf {
+            sender: U256::rand(&mut rng),
+            did_return_sig: true,
+        };
+        validity_prover
+            .sender_leaves
+            .insert(BLOCK_NUMBER, vec![other_leaf, sender_leaf]);
+        (receipt, validity_prover)
+    }
+
+    async fn assert_rejected(validity_prover: &MockValidityProver, receipt: &PaymentReceipt) {
+        match verify_receipt(validity_prover, receipt).await {
+            Err(ClientError::InvalidReceipt(_)) => {}
+            result => panic!("receipt is not rejected: {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_receipt() {
+        let (receipt, validity_prover) = receipt_and_validity_prover();
+        verify_receipt(&validity_prover, &receipt).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tampered_receipt() {
+        let (receipt, validity_prover) = receipt_and_validity_prover();
+
+        let mut tampered = receipt.clone();
+        tampered.transfer.amount = U256::rand(&mut rand::thread_rng());
+        assert_rejected(&validity_prover, &tampered).await;
+
+        // the proof of the other transfer of the tx
+        let mut tampered = receipt.clone();
+        tampered.transfer_index = 0;
+        assert_rejected(&validity_prover, &tampered).await;
+
+        let mut tampered = receipt.clone();
+        tampered.tx.nonce += 1;
+        assert_rejected(&validity_prover, &tampered).await;
+
+        let mut tampered = receipt.clone();
+        tampered.tx_index = 0;
+        assert_rejected(&validity_prover, &tampered).await;
+    }
+
+    #[tokio::test]
+    async fn test_wrong_block_number() {
+        let (mut receipt, validity_prover) = receipt_and_validity_prover();
+        receipt.block_number = BLOCK_NUMBER + 1;
+        assert_rejected(&validity_prover, &receipt).await;
+    }
+
+    #[tokio::test]
+    async fn test_invalid_block() {
+        let (receipt, mut validity_prover) = receipt_and_validity_prover();
+        validity_prover
+            .validity_pis
+            .get_mut(&BLOCK_NUMBER)
+            .unwrap()
+            .is_valid_block = false;
+        assert_rejected(&validity_prover, &receipt).await;
+    }
+
+    #[tokio::test]
+    async fn test_sender_did_not_return_signature() {
+        let (mut receipt, mut validity_prover) = receipt_and_validity_prover();
+        receipt.sender_leaf.did_return_sig = false;
+        validity_prover
+            .sender_leaves
+            .get_mut(&BLOCK_NUMBER)
+            .unwrap()[1]
+            .did_return_sig = false;
+        assert_rejected(&validity_prover, &receipt).await;
+
+        // the leaf of the receipt must match the block as well
+        let (mut receipt, validity_prover) = receipt_and_validity_prover();
+        receipt.sender_leaf.did_return_sig = false;
+        assert_rejected(&validity_prover, &receipt).await;
+    }
+}
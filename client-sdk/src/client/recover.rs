@@ -0,0 +1,369 @@
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        store_vault_server::interface::StoreVaultClientInterface,
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::{ContractWithdrawal, WithdrawalServerClientInterface},
+    },
+    data::{processed_uuids::ProcessedUuids, user_data::UserData},
+    utils::digest::get_digest,
+};
+use intmax2_zkp::{
+    circuits::balance::balance_pis::BalancePublicInputs,
+    common::{signature::key_set::KeySet, withdrawal::get_withdrawal_nullifier},
+};
+
+use super::{
+    client::Client,
+    error::ClientError,
+    strategy::{
+        strategy::{determine_sequence_from, Action, ReceiveAction},
+        withdrawal::fetch_withdrawal_info,
+    },
+    sync::{error::SyncError, utils::get_balance_proof},
+};
+
+#[derive(Debug, Clone)]
+pub struct RecoveryResult {
+    /// The rebuilt user data
+    pub user_data: UserData,
+
+    /// Number of deposits incorporated into the balance proof
+    pub num_deposits: usize,
+
+    /// Number of transfers incorporated into the balance proof
+    pub num_transfers: usize,
+
+    /// Number of txs incorporated into the balance proof
+    pub num_txs: usize,
+
+    /// Uuids of transfers that were skipped because the sender's proof was invalid. They are
+    /// recorded as rejected transfers in the user data, in the same way as sync does.
+    pub skipped_transfer_uuids: Vec<String>,
+
+    /// Number of withdrawals that were already requested to the withdrawal server
+    pub num_requested_withdrawals: usize,
+
+    /// Whether the rebuilt user data was saved to the store vault server
+    pub saved: bool,
+}
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Rebuild the user data from scratch by replaying all deposits, transfers and txs in the
+    /// store vault in the same sequence as `sync`, and verify the result against the validity
+    /// prover.
+    ///
+    /// This is intended for the case where the user data is lost or cannot be decrypted.
+    /// The existing user data is overwritten unless `dry_run` is set. It fails if the store vault
+    /// has garbage collected entries of the account, in which case import a backup instead.
+    pub async fn recover_account(
+        &self,
+        key: KeySet,
+        dry_run: bool,
+    ) -> Result<RecoveryResult, ClientError> {
+        log::info!(
+            "recover_account: pubkey {}, dry_run {}",
+            key.pubkey,
+            dry_run
+        );
+        let mut result = RecoveryResult {
+            user_data: UserData::new(key.pubkey),
+            num_deposits: 0,
+            num_transfers: 0,
+            num_txs: 0,
+            skipped_transfer_uuids: Vec::new(),
+            num_requested_withdrawals: 0,
+            saved: false,
+        };
+
+        // Replay the same sequence as sync does, starting from an empty user data
+        let (sequence, _) = determine_sequence_from(
+            &self.store_vault_server,
+            &self.validity_prover,
+            &self.liquidity_contract,
+            key,
+            &result.user_data,
+            self.config.deposit_timeout,
+            self.config.tx_timeout,
+        )
+        .await?;
+        for action in sequence {
+            match action {
+                Action::Receive {
+                    receives,
+                    new_deposit_lpt,
+                    new_transfer_lpt,
+                    skipped_deposits,
+                    skipped_transfers,
+                } => {
+                    self.replay_receives(key, receives, &mut result).await?;
+                    for meta in &skipped_deposits {
+                        result.user_data.processed_deposit_uuids.skip(meta);
+                    }
+                    for meta in &skipped_transfers {
+                        result.user_data.processed_transfer_uuids.skip(meta);
+                    }
+                    result.user_data.set_deposit_lpt(new_deposit_lpt);
+                    result.user_data.set_transfer_lpt(new_transfer_lpt);
+                }
+                Action::Tx(meta, tx_data) => {
+                    log::info!("replay_tx: {:?}", meta);
+                    self.apply_tx(key, &mut result.user_data, &meta, &tx_data)
+                        .await?;
+                    result.num_txs += 1;
+                }
+                Action::PendingReceives(meta, _tx_data) => {
+                    return Err(SyncError::PendingReceivesError(format!(
+                        "pending receives to proceed tx: {:?}",
+                        meta.uuid
+                    ))
+                    .into());
+                }
+                Action::PendingTx(meta, _tx_data) => {
+                    return Err(
+                        SyncError::PendingTxError(format!("pending tx: {:?}", meta.uuid)).into(),
+                    );
+                }
+            }
+        }
+
+        let user_data = &mut result.user_data;
+        result.num_requested_withdrawals = self.recover_withdrawals(key, user_data).await?;
+
+        self.verify_recovered_user_data(key, user_data, result.num_txs)
+            .await?;
+
+        if !dry_run {
+            // Overwrite the existing user data, which may be undecryptable
+            let prev_digest = self
+                .store_vault_server
+                .get_user_data(key)
+                .await?
+                .map(|encrypted| get_digest(&encrypted));
+            self.store_vault_server
+                .save_user_data(key, prev_digest, &user_data.encrypt(key.pubkey))
+                .await?;
+            result.saved = true;
+        }
+        Ok(result)
+    }
+
+    async fn replay_receives(
+        &self,
+        key: KeySet,
+        receives: Vec<ReceiveAction>,
+        result: &mut RecoveryResult,
+    ) -> Result<(), ClientError> {
+        if receives.is_empty() {
+            return Ok(());
+        }
+        let largest_block_number = receives
+            .iter()
+            .map(|r| r.meta().block_number.unwrap())
+            .max()
+            .unwrap(); // safe to unwrap
+        self.apply_no_send(key, &mut result.user_data, largest_block_number)
+            .await?;
+
+        for receive in receives {
+            match receive {
+                ReceiveAction::Deposit(meta, data) => {
+                    log::info!("replay_deposit: {:?}", meta);
+                    self.apply_deposit(key, &mut result.user_data, &meta, &data)
+                        .await?;
+                    result.num_deposits += 1;
+                }
+                ReceiveAction::Transfer(meta, data) => {
+                    log::info!("replay_transfer: {:?}", meta);
+                    if self
+                        .apply_transfer(key, &mut result.user_data, &meta, &data)
+                        .await?
+                    {
+                        result.num_transfers += 1;
+                    } else {
+                        result.skipped_transfer_uuids.push(meta.uuid.clone());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark withdrawals that the withdrawal server already knows as processed, so that they are
+    /// not proved again. Returns the number of such withdrawals.
+    async fn recover_withdrawals(
+        &self,
+        key: KeySet,
+        user_data: &mut UserData,
+    ) -> Result<usize, ClientError> {
+        let withdrawal_info = fetch_withdrawal_info(
+            &self.store_vault_server,
+            &self.validity_prover,
+            key,
+            0,
+            &ProcessedUuids::new(),
+            self.config.tx_timeout,
+        )
+        .await?;
+        let requested_hashes = self
+            .withdrawal_server
+            .get_withdrawal_info(key)
+            .await?
+            .into_iter()
+            .map(|info| info.contract_withdrawal.withdrawal_hash())
+            .collect::<Vec<_>>();
+
+        let mut num_requested = 0;
+        let mut oldest_unrequested_timestamp = None;
+        for (meta, data) in withdrawal_info.settled.iter() {
+            let transfer = data.transfer;
+            let withdrawal = ContractWithdrawal {
+                recipient: transfer.recipient.to_address().unwrap(),
+                token_index: transfer.token_index,
+                amount: transfer.amount,
+                nullifier: get_withdrawal_nullifier(&transfer),
+            };
+            if requested_hashes.contains(&withdrawal.withdrawal_hash()) {
+                user_data.processed_withdrawal_uuids.insert(meta);
+                num_requested += 1;
+            } else {
+                oldest_unrequested_timestamp = oldest_unrequested_timestamp
+                    .map(|t: u64| t.min(meta.timestamp))
+                    .or(Some(meta.timestamp));
+            }
+        }
+        let current_timestamp =
+            (chrono::Utc::now().timestamp() as u64).saturating_sub(self.config.tx_timeout);
+        let new_withdrawal_lpt = withdrawal_info
+            .pending
+            .iter()
+            .map(|(meta, _)| meta.timestamp)
+            .chain(oldest_unrequested_timestamp)
+            .min()
+            .map(|timestamp| timestamp - 1)
+            .unwrap_or(current_timestamp);
+        user_data.set_withdrawal_lpt(new_withdrawal_lpt);
+        Ok(num_requested)
+    }
+
+    /// Check that the rebuilt balance proof is consistent with the private state and with the
+    /// public state of the validity prover, which is derived from the rollup contract.
+    async fn verify_recovered_user_data(
+        &self,
+        key: KeySet,
+        user_data: &UserData,
+        num_txs: usize,
+    ) -> Result<(), ClientError> {
+        let balance_proof = match get_balance_proof(user_data)? {
+            Some(proof) => proof,
+            None => {
+                if num_txs > 0 {
+                    return Err(ClientError::RecoveryError(
+                        "balance proof is missing though txs exist".to_string(),
+                    ));
+                }
+                return Ok(());
+            }
+        };
+        let balance_pis = BalancePublicInputs::from_pis(&balance_proof.public_inputs);
+        if balance_pis.pubkey != key.pubkey {
+            return Err(ClientError::RecoveryError(
+                "balance proof pubkey mismatch".to_string(),
+            ));
+        }
+        if balance_pis.private_commitment != user_data.private_commitment() {
+            return Err(ClientError::RecoveryError(
+                "private commitment mismatch".to_string(),
+            ));
+        }
+        if user_data.full_private_state.nonce as usize != num_txs {
+            return Err(ClientError::RecoveryError(format!(
+                "nonce mismatch: {} != number of txs {}",
+                user_data.full_private_state.nonce, num_txs
+            )));
+        }
+        let block_number = balance_pis.public_state.block_number;
+        let validity_pis = self
+            .validity_prover
+            .get_validity_pis(block_number)
+            .await?
+            .ok_or(ClientError::RecoveryError(format!(
+                "validity pis not found for block {}",
+                block_number
+            )))?;
+        if validity_pis.public_state != balance_pis.public_state {
+            return Err(ClientError::RecoveryError(format!(
+                "public state mismatch at block {}",
+                block_number
+            )));
+        }
+        let onchain_block_number = self.rollup_contract.get_latest_block_number().await?;
+        if block_number > onchain_block_number {
+            return Err(ClientError::RecoveryError(format!(
+                "balance proof block number {} is greater than onchain block number {}",
+                block_number, onchain_block_number
+            )));
+        }
+        if num_txs > 0 {
+            let account_info = self.validity_prover.get_account_info(key.pubkey).await?;
+            if account_info.account_id.is_none() {
+                return Err(ClientError::RecoveryError(
+                    "account is not registered though txs exist".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::common::signature::key_set::KeySet;
+
+    use crate::client::test_utils::{
+        mock_client, push_transfer, MockStoreVault, MockValidityProver,
+    };
+
+    #[tokio::test]
+    async fn test_recovered_user_data_equals_synced() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let now = chrono::Utc::now().timestamp() as u64;
+        let store_vault = MockStoreVault::default();
+        // neither of them is settled, so no balance proof is needed
+        push_transfer(&store_vault, key.pubkey, "timed-out", now - 3600, None);
+        push_transfer(&store_vault, key.pubkey, "pending", now - 10, None);
+
+        let client = mock_client(store_vault.clone(), MockValidityProver::default());
+        let pending = client.sync(key).await.unwrap();
+        assert_eq!(pending.pending_transfers.len(), 1);
+        client.sync_withdrawals(key).await.unwrap();
+        let (synced, _) = client.get_user_data_and_digest(key).await.unwrap();
+
+        let client = mock_client(store_vault, MockValidityProver::default());
+        let result = client.recover_account(key, false).await.unwrap();
+        assert!(result.saved);
+        assert_eq!(result.num_transfers, 0);
+        let (mut recovered, _) = client.get_user_data_and_digest(key).await.unwrap();
+
+        // the pending transfer is left for the next sync
+        assert_eq!(recovered.transfer_lpt, now - 11);
+        // the lpts without pending entries are set to the current time, which may differ by the
+        // time between the two runs
+        assert!(recovered.deposit_lpt.abs_diff(synced.deposit_lpt) <= 1);
+        assert!(recovered.withdrawal_lpt.abs_diff(synced.withdrawal_lpt) <= 1);
+        recovered.deposit_lpt = synced.deposit_lpt;
+        recovered.withdrawal_lpt = synced.withdrawal_lpt;
+        assert_eq!(
+            serde_json::to_value(&recovered).unwrap(),
+            serde_json::to_value(&synced).unwrap()
+        );
+    }
+}
@@ -6,19 +6,56 @@ use intmax2_interfaces::{
         },
         validity_prover::interface::ValidityProverClientInterface,
     },
-    data::{deposit_data::DepositData, meta_data::MetaData},
+    data::{deposit_data::DepositData, meta_data::MetaData, processed_uuids::ProcessedUuids},
 };
 use intmax2_zkp::common::signature::key_set::KeySet;
+use serde::{Deserialize, Serialize};
 
 use crate::external_api::contract::liquidity_contract::LiquidityContract;
 
 use super::error::StrategyError;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DepositRejectionReason {
+    /// The deposit was not relayed to the rollup within the deposit timeout. It may still be
+    /// relayed later, or be cancelled on the liquidity contract.
+    Timeout,
+    /// The token of the deposit is not known to the liquidity contract. It can still be
+    /// cancelled with the token index of its `Deposited` event.
+    UnknownToken,
+}
+
+impl std::fmt::Display for DepositRejectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DepositRejectionReason::Timeout => write!(f, "not relayed within the deposit timeout"),
+            DepositRejectionReason::UnknownToken => write!(f, "token is not registered"),
+        }
+    }
+}
+
+/// Result of re-checking a deposit against the validity prover
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DepositRecheckResult {
+    /// The deposit is already incorporated into the balance
+    AlreadyProcessed,
+    /// The deposit turned out to be relayed and has been incorporated into the balance
+    Resurrected {
+        block_number: u32,
+    },
+    /// The deposit is not relayed yet, but has not timed out
+    Pending,
+    Rejected(DepositRejectionReason),
+}
+
 #[derive(Debug, Clone)]
 pub struct DepositInfo {
     pub settled: Vec<(MetaData, DepositData)>,
     pub pending: Vec<(MetaData, DepositData)>,
     pub timeout: Vec<(MetaData, DepositData)>,
+    pub unknown_token: Vec<(MetaData, DepositData)>,
 }
 
 pub async fn fetch_deposit_info<S: StoreVaultClientInterface, V: ValidityProverClientInterface>(
@@ -27,18 +64,19 @@ pub async fn fetch_deposit_info<S: StoreVaultClientInterface, V: ValidityProverC
     liquidity_contract: &LiquidityContract,
     key: KeySet,
     deposit_lpt: u64,
-    processed_deposit_uuids: &[String],
+    processed_deposit_uuids: &ProcessedUuids,
     deposit_timeout: u64,
 ) -> Result<DepositInfo, StrategyError> {
     let mut settled = Vec::new();
     let mut pending = Vec::new();
     let mut timeout = Vec::new();
+    let mut unknown_token = Vec::new();
 
     let data_with_meta = store_vault_server
         .get_data_all_after(DataType::Deposit, key, deposit_lpt)
         .await?;
     for DataWithMetaData { meta, data } in data_with_meta {
-        if processed_deposit_uuids.contains(&meta.uuid) {
+        if processed_deposit_uuids.is_processed(&meta, deposit_lpt) {
             log::info!("Deposit {} is already processed", meta.uuid);
             continue;
         }
@@ -53,7 +91,7 @@ pub async fn fetch_deposit_info<S: StoreVaultClientInterface, V: ValidityProverC
                     .await?;
                 if token_index.is_none() {
                     log::error!("Token not found: {:?}", deposit_data);
-                    // ignore this deposit
+                    unknown_token.push((meta, deposit_data));
                     continue;
                 }
                 let mut deposit_data = deposit_data;
@@ -93,5 +131,35 @@ pub async fn fetch_deposit_info<S: StoreVaultClientInterface, V: ValidityProverC
         settled,
         pending,
         timeout,
+        unknown_token,
     })
 }
+
+/// Fetch a single deposit by its uuid, setting the token index if the token is registered.
+pub async fn fetch_deposit_by_uuid<S: StoreVaultClientInterface>(
+    store_vault_server: &S,
+    liquidity_contract: &LiquidityContract,
+    key: KeySet,
+    deposit_uuid: &str,
+) -> Result<Option<(MetaData, DepositData)>, StrategyError> {
+    let data_with_meta = store_vault_server
+        .get_data_batch(key, DataType::Deposit, &[deposit_uuid.to_string()])
+        .await?;
+    let DataWithMetaData { meta, data } = match data_with_meta.into_iter().next() {
+        Some(data_with_meta) => data_with_meta,
+        None => return Ok(None),
+    };
+    let mut deposit_data = DepositData::decrypt(&data, key)
+        .map_err(|e| StrategyError::DepositDecryptionError(e.to_string()))?;
+    let token_index = liquidity_contract
+        .get_token_index(
+            deposit_data.token_type,
+            deposit_data.token_address,
+            deposit_data.token_id,
+        )
+        .await?;
+    if let Some(token_index) = token_index {
+        deposit_data.set_token_index(token_index);
+    }
+    Ok(Some((meta, deposit_data)))
+}
@@ -19,4 +19,7 @@ pub enum StrategyError {
 
     #[error("User data decryption error: {0}")]
     UserDataDecryptionError(String),
+
+    #[error("Deposit decryption error: {0}")]
+    DepositDecryptionError(String),
 }
@@ -32,6 +32,9 @@ pub enum Action {
         receives: Vec<ReceiveAction>,
         new_deposit_lpt: u64,
         new_transfer_lpt: u64,
+        // Deposits and transfers that the lpts pass without being received, e.g. timed out
+        skipped_deposits: Vec<MetaData>,
+        skipped_transfers: Vec<MetaData>,
     },
     Tx(MetaData, TxData),              // Send tx
     PendingReceives(MetaData, TxData), // Pending receives to proceed the next tx
@@ -87,6 +90,32 @@ pub async fn determine_sequence<S: StoreVaultClientInterface, V: ValidityProverC
         .transpose()
         .map_err(|e| StrategyError::UserDataDecryptionError(e.to_string()))?
         .unwrap_or(UserData::new(key.pubkey));
+    determine_sequence_from(
+        store_vault_server,
+        validity_prover,
+        liquidity_contract,
+        key,
+        &user_data,
+        deposit_timeout,
+        tx_timeout,
+    )
+    .await
+}
+
+/// Same as `determine_sequence`, starting from the given user data instead of the saved one.
+/// The account recovery starts from an empty user data.
+pub async fn determine_sequence_from<
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+>(
+    store_vault_server: &S,
+    validity_prover: &V,
+    liquidity_contract: &LiquidityContract,
+    key: KeySet,
+    user_data: &UserData,
+    deposit_timeout: u64,
+    tx_timeout: u64,
+) -> Result<(Vec<Action>, PendingInfo), StrategyError> {
     let mut balances = user_data.balances();
     if balances.is_insufficient() {
         return Err(StrategyError::BalanceInsufficientBeforeSync);
@@ -151,6 +180,19 @@ pub async fn determine_sequence<S: StoreVaultClientInterface, V: ValidityProverC
     let mut deposits = deposit_info.settled;
     let mut transfers = transfer_info.settled;
 
+    // Recorded with the first receive, since the lpts may pass them from then on
+    let mut skipped_deposits = deposit_info
+        .timeout
+        .into_iter()
+        .chain(deposit_info.unknown_token)
+        .map(|(meta, _)| meta)
+        .collect::<Vec<_>>();
+    let mut skipped_transfers = transfer_info
+        .timeout
+        .into_iter()
+        .map(|(meta, _)| meta)
+        .collect::<Vec<_>>();
+
     // Next, for each settled tx, take deposits and transfers that are strictly smaller than the block number of the tx
     let mut sequence = Vec::new();
     for (tx_meta, tx_data) in tx_info.settled.iter() {
@@ -200,6 +242,8 @@ pub async fn determine_sequence<S: StoreVaultClientInterface, V: ValidityProverC
             receives,
             new_deposit_lpt,
             new_transfer_lpt,
+            skipped_deposits: std::mem::take(&mut skipped_deposits),
+            skipped_transfers: std::mem::take(&mut skipped_transfers),
         });
         sequence.push(Action::Tx(tx_meta.clone(), tx_data.clone()));
     }
@@ -216,6 +260,8 @@ pub async fn determine_sequence<S: StoreVaultClientInterface, V: ValidityProverC
         receives,
         new_deposit_lpt,
         new_transfer_lpt,
+        skipped_deposits,
+        skipped_transfers,
     });
     Ok((
         sequence,
@@ -1,13 +1,17 @@
 use super::error::StrategyError;
 use intmax2_interfaces::{
     api::{
+        error::ErrorCode,
         store_vault_server::{
             interface::{DataType, StoreVaultClientInterface},
             types::DataWithMetaData,
         },
         validity_prover::interface::ValidityProverClientInterface,
     },
-    data::{meta_data::MetaData, sender_proof_set::SenderProofSet, transfer_data::TransferData},
+    data::{
+        meta_data::MetaData, processed_uuids::ProcessedUuids, sender_proof_set::SenderProofSet,
+        transfer_data::TransferData,
+    },
 };
 use intmax2_zkp::common::signature::key_set::KeySet;
 use num_bigint::BigUint;
@@ -24,7 +28,7 @@ pub async fn fetch_transfer_info<S: StoreVaultClientInterface, V: ValidityProver
     validity_prover: &V,
     key: KeySet,
     transfer_lpt: u64,
-    processed_transfer_uuids: &[String],
+    processed_transfer_uuids: &ProcessedUuids,
     tx_timeout: u64,
 ) -> Result<TransferInfo, StrategyError> {
     let mut settled = Vec::new();
@@ -35,7 +39,7 @@ pub async fn fetch_transfer_info<S: StoreVaultClientInterface, V: ValidityProver
         .get_data_all_after(DataType::Transfer, key, transfer_lpt)
         .await?;
     for DataWithMetaData { meta, data } in encrypted_data {
-        if processed_transfer_uuids.contains(&meta.uuid) {
+        if processed_transfer_uuids.is_processed(&meta, transfer_lpt) {
             log::info!("Transfer {} is already processed", meta.uuid);
             continue;
         }
@@ -43,9 +47,16 @@ pub async fn fetch_transfer_info<S: StoreVaultClientInterface, V: ValidityProver
             Ok(transfer_data) => {
                 let ephemeral_key =
                     KeySet::new(BigUint::from(transfer_data.sender_proof_set_ephemeral_key).into());
-                let encrypted_sender_proof_set = store_vault_server
-                    .get_sender_proof_set(ephemeral_key)
-                    .await?;
+                let encrypted_sender_proof_set =
+                    match store_vault_server.get_sender_proof_set(ephemeral_key).await {
+                        Ok(data) => data,
+                        Err(e) if e.code() == Some(ErrorCode::NotFound) => {
+                            // garbage collected by the store vault server
+                            log::error!("sender proof set of {} is not found: {}", meta.uuid, e);
+                            continue;
+                        }
+                        Err(e) => return Err(e.into()),
+                    };
                 let sender_proof_set =
                     match SenderProofSet::decrypt(&encrypted_sender_proof_set, ephemeral_key) {
                         Ok(data) => data,
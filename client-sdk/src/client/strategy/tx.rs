@@ -7,7 +7,7 @@ use intmax2_interfaces::{
         },
         validity_prover::interface::ValidityProverClientInterface,
     },
-    data::{meta_data::MetaData, tx_data::TxData},
+    data::{meta_data::MetaData, processed_uuids::ProcessedUuids, tx_data::TxData},
 };
 use intmax2_zkp::common::signature::key_set::KeySet;
 
@@ -23,7 +23,7 @@ pub async fn fetch_tx_info<S: StoreVaultClientInterface, V: ValidityProverClient
     validity_prover: &V,
     key: KeySet,
     tx_lpt: u64,
-    processed_tx_uuids: &[String],
+    processed_tx_uuids: &ProcessedUuids,
     tx_timeout: u64,
 ) -> Result<TxInfo, StrategyError> {
     let mut settled = Vec::new();
@@ -34,7 +34,7 @@ pub async fn fetch_tx_info<S: StoreVaultClientInterface, V: ValidityProverClient
         .get_data_all_after(DataType::Tx, key, tx_lpt)
         .await?;
     for DataWithMetaData { meta, data } in encrypted_data {
-        if processed_tx_uuids.contains(&meta.uuid) {
+        if processed_tx_uuids.is_processed(&meta, tx_lpt) {
             log::info!("Tx {} is already processed", meta.uuid);
             continue;
         }
@@ -1,13 +1,17 @@
 use super::error::StrategyError;
 use intmax2_interfaces::{
     api::{
+        error::ErrorCode,
         store_vault_server::{
             interface::{DataType, StoreVaultClientInterface},
             types::DataWithMetaData,
         },
         validity_prover::interface::ValidityProverClientInterface,
     },
-    data::{meta_data::MetaData, sender_proof_set::SenderProofSet, transfer_data::TransferData},
+    data::{
+        meta_data::MetaData, processed_uuids::ProcessedUuids, sender_proof_set::SenderProofSet,
+        transfer_data::TransferData,
+    },
 };
 use intmax2_zkp::common::signature::key_set::KeySet;
 use num_bigint::BigUint;
@@ -27,7 +31,7 @@ pub async fn fetch_withdrawal_info<
     validity_prover: &V,
     key: KeySet,
     withdrawal_lpt: u64,
-    processed_withdrawal_uuids: &[String],
+    processed_withdrawal_uuids: &ProcessedUuids,
     tx_timeout: u64,
 ) -> Result<WithdrawalInfo, StrategyError> {
     let mut settled = Vec::new();
@@ -38,7 +42,7 @@ pub async fn fetch_withdrawal_info<
         .get_data_all_after(DataType::Withdrawal, key, withdrawal_lpt)
         .await?;
     for DataWithMetaData { meta, data } in encrypted_data {
-        if processed_withdrawal_uuids.contains(&meta.uuid) {
+        if processed_withdrawal_uuids.is_processed(&meta, withdrawal_lpt) {
             log::info!("Withdrawal {} is already processed", meta.uuid);
             continue;
         }
@@ -46,9 +50,16 @@ pub async fn fetch_withdrawal_info<
             Ok(transfer_data) => {
                 let ephemeral_key =
                     KeySet::new(BigUint::from(transfer_data.sender_proof_set_ephemeral_key).into());
-                let encrypted_sender_proof_set = store_vault_server
-                    .get_sender_proof_set(ephemeral_key)
-                    .await?;
+                let encrypted_sender_proof_set =
+                    match store_vault_server.get_sender_proof_set(ephemeral_key).await {
+                        Ok(data) => data,
+                        Err(e) if e.code() == Some(ErrorCode::NotFound) => {
+                            // garbage collected by the store vault server
+                            log::error!("sender proof set of {} is not found: {}", meta.uuid, e);
+                            continue;
+                        }
+                        Err(e) => return Err(e.into()),
+                    };
                 let sender_proof_set =
                     match SenderProofSet::decrypt(&encrypted_sender_proof_set, ephemeral_key) {
                         Ok(data) => data,
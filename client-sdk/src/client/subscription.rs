@@ -0,0 +1,222 @@
+use std::collections::{HashMap, VecDeque};
+
+use intmax2_interfaces::api::{
+    balance_prover::interface::BalanceProverClientInterface,
+    block_builder::interface::BlockBuilderClientInterface,
+    store_vault_server::{
+        interface::{DataSubscription, DataType, StoreVaultClientInterface},
+        types::DataNotification,
+    },
+    validity_prover::interface::ValidityProverClientInterface,
+    withdrawal_server::interface::WithdrawalServerClientInterface,
+};
+use intmax2_zkp::common::signature::key_set::KeySet;
+
+use crate::external_api::utils::time::sleep_for;
+
+use super::{client::Client, error::ClientError};
+
+const DEFAULT_POLL_INTERVAL: u64 = 10; // seconds
+
+// Entries may be notified out of order of their timestamps, so the entries within this window
+// before the newest one are remembered to avoid notifying them twice.
+const REORDER_WINDOW: u64 = 60; // seconds
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Watch the entries of `data_types` saved for the user at or after `since`.
+    pub fn watch_data(
+        &self,
+        key: KeySet,
+        data_types: &[DataType],
+        since: u64,
+    ) -> DataWatcher<'_, S> {
+        DataWatcher::new(&self.store_vault_server, key, data_types, since)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Cursor {
+    since: u64,
+    // uuid -> timestamp of the entries at or after `since`
+    seen: HashMap<String, u64>,
+}
+
+impl Cursor {
+    /// Returns true if the entry has not been seen yet.
+    fn mark_seen(&mut self, notification: &DataNotification) -> bool {
+        if notification.timestamp < self.since
+            || self
+                .seen
+                .insert(notification.uuid.clone(), notification.timestamp)
+                .is_some()
+        {
+            return false;
+        }
+        let newest = self.seen.values().copied().max().unwrap_or(0);
+        self.since = self.since.max(newest.saturating_sub(REORDER_WINDOW));
+        let since = self.since;
+        self.seen.retain(|_, timestamp| *timestamp >= since);
+        true
+    }
+}
+
+/// Notifications of the entries saved for the user, pushed by the store vault server. If the
+/// subscription fails or is closed, the entries are found by polling `get_data_all_after`, so no
+/// entry is missed.
+pub struct DataWatcher<'a, S: StoreVaultClientInterface> {
+    store_vault_server: &'a S,
+    key: KeySet,
+    data_types: Vec<DataType>,
+    poll_interval: u64,
+    subscription: Option<Box<dyn DataSubscription>>,
+    cursors: HashMap<DataType, Cursor>,
+    pending: VecDeque<DataNotification>,
+}
+
+impl<'a, S: StoreVaultClientInterface> DataWatcher<'a, S> {
+    pub fn new(
+        store_vault_server: &'a S,
+        key: KeySet,
+        data_types: &[DataType],
+        since: u64,
+    ) -> Self {
+        let cursors = data_types
+            .iter()
+            .map(|data_type| {
+                let cursor = Cursor {
+                    since,
+                    ..Default::default()
+                };
+                (*data_type, cursor)
+            })
+            .collect();
+        Self {
+            store_vault_server,
+            key,
+            data_types: data_types.to_vec(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            subscription: None,
+            cursors,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Interval of polling while the subscription is unavailable, in seconds.
+    pub fn with_poll_interval(mut self, poll_interval: u64) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns true if the notifications are currently pushed by the server.
+    pub fn is_subscribed(&self) -> bool {
+        self.subscription.is_some()
+    }
+
+    /// Waits for the next entry.
+    pub async fn next(&mut self) -> Result<DataNotification, ClientError> {
+        loop {
+            if let Some(notification) = self.pending.pop_front() {
+                return Ok(notification);
+            }
+            let Some(subscription) = self.subscription.as_mut() else {
+                match self
+                    .store_vault_server
+                    .subscribe(self.key, &self.data_types)
+                    .await
+                {
+                    Ok(subscription) => self.subscription = Some(subscription),
+                    Err(e) => {
+                        log::warn!("Failed to subscribe, falling back to polling: {}", e);
+                        sleep_for(self.poll_interval).await;
+                    }
+                }
+                // catch up on the entries saved while not subscribed
+                self.poll().await?;
+                continue;
+            };
+            match subscription.next().await {
+                Ok(Some(notification)) => {
+                    let is_new = self
+                        .cursors
+                        .get_mut(&notification.data_type)
+                        .is_some_and(|cursor| cursor.mark_seen(&notification));
+                    if is_new {
+                        return Ok(notification);
+                    }
+                }
+                Ok(None) => {
+                    log::info!("Subscription closed by the server, resubscribing");
+                    self.subscription = None;
+                }
+                Err(e) => {
+                    log::warn!("Subscription failed, resubscribing: {}", e);
+                    self.subscription = None;
+                }
+            }
+        }
+    }
+
+    async fn poll(&mut self) -> Result<(), ClientError> {
+        for data_type in self.data_types.iter() {
+            let cursor = self.cursors.get_mut(data_type).unwrap();
+            let data = self
+                .store_vault_server
+                .get_data_all_after(*data_type, self.key, cursor.since)
+                .await?;
+            for entry in data {
+                let notification = DataNotification {
+                    data_type: *data_type,
+                    uuid: entry.meta.uuid,
+                    timestamp: entry.meta.timestamp,
+                };
+                if cursor.mark_seen(&notification) {
+                    self.pending.push_back(notification);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::api::store_vault_server::{
+        interface::DataType, types::DataNotification,
+    };
+
+    use super::{Cursor, REORDER_WINDOW};
+
+    fn notification(uuid: &str, timestamp: u64) -> DataNotification {
+        DataNotification {
+            data_type: DataType::Transfer,
+            uuid: uuid.to_string(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut cursor = Cursor {
+            since: 100,
+            ..Default::default()
+        };
+        assert!(!cursor.mark_seen(&notification("old", 99)));
+        assert!(cursor.mark_seen(&notification("a", 100)));
+        assert!(!cursor.mark_seen(&notification("a", 100)));
+
+        let later = 100 + REORDER_WINDOW + 10;
+        assert!(cursor.mark_seen(&notification("b", later)));
+        assert_eq!(cursor.since, 110);
+        // entries out of order within the window are still notified
+        assert!(cursor.mark_seen(&notification("c", 120)));
+        assert!(!cursor.mark_seen(&notification("c", 120)));
+        assert!(!cursor.seen.contains_key("a"));
+    }
+}
@@ -50,6 +50,9 @@ pub enum SyncError {
         block_number: u32,
     },
 
+    #[error("Deposit not found: {0}")]
+    DepositNotFound(String),
+
     #[error("Deposit info not found: {0}")]
     DepositInfoNotFound(Bytes32),
 
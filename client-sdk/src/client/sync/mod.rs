@@ -7,7 +7,7 @@ use intmax2_interfaces::{
     api::{
         balance_prover::interface::BalanceProverClientInterface,
         block_builder::interface::BlockBuilderClientInterface,
-        store_vault_server::interface::StoreVaultClientInterface,
+        store_vault_server::interface::{DataType, StoreVaultClientInterface},
         validity_prover::interface::ValidityProverClientInterface,
         withdrawal_server::interface::WithdrawalServerClientInterface,
     },
@@ -23,8 +23,10 @@ use intmax2_zkp::{
         signature::key_set::KeySet,
         witness::{transfer_witness::TransferWitness, withdrawal_witness::WithdrawalWitness},
     },
-    ethereum_types::bytes32::Bytes32,
+    ethereum_types::{bytes32::Bytes32, u256::U256},
 };
+use num_bigint::BigUint;
+use std::collections::HashMap;
 
 use utils::{generate_salt, get_balance_proof};
 
@@ -36,7 +38,10 @@ use crate::client::strategy::strategy::ReceiveAction;
 
 use super::{
     client::Client,
-    strategy::strategy::{determine_sequence, determine_withdrawals, Action, PendingInfo},
+    strategy::{
+        deposit::{fetch_deposit_by_uuid, DepositRecheckResult, DepositRejectionReason},
+        strategy::{determine_sequence, determine_withdrawals, Action, PendingInfo},
+    },
 };
 
 impl<BB, S, V, B, W> Client<BB, S, V, B, W>
@@ -66,6 +71,18 @@ where
 
     /// Sync the client's balance proof with the latest block
     pub async fn sync(&self, key: KeySet) -> Result<PendingInfo, SyncError> {
+        let mut acks = Acknowledgements::default();
+        let result = self.sync_inner(key, &mut acks).await;
+        // the entries processed before a failure are acknowledged as well
+        self.acknowledge(key, acks).await;
+        result
+    }
+
+    async fn sync_inner(
+        &self,
+        key: KeySet,
+        acks: &mut Acknowledgements,
+    ) -> Result<PendingInfo, SyncError> {
         let (sequence, pending) = determine_sequence(
             &self.store_vault_server,
             &self.validity_prover,
@@ -81,6 +98,8 @@ where
                     receives,
                     new_deposit_lpt,
                     new_transfer_lpt,
+                    skipped_deposits,
+                    skipped_transfers,
                 } => {
                     if !receives.is_empty() {
                         let largest_block_number = receives
@@ -92,19 +111,21 @@ where
                         for receive in receives {
                             match receive {
                                 ReceiveAction::Deposit(meta, data) => {
-                                    self.sync_deposit(key, &meta, &data).await?;
+                                    self.sync_deposit(key, &meta, &data, acks).await?;
                                 }
                                 ReceiveAction::Transfer(meta, data) => {
-                                    self.sync_transfer(key, &meta, &data).await?;
+                                    self.sync_transfer(key, &meta, &data, acks).await?;
                                 }
                             }
                         }
                     }
-                    self.update_deposit_lpt(key, new_deposit_lpt).await?;
-                    self.update_transfer_lpt(key, new_transfer_lpt).await?;
+                    self.update_deposit_lpt(key, new_deposit_lpt, &skipped_deposits)
+                        .await?;
+                    self.update_transfer_lpt(key, new_transfer_lpt, &skipped_transfers)
+                        .await?;
                 }
                 Action::Tx(meta, tx_data) => {
-                    self.sync_tx(key, &meta, &tx_data).await?;
+                    self.sync_tx(key, &meta, &tx_data, acks).await?;
                 }
                 Action::PendingReceives(meta, _tx_data) => {
                     return Err(SyncError::PendingReceivesError(format!(
@@ -125,6 +146,17 @@ where
 
     /// Sync the client's withdrawals and relays to the withdrawal server
     pub async fn sync_withdrawals(&self, key: KeySet) -> Result<(), SyncError> {
+        let mut acks = Acknowledgements::default();
+        let result = self.sync_withdrawals_inner(key, &mut acks).await;
+        self.acknowledge(key, acks).await;
+        result
+    }
+
+    async fn sync_withdrawals_inner(
+        &self,
+        key: KeySet,
+        acks: &mut Acknowledgements,
+    ) -> Result<(), SyncError> {
         let (withdrawals, new_withdrawal_lpt) = determine_withdrawals(
             &self.store_vault_server,
             &self.validity_prover,
@@ -133,26 +165,191 @@ where
         )
         .await?;
         for (meta, data) in &withdrawals {
-            self.sync_withdrawal(key, meta, data).await?;
+            self.sync_withdrawal(key, meta, data, acks).await?;
         }
         self.update_withdrawal_lpt(key, new_withdrawal_lpt).await?;
         Ok(())
     }
 
+    /// Check a deposit that was rejected on timeout against the validity prover again, and
+    /// incorporate it into the balance if it turns out to be just slow to be relayed.
+    ///
+    /// Timed out deposits are skipped by `sync` once the deposit lpt passes them, so this is the
+    /// only way to receive them afterwards.
+    pub async fn recheck_deposit(
+        &self,
+        key: KeySet,
+        deposit_uuid: &str,
+    ) -> Result<DepositRecheckResult, SyncError> {
+        let (meta, deposit_data) = fetch_deposit_by_uuid(
+            &self.store_vault_server,
+            &self.liquidity_contract,
+            key,
+            deposit_uuid,
+        )
+        .await?
+        .ok_or(SyncError::DepositNotFound(deposit_uuid.to_string()))?;
+        self.recheck_fetched_deposit(key, meta, deposit_data).await
+    }
+
+    // `recheck_deposit` after the deposit is fetched and its token index is resolved
+    pub(crate) async fn recheck_fetched_deposit(
+        &self,
+        key: KeySet,
+        meta: MetaData,
+        deposit_data: DepositData,
+    ) -> Result<DepositRecheckResult, SyncError> {
+        let deposit_uuid = meta.uuid.as_str();
+        let (user_data, _) = self.get_user_data_and_digest(key).await?;
+        // The uuid itself is checked instead of `is_processed`, since a timed out deposit is passed
+        // by the lpt without being processed.
+        if user_data.processed_deposit_uuids.contains(deposit_uuid) {
+            return Ok(DepositRecheckResult::AlreadyProcessed);
+        }
+        let deposit_hash = match deposit_data.deposit_hash() {
+            Some(deposit_hash) => deposit_hash,
+            None => {
+                return Ok(DepositRecheckResult::Rejected(
+                    DepositRejectionReason::UnknownToken,
+                ))
+            }
+        };
+        let deposit_info = match self.validity_prover.get_deposit_info(deposit_hash).await? {
+            Some(deposit_info) => deposit_info,
+            None => {
+                let now = chrono::Utc::now().timestamp() as u64;
+                if meta.timestamp + self.config.deposit_timeout < now {
+                    return Ok(DepositRecheckResult::Rejected(
+                        DepositRejectionReason::Timeout,
+                    ));
+                }
+                return Ok(DepositRecheckResult::Pending);
+            }
+        };
+
+        // Bring the balance proof up to date first, so that it can be advanced to the block of
+        // the deposit without skipping any tx of the user.
+        self.sync(key).await?;
+        let (user_data, _) = self.get_user_data_and_digest(key).await?;
+        if user_data.processed_deposit_uuids.contains(deposit_uuid) {
+            // still within the deposit lpt, so it was received by the sync
+            return Ok(DepositRecheckResult::Resurrected {
+                block_number: deposit_info.block_number,
+            });
+        }
+        if user_data.block_number()? < deposit_info.block_number {
+            self.update_no_send(key, deposit_info.block_number).await?;
+        }
+        let mut meta = meta;
+        meta.block_number = Some(deposit_info.block_number);
+        // The nullifier of the deposit prevents it from being received twice, even if its uuid
+        // has already been compacted from the processed uuids.
+        let mut acks = Acknowledgements::default();
+        self.sync_deposit(key, &meta, &deposit_data, &mut acks)
+            .await?;
+        self.acknowledge(key, acks).await;
+        Ok(DepositRecheckResult::Resurrected {
+            block_number: deposit_info.block_number,
+        })
+    }
+
     // sync deposit without updating the timestamp
     async fn sync_deposit(
         &self,
         key: KeySet,
         meta: &MetaData,
         deposit_data: &DepositData,
+        acks: &mut Acknowledgements,
     ) -> Result<(), SyncError> {
         log::info!("sync_deposit: {:?}", meta);
+        let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
+        self.apply_deposit(key, &mut user_data, meta, deposit_data)
+            .await?;
+        // save user data
+        self.store_vault_server
+            .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
+            .await?;
+        acks.push(DataType::Deposit, meta, None);
+        Ok(())
+    }
+
+    // sync deposit without updating the timestamp
+    async fn sync_transfer(
+        &self,
+        key: KeySet,
+        meta: &MetaData,
+        transfer_data: &TransferData,
+        acks: &mut Acknowledgements,
+    ) -> Result<(), SyncError> {
+        log::info!("sync_transfer: {:?}", meta);
+        let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
+        let is_accepted = self
+            .apply_transfer(key, &mut user_data, meta, transfer_data)
+            .await?;
+
+        // save proof and user data
+        self.store_vault_server
+            .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
+            .await?;
+        // the sender proof set of a rejected transfer is left to the retention policy
+        let sender_proof_set = is_accepted.then_some((
+            transfer_data.sender_proof_set_ephemeral_key,
+            transfer_data.transfer_index,
+        ));
+        acks.push(DataType::Transfer, meta, sender_proof_set);
+
+        Ok(())
+    }
+
+    /// Advances the balance proof of the user data to `to_block_number`, assuming that the user
+    /// sent no tx in between. The user data is only updated in memory, so that it is shared by
+    /// `sync` and the account recovery.
+    pub(crate) async fn apply_no_send(
+        &self,
+        key: KeySet,
+        user_data: &mut UserData,
+        to_block_number: u32,
+    ) -> Result<(), SyncError> {
+        let prev_balance_proof = get_balance_proof(user_data)?;
+        let new_balance_proof = update_no_send(
+            &self.validity_prover,
+            &self.balance_prover,
+            key,
+            &prev_balance_proof,
+            to_block_number,
+        )
+        .await?;
+        let new_balance_pis = BalancePublicInputs::from_pis(&new_balance_proof.public_inputs);
+        let new_block_number = new_balance_pis.public_state.block_number;
+        if new_block_number != to_block_number {
+            return Err(SyncError::BalanceProofBlockNumberMismatch {
+                balance_proof_block_number: new_block_number,
+                block_number: to_block_number,
+            });
+        }
+        if new_balance_pis.private_commitment != user_data.private_commitment() {
+            return Err(SyncError::InternalError(
+                "private commitment mismatch".to_string(),
+            ));
+        }
+        let balance_proof = CompressedBalanceProof::new(&new_balance_proof)?;
+        user_data.balance_proof = Some(balance_proof);
+        Ok(())
+    }
+
+    /// Incorporates the deposit into the balance proof of the user data in memory.
+    pub(crate) async fn apply_deposit(
+        &self,
+        key: KeySet,
+        user_data: &mut UserData,
+        meta: &MetaData,
+        deposit_data: &DepositData,
+    ) -> Result<(), SyncError> {
         if meta.block_number.is_none() {
             return Err(SyncError::BlockNumberIsNotSetForMetaData);
         }
-        let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
         // user's balance proof before applying the tx
-        let prev_balance_proof = get_balance_proof(&user_data)?;
+        let prev_balance_proof = get_balance_proof(user_data)?;
         let new_salt = generate_salt();
         let new_balance_proof = receive_deposit(
             &self.validity_prover,
@@ -174,30 +371,28 @@ where
         let new_balance_proof = CompressedBalanceProof::new(&new_balance_proof)?;
         // update user data
         user_data.balance_proof = Some(new_balance_proof);
-        user_data.processed_deposit_uuids.push(meta.uuid.clone());
-        // save user data
-        self.store_vault_server
-            .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
-            .await?;
+        user_data.processed_deposit_uuids.insert(meta);
         Ok(())
     }
 
-    // sync deposit without updating the timestamp
-    async fn sync_transfer(
+    /// Incorporates the transfer into the balance proof of the user data in memory.
+    ///
+    /// Returns false if the transfer is rejected because the sender's proof is invalid, in which
+    /// case it is recorded as a rejected transfer instead.
+    pub(crate) async fn apply_transfer(
         &self,
         key: KeySet,
+        user_data: &mut UserData,
         meta: &MetaData,
         transfer_data: &TransferData,
-    ) -> Result<(), SyncError> {
-        log::info!("sync_transfer: {:?}", meta);
+    ) -> Result<bool, SyncError> {
         if meta.block_number.is_none() {
             return Err(SyncError::InternalError(
                 "block number is not set".to_string(),
             ));
         }
-        let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
         // user's balance proof before applying the tx
-        let prev_balance_proof = get_balance_proof(&user_data)?;
+        let prev_balance_proof = get_balance_proof(user_data)?;
 
         // sender balance proof after applying the tx
         let new_sender_balance_proof = match update_send_by_receiver(
@@ -217,7 +412,13 @@ where
                     meta.uuid,
                     e
                 );
-                return Ok(());
+                user_data.add_rejected_transfer(
+                    meta,
+                    transfer_data.sender,
+                    transfer_data.transfer,
+                    &e,
+                );
+                return Ok(false);
             }
             Err(e) => return Err(e),
         };
@@ -244,13 +445,53 @@ where
         // update user data
         let balance_proof = CompressedBalanceProof::new(&new_balance_proof)?;
         user_data.balance_proof = Some(balance_proof);
-        user_data.processed_transfer_uuids.push(meta.uuid.clone());
+        user_data.processed_transfer_uuids.insert(meta);
+        Ok(true)
+    }
 
-        // save proof and user data
-        self.store_vault_server
-            .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
-            .await?;
+    /// Incorporates the tx sent by the user into the balance proof of the user data in memory.
+    pub(crate) async fn apply_tx(
+        &self,
+        key: KeySet,
+        user_data: &mut UserData,
+        meta: &MetaData,
+        tx_data: &TxData,
+    ) -> Result<(), SyncError> {
+        if meta.block_number.is_none() {
+            return Err(SyncError::InternalError(
+                "block number is not set".to_string(),
+            ));
+        }
+        let prev_balance_proof = get_balance_proof(user_data)?;
+        let balance_proof = update_send_by_sender(
+            &self.validity_prover,
+            &self.balance_prover,
+            key,
+            &mut user_data.full_private_state,
+            &prev_balance_proof,
+            meta.block_number.unwrap(),
+            tx_data,
+        )
+        .await?;
+        let balance_pis = BalancePublicInputs::from_pis(&balance_proof.public_inputs);
+        // validation
+        if balance_pis.public_state.block_number != meta.block_number.unwrap() {
+            return Err(SyncError::BalanceProofBlockNumberMismatch {
+                balance_proof_block_number: balance_pis.public_state.block_number,
+                block_number: meta.block_number.unwrap(),
+            });
+        }
+        if balance_pis.private_commitment != user_data.private_commitment() {
+            return Err(SyncError::InternalError(
+                "private commitment mismatch".to_string(),
+            ));
+        }
 
+        // update user data
+        let balance_proof = CompressedBalanceProof::new(&balance_proof)?;
+        user_data.balance_proof = Some(balance_proof);
+        user_data.set_tx_lpt(meta.timestamp);
+        user_data.processed_tx_uuids.insert(meta);
         Ok(())
     }
 
@@ -259,6 +500,7 @@ where
         key: KeySet,
         meta: &MetaData,
         withdrawal_data: &TransferData,
+        acks: &mut Acknowledgements,
     ) -> Result<(), SyncError> {
         log::info!("sync_withdrawal: {:?}", meta);
         if meta.block_number.is_none() {
@@ -285,6 +527,17 @@ where
                     meta.uuid,
                     e
                 );
+                let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
+                user_data.add_rejected_transfer(
+                    meta,
+                    withdrawal_data.sender,
+                    withdrawal_data.transfer,
+                    &e,
+                );
+                self.store_vault_server
+                    .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
+                    .await?;
+                acks.push(DataType::Withdrawal, meta, None);
                 return Ok(());
             }
             Err(e) => return Err(e),
@@ -311,28 +564,54 @@ where
 
         // update user data
         let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
-        user_data.processed_withdrawal_uuids.push(meta.uuid.clone());
+        user_data.processed_withdrawal_uuids.insert(meta);
         self.store_vault_server
             .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
             .await?;
+        acks.push(
+            DataType::Withdrawal,
+            meta,
+            Some((
+                withdrawal_data.sender_proof_set_ephemeral_key,
+                withdrawal_data.transfer_index,
+            )),
+        );
 
         Ok(())
     }
 
-    async fn update_deposit_lpt(&self, key: KeySet, timestamp: u64) -> Result<(), SyncError> {
+    // Record the skipped deposits before the lpt passes them
+    async fn update_deposit_lpt(
+        &self,
+        key: KeySet,
+        timestamp: u64,
+        skipped: &[MetaData],
+    ) -> Result<(), SyncError> {
         log::info!("update_deposit_lpt: {:?}", timestamp);
         let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
-        user_data.deposit_lpt = timestamp;
+        for meta in skipped {
+            user_data.processed_deposit_uuids.skip(meta);
+        }
+        user_data.set_deposit_lpt(timestamp);
         self.store_vault_server
             .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
             .await?;
         Ok(())
     }
 
-    async fn update_transfer_lpt(&self, key: KeySet, timestamp: u64) -> Result<(), SyncError> {
+    // Record the skipped transfers before the lpt passes them
+    async fn update_transfer_lpt(
+        &self,
+        key: KeySet,
+        timestamp: u64,
+        skipped: &[MetaData],
+    ) -> Result<(), SyncError> {
         log::info!("update_transfer_lpt: {:?}", timestamp);
         let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
-        user_data.transfer_lpt = timestamp;
+        for meta in skipped {
+            user_data.processed_transfer_uuids.skip(meta);
+        }
+        user_data.set_transfer_lpt(timestamp);
         self.store_vault_server
             .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
             .await?;
@@ -342,7 +621,7 @@ where
     async fn update_withdrawal_lpt(&self, key: KeySet, timestamp: u64) -> Result<(), SyncError> {
         log::info!("update_withdrawal_lpt: {:?}", timestamp);
         let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
-        user_data.withdrawal_lpt = timestamp;
+        user_data.set_withdrawal_lpt(timestamp);
         self.store_vault_server
             .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
             .await?;
@@ -354,52 +633,65 @@ where
         key: KeySet,
         meta: &MetaData,
         tx_data: &TxData,
+        acks: &mut Acknowledgements,
     ) -> Result<(), SyncError> {
         log::info!("sync_tx: {:?}", meta);
-        if meta.block_number.is_none() {
-            return Err(SyncError::InternalError(
-                "block number is not set".to_string(),
-            ));
-        }
         let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
-        let prev_balance_proof = get_balance_proof(&user_data)?;
-        let balance_proof = update_send_by_sender(
-            &self.validity_prover,
-            &self.balance_prover,
-            key,
-            &mut user_data.full_private_state,
-            &prev_balance_proof,
-            meta.block_number.unwrap(),
-            tx_data,
-        )
-        .await?;
-        let balance_pis = BalancePublicInputs::from_pis(&balance_proof.public_inputs);
-        // validation
-        if balance_pis.public_state.block_number != meta.block_number.unwrap() {
-            return Err(SyncError::BalanceProofBlockNumberMismatch {
-                balance_proof_block_number: balance_pis.public_state.block_number,
-                block_number: meta.block_number.unwrap(),
-            });
-        }
-        if balance_pis.private_commitment != user_data.private_commitment() {
-            return Err(SyncError::InternalError(
-                "private commitment mismatch".to_string(),
-            ));
-        }
-
-        // update user data
-        let balance_proof = CompressedBalanceProof::new(&balance_proof)?;
-        user_data.balance_proof = Some(balance_proof);
-        user_data.tx_lpt = meta.timestamp;
-        user_data.processed_tx_uuids.push(meta.uuid.clone());
+        self.apply_tx(key, &mut user_data, meta, tx_data).await?;
 
         // save user data
         self.store_vault_server
             .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
             .await?;
+        // the sender proof set is kept for the recipients
+        acks.push(DataType::Tx, meta, None);
         Ok(())
     }
 
+    /// Acknowledges the processed entries and the transfers of the sender proof sets they
+    /// consumed to the store vault server, so that they can be garbage collected. Skipped if the
+    /// server has no GC. Failures are only logged since the entries are already recorded as
+    /// processed in the user data.
+    async fn acknowledge(&self, key: KeySet, acks: Acknowledgements) {
+        if acks.is_empty() {
+            return;
+        }
+        match self.store_vault_server.is_gc_enabled().await {
+            Ok(true) => {}
+            Ok(false) => return,
+            // acknowledged anyway, since a server with GC would keep the entries until they expire
+            Err(e) => log::warn!("failed to check if the store vault has GC: {}", e),
+        }
+        for (data_type, uuids) in acks.uuids {
+            if let Err(e) = self
+                .store_vault_server
+                .acknowledge_data(key, data_type, &uuids)
+                .await
+            {
+                log::warn!(
+                    "failed to acknowledge {} {} entries: {}",
+                    uuids.len(),
+                    data_type,
+                    e
+                );
+            }
+        }
+        for (ephemeral_privkey, transfer_index) in acks.sender_proof_sets {
+            let ephemeral_key = KeySet::new(BigUint::from(ephemeral_privkey).into());
+            if let Err(e) = self
+                .store_vault_server
+                .acknowledge_sender_proof_set(ephemeral_key, transfer_index)
+                .await
+            {
+                log::warn!(
+                    "failed to acknowledge transfer {} of sender proof set: {}",
+                    transfer_index,
+                    e
+                );
+            }
+        }
+    }
+
     async fn update_no_send(&self, key: KeySet, to_block_number: u32) -> Result<(), SyncError> {
         log::info!("update_no_send: {:?}", to_block_number);
         let (mut user_data, digest) = self.get_user_data_and_digest(key).await?;
@@ -408,31 +700,9 @@ where
             user_data.block_number()?,
             to_block_number
         );
-        let prev_balance_proof = get_balance_proof(&user_data)?;
-        let new_balance_proof = update_no_send(
-            &self.validity_prover,
-            &self.balance_prover,
-            key,
-            &prev_balance_proof,
-            to_block_number,
-        )
-        .await?;
-        let new_balance_pis = BalancePublicInputs::from_pis(&new_balance_proof.public_inputs);
-        let new_block_number = new_balance_pis.public_state.block_number;
-        if new_block_number != to_block_number {
-            return Err(SyncError::BalanceProofBlockNumberMismatch {
-                balance_proof_block_number: new_block_number,
-                block_number: to_block_number,
-            });
-        }
-        if new_balance_pis.private_commitment != user_data.private_commitment() {
-            return Err(SyncError::InternalError(
-                "private commitment mismatch".to_string(),
-            ));
-        }
+        self.apply_no_send(key, &mut user_data, to_block_number)
+            .await?;
         // update user data
-        let balance_proof = CompressedBalanceProof::new(&new_balance_proof)?;
-        user_data.balance_proof = Some(balance_proof);
         self.store_vault_server
             .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
             .await?;
@@ -440,3 +710,176 @@ where
         Ok(())
     }
 }
+
+/// Entries processed during a sync, acknowledged together once it ends
+#[derive(Debug, Default)]
+struct Acknowledgements {
+    uuids: HashMap<DataType, Vec<String>>,
+    // ephemeral private keys and transfer indices of the consumed sender proof sets
+    sender_proof_sets: Vec<(U256, u32)>,
+}
+
+impl Acknowledgements {
+    fn push(
+        &mut self,
+        data_type: DataType,
+        meta: &MetaData,
+        sender_proof_set: Option<(U256, u32)>,
+    ) {
+        self.uuids
+            .entry(data_type)
+            .or_default()
+            .push(meta.uuid.clone());
+        self.sender_proof_sets.extend(sender_proof_set);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.uuids.is_empty() && self.sender_proof_sets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::{
+        api::store_vault_server::interface::DataType,
+        data::{
+            deposit_data::{DepositData, TokenType},
+            meta_data::MetaData,
+            transfer_data::MAX_MEMO_LEN,
+            user_data::UserData,
+        },
+    };
+    use intmax2_zkp::{
+        common::signature::key_set::KeySet,
+        ethereum_types::{
+            address::Address, bytes32::Bytes32, u256::U256, u32limb_trait::U32LimbTrait as _,
+        },
+    };
+
+    use crate::client::{
+        strategy::deposit::{DepositRecheckResult, DepositRejectionReason},
+        test_utils::{mock_client, push_transfer, MockStoreVault, MockValidityProver},
+    };
+
+    use super::{utils::generate_salt, Acknowledgements};
+
+    fn deposit(token_index: Option<u32>, uuid: &str, timestamp: u64) -> (MetaData, DepositData) {
+        let meta = MetaData {
+            uuid: uuid.to_string(),
+            timestamp,
+            block_number: None,
+        };
+        let mut rng = rand::thread_rng();
+        let deposit_data = DepositData {
+            deposit_salt: generate_salt(),
+            depositor: Address::rand(&mut rng),
+            pubkey_salt_hash: Bytes32::rand(&mut rng),
+            amount: U256::default(),
+            is_eligible: true,
+            token_type: TokenType::ERC20,
+            token_address: Address::rand(&mut rng),
+            token_id: U256::default(),
+            token_index,
+        };
+        (meta, deposit_data)
+    }
+
+    #[tokio::test]
+    async fn test_recheck_deposit() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let now = chrono::Utc::now().timestamp() as u64;
+        let store_vault = MockStoreVault::default();
+        let client = mock_client(store_vault.clone(), MockValidityProver::default());
+        let deposit_timeout = client.config.deposit_timeout;
+
+        // not relayed within the timeout
+        let (meta, deposit_data) = deposit(Some(1), "timed-out", now - deposit_timeout - 1);
+        let result = client
+            .recheck_fetched_deposit(key, meta, deposit_data)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            DepositRecheckResult::Rejected(DepositRejectionReason::Timeout)
+        );
+
+        // not relayed, but still within the timeout
+        let (meta, deposit_data) = deposit(Some(1), "pending", now - 10);
+        let result = client
+            .recheck_fetched_deposit(key, meta, deposit_data)
+            .await
+            .unwrap();
+        assert_eq!(result, DepositRecheckResult::Pending);
+
+        // the token is not registered, so the deposit cannot be relayed regardless of its age
+        let (meta, deposit_data) = deposit(None, "unknown-token", now - 10);
+        let result = client
+            .recheck_fetched_deposit(key, meta, deposit_data)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            DepositRecheckResult::Rejected(DepositRejectionReason::UnknownToken)
+        );
+
+        // already incorporated into the balance
+        let (meta, deposit_data) = deposit(Some(1), "processed", now - deposit_timeout - 1);
+        let mut user_data = UserData::new(key.pubkey);
+        user_data.processed_deposit_uuids.insert(&meta);
+        store_vault
+            .user_data
+            .borrow_mut()
+            .insert(key.pubkey, user_data.encrypt(key.pubkey));
+        let result = client
+            .recheck_fetched_deposit(key, meta, deposit_data)
+            .await
+            .unwrap();
+        assert_eq!(result, DepositRecheckResult::AlreadyProcessed);
+    }
+
+    #[tokio::test]
+    async fn test_sync_accepts_over_long_memo() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let now = chrono::Utc::now().timestamp() as u64;
+        let store_vault = MockStoreVault::default();
+        // sent by a client that does not enforce the memo limit
+        let memo = "a".repeat(MAX_MEMO_LEN + 1);
+        push_transfer(&store_vault, key.pubkey, "long-memo", now - 10, Some(memo));
+
+        let client = mock_client(store_vault, MockValidityProver::default());
+        let pending = client.sync(key).await.unwrap();
+        assert_eq!(pending.pending_transfers.len(), 1);
+        let (meta, transfer_data) = &pending.pending_transfers[0];
+        assert_eq!(meta.uuid, "long-memo");
+        assert_eq!(transfer_data.memo, Some("a".repeat(MAX_MEMO_LEN)));
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge_batches_and_skips_without_gc() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let store_vault = MockStoreVault::default();
+        let client = mock_client(store_vault.clone(), MockValidityProver::default());
+        let acks = || {
+            let mut acks = Acknowledgements::default();
+            for uuid in ["a", "b"] {
+                let (meta, _) = deposit(Some(1), uuid, 0);
+                acks.push(DataType::Deposit, &meta, None);
+            }
+            acks
+        };
+
+        // the server keeps everything until retention, so nothing is sent
+        client.acknowledge(key, acks()).await;
+        assert!(store_vault.acknowledged.borrow().is_empty());
+
+        store_vault.gc_enabled.set(true);
+        client.acknowledge(key, acks()).await;
+        assert_eq!(
+            *store_vault.acknowledged.borrow(),
+            vec![
+                (DataType::Deposit, "a".to_string()),
+                (DataType::Deposit, "b".to_string())
+            ]
+        );
+    }
+}
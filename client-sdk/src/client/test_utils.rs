@@ -0,0 +1,598 @@
+//! In-memory mocks of the servers, to test the client logic without running them. The methods
+//! that the tests do not need are left unimplemented.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+use async_trait::async_trait;
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::{BlockBuilderClientInterface, BlockBuilderStatus, FeeProof},
+        error::{ErrorCode, ServerError},
+        store_vault_server::{
+            interface::{DataSubscription, DataType, SaveDataEntry, StoreVaultClientInterface},
+            types::{
+                DataWithMetaData, DeleteMyDataResponse, RequestDataDeletionResponse,
+                SaveDataBatchRequest, UserDataVersion,
+            },
+        },
+        validity_prover::interface::{AccountInfo, DepositInfo, ValidityProverClientInterface},
+        withdrawal_server::interface::{Fee, WithdrawalInfo, WithdrawalServerClientInterface},
+    },
+    data::{
+        meta_data::MetaData,
+        proof_compression::{CompressedBalanceProof, CompressedSpentProof},
+        sender_proof_set::SenderProofSet,
+        transfer_data::TransferData,
+    },
+    utils::{digest::get_digest, signature::WithAuth},
+};
+use intmax2_zkp::{
+    circuits::validity::validity_pis::ValidityPublicInputs,
+    common::{
+        block_builder::BlockProposal,
+        generic_address::GenericAddress,
+        signature::{flatten::FlatG2, key_set::KeySet},
+        transfer::Transfer,
+        trees::{
+            block_hash_tree::BlockHashMerkleProof, deposit_tree::DepositMerkleProof,
+            sender_tree::SenderLeaf, transfer_tree::TransferTree, tx_tree::TxTree,
+        },
+        tx::Tx,
+        witness::{
+            receive_deposit_witness::ReceiveDepositWitness,
+            receive_transfer_witness::ReceiveTransferWitness, spent_witness::SpentWitness,
+            tx_witness::TxWitness, update_witness::UpdateWitness,
+            withdrawal_witness::WithdrawalWitness,
+        },
+    },
+    constants::{TRANSFER_TREE_HEIGHT, TX_TREE_HEIGHT},
+    ethereum_types::{address::Address, bytes32::Bytes32, u256::U256},
+};
+use num_bigint::BigUint;
+use plonky2::{
+    field::goldilocks_field::GoldilocksField,
+    plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
+};
+
+use crate::external_api::contract::{
+    liquidity_contract::LiquidityContract, rollup_contract::RollupContract,
+};
+
+use super::{client::Client, config::ClientConfig};
+
+type F = GoldilocksField;
+type C = PoseidonGoldilocksConfig;
+const D: usize = 2;
+
+pub(crate) type MockClient = Client<
+    MockBlockBuilder,
+    MockStoreVault,
+    MockValidityProver,
+    MockBalanceProver,
+    MockWithdrawalServer,
+>;
+
+/// Client with the given mocks. The contracts point to an unreachable rpc, so the tests must not
+/// depend on them.
+pub(crate) fn mock_client(
+    store_vault_server: MockStoreVault,
+    validity_prover: MockValidityProver,
+) -> MockClient {
+    Client {
+        config: ClientConfig::default(),
+        block_builder: MockBlockBuilder::default(),
+        store_vault_server,
+        validity_prover,
+        balance_prover: MockBalanceProver,
+        withdrawal_server: MockWithdrawalServer::default(),
+        liquidity_contract: LiquidityContract::new("http://127.0.0.1:0", 0, Default::default()),
+        rollup_contract: RollupContract::new("http://127.0.0.1:0", 0, Default::default(), 0),
+    }
+}
+
+/// Transfer to `recipient` whose sender proof set is saved to the store vault. The proofs of the
+/// sender proof set are placeholders, so the transfer cannot be incorporated into a balance proof.
+pub(crate) fn push_transfer(
+    store_vault_server: &MockStoreVault,
+    recipient: U256,
+    uuid: &str,
+    timestamp: u64,
+    memo: Option<String>,
+) -> TransferData {
+    let mut rng = rand::thread_rng();
+    let mut transfer = Transfer::rand(&mut rng);
+    transfer.recipient = GenericAddress::from_pubkey(recipient);
+    let mut transfer_tree = TransferTree::new(TRANSFER_TREE_HEIGHT);
+    transfer_tree.push(transfer);
+    let tx = Tx {
+        transfer_tree_root: transfer_tree.get_root(),
+        nonce: 0,
+    };
+    let mut tx_tree = TxTree::new(TX_TREE_HEIGHT);
+    tx_tree.push(tx);
+    let ephemeral_key = KeySet::rand(&mut rng);
+    let transfer_data = TransferData {
+        sender_proof_set_ephemeral_key: BigUint::from(ephemeral_key.privkey).try_into().unwrap(),
+        sender_proof_set: None,
+        sender: U256::rand(&mut rng),
+        tx,
+        tx_index: 0,
+        tx_merkle_proof: tx_tree.prove(0),
+        tx_tree_root: tx_tree.get_root().into(),
+        transfer,
+        transfer_index: 0,
+        transfer_merkle_proof: transfer_tree.prove(0),
+        memo,
+    };
+    let sender_proof_set = SenderProofSet {
+        spent_proof: CompressedSpentProof(vec![0; 32]),
+        prev_balance_proof: CompressedBalanceProof(vec![0; 32]),
+    };
+    store_vault_server.sender_proof_sets.borrow_mut().insert(
+        ephemeral_key.pubkey,
+        sender_proof_set.encrypt(ephemeral_key.pubkey),
+    );
+    store_vault_server.push_entry(
+        DataType::Transfer,
+        recipient,
+        uuid,
+        timestamp,
+        transfer_data.encrypt(recipient),
+    );
+    transfer_data
+}
+
+fn not_found(message: &str) -> ServerError {
+    ServerError::ApiError {
+        status: ErrorCode::NotFound.status(),
+        code: ErrorCode::NotFound,
+        message: message.to_string(),
+        retryable: false,
+        details: None,
+        url: String::new(),
+    }
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct MockStoreVault {
+    pub user_data: RefCell<HashMap<U256, Vec<u8>>>,
+    pub entries: RefCell<Vec<(DataType, U256, DataWithMetaData)>>,
+    pub sender_proof_sets: RefCell<HashMap<U256, Vec<u8>>>,
+    pub acknowledged: RefCell<Vec<(DataType, String)>>,
+    pub gc_enabled: Cell<bool>,
+}
+
+impl MockStoreVault {
+    /// Saves an entry to the inbox of `pubkey` with the given timestamp
+    pub fn push_entry(
+        &self,
+        data_type: DataType,
+        pubkey: U256,
+        uuid: &str,
+        timestamp: u64,
+        data: Vec<u8>,
+    ) {
+        let meta = MetaData {
+            uuid: uuid.to_string(),
+            timestamp,
+            block_number: None,
+        };
+        self.entries
+            .borrow_mut()
+            .push((data_type, pubkey, DataWithMetaData { meta, data }));
+    }
+}
+
+#[async_trait(?Send)]
+impl StoreVaultClientInterface for MockStoreVault {
+    async fn save_user_data(
+        &self,
+        key: KeySet,
+        prev_digest: Option<Bytes32>,
+        encrypted_data: &[u8],
+    ) -> Result<(), ServerError> {
+        let mut user_data = self.user_data.borrow_mut();
+        let digest = user_data.get(&key.pubkey).map(|data| get_digest(data));
+        if digest != prev_digest {
+            return Err(ServerError::InvalidResponse("digest mismatch".to_string()));
+        }
+        user_data.insert(key.pubkey, encrypted_data.to_vec());
+        Ok(())
+    }
+
+    async fn get_user_data(&self, key: KeySet) -> Result<Option<Vec<u8>>, ServerError> {
+        Ok(self.user_data.borrow().get(&key.pubkey).cloned())
+    }
+
+    async fn list_user_data_versions(
+        &self,
+        _key: KeySet,
+    ) -> Result<Vec<UserDataVersion>, ServerError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_user_data_version(
+        &self,
+        _key: KeySet,
+        _version: u64,
+    ) -> Result<Option<Vec<u8>>, ServerError> {
+        Ok(None)
+    }
+
+    async fn save_sender_proof_set(
+        &self,
+        ephemeral_key: KeySet,
+        encrypted_data: &[u8],
+        _num_transfers: Option<u32>,
+    ) -> Result<(), ServerError> {
+        self.sender_proof_sets
+            .borrow_mut()
+            .insert(ephemeral_key.pubkey, encrypted_data.to_vec());
+        Ok(())
+    }
+
+    async fn get_sender_proof_set(&self, ephemeral_key: KeySet) -> Result<Vec<u8>, ServerError> {
+        self.sender_proof_sets
+            .borrow()
+            .get(&ephemeral_key.pubkey)
+            .cloned()
+            .ok_or_else(|| not_found("sender proof set not found"))
+    }
+
+    async fn acknowledge_sender_proof_set(
+        &self,
+        _ephemeral_key: KeySet,
+        _transfer_index: u32,
+    ) -> Result<(), ServerError> {
+        Ok(())
+    }
+
+    async fn save_data_batch(
+        &self,
+        _key: KeySet,
+        _entries: &[SaveDataEntry],
+    ) -> Result<Vec<String>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn save_data_batch_with_auth(
+        &self,
+        _request: &WithAuth<SaveDataBatchRequest>,
+    ) -> Result<Vec<String>, ServerError> {
+        unimplemented!()
+    }
+
+    fn attach_pow(&self, _writer: U256, _entries: &mut [SaveDataEntry]) {}
+
+    async fn get_data_all_after(
+        &self,
+        data_type: DataType,
+        key: KeySet,
+        timestamp: u64,
+    ) -> Result<Vec<DataWithMetaData>, ServerError> {
+        let mut entries = self
+            .entries
+            .borrow()
+            .iter()
+            .filter(|(t, pubkey, entry)| {
+                *t == data_type && *pubkey == key.pubkey && entry.meta.timestamp >= timestamp
+            })
+            .map(|(_, _, entry)| entry.clone())
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| (entry.meta.timestamp, entry.meta.uuid.clone()));
+        Ok(entries)
+    }
+
+    async fn get_data_batch(
+        &self,
+        key: KeySet,
+        data_type: DataType,
+        uuids: &[String],
+    ) -> Result<Vec<DataWithMetaData>, ServerError> {
+        Ok(self
+            .entries
+            .borrow()
+            .iter()
+            .filter(|(t, pubkey, entry)| {
+                *t == data_type && *pubkey == key.pubkey && uuids.contains(&entry.meta.uuid)
+            })
+            .map(|(_, _, entry)| entry.clone())
+            .collect())
+    }
+
+    async fn acknowledge_data(
+        &self,
+        _key: KeySet,
+        data_type: DataType,
+        uuids: &[String],
+    ) -> Result<(), ServerError> {
+        self.acknowledged
+            .borrow_mut()
+            .extend(uuids.iter().map(|uuid| (data_type, uuid.clone())));
+        Ok(())
+    }
+
+    async fn is_gc_enabled(&self) -> Result<bool, ServerError> {
+        Ok(self.gc_enabled.get())
+    }
+
+    async fn request_data_deletion(
+        &self,
+        _key: KeySet,
+    ) -> Result<RequestDataDeletionResponse, ServerError> {
+        unimplemented!()
+    }
+
+    async fn delete_my_data(
+        &self,
+        _key: KeySet,
+        _token: &str,
+        _sender_proof_set_keys: &[KeySet],
+    ) -> Result<DeleteMyDataResponse, ServerError> {
+        unimplemented!()
+    }
+
+    async fn subscribe(
+        &self,
+        _key: KeySet,
+        _data_types: &[DataType],
+    ) -> Result<Box<dyn DataSubscription>, ServerError> {
+        unimplemented!()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MockValidityProver {
+    pub block_number: Cell<u32>,
+    pub deposit_infos: RefCell<HashMap<Bytes32, DepositInfo>>,
+    pub tx_tree_roots: RefCell<HashMap<Bytes32, u32>>,
+    pub validity_pis: HashMap<u32, ValidityPublicInputs>,
+    pub sender_leaves: HashMap<u32, Vec<SenderLeaf>>,
+    pub block_merkle_proofs: HashMap<(u32, u32), BlockHashMerkleProof>,
+    pub account_ids: HashMap<U256, u64>,
+    /// Number of calls to the implemented methods
+    pub num_calls: Cell<usize>,
+}
+
+impl MockValidityProver {
+    fn count_call(&self) {
+        self.num_calls.set(self.num_calls.get() + 1);
+    }
+}
+
+#[async_trait(?Send)]
+impl ValidityProverClientInterface for MockValidityProver {
+    async fn get_block_number(&self) -> Result<u32, ServerError> {
+        self.count_call();
+        Ok(self.block_number.get())
+    }
+
+    async fn get_next_deposit_index(&self) -> Result<u32, ServerError> {
+        self.count_call();
+        Ok(self.deposit_infos.borrow().len() as u32)
+    }
+
+    async fn get_update_witness(
+        &self,
+        _pubkey: U256,
+        _root_block_number: u32,
+        _leaf_block_number: u32,
+        _is_prev_account_tree: bool,
+    ) -> Result<UpdateWitness<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn get_deposit_info(
+        &self,
+        deposit_hash: Bytes32,
+    ) -> Result<Option<DepositInfo>, ServerError> {
+        self.count_call();
+        Ok(self.deposit_infos.borrow().get(&deposit_hash).cloned())
+    }
+
+    async fn get_block_number_by_tx_tree_root(
+        &self,
+        tx_tree_root: Bytes32,
+    ) -> Result<Option<u32>, ServerError> {
+        self.count_call();
+        Ok(self.tx_tree_roots.borrow().get(&tx_tree_root).cloned())
+    }
+
+    async fn get_validity_pis(
+        &self,
+        block_number: u32,
+    ) -> Result<Option<ValidityPublicInputs>, ServerError> {
+        self.count_call();
+        Ok(self.validity_pis.get(&block_number).cloned())
+    }
+
+    async fn get_sender_leaves(
+        &self,
+        block_number: u32,
+    ) -> Result<Option<Vec<SenderLeaf>>, ServerError> {
+        self.count_call();
+        Ok(self.sender_leaves.get(&block_number).cloned())
+    }
+
+    async fn get_block_merkle_proof(
+        &self,
+        root_block_number: u32,
+        leaf_block_number: u32,
+    ) -> Result<BlockHashMerkleProof, ServerError> {
+        self.count_call();
+        self.block_merkle_proofs
+            .get(&(root_block_number, leaf_block_number))
+            .cloned()
+            .ok_or_else(|| not_found("block merkle proof not found"))
+    }
+
+    async fn get_deposit_merkle_proof(
+        &self,
+        _block_number: u32,
+        _deposit_index: u32,
+    ) -> Result<DepositMerkleProof, ServerError> {
+        unimplemented!()
+    }
+
+    async fn get_account_info(&self, pubkey: U256) -> Result<AccountInfo, ServerError> {
+        self.count_call();
+        Ok(AccountInfo {
+            account_id: self.account_ids.get(&pubkey).cloned(),
+            block_number: self.block_number.get(),
+        })
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MockBlockBuilder {
+    /// Number of `query_proposal` calls before the proposal is returned
+    pub proposal_delay: RefCell<u32>,
+    pub proposal: Option<BlockProposal>,
+}
+
+#[async_trait(?Send)]
+impl BlockBuilderClientInterface for MockBlockBuilder {
+    async fn get_status(
+        &self,
+        _block_builder_url: &str,
+        _is_registration_block: bool,
+    ) -> Result<BlockBuilderStatus, ServerError> {
+        Ok(BlockBuilderStatus::AcceptingTxs)
+    }
+
+    async fn send_tx_request(
+        &self,
+        _block_builder_url: &str,
+        _is_registration_block: bool,
+        _pubkey: U256,
+        _tx: Tx,
+        _fee_proof: Option<FeeProof>,
+    ) -> Result<(), ServerError> {
+        Ok(())
+    }
+
+    async fn query_proposal(
+        &self,
+        _block_builder_url: &str,
+        _is_registration_block: bool,
+        _pubkey: U256,
+        _tx: Tx,
+    ) -> Result<Option<BlockProposal>, ServerError> {
+        let mut delay = self.proposal_delay.borrow_mut();
+        if *delay > 0 {
+            *delay -= 1;
+            return Ok(None);
+        }
+        Ok(self.proposal.clone())
+    }
+
+    async fn post_signature(
+        &self,
+        _block_builder_url: &str,
+        _is_registration_block: bool,
+        _pubkey: U256,
+        _tx: Tx,
+        _signature: FlatG2,
+    ) -> Result<(), ServerError> {
+        Ok(())
+    }
+}
+
+/// Balance prover that cannot prove. The tests use scenarios that need no balance proof.
+pub(crate) struct MockBalanceProver;
+
+#[async_trait(?Send)]
+impl BalanceProverClientInterface for MockBalanceProver {
+    async fn prove_spent(
+        &self,
+        _key: KeySet,
+        _spent_witness: &SpentWitness,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn prove_send(
+        &self,
+        _key: KeySet,
+        _pubkey: U256,
+        _tx_witness: &TxWitness,
+        _update_witness: &UpdateWitness<F, C, D>,
+        _spent_proof: &ProofWithPublicInputs<F, C, D>,
+        _prev_proof: &Option<ProofWithPublicInputs<F, C, D>>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn prove_update(
+        &self,
+        _key: KeySet,
+        _pubkey: U256,
+        _update_witness: &UpdateWitness<F, C, D>,
+        _prev_proof: &Option<ProofWithPublicInputs<F, C, D>>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn prove_receive_transfer(
+        &self,
+        _key: KeySet,
+        _pubkey: U256,
+        _receive_transfer_witness: &ReceiveTransferWitness<F, C, D>,
+        _prev_proof: &Option<ProofWithPublicInputs<F, C, D>>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn prove_receive_deposit(
+        &self,
+        _key: KeySet,
+        _pubkey: U256,
+        _receive_deposit_witness: &ReceiveDepositWitness,
+        _prev_proof: &Option<ProofWithPublicInputs<F, C, D>>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+
+    async fn prove_single_withdrawal(
+        &self,
+        _key: KeySet,
+        _withdrawal_witness: &WithdrawalWitness<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ServerError> {
+        unimplemented!()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MockWithdrawalServer {
+    pub withdrawals: Vec<WithdrawalInfo>,
+}
+
+#[async_trait(?Send)]
+impl WithdrawalServerClientInterface for MockWithdrawalServer {
+    async fn fee(&self) -> Result<Vec<Fee>, ServerError> {
+        Ok(Vec::new())
+    }
+
+    async fn request_withdrawal(
+        &self,
+        _pubkey: U256,
+        _single_withdrawal_proof: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<(), ServerError> {
+        unimplemented!()
+    }
+
+    async fn get_withdrawal_info(&self, _key: KeySet) -> Result<Vec<WithdrawalInfo>, ServerError> {
+        Ok(self.withdrawals.clone())
+    }
+
+    async fn get_withdrawal_info_by_recipient(
+        &self,
+        _recipient: Address,
+    ) -> Result<Vec<WithdrawalInfo>, ServerError> {
+        Ok(self.withdrawals.clone())
+    }
+}
@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        error::ErrorCode,
+        store_vault_server::{interface::StoreVaultClientInterface, types::SaveDataBatchRequest},
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    utils::{
+        signature::{SignContent, Signable as _, WithAuth},
+        threshold_signature::{
+            combine_auth, combine_proposal_signature, partial_sign_message, partial_sign_proposal,
+            PartialSignature, ThresholdKeyShare,
+        },
+    },
+};
+use intmax2_zkp::{
+    common::{block_builder::BlockProposal, transfer::Transfer, tx::Tx},
+    ethereum_types::u256::U256,
+};
+
+use super::{
+    client::{finalize_entries, tx_result, Client, TxRequestMemo, TxResult},
+    error::ClientError,
+    sync::utils::generate_transfer_tree,
+};
+
+// Seconds for which the threshold signed auth is valid. Longer than the auth signed with the
+// full key, because the co-signers may take a while to respond.
+const THRESHOLD_AUTH_EXPIRY: u64 = 300;
+
+/// A holder of a key share of a threshold account, e.g. a remote signing service or a hardware
+/// device.
+#[async_trait(?Send)]
+pub trait CoSignerInterface {
+    /// Partial signature of the block proposal. The co-signer is expected to check the tx and its
+    /// transfers before signing.
+    async fn sign_proposal(
+        &self,
+        tx: Tx,
+        transfers: &[Transfer],
+        proposal: &BlockProposal,
+    ) -> Result<PartialSignature, ClientError>;
+
+    /// Partial signature of the auth of the store vault request that saves the tx data.
+    async fn sign_auth(&self, content: &SignContent) -> Result<PartialSignature, ClientError>;
+}
+
+/// Co-signer holding its key share in memory.
+#[derive(Debug, Clone)]
+pub struct LocalCoSigner {
+    pub share: ThresholdKeyShare,
+}
+
+impl LocalCoSigner {
+    pub fn new(share: ThresholdKeyShare) -> Self {
+        Self { share }
+    }
+}
+
+#[async_trait(?Send)]
+impl CoSignerInterface for LocalCoSigner {
+    async fn sign_proposal(
+        &self,
+        tx: Tx,
+        transfers: &[Transfer],
+        proposal: &BlockProposal,
+    ) -> Result<PartialSignature, ClientError> {
+        proposal
+            .verify(tx)
+            .map_err(|e| ClientError::InvalidBlockProposal(format!("{}", e)))?;
+        if generate_transfer_tree(transfers).get_root() != tx.transfer_tree_root {
+            return Err(ClientError::InvalidBlockProposal(
+                "transfers do not match the tx".to_string(),
+            ));
+        }
+        Ok(partial_sign_proposal(&self.share, proposal))
+    }
+
+    async fn sign_auth(&self, content: &SignContent) -> Result<PartialSignature, ClientError> {
+        if content.pubkey != self.share.pubkey {
+            return Err(ClientError::ThresholdSignatureError(
+                "sign content is not of the shared account".to_string(),
+            ));
+        }
+        Ok(partial_sign_message(&self.share, content.hash()))
+    }
+}
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// Same as `finalize_tx` for an account whose key is split into shares, collecting partial
+    /// signatures from the co-signers until `threshold` of them are obtained.
+    ///
+    /// Only the signatures of the tx and of the store vault request are threshold controlled.
+    /// The data of the account is encrypted to its key, so preparing the tx request
+    /// (`sync`, `send_tx_request`) still requires the full `KeySet`. The co-signers gate each tx
+    /// of the process holding the key; they do not keep the key out of it.
+    pub async fn finalize_tx_with_cosigners(
+        &self,
+        block_builder_url: &str,
+        pubkey: U256,
+        threshold: u32,
+        co_signers: &[&dyn CoSignerInterface],
+        memo: &TxRequestMemo,
+        proposal: &BlockProposal,
+    ) -> Result<TxResult, ClientError> {
+        // verify proposal
+        proposal
+            .verify(memo.tx)
+            .map_err(|e| ClientError::InvalidBlockProposal(format!("{}", e)))?;
+
+        let mut entries = finalize_entries(pubkey, memo, proposal);
+        let mut retried = false;
+        let uuids = loop {
+            self.store_vault_server.attach_pow(pubkey, &mut entries);
+            let request = SaveDataBatchRequest {
+                data: entries.clone(),
+            };
+            let sign_content = SignContent::new(pubkey, THRESHOLD_AUTH_EXPIRY, &request.content());
+            let mut partials = vec![];
+            for co_signer in co_signers {
+                if partials.len() == threshold as usize {
+                    break;
+                }
+                match co_signer.sign_auth(&sign_content).await {
+                    Ok(partial) => partials.push(partial),
+                    Err(e) => log::warn!("co-signer failed to sign auth: {}", e),
+                }
+            }
+            let auth = combine_auth(threshold, &sign_content, &partials)
+                .map_err(|e| ClientError::ThresholdSignatureError(e.to_string()))?;
+            match self
+                .store_vault_server
+                .save_data_batch_with_auth(&WithAuth {
+                    inner: request,
+                    auth,
+                })
+                .await
+            {
+                // the difficulty is learned from the error, so sign again with the proof of work
+                Err(e) if !retried && e.code() == Some(ErrorCode::ProofOfWorkRequired) => {
+                    retried = true;
+                }
+                result => break result?,
+            }
+        };
+
+        // sign and post signature
+        let mut partials = vec![];
+        for co_signer in co_signers {
+            if partials.len() == threshold as usize {
+                break;
+            }
+            match co_signer
+                .sign_proposal(memo.tx, &memo.transfers, proposal)
+                .await
+            {
+                Ok(partial) => partials.push(partial),
+                Err(e) => log::warn!("co-signer failed to sign proposal: {}", e),
+            }
+        }
+        let signature = combine_proposal_signature(pubkey, threshold, proposal, &partials)
+            .map_err(|e| ClientError::ThresholdSignatureError(e.to_string()))?;
+        self.block_builder
+            .post_signature(
+                block_builder_url,
+                memo.is_registration_block,
+                signature.pubkey,
+                memo.tx,
+                signature.signature,
+            )
+            .await?;
+
+        Ok(tx_result(proposal, &uuids, &entries))
+    }
+}
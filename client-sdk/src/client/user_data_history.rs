@@ -0,0 +1,66 @@
+use intmax2_interfaces::{
+    api::{
+        balance_prover::interface::BalanceProverClientInterface,
+        block_builder::interface::BlockBuilderClientInterface,
+        store_vault_server::{interface::StoreVaultClientInterface, types::UserDataVersion},
+        validity_prover::interface::ValidityProverClientInterface,
+        withdrawal_server::interface::WithdrawalServerClientInterface,
+    },
+    data::user_data::UserData,
+};
+use intmax2_zkp::common::signature::key_set::KeySet;
+
+use super::{client::Client, error::ClientError};
+
+impl<BB, S, V, B, W> Client<BB, S, V, B, W>
+where
+    BB: BlockBuilderClientInterface,
+    S: StoreVaultClientInterface,
+    V: ValidityProverClientInterface,
+    B: BalanceProverClientInterface,
+    W: WithdrawalServerClientInterface,
+{
+    /// List the versions of the user data kept by the store vault server, the newest first.
+    pub async fn list_user_data_versions(
+        &self,
+        key: KeySet,
+    ) -> Result<Vec<UserDataVersion>, ClientError> {
+        let versions = self.store_vault_server.list_user_data_versions(key).await?;
+        Ok(versions)
+    }
+
+    /// Fetch and decrypt a prior version of the user data.
+    pub async fn get_user_data_version(
+        &self,
+        key: KeySet,
+        version: u64,
+    ) -> Result<UserData, ClientError> {
+        let encrypted = self
+            .store_vault_server
+            .get_user_data_version(key, version)
+            .await?
+            .ok_or(ClientError::UserDataVersionError(format!(
+                "version {} not found",
+                version
+            )))?;
+        let user_data = UserData::decrypt(&encrypted, key).map_err(|e| {
+            ClientError::UserDataVersionError(format!(
+                "failed to decrypt version {}: {}",
+                version, e
+            ))
+        })?;
+        Ok(user_data)
+    }
+
+    /// Restore `version` of the user data as the newest version. The current user data is kept
+    /// as a prior version, so the rollback itself can be undone in the same way.
+    pub async fn rollback_user_data(&self, key: KeySet, version: u64) -> Result<(), ClientError> {
+        let user_data = self.get_user_data_version(key, version).await?;
+        let (_, digest) = self.get_user_data_and_digest(key).await?;
+        log::info!("rollback user data to version {}", version);
+        self.store_vault_server
+            .save_user_data(key, digest, &user_data.encrypt(key.pubkey))
+            .await?;
+        Ok(())
+    }
+}
@@ -26,7 +26,7 @@ use plonky2::{
     plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
 };
 
-use super::utils::query::post_request;
+use super::utils::transport::HttpTransport;
 
 type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
@@ -35,12 +35,18 @@ const D: usize = 2;
 #[derive(Debug, Clone)]
 pub struct BalanceProverClient {
     base_url: String,
+    transport: HttpTransport,
 }
 
 impl BalanceProverClient {
     pub fn new(base_url: &str) -> Self {
+        Self::with_transport(base_url, HttpTransport::default())
+    }
+
+    pub fn with_transport(base_url: &str, transport: HttpTransport) -> Self {
         BalanceProverClient {
             base_url: base_url.to_string(),
+            transport,
         }
     }
 }
@@ -55,12 +61,14 @@ impl BalanceProverClientInterface for BalanceProverClient {
         let request = ProveSpentRequest {
             spent_witness: spent_witness.clone(),
         };
-        let response: ProveResponse = post_request(
-            &self.base_url,
-            "/balance-prover/prove-spent",
-            Some(&request),
-        )
-        .await?;
+        let response: ProveResponse = self
+            .transport
+            .post_idempotent_request(
+                &self.base_url,
+                "/balance-prover/prove-spent",
+                Some(&request),
+            )
+            .await?;
         Ok(response.proof)
     }
 
@@ -80,8 +88,10 @@ impl BalanceProverClientInterface for BalanceProverClient {
             spent_proof: spent_proof.clone(),
             prev_proof: prev_proof.clone(),
         };
-        let response: ProveResponse =
-            post_request(&self.base_url, "/balance-prover/prove-send", Some(&request)).await?;
+        let response: ProveResponse = self
+            .transport
+            .post_idempotent_request(&self.base_url, "/balance-prover/prove-send", Some(&request))
+            .await?;
         Ok(response.proof)
     }
 
@@ -97,12 +107,14 @@ impl BalanceProverClientInterface for BalanceProverClient {
             update_witness: update_witness.clone(),
             prev_proof: prev_proof.clone(),
         };
-        let response: ProveResponse = post_request(
-            &self.base_url,
-            "/balance-prover/prove-update",
-            Some(&request),
-        )
-        .await?;
+        let response: ProveResponse = self
+            .transport
+            .post_idempotent_request(
+                &self.base_url,
+                "/balance-prover/prove-update",
+                Some(&request),
+            )
+            .await?;
         Ok(response.proof)
     }
 
@@ -118,12 +130,14 @@ impl BalanceProverClientInterface for BalanceProverClient {
             receive_transfer_witness: receive_transfer_witness.clone(),
             prev_proof: prev_proof.clone(),
         };
-        let response: ProveResponse = post_request(
-            &self.base_url,
-            "/balance-prover/prove-receive-transfer",
-            Some(&request),
-        )
-        .await?;
+        let response: ProveResponse = self
+            .transport
+            .post_idempotent_request(
+                &self.base_url,
+                "/balance-prover/prove-receive-transfer",
+                Some(&request),
+            )
+            .await?;
         Ok(response.proof)
     }
 
@@ -139,12 +153,14 @@ impl BalanceProverClientInterface for BalanceProverClient {
             receive_deposit_witness: receive_deposit_witness.clone(),
             prev_proof: prev_proof.clone(),
         };
-        let response: ProveResponse = post_request(
-            &self.base_url,
-            "/balance-prover/prove-receive-deposit",
-            Some(&request),
-        )
-        .await?;
+        let response: ProveResponse = self
+            .transport
+            .post_idempotent_request(
+                &self.base_url,
+                "/balance-prover/prove-receive-deposit",
+                Some(&request),
+            )
+            .await?;
         Ok(response.proof)
     }
 
@@ -156,12 +172,14 @@ impl BalanceProverClientInterface for BalanceProverClient {
         let request = ProveSingleWithdrawalRequest {
             withdrawal_witness: withdrawal_witness.clone(),
         };
-        let response: ProveResponse = post_request(
-            &self.base_url,
-            "/balance-prover/prove-single-withdrawal",
-            Some(&request),
-        )
-        .await?;
+        let response: ProveResponse = self
+            .transport
+            .post_idempotent_request(
+                &self.base_url,
+                "/balance-prover/prove-single-withdrawal",
+                Some(&request),
+            )
+            .await?;
         Ok(response.proof)
     }
 }
@@ -14,14 +14,20 @@ use intmax2_zkp::{
     ethereum_types::u256::U256,
 };
 
-use super::utils::query::{get_request, post_request};
+use super::utils::transport::HttpTransport;
 
 #[derive(Debug, Clone)]
-pub struct BlockBuilderClient;
+pub struct BlockBuilderClient {
+    transport: HttpTransport,
+}
 
 impl BlockBuilderClient {
     pub fn new() -> Self {
-        BlockBuilderClient
+        Self::with_transport(HttpTransport::default())
+    }
+
+    pub fn with_transport(transport: HttpTransport) -> Self {
+        BlockBuilderClient { transport }
     }
 }
 
@@ -41,12 +47,14 @@ impl BlockBuilderClientInterface for BlockBuilderClient {
         let query = GetBlockBuilderStatusQuery {
             is_registration_block,
         };
-        let response = get_request::<GetBlockBuilderStatusQuery, GetBlockBuilderStatusResponse>(
-            block_builder_url,
-            "/block-builder/status",
-            Some(query),
-        )
-        .await?;
+        let response = self
+            .transport
+            .get_request::<GetBlockBuilderStatusQuery, GetBlockBuilderStatusResponse>(
+                block_builder_url,
+                "/block-builder/status",
+                Some(query),
+            )
+            .await?;
         Ok(response.status)
     }
 
@@ -64,12 +72,13 @@ impl BlockBuilderClientInterface for BlockBuilderClient {
             tx,
             fee_proof,
         };
-        post_request::<_, ()>(
-            block_builder_url,
-            "/block-builder/tx-request",
-            Some(&request),
-        )
-        .await
+        self.transport
+            .post_request::<_, ()>(
+                block_builder_url,
+                "/block-builder/tx-request",
+                Some(&request),
+            )
+            .await
     }
 
     async fn query_proposal(
@@ -84,12 +93,14 @@ impl BlockBuilderClientInterface for BlockBuilderClient {
             pubkey,
             tx,
         };
-        let response: QueryProposalResponse = post_request(
-            block_builder_url,
-            "/block-builder/query-proposal",
-            Some(&request),
-        )
-        .await?;
+        let response: QueryProposalResponse = self
+            .transport
+            .post_idempotent_request(
+                block_builder_url,
+                "/block-builder/query-proposal",
+                Some(&request),
+            )
+            .await?;
         Ok(response.block_proposal)
     }
 
@@ -107,11 +118,12 @@ impl BlockBuilderClientInterface for BlockBuilderClient {
             tx,
             signature,
         };
-        post_request::<_, ()>(
-            block_builder_url,
-            "/block-builder/post-signature",
-            Some(&request),
-        )
-        .await
+        self.transport
+            .post_request::<_, ()>(
+                block_builder_url,
+                "/block-builder/post-signature",
+                Some(&request),
+            )
+            .await
     }
 }
@@ -0,0 +1,329 @@
+use std::{collections::HashMap, hash::Hash, sync::RwLock};
+
+use async_trait::async_trait;
+use intmax2_interfaces::api::{
+    error::ServerError,
+    validity_prover::interface::{AccountInfo, DepositInfo, ValidityProverClientInterface},
+};
+use intmax2_zkp::{
+    circuits::validity::validity_pis::ValidityPublicInputs,
+    common::{
+        trees::{
+            block_hash_tree::BlockHashMerkleProof, deposit_tree::DepositMerkleProof,
+            sender_tree::SenderLeaf,
+        },
+        witness::update_witness::UpdateWitness,
+    },
+    ethereum_types::{bytes32::Bytes32, u256::U256},
+};
+use plonky2::{field::goldilocks_field::GoldilocksField, plonk::config::PoseidonGoldilocksConfig};
+
+type F = GoldilocksField;
+type C = PoseidonGoldilocksConfig;
+const D: usize = 2;
+
+// Seconds for which the latest block number is reused
+const BLOCK_NUMBER_TTL: u64 = 5;
+
+// Entries kept per cache before it is cleared
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+/// Validity prover client that caches the responses that never change once they are available,
+/// so that they can be shared among many accounts syncing against the same validity prover.
+///
+/// Responses that depend on the latest state (account info, next deposit index) are not cached,
+/// and the latest block number is reused only for `BLOCK_NUMBER_TTL` seconds.
+pub struct CachedValidityProver<V: ValidityProverClientInterface> {
+    inner: V,
+    block_number: RwLock<Option<(u32, u64)>>,
+    deposit_info: Cache<Bytes32, DepositInfo>,
+    block_number_by_tx_tree_root: Cache<Bytes32, u32>,
+    validity_pis: Cache<u32, ValidityPublicInputs>,
+    sender_leaves: Cache<u32, Vec<SenderLeaf>>,
+    block_merkle_proof: Cache<(u32, u32), BlockHashMerkleProof>,
+    deposit_merkle_proof: Cache<(u32, u32), DepositMerkleProof>,
+}
+
+impl<V: ValidityProverClientInterface> CachedValidityProver<V> {
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            block_number: RwLock::new(None),
+            deposit_info: Cache::default(),
+            block_number_by_tx_tree_root: Cache::default(),
+            validity_pis: Cache::default(),
+            sender_leaves: Cache::default(),
+            block_merkle_proof: Cache::default(),
+            deposit_merkle_proof: Cache::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+}
+
+#[async_trait(?Send)]
+impl<V: ValidityProverClientInterface> ValidityProverClientInterface for CachedValidityProver<V> {
+    async fn get_block_number(&self) -> Result<u32, ServerError> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        if let Some((block_number, fetched_at)) = *self.block_number.read().unwrap() {
+            if now < fetched_at + BLOCK_NUMBER_TTL {
+                return Ok(block_number);
+            }
+        }
+        let block_number = self.inner.get_block_number().await?;
+        *self.block_number.write().unwrap() = Some((block_number, now));
+        Ok(block_number)
+    }
+
+    async fn get_next_deposit_index(&self) -> Result<u32, ServerError> {
+        self.inner.get_next_deposit_index().await
+    }
+
+    async fn get_update_witness(
+        &self,
+        pubkey: U256,
+        root_block_number: u32,
+        leaf_block_number: u32,
+        is_prev_account_tree: bool,
+    ) -> Result<UpdateWitness<F, C, D>, ServerError> {
+        // specific to a single account, so there is nothing to share
+        self.inner
+            .get_update_witness(
+                pubkey,
+                root_block_number,
+                leaf_block_number,
+                is_prev_account_tree,
+            )
+            .await
+    }
+
+    async fn get_deposit_info(
+        &self,
+        deposit_hash: Bytes32,
+    ) -> Result<Option<DepositInfo>, ServerError> {
+        if let Some(deposit_info) = self.deposit_info.get(&deposit_hash) {
+            return Ok(Some(deposit_info));
+        }
+        let deposit_info = self.inner.get_deposit_info(deposit_hash).await?;
+        if let Some(deposit_info) = &deposit_info {
+            self.deposit_info.insert(deposit_hash, deposit_info.clone());
+        }
+        Ok(deposit_info)
+    }
+
+    async fn get_block_number_by_tx_tree_root(
+        &self,
+        tx_tree_root: Bytes32,
+    ) -> Result<Option<u32>, ServerError> {
+        if let Some(block_number) = self.block_number_by_tx_tree_root.get(&tx_tree_root) {
+            return Ok(Some(block_number));
+        }
+        let block_number = self
+            .inner
+            .get_block_number_by_tx_tree_root(tx_tree_root)
+            .await?;
+        if let Some(block_number) = block_number {
+            self.block_number_by_tx_tree_root
+                .insert(tx_tree_root, block_number);
+        }
+        Ok(block_number)
+    }
+
+    async fn get_validity_pis(
+        &self,
+        block_number: u32,
+    ) -> Result<Option<ValidityPublicInputs>, ServerError> {
+        if let Some(validity_pis) = self.validity_pis.get(&block_number) {
+            return Ok(Some(validity_pis));
+        }
+        let validity_pis = self.inner.get_validity_pis(block_number).await?;
+        if let Some(validity_pis) = &validity_pis {
+            self.validity_pis.insert(block_number, validity_pis.clone());
+        }
+        Ok(validity_pis)
+    }
+
+    async fn get_sender_leaves(
+        &self,
+        block_number: u32,
+    ) -> Result<Option<Vec<SenderLeaf>>, ServerError> {
+        if let Some(sender_leaves) = self.sender_leaves.get(&block_number) {
+            return Ok(Some(sender_leaves));
+        }
+        let sender_leaves = self.inner.get_sender_leaves(block_number).await?;
+        if let Some(sender_leaves) = &sender_leaves {
+            self.sender_leaves
+                .insert(block_number, sender_leaves.clone());
+        }
+        Ok(sender_leaves)
+    }
+
+    async fn get_block_merkle_proof(
+        &self,
+        root_block_number: u32,
+        leaf_block_number: u32,
+    ) -> Result<BlockHashMerkleProof, ServerError> {
+        let key = (root_block_number, leaf_block_number);
+        if let Some(proof) = self.block_merkle_proof.get(&key) {
+            return Ok(proof);
+        }
+        let proof = self
+            .inner
+            .get_block_merkle_proof(root_block_number, leaf_block_number)
+            .await?;
+        self.block_merkle_proof.insert(key, proof.clone());
+        Ok(proof)
+    }
+
+    async fn get_deposit_merkle_proof(
+        &self,
+        block_number: u32,
+        deposit_index: u32,
+    ) -> Result<DepositMerkleProof, ServerError> {
+        let key = (block_number, deposit_index);
+        if let Some(proof) = self.deposit_merkle_proof.get(&key) {
+            return Ok(proof);
+        }
+        let proof = self
+            .inner
+            .get_deposit_merkle_proof(block_number, deposit_index)
+            .await?;
+        self.deposit_merkle_proof.insert(key, proof.clone());
+        Ok(proof)
+    }
+
+    async fn get_account_info(&self, pubkey: U256) -> Result<AccountInfo, ServerError> {
+        self.inner.get_account_info(pubkey).await
+    }
+}
+
+/// Map that is cleared when it reaches `MAX_CACHE_ENTRIES`, to bound the memory usage.
+struct Cache<K, T> {
+    entries: RwLock<HashMap<K, T>>,
+}
+
+impl<K, T> Default for Cache<K, T> {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash, T: Clone> Cache<K, T> {
+    fn get(&self, key: &K) -> Option<T> {
+        self.entries.read().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: K, value: T) {
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::api::validity_prover::interface::ValidityProverClientInterface as _;
+    use intmax2_zkp::ethereum_types::{
+        bytes32::Bytes32, u256::U256, u32limb_trait::U32LimbTrait as _,
+    };
+
+    use crate::client::test_utils::MockValidityProver;
+
+    use super::{Cache, CachedValidityProver, BLOCK_NUMBER_TTL, MAX_CACHE_ENTRIES};
+
+    #[tokio::test]
+    async fn test_block_number_ttl() {
+        let cached = CachedValidityProver::new(MockValidityProver::default());
+        cached.inner().block_number.set(1);
+        assert_eq!(cached.get_block_number().await.unwrap(), 1);
+
+        // reused within the ttl
+        cached.inner().block_number.set(2);
+        assert_eq!(cached.get_block_number().await.unwrap(), 1);
+        assert_eq!(cached.inner().num_calls.get(), 1);
+
+        // fetched again once the ttl has passed
+        let now = chrono::Utc::now().timestamp() as u64;
+        *cached.block_number.write().unwrap() = Some((1, now - BLOCK_NUMBER_TTL));
+        assert_eq!(cached.get_block_number().await.unwrap(), 2);
+        assert_eq!(cached.inner().num_calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_responses_are_not_cached() {
+        let cached = CachedValidityProver::new(MockValidityProver::default());
+        let tx_tree_root = Bytes32::from_u32_slice(&[1; 8]);
+        assert_eq!(
+            cached
+                .get_block_number_by_tx_tree_root(tx_tree_root)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // the tx is included in a block later
+        cached
+            .inner()
+            .tx_tree_roots
+            .borrow_mut()
+            .insert(tx_tree_root, 5);
+        assert_eq!(
+            cached
+                .get_block_number_by_tx_tree_root(tx_tree_root)
+                .await
+                .unwrap(),
+            Some(5)
+        );
+        assert_eq!(cached.inner().num_calls.get(), 2);
+
+        // found responses never change, so they are served from the cache
+        cached.inner().tx_tree_roots.borrow_mut().clear();
+        assert_eq!(
+            cached
+                .get_block_number_by_tx_tree_root(tx_tree_root)
+                .await
+                .unwrap(),
+            Some(5)
+        );
+        assert_eq!(cached.inner().num_calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_latest_state_is_not_cached() {
+        let cached = CachedValidityProver::new(MockValidityProver::default());
+        let pubkey = U256::from_u32_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        assert!(cached
+            .get_account_info(pubkey)
+            .await
+            .unwrap()
+            .account_id
+            .is_none());
+        assert!(cached
+            .get_account_info(pubkey)
+            .await
+            .unwrap()
+            .account_id
+            .is_none());
+        assert_eq!(cached.inner().num_calls.get(), 2);
+    }
+
+    #[test]
+    fn test_cache_is_cleared_when_full() {
+        let cache = Cache::<u32, u32>::default();
+        for i in 0..MAX_CACHE_ENTRIES as u32 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.entries.read().unwrap().len(), MAX_CACHE_ENTRIES);
+
+        cache.insert(MAX_CACHE_ENTRIES as u32, 0);
+        assert_eq!(cache.get(&0), None);
+        assert_eq!(cache.entries.read().unwrap().len(), 1);
+    }
+}
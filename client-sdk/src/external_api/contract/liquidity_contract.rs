@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use ethers::{
     contract::abigen,
@@ -9,7 +12,8 @@ use ethers::{
     types::{Address as EthAddress, H256},
 };
 use intmax2_interfaces::{
-    api::withdrawal_server::interface::ContractWithdrawal, data::deposit_data::TokenType,
+    api::withdrawal_server::interface::ContractWithdrawal,
+    data::deposit_data::{DepositData, TokenType},
 };
 use intmax2_zkp::ethereum_types::{
     address::Address, bytes32::Bytes32, u256::U256, u32limb_trait::U32LimbTrait as _,
@@ -21,16 +25,52 @@ use super::{
     error::BlockchainError,
     handlers::handle_contract_call,
     proxy_contract::ProxyContract,
-    utils::{get_client, get_client_with_signer},
+    utils::{get_client, get_client_with_signer, get_latest_block_number},
 };
 
+const EVENT_BLOCK_RANGE: u64 = 10000;
+
 abigen!(Liquidity, "abi/Liquidity.json",);
 
+#[derive(Clone, Debug)]
+pub struct Deposited {
+    pub deposit_id: U256,
+    pub depositor: Address,
+    pub pubkey_salt_hash: Bytes32,
+    pub token_index: u32,
+    pub amount: U256,
+    pub deposited_at: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct LiquidityContract {
     pub rpc_url: String,
     pub chain_id: u64,
     pub address: EthAddress,
+    // Shared by the clones, so that many accounts syncing with the same client look up each token
+    // only once
+    token_cache: Arc<RwLock<TokenCache>>,
+}
+
+/// Lookups of registered tokens, which never change once registered. Tokens that are not
+/// registered are not cached, since they can be registered later.
+#[derive(Debug, Default)]
+struct TokenCache {
+    // (token type, token address, token id) -> token index
+    indices: HashMap<(u8, Vec<u8>, Vec<u8>), u32>,
+    infos: HashMap<u32, (TokenType, Address, U256)>,
+}
+
+fn token_key(
+    token_type: TokenType,
+    token_address: Address,
+    token_id: U256,
+) -> (u8, Vec<u8>, Vec<u8>) {
+    (
+        token_type as u8,
+        token_address.to_bytes_be(),
+        token_id.to_bytes_be(),
+    )
 }
 
 impl LiquidityContract {
@@ -39,6 +79,7 @@ impl LiquidityContract {
             rpc_url: rpc_url.to_string(),
             chain_id,
             address,
+            token_cache: Arc::new(RwLock::new(TokenCache::default())),
         }
     }
 
@@ -116,6 +157,10 @@ impl LiquidityContract {
             // The contract will revert in this invalid case so we just return None before calling the contract
             return Ok(None);
         }
+        let key = token_key(token_type, token_address, token_id);
+        if let Some(token_index) = self.token_cache.read().unwrap().indices.get(&key) {
+            return Ok(Some(*token_index));
+        }
         let contract = self.get_contract().await?;
         let token_id = ethers::types::U256::from_big_endian(&token_id.to_bytes_be());
         let token_address = EthAddress::from_slice(&token_address.to_bytes_be());
@@ -128,16 +173,23 @@ impl LiquidityContract {
         .await
         .map_err(|e| BlockchainError::RPCError(format!("Error getting token index: {:?}", e)))?;
         if !is_found {
-            Ok(None)
-        } else {
-            Ok(Some(token_index))
+            return Ok(None);
         }
+        self.token_cache
+            .write()
+            .unwrap()
+            .indices
+            .insert(key, token_index);
+        Ok(Some(token_index))
     }
 
     pub async fn get_token_info(
         &self,
         token_index: u32,
     ) -> Result<(TokenType, Address, U256), BlockchainError> {
+        if let Some(token_info) = self.token_cache.read().unwrap().infos.get(&token_index) {
+            return Ok(*token_info);
+        }
         let contract = self.get_contract().await?;
         let token_info = with_retry(|| async { contract.get_token_info(token_index).call().await })
             .await
@@ -152,7 +204,31 @@ impl LiquidityContract {
             token_info.token_id.to_big_endian(&mut buf);
             U256::from_bytes_be(&buf)
         };
-        Ok((token_type, token_address, token_id))
+        let token_info = (token_type, token_address, token_id);
+        self.token_cache
+            .write()
+            .unwrap()
+            .infos
+            .insert(token_index, token_info);
+        Ok(token_info)
+    }
+
+    /// Seed the cache with a registered token, e.g. in tests without an rpc.
+    #[cfg(test)]
+    pub(crate) fn cache_token(
+        &self,
+        token_index: u32,
+        token_type: TokenType,
+        token_address: Address,
+        token_id: U256,
+    ) {
+        let mut cache = self.token_cache.write().unwrap();
+        cache
+            .indices
+            .insert(token_key(token_type, token_address, token_id), token_index);
+        cache
+            .infos
+            .insert(token_index, (token_type, token_address, token_id));
     }
 
     pub async fn check_if_claimable(
@@ -271,4 +347,101 @@ impl LiquidityContract {
         handle_contract_call(&client, &mut tx, "claim_withdrawals").await?;
         Ok(())
     }
+
+    /// Find the `Deposited` event of the deposit made by `depositor` to `pubkey_salt_hash`,
+    /// searching the events emitted since `from_block`.
+    pub async fn get_deposited_event(
+        &self,
+        depositor: Address,
+        pubkey_salt_hash: Bytes32,
+        from_block: u64,
+    ) -> Result<Option<Deposited>, BlockchainError> {
+        let contract = self.get_contract().await?;
+        let sender = H256::from_slice(&{
+            let mut buf = [0u8; 32];
+            buf[12..].copy_from_slice(&depositor.to_bytes_be());
+            buf
+        });
+        let recipient_salt_hash = H256::from_slice(&pubkey_salt_hash.to_bytes_be());
+        let latest_block_number = get_latest_block_number(&self.rpc_url).await?;
+        let mut from_block = from_block;
+        while from_block <= latest_block_number {
+            let to_block = (from_block + EVENT_BLOCK_RANGE - 1).min(latest_block_number);
+            let events = with_retry(|| async {
+                contract
+                    .deposited_filter()
+                    .address(self.address.into())
+                    .topic2(sender)
+                    .topic3(recipient_salt_hash)
+                    .from_block(from_block)
+                    .to_block(to_block)
+                    .query()
+                    .await
+            })
+            .await
+            .map_err(|_| BlockchainError::RPCError("failed to get deposited event".to_string()))?;
+            if let Some(event) = events.first() {
+                let mut deposit_id = [0u8; 32];
+                event.deposit_id.to_big_endian(&mut deposit_id);
+                let mut amount = [0u8; 32];
+                event.amount.to_big_endian(&mut amount);
+                return Ok(Some(Deposited {
+                    deposit_id: U256::from_bytes_be(&deposit_id),
+                    depositor: Address::from_bytes_be(event.sender.as_bytes()),
+                    pubkey_salt_hash: Bytes32::from_bytes_be(&event.recipient_salt_hash),
+                    token_index: event.token_index,
+                    amount: U256::from_bytes_be(&amount),
+                    deposited_at: event.deposited_at.as_u64(),
+                }));
+            }
+            from_block += EVENT_BLOCK_RANGE;
+        }
+        Ok(None)
+    }
+
+    pub async fn get_deposit_data_hash(
+        &self,
+        deposit_id: U256,
+    ) -> Result<Bytes32, BlockchainError> {
+        let contract = self.get_contract().await?;
+        let deposit_id = ethers::types::U256::from_big_endian(&deposit_id.to_bytes_be());
+        let deposit_hash: [u8; 32] =
+            with_retry(|| async { contract.get_deposit_data_hash(deposit_id).call().await })
+                .await
+                .map_err(|e| {
+                    BlockchainError::RPCError(format!("Error getting deposit data hash: {:?}", e))
+                })?;
+        Ok(Bytes32::from_bytes_be(&deposit_hash))
+    }
+
+    /// Cancel a deposit that has not been relayed to the rollup, refunding it to the depositor.
+    /// Must be sent by the depositor.
+    pub async fn cancel_deposit(
+        &self,
+        signer_private_key: H256,
+        deposit_id: U256,
+        deposit_data: &DepositData,
+    ) -> Result<(), BlockchainError> {
+        let token_index = deposit_data
+            .token_index
+            .ok_or(BlockchainError::TokenNotFound)?;
+        let deposit = Deposit {
+            depositor: EthAddress::from_slice(&deposit_data.depositor.to_bytes_be()),
+            recipient_salt_hash: deposit_data
+                .pubkey_salt_hash
+                .to_bytes_be()
+                .try_into()
+                .unwrap(),
+            amount: ethers::types::U256::from_big_endian(&deposit_data.amount.to_bytes_be()),
+            token_index,
+            is_eligible: deposit_data.is_eligible,
+        };
+        let deposit_id = ethers::types::U256::from_big_endian(&deposit_id.to_bytes_be());
+        let contract = self.get_contract_with_signer(signer_private_key).await?;
+        let mut tx = contract.cancel_deposit(deposit_id, deposit);
+        let client =
+            get_client_with_signer(&self.rpc_url, self.chain_id, signer_private_key).await?;
+        handle_contract_call(&client, &mut tx, "cancel_deposit").await?;
+        Ok(())
+    }
 }
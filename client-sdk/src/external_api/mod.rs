@@ -1,5 +1,6 @@
 pub mod balance_prover;
 pub mod block_builder;
+pub mod cached_validity_prover;
 pub mod contract;
 pub mod indexer;
 pub mod private_zkp_prover;
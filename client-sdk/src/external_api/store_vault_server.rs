@@ -1,35 +1,175 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, OnceLock,
+};
+
 use async_trait::async_trait;
 use intmax2_interfaces::{
     api::{
-        error::ServerError,
+        error::{ErrorCode, ServerError},
+        session::SessionAudience,
         store_vault_server::{
-            interface::{DataType, SaveDataEntry, StoreVaultClientInterface},
+            interface::{DataSubscription, DataType, SaveDataEntry, StoreVaultClientInterface},
             types::{
-                DataWithMetaData, GetDataAllAfterRequest, GetDataAllAfterResponse,
-                GetSenderProofSetRequest, GetSenderProofSetResponse, GetUserDataRequest,
-                GetUserDataResponse, SaveDataBatchRequest, SaveDataBatchResponse,
-                SaveSenderProofSetRequest, SaveUserDataRequest,
+                AcknowledgeDataRequest, AcknowledgeSenderProofSetRequest, DataWithMetaData,
+                DeleteMyDataRequest, DeleteMyDataResponse, GetDataAllAfterRequest,
+                GetDataAllAfterResponse, GetDataBatchRequest, GetDataBatchResponse,
+                GetGcMetricsResponse, GetSenderProofSetRequest, GetSenderProofSetResponse,
+                GetUserDataRequest, GetUserDataResponse, GetUserDataVersionRequest,
+                GetUserDataVersionResponse, ListUserDataVersionsRequest,
+                ListUserDataVersionsResponse, RequestDataDeletionRequest,
+                RequestDataDeletionResponse, SaveDataBatchRequest, SaveDataBatchResponse,
+                SaveSenderProofSetRequest, SaveUserDataRequest, SubscribeRequest, UserDataVersion,
             },
         },
     },
-    utils::signature::Signable,
+    utils::{
+        pow::solve_pow,
+        signature::{Auth, Signable, WithAuth, WithOptionalAuth},
+    },
 };
-use intmax2_zkp::{common::signature::key_set::KeySet, ethereum_types::bytes32::Bytes32};
+use intmax2_zkp::{
+    common::signature::key_set::KeySet,
+    ethereum_types::{bytes32::Bytes32, u256::U256},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
 
-use super::utils::query::post_request;
+use super::utils::{session::SessionCache, transport::HttpTransport};
 
 const TIME_TO_EXPIRY: u64 = 60; // 1 minute
+const MAX_GET_DATA_BATCH_SIZE: usize = 100; // limit of the server
+const MAX_ACKNOWLEDGE_BATCH_SIZE: usize = 1000; // limit of the server
 
 #[derive(Debug, Clone)]
 pub struct StoreVaultServerClient {
     base_url: String,
+    transport: HttpTransport,
+    // difficulty of the proof of work learned from the server
+    pow_difficulty: Arc<AtomicU32>,
+    // whether the server garbage collects acknowledged data, learned once
+    gc_enabled: Arc<OnceLock<bool>>,
+    sessions: Option<SessionCache>,
 }
 
 impl StoreVaultServerClient {
     pub fn new(base_url: &str) -> Self {
+        Self::with_transport(base_url, HttpTransport::default())
+    }
+
+    pub fn with_transport(base_url: &str, transport: HttpTransport) -> Self {
         StoreVaultServerClient {
             base_url: base_url.to_string(),
+            transport,
+            pow_difficulty: Arc::new(AtomicU32::new(0)),
+            gc_enabled: Arc::new(OnceLock::new()),
+            sessions: None,
+        }
+    }
+
+    /// Authenticates the requests of a key by a session token, signing only the request creating
+    /// the session. Requests with ephemeral keys and subscriptions are still signed.
+    pub fn with_sessions(mut self) -> Self {
+        self.sessions = Some(SessionCache::default());
+        self
+    }
+
+    /// Posts the request authenticated by the session of the key if sessions are enabled, or
+    /// signed by the key otherwise.
+    async fn post_authorized<T, R>(
+        &self,
+        key: KeySet,
+        endpoint: &str,
+        request: T,
+        idempotent: bool,
+    ) -> Result<R, ServerError>
+    where
+        T: Signable + Serialize,
+        R: DeserializeOwned,
+    {
+        if let Some(sessions) = &self.sessions {
+            let transport = sessions
+                .transport(
+                    &self.transport,
+                    &self.base_url,
+                    "/store-vault-server/create-session",
+                    SessionAudience::StoreVaultServer,
+                    key,
+                )
+                .await;
+            match transport {
+                Ok(transport) => {
+                    let body = WithOptionalAuth {
+                        inner: &request,
+                        auth: None,
+                    };
+                    match post(&transport, &self.base_url, endpoint, &body, idempotent).await {
+                        // e.g. the server restarted without a shared secret, so sign this time
+                        Err(e) if e.code() == Some(ErrorCode::Unauthorized) => {
+                            log::warn!("Session rejected, signing the request: {}", e);
+                            sessions.invalidate(key.pubkey);
+                        }
+                        result => return result,
+                    }
+                }
+                Err(e) => log::warn!("Failed to create a session, signing the request: {}", e),
+            }
+        }
+        self.post_signed(key, endpoint, request, idempotent).await
+    }
+
+    /// Posts the request signed by the key. If a retry of the transport is rejected as a replay
+    /// of the first attempt, the request is signed again with a new nonce.
+    async fn post_signed<T, R>(
+        &self,
+        key: KeySet,
+        endpoint: &str,
+        request: T,
+        idempotent: bool,
+    ) -> Result<R, ServerError>
+    where
+        T: Signable + Serialize,
+        R: DeserializeOwned,
+    {
+        let mut retried = false;
+        loop {
+            let body = WithAuth {
+                auth: Auth::sign(key, TIME_TO_EXPIRY, &request.content()),
+                inner: &request,
+            };
+            match post(&self.transport, &self.base_url, endpoint, &body, idempotent).await {
+                Err(e) if !retried && e.code() == Some(ErrorCode::ReplayedRequest) => {
+                    retried = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_data_batch(
+        &self,
+        request: &WithAuth<SaveDataBatchRequest>,
+    ) -> Result<Vec<String>, ServerError> {
+        let result: Result<SaveDataBatchResponse, ServerError> = self
+            .transport
+            .post_request(
+                &self.base_url,
+                "/store-vault-server/save-data-batch",
+                Some(request),
+            )
+            .await;
+        if let Err(ServerError::ApiError {
+            code: ErrorCode::ProofOfWorkRequired,
+            details: Some(details),
+            ..
+        }) = &result
+        {
+            if let Some(difficulty) = details.get("difficulty").and_then(|d| d.as_u64()) {
+                self.pow_difficulty
+                    .store(difficulty as u32, Ordering::Relaxed);
+            }
         }
+        Ok(result?.uuids)
     }
 }
 
@@ -45,25 +185,49 @@ impl StoreVaultClientInterface for StoreVaultServerClient {
             data: encrypted_data.to_vec(),
             prev_digest,
         };
-        let request_with_auth = request.sign(key, TIME_TO_EXPIRY);
-        post_request::<_, ()>(
-            &self.base_url,
-            "/store-vault-server/save-user-data",
-            Some(&request_with_auth),
-        )
-        .await?;
+        self.post_authorized::<_, ()>(key, "/store-vault-server/save-user-data", request, false)
+            .await?;
         Ok(())
     }
 
     async fn get_user_data(&self, key: KeySet) -> Result<Option<Vec<u8>>, ServerError> {
         let request = GetUserDataRequest;
-        let request_with_auth = request.sign(key, TIME_TO_EXPIRY);
-        let response: GetUserDataResponse = post_request(
-            &self.base_url,
-            "/store-vault-server/get-user-data",
-            Some(&request_with_auth),
-        )
-        .await?;
+        let response: GetUserDataResponse = self
+            .post_authorized(key, "/store-vault-server/get-user-data", request, true)
+            .await?;
+        Ok(response.data)
+    }
+
+    async fn list_user_data_versions(
+        &self,
+        key: KeySet,
+    ) -> Result<Vec<UserDataVersion>, ServerError> {
+        let request = ListUserDataVersionsRequest;
+        let response: ListUserDataVersionsResponse = self
+            .post_authorized(
+                key,
+                "/store-vault-server/list-user-data-versions",
+                request,
+                true,
+            )
+            .await?;
+        Ok(response.versions)
+    }
+
+    async fn get_user_data_version(
+        &self,
+        key: KeySet,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, ServerError> {
+        let request = GetUserDataVersionRequest { version };
+        let response: GetUserDataVersionResponse = self
+            .post_authorized(
+                key,
+                "/store-vault-server/get-user-data-version",
+                request,
+                true,
+            )
+            .await?;
         Ok(response.data)
     }
 
@@ -71,15 +235,17 @@ impl StoreVaultClientInterface for StoreVaultServerClient {
         &self,
         ephemeral_key: KeySet,
         encrypted_data: &[u8],
+        num_transfers: Option<u32>,
     ) -> Result<(), ServerError> {
         let request = SaveSenderProofSetRequest {
             data: encrypted_data.to_vec(),
+            num_transfers,
         };
-        let request_with_auth = request.sign(ephemeral_key, TIME_TO_EXPIRY);
-        post_request::<_, ()>(
-            &self.base_url,
+        self.post_signed::<_, ()>(
+            ephemeral_key,
             "/store-vault-server/save-sender-proof-set",
-            Some(&request_with_auth),
+            request,
+            false,
         )
         .await?;
         Ok(())
@@ -87,14 +253,31 @@ impl StoreVaultClientInterface for StoreVaultServerClient {
 
     async fn get_sender_proof_set(&self, ephemeral_key: KeySet) -> Result<Vec<u8>, ServerError> {
         let request = GetSenderProofSetRequest;
-        let request_with_auth = request.sign(ephemeral_key, TIME_TO_EXPIRY);
-        let response: GetSenderProofSetResponse = post_request(
-            &self.base_url,
-            "/store-vault-server/get-sender-proof-set",
-            Some(&request_with_auth),
+        let response: GetSenderProofSetResponse = self
+            .post_signed(
+                ephemeral_key,
+                "/store-vault-server/get-sender-proof-set",
+                request,
+                true,
+            )
+            .await?;
+        Ok(response.data)
+    }
+
+    async fn acknowledge_sender_proof_set(
+        &self,
+        ephemeral_key: KeySet,
+        transfer_index: u32,
+    ) -> Result<(), ServerError> {
+        let request = AcknowledgeSenderProofSetRequest { transfer_index };
+        self.post_signed::<_, ()>(
+            ephemeral_key,
+            "/store-vault-server/acknowledge-sender-proof-set",
+            request,
+            true,
         )
         .await?;
-        Ok(response.data)
+        Ok(())
     }
 
     async fn save_data_batch(
@@ -102,17 +285,41 @@ impl StoreVaultClientInterface for StoreVaultServerClient {
         key: KeySet,
         entries: &[SaveDataEntry],
     ) -> Result<Vec<String>, ServerError> {
-        let request = SaveDataBatchRequest {
-            data: entries.to_vec(),
-        };
-        let request_with_auth = request.sign(key, TIME_TO_EXPIRY);
-        let response: SaveDataBatchResponse = post_request(
-            &self.base_url,
-            "/store-vault-server/save-data-batch",
-            Some(&request_with_auth),
-        )
-        .await?;
-        Ok(response.uuids)
+        let mut entries = entries.to_vec();
+        let mut retried = false;
+        loop {
+            self.attach_pow(key.pubkey, &mut entries);
+            let request = SaveDataBatchRequest {
+                data: entries.clone(),
+            };
+            let request_with_auth = request.sign(key, TIME_TO_EXPIRY);
+            match self.post_data_batch(&request_with_auth).await {
+                // the difficulty is learned from the error
+                Err(e) if !retried && e.code() == Some(ErrorCode::ProofOfWorkRequired) => {
+                    retried = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn save_data_batch_with_auth(
+        &self,
+        request: &WithAuth<SaveDataBatchRequest>,
+    ) -> Result<Vec<String>, ServerError> {
+        self.post_data_batch(request).await
+    }
+
+    fn attach_pow(&self, writer: U256, entries: &mut [SaveDataEntry]) {
+        let difficulty = self.pow_difficulty.load(Ordering::Relaxed);
+        if difficulty == 0 {
+            return;
+        }
+        for entry in entries.iter_mut() {
+            if entry.pubkey != writer && entry.pow_nonce.is_none() {
+                entry.pow_nonce = Some(solve_pow(writer, entry, difficulty));
+            }
+        }
     }
 
     async fn get_data_all_after(
@@ -125,13 +332,182 @@ impl StoreVaultClientInterface for StoreVaultServerClient {
             data_type,
             timestamp,
         };
-        let request_with_auth = request.sign(key, TIME_TO_EXPIRY);
-        let response: GetDataAllAfterResponse = post_request(
-            &self.base_url,
-            "/store-vault-server/get-data-all-after",
-            Some(&request_with_auth),
-        )
-        .await?;
+        let response: GetDataAllAfterResponse = self
+            .post_authorized(key, "/store-vault-server/get-data-all-after", request, true)
+            .await?;
         Ok(response.data)
     }
+    async fn get_data_batch(
+        &self,
+        key: KeySet,
+        data_type: DataType,
+        uuids: &[String],
+    ) -> Result<Vec<DataWithMetaData>, ServerError> {
+        let mut data = Vec::new();
+        for chunk in uuids.chunks(MAX_GET_DATA_BATCH_SIZE) {
+            let request = GetDataBatchRequest {
+                data_type,
+                uuids: chunk.to_vec(),
+            };
+            let response: GetDataBatchResponse = self
+                .post_authorized(key, "/store-vault-server/get-data-batch", request, true)
+                .await?;
+            data.extend(response.data);
+        }
+        Ok(data)
+    }
+
+    async fn acknowledge_data(
+        &self,
+        key: KeySet,
+        data_type: DataType,
+        uuids: &[String],
+    ) -> Result<(), ServerError> {
+        for chunk in uuids.chunks(MAX_ACKNOWLEDGE_BATCH_SIZE) {
+            let request = AcknowledgeDataRequest {
+                data_type,
+                uuids: chunk.to_vec(),
+            };
+            self.post_authorized::<_, ()>(
+                key,
+                "/store-vault-server/acknowledge-data",
+                request,
+                true,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn is_gc_enabled(&self) -> Result<bool, ServerError> {
+        if let Some(gc_enabled) = self.gc_enabled.get() {
+            return Ok(*gc_enabled);
+        }
+        let gc_enabled = match self
+            .transport
+            .get_request::<(), GetGcMetricsResponse>(
+                &self.base_url,
+                "/store-vault-server/gc-metrics",
+                None,
+            )
+            .await
+        {
+            Ok(response) => response.gc_enabled,
+            // an older server without GC metrics
+            Err(ServerError::ServerError(404, ..)) => false,
+            Err(e) if e.code() == Some(ErrorCode::NotFound) => false,
+            Err(e) => return Err(e),
+        };
+        Ok(*self.gc_enabled.get_or_init(|| gc_enabled))
+    }
+
+    async fn request_data_deletion(
+        &self,
+        key: KeySet,
+    ) -> Result<RequestDataDeletionResponse, ServerError> {
+        let request = RequestDataDeletionRequest;
+        self.post_signed(
+            key,
+            "/store-vault-server/request-data-deletion",
+            request,
+            false,
+        )
+        .await
+    }
+
+    async fn delete_my_data(
+        &self,
+        key: KeySet,
+        token: &str,
+        sender_proof_set_keys: &[KeySet],
+    ) -> Result<DeleteMyDataResponse, ServerError> {
+        let content = DeleteMyDataRequest::sender_proof_set_content(token);
+        let sender_proof_set_auths = sender_proof_set_keys
+            .iter()
+            .map(|ephemeral_key| Auth::sign(*ephemeral_key, TIME_TO_EXPIRY, &content))
+            .collect();
+        let request = DeleteMyDataRequest {
+            token: token.to_string(),
+            sender_proof_set_auths,
+        };
+        // not retried since the token is consumed by the first request
+        self.post_signed(key, "/store-vault-server/delete-my-data", request, false)
+            .await
+    }
+
+    async fn subscribe(
+        &self,
+        key: KeySet,
+        data_types: &[DataType],
+    ) -> Result<Box<dyn DataSubscription>, ServerError> {
+        let request = SubscribeRequest {
+            data_types: data_types.to_vec(),
+        };
+        let request_with_auth = request.sign(key, TIME_TO_EXPIRY);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let stream = self
+                .transport
+                .post_event_stream(
+                    &self.base_url,
+                    "/store-vault-server/subscribe",
+                    &request_with_auth,
+                )
+                .await?;
+            Ok(Box::new(subscription::StoreVaultSubscription { stream }))
+        }
+        // reqwest does not support reading the response body in chunks on wasm
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = request_with_auth;
+            Err(ServerError::InternalError(
+                "Subscriptions are not supported on wasm".to_string(),
+            ))
+        }
+    }
+}
+
+async fn post<B: Serialize, R: DeserializeOwned>(
+    transport: &HttpTransport,
+    base_url: &str,
+    endpoint: &str,
+    body: &B,
+    idempotent: bool,
+) -> Result<R, ServerError> {
+    if idempotent {
+        transport
+            .post_idempotent_request(base_url, endpoint, Some(body))
+            .await
+    } else {
+        transport.post_request(base_url, endpoint, Some(body)).await
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod subscription {
+    use async_trait::async_trait;
+    use intmax2_interfaces::api::{
+        error::ServerError,
+        store_vault_server::{interface::DataSubscription, types::DataNotification},
+    };
+
+    use crate::external_api::utils::event_stream::EventStream;
+
+    pub(super) struct StoreVaultSubscription {
+        pub(super) stream: EventStream,
+    }
+
+    #[async_trait(?Send)]
+    impl DataSubscription for StoreVaultSubscription {
+        async fn next(&mut self) -> Result<Option<DataNotification>, ServerError> {
+            while let Some(event) = self.stream.next_event().await? {
+                if event.event == "data" {
+                    let notification = serde_json::from_str(&event.data)
+                        .map_err(|e| ServerError::DeserializationError(e.to_string()))?;
+                    return Ok(Some(notification));
+                }
+            }
+            Ok(None)
+        }
+    }
 }
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+
+use intmax2_interfaces::api::error::ServerError;
+use reqwest::Response;
+
+/// An event of a server-sent events stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub event: String,
+    pub data: String,
+}
+
+/// Parser of the `event` and `data` fields of server-sent events. Other fields and comments,
+/// e.g. keep-alives, are ignored.
+#[derive(Debug, Default)]
+struct EventParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl EventParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=pos).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                let event = self.event.take();
+                if !self.data.is_empty() {
+                    events.push(Event {
+                        event: event.unwrap_or_else(|| "message".to_string()),
+                        data: self.data.join("\n"),
+                    });
+                    self.data.clear();
+                }
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Stream of server-sent events, see `HttpTransport::post_event_stream`
+#[derive(Debug)]
+pub struct EventStream {
+    response: Response,
+    parser: EventParser,
+    pending: VecDeque<Event>,
+}
+
+impl EventStream {
+    pub(super) fn new(response: Response) -> Self {
+        Self {
+            response,
+            parser: EventParser::default(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next event, or None if the server closed the stream.
+    pub async fn next_event(&mut self) -> Result<Option<Event>, ServerError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            let chunk = self
+                .response
+                .chunk()
+                .await
+                .map_err(|e| ServerError::NetworkError(e.to_string()))?;
+            match chunk {
+                Some(chunk) => self.pending.extend(self.parser.push(&chunk)),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, EventParser};
+
+    #[test]
+    fn test_event_parser() {
+        let mut parser = EventParser::default();
+        assert!(parser.push(b": keep-alive\n\nevent: data\nda").is_empty());
+        assert_eq!(
+            parser.push(b"ta: {\"a\":1}\r\n\ndata: x\ndata: y\n\n"),
+            vec![
+                Event {
+                    event: "data".to_string(),
+                    data: "{\"a\":1}".to_string(),
+                },
+                Event {
+                    event: "message".to_string(),
+                    data: "x\ny".to_string(),
+                },
+            ]
+        );
+    }
+}
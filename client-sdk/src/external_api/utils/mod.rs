@@ -1,4 +1,8 @@
 pub mod debug;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod event_stream;
 pub mod query;
 pub mod retry;
+pub mod session;
 pub mod time;
+pub mod transport;
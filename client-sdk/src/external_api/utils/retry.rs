@@ -2,36 +2,90 @@ use std::{future::Future, time::Duration};
 
 use log::warn;
 
-use crate::external_api::utils::time::sleep_for;
+use crate::external_api::utils::time::sleep_for_duration;
 
 const MAX_RETRIES: u32 = 5;
 const INITIAL_DELAY: u64 = 1000;
+const MAX_DELAY: u64 = 60_000;
+
+/// Exponential backoff settings used by `with_retry` and the http transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt. 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds
+    pub initial_delay: u64,
+    /// Upper bound of the delay in milliseconds
+    pub max_delay: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: MAX_RETRIES,
+            initial_delay: INITIAL_DELAY,
+            max_delay: MAX_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn no_retry() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before the retry following the given attempt (0-indexed).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_delay
+            .saturating_mul(2u64.saturating_pow(attempt))
+            .min(self.max_delay);
+        Duration::from_millis(delay)
+    }
+}
 
 pub async fn with_retry<'a, T, E, F, Fut>(f: F) -> Result<T, E>
 where
     E: std::error::Error,
     F: Fn() -> Fut,
     Fut: Future<Output = Result<T, E>> + 'a,
+{
+    with_retry_policy(&RetryPolicy::default(), f, |_| true).await
+}
+
+/// Retry `f` with the given policy while `is_retryable` returns true for the error.
+pub async fn with_retry_policy<'a, T, E, F, Fut, P>(
+    policy: &RetryPolicy,
+    f: F,
+    is_retryable: P,
+) -> Result<T, E>
+where
+    E: std::error::Error,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>> + 'a,
+    P: Fn(&E) -> bool,
 {
     let mut retries = 0;
-    let mut delay = Duration::from_millis(INITIAL_DELAY);
 
     loop {
         match f().await {
             Ok(result) => return Ok(result),
             Err(e) => {
-                if retries >= MAX_RETRIES {
+                if retries >= policy.max_retries || !is_retryable(&e) {
                     return Err(e);
                 }
+                let delay = policy.delay(retries);
                 warn!(
                     "Attempt {} failed: {}. Retrying in {:?}...",
                     retries + 1,
                     e.to_string(),
                     delay
                 );
-                sleep_for(delay.as_secs()).await;
+                sleep_for_duration(delay).await;
                 retries += 1;
-                delay *= 2; // Exponential backoff
             }
         }
     }
@@ -0,0 +1,74 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use intmax2_interfaces::{
+    api::{
+        error::ServerError,
+        session::{CreateSessionRequest, CreateSessionResponse, SessionAudience},
+    },
+    utils::signature::{current_time, Signable as _},
+};
+use intmax2_zkp::{
+    common::signature::key_set::KeySet,
+    ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _},
+};
+
+use super::transport::HttpTransport;
+
+const TIME_TO_EXPIRY: u64 = 60; // 1 minute
+
+// A session is renewed this many seconds before its expiry, so that it does not expire in flight.
+const RENEWAL_MARGIN: u64 = 30;
+
+/// Session tokens of the keys, so that the requests of a key are authenticated by a bearer token
+/// instead of a signature each. A session is created by a signed `create-session` request on
+/// first use, and renewed when it is about to expire.
+///
+/// Cloning is cheap and the clones share the sessions.
+#[derive(Debug, Clone, Default)]
+pub struct SessionCache {
+    // pubkey -> session
+    sessions: Arc<Mutex<HashMap<String, CreateSessionResponse>>>,
+}
+
+impl SessionCache {
+    /// Returns a transport attaching the session token of the key, creating a session via
+    /// `endpoint` if there is no valid one.
+    pub async fn transport(
+        &self,
+        transport: &HttpTransport,
+        base_url: &str,
+        endpoint: &str,
+        audience: SessionAudience,
+        key: KeySet,
+    ) -> Result<HttpTransport, ServerError> {
+        let pubkey = key.pubkey.to_hex();
+        let cached = self
+            .sessions
+            .lock()
+            .unwrap()
+            .get(&pubkey)
+            .filter(|session| session.expires_at > current_time() + RENEWAL_MARGIN)
+            .map(|session| session.token.clone());
+        let token = match cached {
+            Some(token) => token,
+            None => {
+                let request = CreateSessionRequest { audience }.sign(key, TIME_TO_EXPIRY);
+                let session: CreateSessionResponse = transport
+                    .post_request(base_url, endpoint, Some(&request))
+                    .await?;
+                let token = session.token.clone();
+                self.sessions.lock().unwrap().insert(pubkey, session);
+                token
+            }
+        };
+        Ok(transport.with_bearer_token(&token))
+    }
+
+    /// Forgets the session of the pubkey, e.g. when the server no longer accepts it.
+    pub fn invalidate(&self, pubkey: U256) {
+        self.sessions.lock().unwrap().remove(&pubkey.to_hex());
+    }
+}
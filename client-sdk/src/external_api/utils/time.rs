@@ -23,3 +23,32 @@ pub async fn sleep_for(seconds: u64) {
     let target = chrono::Utc::now().timestamp() as u64 + seconds;
     sleep_until(target).await;
 }
+
+/// Sleep for the duration with millisecond granularity, e.g. for the backoff of retries. Works
+/// correctly even when PC is in sleep mode, like `sleep_for`.
+pub async fn sleep_for_duration(duration: Duration) {
+    let target = chrono::Utc::now().timestamp_millis() as u64 + duration.as_millis() as u64;
+    loop {
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        if now >= target {
+            break;
+        }
+        sleep(Duration::from_millis((target - now).min(1000))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::sleep_for_duration;
+
+    #[tokio::test]
+    async fn test_sleep_for_sub_second_duration() {
+        let start = Instant::now();
+        sleep_for_duration(Duration::from_millis(300)).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,494 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use intmax2_interfaces::api::{
+    encoding::WireFormat,
+    error::{ErrorResponse, ServerError},
+};
+use log::warn;
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    Method, RequestBuilder, Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::event_stream::EventStream;
+use super::{debug::is_debug_mode, retry::RetryPolicy, time::sleep_for_duration};
+
+/// Error body of servers that do not return an `ErrorResponse`
+#[derive(Debug, Deserialize)]
+struct LegacyErrorResponse {
+    error: String,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Settings of an `HttpTransport`.
+#[derive(Debug, Clone, Default)]
+pub struct TransportConfig {
+    /// Timeout of requests to endpoints that have no entry in `endpoint_timeouts`.
+    /// None means no timeout.
+    pub default_timeout: Option<Duration>,
+
+    /// Timeout per endpoint path, e.g. "/balance-prover/prove-send".
+    pub endpoint_timeouts: HashMap<String, Duration>,
+
+    pub connect_timeout: Option<Duration>,
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout: Option<Duration>,
+
+    pub retry_policy: RetryPolicy,
+
+    /// Headers attached to every request, e.g. an api key.
+    pub headers: Vec<(String, String)>,
+
+    /// Encoding of request bodies, and the preferred encoding of responses. Only use bincode with
+    /// servers that support it, i.e. the balance prover and the validity prover.
+    pub wire_format: WireFormat,
+}
+
+impl TransportConfig {
+    pub fn timeout_for(&self, endpoint: &str) -> Option<Duration> {
+        self.endpoint_timeouts
+            .get(endpoint)
+            .copied()
+            .or(self.default_timeout)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    pub method: Method,
+    pub url: String,
+    pub endpoint: String,
+    // 0 for the first attempt
+    pub attempt: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseInfo {
+    // None if no response was received
+    pub status: Option<u16>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Hook called around every attempt of a request, e.g. for logging or metrics.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &RequestInfo) {}
+
+    fn on_response(&self, _request: &RequestInfo, _response: &ResponseInfo) {}
+}
+
+/// Http client shared by the server clients.
+///
+/// Cloning is cheap and the clones share the connection pool.
+#[derive(Clone)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+    config: Arc<TransportConfig>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl fmt::Debug for HttpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpTransport")
+            .field("config", &self.config)
+            .field("middlewares", &self.middlewares.len())
+            .finish()
+    }
+}
+
+static DEFAULT_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+impl Default for HttpTransport {
+    /// Returns a transport with the default config. The connection pool is shared by all default
+    /// transports.
+    fn default() -> Self {
+        let config = TransportConfig::default();
+        let client = DEFAULT_CLIENT.get_or_init(|| build_client(&config)).clone();
+        Self {
+            client,
+            config: Arc::new(config),
+            middlewares: Vec::new(),
+        }
+    }
+}
+
+impl HttpTransport {
+    pub fn new(config: TransportConfig) -> Self {
+        Self {
+            client: build_client(&config),
+            config: Arc::new(config),
+            middlewares: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &TransportConfig {
+        &self.config
+    }
+
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Returns a transport that sends an additional header, sharing the connection pool.
+    pub fn with_header(&self, name: &str, value: &str) -> Self {
+        let mut config = (*self.config).clone();
+        config.headers.push((name.to_string(), value.to_string()));
+        Self {
+            client: self.client.clone(),
+            config: Arc::new(config),
+            middlewares: self.middlewares.clone(),
+        }
+    }
+
+    pub fn with_bearer_token(&self, token: &str) -> Self {
+        self.with_header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Returns a transport that uses the given wire format, sharing the connection pool.
+    pub fn with_wire_format(&self, wire_format: WireFormat) -> Self {
+        let mut config = (*self.config).clone();
+        config.wire_format = wire_format;
+        Self {
+            client: self.client.clone(),
+            config: Arc::new(config),
+            middlewares: self.middlewares.clone(),
+        }
+    }
+
+    pub async fn get_request<Q, R>(
+        &self,
+        base_url: &str,
+        endpoint: &str,
+        query: Option<Q>,
+    ) -> Result<R, ServerError>
+    where
+        Q: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut url = format!("{}{}", base_url, endpoint);
+        let query_str = query
+            .as_ref()
+            .map(|q| {
+                serde_qs::to_string(&q).map_err(|e| {
+                    ServerError::SerializeError(format!("Failed to serialize query: {}", e))
+                })
+            })
+            .transpose()?;
+        if query_str.is_some() {
+            url = format!("{}?{}", url, query_str.as_ref().unwrap());
+        }
+        if is_debug_mode() {
+            log::info!("GET request url: {}", url);
+        }
+        let response = self
+            .send(Method::GET, &url, endpoint, None, true, false)
+            .await?;
+        handle_response(response, &url, &query_str).await
+    }
+
+    /// Send a POST request that is only retried if it did not reach the server.
+    pub async fn post_request<B: Serialize, R: DeserializeOwned>(
+        &self,
+        base_url: &str,
+        endpoint: &str,
+        body: Option<&B>,
+    ) -> Result<R, ServerError> {
+        self.post(base_url, endpoint, body, false).await
+    }
+
+    /// Send a POST request that has no side effects on the server, and thus is also retried on
+    /// timeouts and transient errors.
+    pub async fn post_idempotent_request<B: Serialize, R: DeserializeOwned>(
+        &self,
+        base_url: &str,
+        endpoint: &str,
+        body: Option<&B>,
+    ) -> Result<R, ServerError> {
+        self.post(base_url, endpoint, body, true).await
+    }
+
+    async fn post<B: Serialize, R: DeserializeOwned>(
+        &self,
+        base_url: &str,
+        endpoint: &str,
+        body: Option<&B>,
+        idempotent: bool,
+    ) -> Result<R, ServerError> {
+        let url = format!("{}{}", base_url, endpoint);
+        let wire_format = self.config.wire_format;
+        let body_bytes = body.map(|body| wire_format.encode(body)).transpose()?;
+        if is_debug_mode() {
+            let body_size = body_bytes.as_ref().map(|b| b.len()).unwrap_or(0);
+            log::info!("POST request url: {} body size: {} bytes", url, body_size);
+        }
+        let response = self
+            .send(
+                Method::POST,
+                &url,
+                endpoint,
+                body_bytes.as_deref(),
+                idempotent,
+                false,
+            )
+            .await?;
+        let body_str = body_bytes.map(|bytes| match wire_format {
+            WireFormat::Json => String::from_utf8_lossy(&bytes).to_string(),
+            WireFormat::Bincode => format!("<bincode body of {} bytes>", bytes.len()),
+        });
+        handle_response(response, &url, &body_str).await
+    }
+
+    /// Send a POST request whose response is a stream of server-sent events. The stream is not
+    /// subject to the timeouts of the config.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn post_event_stream<B: Serialize>(
+        &self,
+        base_url: &str,
+        endpoint: &str,
+        body: &B,
+    ) -> Result<EventStream, ServerError> {
+        let url = format!("{}{}", base_url, endpoint);
+        let body_bytes = self.config.wire_format.encode(body)?;
+        if is_debug_mode() {
+            log::info!("POST event stream url: {}", url);
+        }
+        let response = self
+            .send(Method::POST, &url, endpoint, Some(&body_bytes), false, true)
+            .await?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response, &url, &None).await);
+        }
+        Ok(EventStream::new(response))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        endpoint: &str,
+        body: Option<&[u8]>,
+        idempotent: bool,
+        stream: bool,
+    ) -> Result<Response, ServerError> {
+        let policy = self.config.retry_policy;
+        let mut attempt = 0;
+        loop {
+            let request_info = RequestInfo {
+                method: method.clone(),
+                url: url.to_string(),
+                endpoint: endpoint.to_string(),
+                attempt,
+            };
+            for middleware in self.middlewares.iter() {
+                middleware.on_request(&request_info);
+            }
+            let start = chrono::Utc::now().timestamp_millis();
+            let result = self
+                .build_request(&method, url, endpoint, body, stream)
+                .send()
+                .await;
+            let response_info = ResponseInfo {
+                status: result.as_ref().ok().map(|r| r.status().as_u16()),
+                elapsed_ms: (chrono::Utc::now().timestamp_millis() - start).max(0) as u64,
+                error: result.as_ref().err().map(|e| e.to_string()),
+            };
+            for middleware in self.middlewares.iter() {
+                middleware.on_response(&request_info, &response_info);
+            }
+
+            let retryable = match &result {
+                Ok(response) => idempotent && is_transient_status(response.status()),
+                Err(e) => is_retryable_error(e, idempotent),
+            };
+            if !retryable || attempt >= policy.max_retries {
+                return result.map_err(|e| ServerError::NetworkError(e.to_string()));
+            }
+            let delay = policy.delay(attempt);
+            warn!(
+                "{} {} attempt {} failed: {}. Retrying in {:?}...",
+                method,
+                url,
+                attempt + 1,
+                response_info
+                    .error
+                    .unwrap_or_else(|| format!("status {}", response_info.status.unwrap())),
+                delay
+            );
+            sleep_for_duration(delay).await;
+            attempt += 1;
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn build_request(
+        &self,
+        method: &Method,
+        url: &str,
+        endpoint: &str,
+        body: Option<&[u8]>,
+        stream: bool,
+    ) -> RequestBuilder {
+        let mut request = self
+            .client
+            .request(method.clone(), url)
+            .header(ACCEPT, self.config.wire_format.accept());
+        for (name, value) in self.config.headers.iter() {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request
+                .header(CONTENT_TYPE, self.config.wire_format.content_type())
+                .body(body.to_vec());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = self.config.timeout_for(endpoint).filter(|_| !stream) {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+fn build_client(config: &TransportConfig) -> reqwest::Client {
+    #[allow(unused_mut)]
+    let mut builder = reqwest::Client::builder();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Some(connect_timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = config.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle_timeout);
+        }
+    }
+    builder.build().expect("Failed to build http client")
+}
+
+/// Statuses that indicate the server may accept the same request later.
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_retryable_error(e: &reqwest::Error, idempotent: bool) -> bool {
+    // the connection was never established, so the server did not see the request
+    #[cfg(not(target_arch = "wasm32"))]
+    if e.is_connect() {
+        return true;
+    }
+    idempotent && (e.is_timeout() || e.is_request())
+}
+
+async fn handle_response<R: DeserializeOwned>(
+    response: Response,
+    url: &str,
+    request_str: &Option<String>,
+) -> Result<R, ServerError> {
+    if !response.status().is_success() {
+        return Err(error_from_response(response, url, request_str).await);
+    }
+    // the server decides the format of the response, which is JSON if it does not support bincode
+    let wire_format = WireFormat::from_content_type(
+        response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok()),
+    );
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| ServerError::DeserializationError(e.to_string()))?;
+    wire_format.decode(&bytes)
+}
+
+async fn error_from_response(
+    response: Response,
+    url: &str,
+    request_str: &Option<String>,
+) -> ServerError {
+    let status = response.status();
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Failed to read error response".to_string());
+    if let Ok(error_resp) = serde_json::from_str::<ErrorResponse>(&error_text) {
+        return ServerError::ApiError {
+            status: status.into(),
+            code: error_resp.code,
+            message: error_resp.message,
+            retryable: error_resp.retryable,
+            details: error_resp.details,
+            url: url.to_string(),
+        };
+    }
+    let error_message = match serde_json::from_str::<LegacyErrorResponse>(&error_text) {
+        Ok(error_resp) => error_resp.message.unwrap_or(error_resp.error),
+        Err(_) => error_text,
+    };
+    let abr_request = if is_debug_mode() {
+        // full request string
+        request_str.clone().unwrap_or_default()
+    } else {
+        // Truncate the request string to 500 characters if it is too long
+        request_str
+            .as_ref()
+            .map(|s| s.chars().take(500).collect::<String>())
+            .unwrap_or_default()
+    };
+    ServerError::ServerError(status.into(), error_message, url.to_string(), abr_request)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use reqwest::StatusCode;
+
+    use super::{is_transient_status, TransportConfig};
+
+    #[test]
+    fn test_transient_status() {
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_transient_status(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_endpoint_timeout() {
+        let config = TransportConfig {
+            default_timeout: Some(Duration::from_secs(10)),
+            endpoint_timeouts: HashMap::from([(
+                "/balance-prover/prove-send".to_string(),
+                Duration::from_secs(300),
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.timeout_for("/balance-prover/prove-send"),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            config.timeout_for("/balance-prover/prove-spent"),
+            Some(Duration::from_secs(10))
+        );
+    }
+}
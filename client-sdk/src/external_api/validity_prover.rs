@@ -30,7 +30,7 @@ use plonky2::{
     plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
 };
 
-use super::utils::query::{get_request, post_request};
+use super::utils::transport::HttpTransport;
 
 type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
@@ -39,17 +39,25 @@ const D: usize = 2;
 #[derive(Debug, Clone)]
 pub struct ValidityProverClient {
     base_url: String,
+    transport: HttpTransport,
 }
 
 impl ValidityProverClient {
     pub fn new(base_url: &str) -> Self {
+        Self::with_transport(base_url, HttpTransport::default())
+    }
+
+    pub fn with_transport(base_url: &str, transport: HttpTransport) -> Self {
         ValidityProverClient {
             base_url: base_url.to_string(),
+            transport,
         }
     }
 
     pub async fn sync(&self) -> Result<(), ServerError> {
-        get_request::<(), ()>(&self.base_url, "/validity-prover/sync", None).await?;
+        self.transport
+            .get_request::<(), ()>(&self.base_url, "/validity-prover/sync", None)
+            .await?;
         Ok(())
     }
 }
@@ -57,15 +65,18 @@ impl ValidityProverClient {
 #[async_trait(?Send)]
 impl ValidityProverClientInterface for ValidityProverClient {
     async fn get_block_number(&self) -> Result<u32, ServerError> {
-        let response: GetBlockNumberResponse =
-            get_request::<(), _>(&self.base_url, "/validity-prover/block-number", None).await?;
+        let response: GetBlockNumberResponse = self
+            .transport
+            .get_request::<(), _>(&self.base_url, "/validity-prover/block-number", None)
+            .await?;
         Ok(response.block_number)
     }
 
     async fn get_next_deposit_index(&self) -> Result<u32, ServerError> {
-        let response: GetNextDepositIndexResponse =
-            get_request::<(), _>(&self.base_url, "/validity-prover/next-deposit-index", None)
-                .await?;
+        let response: GetNextDepositIndexResponse = self
+            .transport
+            .get_request::<(), _>(&self.base_url, "/validity-prover/next-deposit-index", None)
+            .await?;
         Ok(response.deposit_index)
     }
 
@@ -82,12 +93,14 @@ impl ValidityProverClientInterface for ValidityProverClient {
             leaf_block_number,
             is_prev_account_tree,
         };
-        let response: GetUpdateWitnessResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-update-witness",
-            Some(query),
-        )
-        .await?;
+        let response: GetUpdateWitnessResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-update-witness",
+                Some(query),
+            )
+            .await?;
         Ok(response.update_witness)
     }
 
@@ -96,12 +109,14 @@ impl ValidityProverClientInterface for ValidityProverClient {
         deposit_hash: Bytes32,
     ) -> Result<Option<DepositInfo>, ServerError> {
         let query = GetDepositInfoQuery { deposit_hash };
-        let response: GetDepositInfoResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-deposit-info",
-            Some(query),
-        )
-        .await?;
+        let response: GetDepositInfoResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-deposit-info",
+                Some(query),
+            )
+            .await?;
         Ok(response.deposit_info)
     }
 
@@ -110,12 +125,14 @@ impl ValidityProverClientInterface for ValidityProverClient {
         tx_tree_root: Bytes32,
     ) -> Result<Option<u32>, ServerError> {
         let query = GetBlockNumberByTxTreeRootQuery { tx_tree_root };
-        let response: GetBlockNumberByTxTreeRootResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-block-number-by-tx-tree-root",
-            Some(query),
-        )
-        .await?;
+        let response: GetBlockNumberByTxTreeRootResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-block-number-by-tx-tree-root",
+                Some(query),
+            )
+            .await?;
         Ok(response.block_number)
     }
 
@@ -124,12 +141,14 @@ impl ValidityProverClientInterface for ValidityProverClient {
         block_number: u32,
     ) -> Result<Option<ValidityPublicInputs>, ServerError> {
         let query = GetValidityPisQuery { block_number };
-        let response: GetValidityPisResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-validity-pis",
-            Some(query),
-        )
-        .await?;
+        let response: GetValidityPisResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-validity-pis",
+                Some(query),
+            )
+            .await?;
         Ok(response.validity_pis)
     }
 
@@ -138,12 +157,14 @@ impl ValidityProverClientInterface for ValidityProverClient {
         block_number: u32,
     ) -> Result<Option<Vec<SenderLeaf>>, ServerError> {
         let query = GetSenderLeavesQuery { block_number };
-        let response: GetSenderLeavesResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-sender-leaves",
-            Some(query),
-        )
-        .await?;
+        let response: GetSenderLeavesResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-sender-leaves",
+                Some(query),
+            )
+            .await?;
         Ok(response.sender_leaves)
     }
 
@@ -156,12 +177,14 @@ impl ValidityProverClientInterface for ValidityProverClient {
             root_block_number,
             leaf_block_number,
         };
-        let response: GetBlockMerkleProofResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-block-merkle-proof",
-            Some(query),
-        )
-        .await?;
+        let response: GetBlockMerkleProofResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-block-merkle-proof",
+                Some(query),
+            )
+            .await?;
         Ok(response.block_merkle_proof)
     }
 
@@ -174,23 +197,27 @@ impl ValidityProverClientInterface for ValidityProverClient {
             block_number,
             deposit_index,
         };
-        let response: GetDepositMerkleProofResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-deposit-merkle-proof",
-            Some(query),
-        )
-        .await?;
+        let response: GetDepositMerkleProofResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-deposit-merkle-proof",
+                Some(query),
+            )
+            .await?;
         Ok(response.deposit_merkle_proof)
     }
 
     async fn get_account_info(&self, pubkey: U256) -> Result<AccountInfo, ServerError> {
         let query = GetAccountInfoQuery { pubkey };
-        let response: GetAccountInfoResponse = get_request(
-            &self.base_url,
-            "/validity-prover/get-account-info",
-            Some(query),
-        )
-        .await?;
+        let response: GetAccountInfoResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/validity-prover/get-account-info",
+                Some(query),
+            )
+            .await?;
         Ok(response.account_info)
     }
 }
@@ -198,8 +225,10 @@ impl ValidityProverClientInterface for ValidityProverClient {
 // coordinator client
 impl ValidityProverClient {
     pub async fn assign_task(&self) -> Result<Option<TransitionProofTask>, ServerError> {
-        let response: AssignResponse =
-            post_request::<(), _>(&self.base_url, "/coordinator/assign", None).await?;
+        let response: AssignResponse = self
+            .transport
+            .post_request::<(), _>(&self.base_url, "/coordinator/assign", None)
+            .await?;
         Ok(response.task)
     }
 
@@ -212,13 +241,17 @@ impl ValidityProverClient {
             block_number,
             transition_proof,
         };
-        post_request::<_, ()>(&self.base_url, "/coordinator/complete", Some(&request)).await?;
+        self.transport
+            .post_request::<_, ()>(&self.base_url, "/coordinator/complete", Some(&request))
+            .await?;
         Ok(())
     }
 
     pub async fn heartbeat(&self, block_number: u32) -> Result<(), ServerError> {
         let request = HeartBeatRequest { block_number };
-        post_request::<_, ()>(&self.base_url, "/coordinator/heartbeat", Some(&request)).await?;
+        self.transport
+            .post_request::<_, ()>(&self.base_url, "/coordinator/heartbeat", Some(&request))
+            .await?;
         Ok(())
     }
 }
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use intmax2_interfaces::api::{
-    error::ServerError,
+    error::{ErrorCode, ServerError},
+    session::SessionAudience,
     withdrawal_server::{
         interface::{Fee, WithdrawalInfo, WithdrawalServerClientInterface},
         types::{
@@ -18,7 +19,7 @@ use plonky2::{
     plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
 };
 
-use super::utils::query::{get_request, post_request};
+use super::utils::{session::SessionCache, transport::HttpTransport};
 
 type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
@@ -27,21 +28,38 @@ const D: usize = 2;
 #[derive(Debug, Clone)]
 pub struct WithdrawalServerClient {
     base_url: String,
+    transport: HttpTransport,
+    sessions: Option<SessionCache>,
 }
 
 impl WithdrawalServerClient {
     pub fn new(base_url: &str) -> Self {
+        Self::with_transport(base_url, HttpTransport::default())
+    }
+
+    pub fn with_transport(base_url: &str, transport: HttpTransport) -> Self {
         WithdrawalServerClient {
             base_url: base_url.to_string(),
+            transport,
+            sessions: None,
         }
     }
+
+    /// Authenticates the requests of a key by a session token, which is created by a signed
+    /// request on first use.
+    pub fn with_sessions(mut self) -> Self {
+        self.sessions = Some(SessionCache::default());
+        self
+    }
 }
 
 #[async_trait(?Send)]
 impl WithdrawalServerClientInterface for WithdrawalServerClient {
     async fn fee(&self) -> Result<Vec<Fee>, ServerError> {
-        let response: GetFeeResponse =
-            get_request::<(), _>(&self.base_url, "/withdrawal-server/fee", None).await?;
+        let response: GetFeeResponse = self
+            .transport
+            .get_request::<(), _>(&self.base_url, "/withdrawal-server/fee", None)
+            .await?;
         Ok(response.fees)
     }
 
@@ -54,25 +72,47 @@ impl WithdrawalServerClientInterface for WithdrawalServerClient {
             pubkey,
             single_withdrawal_proof: single_withdrawal_proof.clone(),
         };
-        post_request::<_, ()>(
-            &self.base_url,
-            "/withdrawal-server/request-withdrawal",
-            Some(&request),
-        )
-        .await
+        self.transport
+            .post_request::<_, ()>(
+                &self.base_url,
+                "/withdrawal-server/request-withdrawal",
+                Some(&request),
+            )
+            .await
     }
 
     async fn get_withdrawal_info(&self, key: KeySet) -> Result<Vec<WithdrawalInfo>, ServerError> {
         let pubkey = key.pubkey;
         let signature = FlatG2::default(); // todo: get signature from key
         let query = GetWithdrawalInfoRequest { pubkey, signature };
-        let response: GetWithdrawalInfoResponse = get_request(
-            &self.base_url,
-            "/withdrawal-server/get-withdrawal-info",
-            Some(query),
-        )
-        .await?;
-        Ok(response.withdrawal_info)
+        let transport = match &self.sessions {
+            Some(sessions) => {
+                sessions
+                    .transport(
+                        &self.transport,
+                        &self.base_url,
+                        "/withdrawal-server/create-session",
+                        SessionAudience::WithdrawalServer,
+                        key,
+                    )
+                    .await?
+            }
+            None => self.transport.clone(),
+        };
+        let result: Result<GetWithdrawalInfoResponse, ServerError> = transport
+            .get_request(
+                &self.base_url,
+                "/withdrawal-server/get-withdrawal-info",
+                Some(query),
+            )
+            .await;
+        if let (Some(sessions), Err(e)) = (&self.sessions, &result) {
+            // e.g. the server restarted without a shared secret, so create a new session next time
+            if e.code() == Some(ErrorCode::Unauthorized) {
+                sessions.invalidate(pubkey);
+            }
+        }
+        Ok(result?.withdrawal_info)
     }
 
     async fn get_withdrawal_info_by_recipient(
@@ -80,12 +120,14 @@ impl WithdrawalServerClientInterface for WithdrawalServerClient {
         recipient: Address,
     ) -> Result<Vec<WithdrawalInfo>, ServerError> {
         let query = GetWithdrawalInfoByRecipientRequest { recipient };
-        let response: GetWithdrawalInfoResponse = get_request(
-            &self.base_url,
-            "/withdrawal-server/get-withdrawal-info-by-recipient",
-            Some(query),
-        )
-        .await?;
+        let response: GetWithdrawalInfoResponse = self
+            .transport
+            .get_request(
+                &self.base_url,
+                "/withdrawal-server/get-withdrawal-info-by-recipient",
+                Some(query),
+            )
+            .await?;
         Ok(response.withdrawal_info)
     }
 }
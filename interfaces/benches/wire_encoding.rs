@@ -0,0 +1,123 @@
+//! Size and encoding time of real balance proofs and update witnesses in each wire format.
+//!
+//! Run with `cargo bench -p intmax2-interfaces --bench wire_encoding`. The fixtures are read from
+//! `test_data`, or from the directories given by `BALANCE_PROOF_FIXTURES` and
+//! `UPDATE_WITNESS_FIXTURES`:
+//! - `balance_proofs/*.bin`: the bytes of `CompressedBalanceProof`s, i.e. the `balance_proof` of
+//!   the user data of synced accounts (e.g. taken from a backup written by the `export-backup`
+//!   command of the CLI).
+//! - `update_witnesses/*.json`: `GetUpdateWitnessResponse` bodies of the validity prover.
+//!
+//! Each proof is measured as a `ProveResponse` in JSON and bincode, and as the
+//! `CompressedBalanceProof` stored in the user data, raw and in JSON (base64). Each update
+//! witness is measured as a `GetUpdateWitnessResponse` in JSON and bincode. Transfer time over the
+//! network is roughly proportional to the size. A kind of fixture that is missing is skipped.
+
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use intmax2_interfaces::{
+    api::{
+        balance_prover::types::ProveResponse, encoding::WireFormat,
+        validity_prover::types::GetUpdateWitnessResponse,
+    },
+    data::proof_compression::CompressedBalanceProof,
+};
+
+/// Files with the given extension in the fixture directory, as (name, bytes) sorted by name.
+fn fixtures(var: &str, default_dir: &str, extension: &str) -> Vec<(String, Vec<u8>)> {
+    let dir = std::env::var(var)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(default_dir));
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("skipping fixtures in {}: {}", dir.display(), e);
+            return Vec::new();
+        }
+    };
+    let mut fixtures = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            (name, std::fs::read(&path).unwrap())
+        })
+        .collect::<Vec<_>>();
+    if fixtures.is_empty() {
+        println!("no .{} fixture in {}, skipping", extension, dir.display());
+    }
+    fixtures.sort_by(|a, b| a.0.cmp(&b.0));
+    fixtures
+}
+
+fn balance_proofs(c: &mut Criterion) {
+    for (name, bytes) in fixtures("BALANCE_PROOF_FIXTURES", "test_data/balance_proofs", "bin") {
+        let compressed = CompressedBalanceProof(bytes);
+        let proof = compressed
+            .decompress()
+            .unwrap_or_else(|e| panic!("fixture {} is not a balance proof: {}", name, e));
+        let response = ProveResponse {
+            proof: proof.clone(),
+        };
+
+        let mut group = c.benchmark_group(format!("balance proof {}", name));
+        for format in [WireFormat::Json, WireFormat::Bincode] {
+            let encoded = format.encode(&response).unwrap();
+            println!("{} {:?} size: {} bytes", name, format, encoded.len());
+
+            group.throughput(Throughput::Bytes(encoded.len() as u64));
+            group.bench_function(format!("encode {:?}", format), |b| {
+                b.iter(|| format.encode(black_box(&response)).unwrap())
+            });
+            group.bench_function(format!("decode {:?}", format), |b| {
+                b.iter(|| format.decode::<ProveResponse>(black_box(&encoded)).unwrap())
+            });
+        }
+
+        let json = serde_json::to_vec(&compressed).unwrap();
+        println!("{} compressed size: {} bytes", name, compressed.0.len());
+        println!("{} compressed Json size: {} bytes", name, json.len());
+        group.throughput(Throughput::Bytes(compressed.0.len() as u64));
+        group.bench_function("compress", |b| {
+            b.iter(|| CompressedBalanceProof::new(black_box(&proof)).unwrap())
+        });
+        group.bench_function("decompress", |b| {
+            b.iter(|| black_box(&compressed).decompress().unwrap())
+        });
+        group.finish();
+    }
+}
+
+fn update_witnesses(c: &mut Criterion) {
+    for (name, bytes) in fixtures(
+        "UPDATE_WITNESS_FIXTURES",
+        "test_data/update_witnesses",
+        "json",
+    ) {
+        let response: GetUpdateWitnessResponse = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|e| panic!("fixture {} is not an update witness: {}", name, e));
+
+        let mut group = c.benchmark_group(format!("update witness {}", name));
+        for format in [WireFormat::Json, WireFormat::Bincode] {
+            let encoded = format.encode(&response).unwrap();
+            println!("{} {:?} size: {} bytes", name, format, encoded.len());
+
+            group.throughput(Throughput::Bytes(encoded.len() as u64));
+            group.bench_function(format!("encode {:?}", format), |b| {
+                b.iter(|| format.encode(black_box(&response)).unwrap())
+            });
+            group.bench_function(format!("decode {:?}", format), |b| {
+                b.iter(|| {
+                    format
+                        .decode::<GetUpdateWitnessResponse>(black_box(&encoded))
+                        .unwrap()
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, balance_proofs, update_witnesses);
+criterion_main!(benches);
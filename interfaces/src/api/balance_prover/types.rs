@@ -12,59 +12,77 @@ use plonky2::{
     plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
 const D: usize = 2;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveResponse {
+    #[schema(value_type = Object)]
     pub proof: ProofWithPublicInputs<F, C, D>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveSpentRequest {
+    #[schema(value_type = Object)]
     pub spent_witness: SpentWitness,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveSendRequest {
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub tx_witnes: TxWitness,
+    #[schema(value_type = Object)]
     pub update_witness: UpdateWitness<F, C, D>,
+    #[schema(value_type = Object)]
     pub spent_proof: ProofWithPublicInputs<F, C, D>,
+    #[schema(value_type = Option<Object>)]
     pub prev_proof: Option<ProofWithPublicInputs<F, C, D>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveUpdateRequest {
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub update_witness: UpdateWitness<F, C, D>,
+    #[schema(value_type = Option<Object>)]
     pub prev_proof: Option<ProofWithPublicInputs<F, C, D>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveReceiveTransferRequest {
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub receive_transfer_witness: ReceiveTransferWitness<F, C, D>,
+    #[schema(value_type = Option<Object>)]
     pub prev_proof: Option<ProofWithPublicInputs<F, C, D>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveReceiveDepositRequest {
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub receive_deposit_witness: ReceiveDepositWitness,
+    #[schema(value_type = Option<Object>)]
     pub prev_proof: Option<ProofWithPublicInputs<F, C, D>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProveSingleWithdrawalRequest {
+    #[schema(value_type = Object)]
     pub withdrawal_witness: WithdrawalWitness<F, C, D>,
 }
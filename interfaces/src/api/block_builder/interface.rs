@@ -11,6 +11,7 @@ use plonky2::{
     plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::api::error::ServerError;
 
@@ -18,15 +19,18 @@ type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
 const D: usize = 2;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FeeProof {
+    #[schema(value_type = Object)]
     pub spent_proof: ProofWithPublicInputs<F, C, D>,
+    #[schema(value_type = Object)]
     pub prev_balance_proof: ProofWithPublicInputs<F, C, D>,
+    #[schema(value_type = Object)]
     pub transfer_witness: TransferWitness,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum BlockBuilderStatus {
     Pausing,        // not accepting tx requests
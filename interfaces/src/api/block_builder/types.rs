@@ -3,48 +3,58 @@ use intmax2_zkp::{
     ethereum_types::u256::U256,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use super::interface::{BlockBuilderStatus, FeeProof};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TxRequestRequest {
     pub is_registration_block: bool,
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub tx: Tx,
     pub fee_proof: Option<FeeProof>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryProposalRequest {
     pub is_registration_block: bool,
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub tx: Tx,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryProposalResponse {
+    #[schema(value_type = Option<Object>)]
     pub block_proposal: Option<BlockProposal>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PostSignatureRequest {
     pub is_registration_block: bool,
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub tx: Tx,
+    #[schema(value_type = Object)]
     pub signature: FlatG2,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct GetBlockBuilderStatusQuery {
     pub is_registration_block: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetBlockBuilderStatusResponse {
     pub status: BlockBuilderStatus,
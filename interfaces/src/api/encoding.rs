@@ -0,0 +1,137 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::error::ServerError;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const BINCODE_CONTENT_TYPE: &str = "application/x-bincode";
+
+/// Encoding of request and response bodies.
+///
+/// JSON is the default. Servers that support bincode use it for the request body if the
+/// Content-Type is bincode, and for the response if the Accept header prefers bincode, so that
+/// clients and servers can be upgraded independently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Bincode,
+}
+
+impl WireFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => JSON_CONTENT_TYPE,
+            Self::Bincode => BINCODE_CONTENT_TYPE,
+        }
+    }
+
+    /// Accept header requesting this format, falling back to JSON for servers that do not
+    /// support bincode.
+    pub fn accept(&self) -> &'static str {
+        match self {
+            Self::Json => JSON_CONTENT_TYPE,
+            Self::Bincode => "application/x-bincode, application/json;q=0.5",
+        }
+    }
+
+    /// Format of a body with the given Content-Type. JSON if the header is missing or unknown.
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type.map(media_type) {
+            Some(media_type) if media_type == BINCODE_CONTENT_TYPE => Self::Bincode,
+            _ => Self::Json,
+        }
+    }
+
+    /// Format preferred by the given Accept header. Bincode is used only if it is listed
+    /// explicitly with a quality not lower than that of JSON.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Self::Json;
+        };
+        let mut bincode_q = 0.0f32;
+        let mut json_q = 0.0f32;
+        for entry in accept.split(',') {
+            let mut parts = entry.split(';');
+            let media_type = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            match media_type.as_str() {
+                BINCODE_CONTENT_TYPE => bincode_q = bincode_q.max(q),
+                JSON_CONTENT_TYPE | "application/*" | "*/*" => json_q = json_q.max(q),
+                _ => {}
+            }
+        }
+        if bincode_q > 0.0 && bincode_q >= json_q {
+            Self::Bincode
+        } else {
+            Self::Json
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ServerError> {
+        match self {
+            Self::Json => serde_json::to_vec(value)
+                .map_err(|e| ServerError::SerializeError(format!("Failed to serialize: {}", e))),
+            Self::Bincode => bincode::serialize(value)
+                .map_err(|e| ServerError::SerializeError(format!("Failed to serialize: {}", e))),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ServerError> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|e| {
+                ServerError::DeserializationError(format!("Failed to deserialize: {}", e))
+            }),
+            Self::Bincode => bincode::deserialize(bytes).map_err(|e| {
+                ServerError::DeserializationError(format!("Failed to deserialize: {}", e))
+            }),
+        }
+    }
+}
+
+// media type without parameters, e.g. "application/json" of "application/json; charset=utf-8"
+fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WireFormat;
+
+    #[test]
+    fn test_wire_format_negotiation() {
+        assert_eq!(WireFormat::from_accept(None), WireFormat::Json);
+        assert_eq!(WireFormat::from_accept(Some("*/*")), WireFormat::Json);
+        assert_eq!(
+            WireFormat::from_accept(Some(WireFormat::Bincode.accept())),
+            WireFormat::Bincode
+        );
+        assert_eq!(
+            WireFormat::from_accept(Some("application/x-bincode;q=0.2, application/json")),
+            WireFormat::Json
+        );
+        assert_eq!(
+            WireFormat::from_content_type(Some("application/json; charset=utf-8")),
+            WireFormat::Json
+        );
+        assert_eq!(
+            WireFormat::from_content_type(Some("application/x-bincode")),
+            WireFormat::Bincode
+        );
+
+        let value = (1u64, "intmax2".to_string(), vec![1u32, 2, 3]);
+        for format in [WireFormat::Json, WireFormat::Bincode] {
+            let encoded = format.encode(&value).unwrap();
+            let decoded: (u64, String, Vec<u32>) = format.decode(&encoded).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
 #[derive(Debug, thiserror::Error)]
 pub enum ServerError {
     #[error("Env error: {0}")]
@@ -9,6 +12,17 @@ pub enum ServerError {
     #[error("Server error status={0}, message={1}, url={2}, query={3}")]
     ServerError(u16, String, String, String),
 
+    /// Structured error returned by the server
+    #[error("Server error status={status}, code={code}, message={message}, url={url}")]
+    ApiError {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+        retryable: bool,
+        details: Option<serde_json::Value>,
+        url: String,
+    },
+
     #[error("Unknown error: {0}")]
     UnknownError(String),
 
@@ -30,3 +44,158 @@ pub enum ServerError {
     #[error("Internal error: {0}")]
     InternalError(String),
 }
+
+impl ServerError {
+    /// Returns the error code if the server returned a structured error
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            ServerError::ApiError { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the same request may succeed later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ServerError::ApiError { retryable, .. } => *retryable,
+            ServerError::NetworkError(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Stable code of an error returned by the servers, which clients can match on instead of the
+/// message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    ReplayedRequest,
+    NotFound,
+    PayloadTooLarge,
+    TooManyRequests,
+    Internal,
+    Unavailable,
+
+    // block builder
+    NotAcceptingTx,
+    BlockIsFull,
+    OnlyOneSenderAllowed,
+    BlockBuilderIsPausing,
+    NotProposing,
+    TxRequestNotFound,
+    AccountAlreadyRegistered,
+    AccountNotFound,
+    InvalidSignature,
+    ValidityProverNotSynced,
+
+    // store vault server
+    DigestMismatch,
+    ProofOfWorkRequired,
+    InvalidConfirmationToken,
+    AccountDeleted,
+
+    // provers and withdrawal server
+    ProofGenerationFailed,
+    ProofVerificationFailed,
+
+    /// A code unknown to this version of the client
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    /// HTTP status of the error
+    pub fn status(&self) -> u16 {
+        match self {
+            ErrorCode::BadRequest | ErrorCode::InvalidSignature => 400,
+            ErrorCode::Unauthorized | ErrorCode::ReplayedRequest => 401,
+            ErrorCode::ProofOfWorkRequired | ErrorCode::InvalidConfirmationToken => 403,
+            ErrorCode::NotFound | ErrorCode::TxRequestNotFound | ErrorCode::AccountNotFound => 404,
+            ErrorCode::NotAcceptingTx
+            | ErrorCode::OnlyOneSenderAllowed
+            | ErrorCode::NotProposing
+            | ErrorCode::AccountAlreadyRegistered
+            | ErrorCode::DigestMismatch => 409,
+            ErrorCode::AccountDeleted => 410,
+            ErrorCode::PayloadTooLarge => 413,
+            ErrorCode::ProofGenerationFailed | ErrorCode::ProofVerificationFailed => 422,
+            ErrorCode::TooManyRequests => 429,
+            ErrorCode::Internal | ErrorCode::Unknown => 500,
+            ErrorCode::Unavailable
+            | ErrorCode::BlockIsFull
+            | ErrorCode::BlockBuilderIsPausing
+            | ErrorCode::ValidityProverNotSynced => 503,
+        }
+    }
+
+    /// Whether the same request may succeed later without any change
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::TooManyRequests
+                | ErrorCode::Unavailable
+                | ErrorCode::NotAcceptingTx
+                | ErrorCode::BlockIsFull
+                | ErrorCode::OnlyOneSenderAllowed
+                | ErrorCode::BlockBuilderIsPausing
+                | ErrorCode::NotProposing
+                | ErrorCode::ValidityProverNotSynced
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = serde_json::to_value(self).unwrap();
+        write!(f, "{}", s.as_str().unwrap())
+    }
+}
+
+/// Body of every error response of the servers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retryable: code.is_retryable(),
+            details: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorCode, ErrorResponse};
+
+    #[test]
+    fn test_error_response_roundtrip() {
+        let response = ErrorResponse::new(ErrorCode::BlockIsFull, "Block is full");
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"code":"BLOCK_IS_FULL","message":"Block is full","retryable":true}"#
+        );
+        let decoded: ErrorResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.code, ErrorCode::BlockIsFull);
+        assert_eq!(ErrorCode::BlockIsFull.to_string(), "BLOCK_IS_FULL");
+
+        // codes added by newer servers are still parsed
+        let decoded: ErrorResponse =
+            serde_json::from_str(r#"{"code":"SOMETHING_NEW","message":"","retryable":false}"#)
+                .unwrap();
+        assert_eq!(decoded.code, ErrorCode::Unknown);
+    }
+}
@@ -1,7 +1,9 @@
 pub mod balance_prover;
 pub mod block_builder;
+pub mod encoding;
 pub mod error;
 pub mod indexer;
+pub mod session;
 pub mod store_vault_server;
 pub mod validity_prover;
 pub mod withdrawal_server;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::utils::signature::Signable;
+
+/// Server for which a session token is issued. A token is only accepted by the server it was
+/// issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionAudience {
+    StoreVaultServer,
+    WithdrawalServer,
+}
+
+impl SessionAudience {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionAudience::StoreVaultServer => "store-vault-server",
+            SessionAudience::WithdrawalServer => "withdrawal-server",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSessionRequest {
+    pub audience: SessionAudience,
+}
+
+impl Signable for CreateSessionRequest {
+    fn content(&self) -> Vec<u8> {
+        bincode::serialize(&("create-session", self.audience)).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSessionResponse {
+    /// Bearer token authenticating the requests of the pubkey until `expires_at`
+    pub token: String,
+    pub expires_at: u64,
+}
@@ -7,12 +7,16 @@ use intmax2_zkp::{
 };
 use serde::{Deserialize, Serialize};
 use serde_with::{base64::Base64, serde_as};
+use utoipa::ToSchema;
 
-use crate::api::error::ServerError;
+use crate::{api::error::ServerError, utils::signature::WithAuth};
 
-use super::types::DataWithMetaData;
+use super::types::{
+    DataNotification, DataWithMetaData, DeleteMyDataResponse, RequestDataDeletionResponse,
+    SaveDataBatchRequest, UserDataVersion,
+};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum DataType {
     Deposit,
@@ -60,13 +64,19 @@ impl FromStr for DataType {
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SaveDataEntry {
     pub data_type: DataType,
+    #[schema(value_type = String)]
     pub pubkey: U256,
     #[serde_as(as = "Base64")]
+    #[schema(value_type = String, format = Byte)]
     pub encrypted_data: Vec<u8>,
+    /// Proof of work of an entry written to the inbox of another user, if the server requires
+    /// it. See `utils::pow`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pow_nonce: Option<u64>,
 }
 
 #[async_trait(?Send)]
@@ -80,26 +90,116 @@ pub trait StoreVaultClientInterface {
 
     async fn get_user_data(&self, key: KeySet) -> Result<Option<Vec<u8>>, ServerError>;
 
+    /// Lists the versions of the user data kept by the server, the newest first.
+    async fn list_user_data_versions(
+        &self,
+        key: KeySet,
+    ) -> Result<Vec<UserDataVersion>, ServerError>;
+
+    async fn get_user_data_version(
+        &self,
+        key: KeySet,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, ServerError>;
+
+    /// Saves the sender proof set shared by the `num_transfers` transfers of a tx. It is
+    /// considered acknowledged once every transfer has been acknowledged, and never if
+    /// `num_transfers` is `None`.
     async fn save_sender_proof_set(
         &self,
         ephemeral_key: KeySet,
         encrypted_data: &[u8],
+        num_transfers: Option<u32>,
     ) -> Result<(), ServerError>;
 
     async fn get_sender_proof_set(&self, ephemeral_key: KeySet) -> Result<Vec<u8>, ServerError>;
 
+    /// Notifies that the transfer of `transfer_index` of the sender proof set has been
+    /// incorporated. Once all the transfers of the tx have been acknowledged, the server may
+    /// delete the set according to its retention policy.
+    async fn acknowledge_sender_proof_set(
+        &self,
+        ephemeral_key: KeySet,
+        transfer_index: u32,
+    ) -> Result<(), ServerError>;
+
     async fn save_data_batch(
         &self,
         key: KeySet,
         entries: &[SaveDataEntry],
     ) -> Result<Vec<String>, ServerError>;
 
+    /// Same as `save_data_batch` with an auth that is signed in advance, e.g. by threshold
+    /// signing. The entries must already carry the proof of work required by the server, see
+    /// `attach_pow`.
+    async fn save_data_batch_with_auth(
+        &self,
+        request: &WithAuth<SaveDataBatchRequest>,
+    ) -> Result<Vec<String>, ServerError>;
+
+    /// Attaches the proof of work required by the server, as far as known to the client, to the
+    /// entries written by `writer` to the inboxes of other users.
+    fn attach_pow(&self, writer: U256, entries: &mut [SaveDataEntry]);
+
     async fn get_data_all_after(
         &self,
         data_type: DataType,
         key: KeySet,
         timestamp: u64,
     ) -> Result<Vec<DataWithMetaData>, ServerError>;
+
+    /// Fetches the entries of the user by their uuids. Unknown uuids are omitted from the result.
+    async fn get_data_batch(
+        &self,
+        key: KeySet,
+        data_type: DataType,
+        uuids: &[String],
+    ) -> Result<Vec<DataWithMetaData>, ServerError>;
+
+    /// Notifies that the entries have been processed, so that the server may delete them
+    /// according to its retention policy.
+    async fn acknowledge_data(
+        &self,
+        key: KeySet,
+        data_type: DataType,
+        uuids: &[String],
+    ) -> Result<(), ServerError>;
+
+    /// Whether the server garbage collects acknowledged data, i.e. whether the acknowledgements
+    /// have any effect. False for servers without GC metrics.
+    async fn is_gc_enabled(&self) -> Result<bool, ServerError>;
+
+    /// Requests a token to confirm the deletion of all data of the user via `delete_my_data`.
+    async fn request_data_deletion(
+        &self,
+        key: KeySet,
+    ) -> Result<RequestDataDeletionResponse, ServerError>;
+
+    /// Deletes the user data with its history and all entries of the user, together with the
+    /// sender proof sets of `sender_proof_set_keys`. The server keeps a tombstone of the account,
+    /// so that `get_user_data` fails with `AccountDeleted` afterwards.
+    async fn delete_my_data(
+        &self,
+        key: KeySet,
+        token: &str,
+        sender_proof_set_keys: &[KeySet],
+    ) -> Result<DeleteMyDataResponse, ServerError>;
+
+    /// Subscribes to the notifications of the entries of `data_types` saved for the user. The
+    /// notifications may be missed while not subscribed, so the clients should also poll via
+    /// `get_data_all_after`.
+    async fn subscribe(
+        &self,
+        key: KeySet,
+        data_types: &[DataType],
+    ) -> Result<Box<dyn DataSubscription>, ServerError>;
+}
+
+#[async_trait(?Send)]
+pub trait DataSubscription {
+    /// Returns the next notification, or None if the server closed the subscription, e.g. since
+    /// the subscriber could not keep up with the notifications.
+    async fn next(&mut self) -> Result<Option<DataNotification>, ServerError>;
 }
 
 #[cfg(test)]
@@ -1,15 +1,21 @@
 use super::interface::{DataType, SaveDataEntry};
-use crate::{data::meta_data::MetaData, utils::signature::Signable};
-use intmax2_zkp::ethereum_types::bytes32::Bytes32;
+use crate::{
+    data::meta_data::MetaData,
+    utils::signature::{Auth, Signable},
+};
+use intmax2_zkp::ethereum_types::{bytes32::Bytes32, u256::U256};
 use serde::{Deserialize, Serialize};
 use serde_with::{base64::Base64, serde_as};
+use utoipa::ToSchema;
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SaveUserDataRequest {
     #[serde_as(as = "Base64")]
+    #[schema(value_type = String, format = Byte)]
     pub data: Vec<u8>,
+    #[schema(value_type = Option<String>)]
     pub prev_digest: Option<Bytes32>,
 }
 
@@ -19,7 +25,7 @@ impl Signable for SaveUserDataRequest {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetUserDataRequest;
 
@@ -30,28 +36,87 @@ impl Signable for GetUserDataRequest {
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetUserDataResponse {
     #[serde_as(as = "Option<Base64>")]
+    #[schema(value_type = Option<String>, format = Byte)]
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUserDataVersionsRequest;
+
+impl Signable for ListUserDataVersionsRequest {
+    fn content(&self) -> Vec<u8> {
+        b"list-user-data-versions".to_vec()
+    }
+}
+
+/// A version of the encrypted user data kept by the store vault server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataVersion {
+    pub version: u64,
+    #[schema(value_type = String)]
+    pub digest: Bytes32,
+    pub timestamp: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUserDataVersionsResponse {
+    /// Newest first, i.e. the first one is the current user data
+    pub versions: Vec<UserDataVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUserDataVersionRequest {
+    pub version: u64,
+}
+
+impl Signable for GetUserDataVersionRequest {
+    fn content(&self) -> Vec<u8> {
+        bincode::serialize(&("get-user-data-version", self.version)).unwrap()
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUserDataVersionResponse {
+    #[serde_as(as = "Option<Base64>")]
+    #[schema(value_type = Option<String>, format = Byte)]
     pub data: Option<Vec<u8>>,
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SaveSenderProofSetRequest {
     #[serde_as(as = "Base64")]
+    #[schema(value_type = String, format = Byte)]
     pub data: Vec<u8>,
+    /// Number of transfers of the tx, each of which acknowledges the set once. A set saved
+    /// without it is never considered acknowledged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_transfers: Option<u32>,
 }
 
 impl Signable for SaveSenderProofSetRequest {
     fn content(&self) -> Vec<u8> {
-        bincode::serialize(&self.data).unwrap()
+        match self.num_transfers {
+            // keeps the content of the requests without it unchanged
+            None => bincode::serialize(&self.data).unwrap(),
+            Some(num_transfers) => bincode::serialize(&(&self.data, num_transfers)).unwrap(),
+        }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetSenderProofSetRequest;
 
@@ -62,14 +127,31 @@ impl Signable for GetSenderProofSetRequest {
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetSenderProofSetResponse {
     #[serde_as(as = "Base64")]
+    #[schema(value_type = String, format = Byte)]
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AcknowledgeSenderProofSetRequest {
+    /// Index of the acknowledged transfer in the tx
+    pub transfer_index: u32,
+}
+
+impl Signable for AcknowledgeSenderProofSetRequest {
+    fn content(&self) -> Vec<u8> {
+        // distinguished from the empty content of `GetSenderProofSetRequest`
+        let mut content = b"acknowledge-sender-proof-set".to_vec();
+        content.extend_from_slice(&self.transfer_index.to_be_bytes());
+        content
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SaveDataBatchRequest {
     pub data: Vec<SaveDataEntry>,
@@ -81,13 +163,13 @@ impl Signable for SaveDataBatchRequest {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SaveDataBatchResponse {
     pub uuids: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetDataAllAfterRequest {
     pub data_type: DataType,
@@ -100,17 +182,152 @@ impl Signable for GetDataAllAfterRequest {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetDataAllAfterResponse {
     pub data: Vec<DataWithMetaData>,
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DataWithMetaData {
     pub meta: MetaData,
     #[serde_as(as = "Base64")]
+    #[schema(value_type = String, format = Byte)]
     pub data: Vec<u8>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDataBatchRequest {
+    pub data_type: DataType,
+    pub uuids: Vec<String>,
+}
+
+impl Signable for GetDataBatchRequest {
+    fn content(&self) -> Vec<u8> {
+        bincode::serialize(&("get-data-batch", self.data_type, &self.uuids)).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDataBatchResponse {
+    /// Entries found, in the order of timestamp. Unknown uuids and entries of other users are
+    /// omitted.
+    pub data: Vec<DataWithMetaData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AcknowledgeDataRequest {
+    pub data_type: DataType,
+    pub uuids: Vec<String>,
+}
+
+impl Signable for AcknowledgeDataRequest {
+    fn content(&self) -> Vec<u8> {
+        bincode::serialize(&("acknowledge-data", self.data_type, &self.uuids)).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetGcMetricsResponse {
+    /// Whether GC is enabled, i.e. whether the server deletes acknowledged data. Clients skip the
+    /// acknowledgements otherwise. Missing in the responses of older servers.
+    #[serde(default)]
+    pub gc_enabled: bool,
+    pub runs: u64,
+    pub failures: u64,
+    pub last_run_at: Option<u64>,
+    pub targets: Vec<GcTargetMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GcTargetMetrics {
+    pub target: String,
+    pub deleted_rows: u64,
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeRequest {
+    /// Data types to be notified of. Empty means all.
+    pub data_types: Vec<DataType>,
+}
+
+impl Signable for SubscribeRequest {
+    fn content(&self) -> Vec<u8> {
+        bincode::serialize(&("subscribe", &self.data_types)).unwrap()
+    }
+}
+
+/// Notification of an entry saved for the subscriber, sent as the `data` event of the
+/// `/subscribe` stream. The entry itself is fetched via `get-data-batch`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DataNotification {
+    pub data_type: DataType,
+    pub uuid: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestDataDeletionRequest;
+
+impl Signable for RequestDataDeletionRequest {
+    fn content(&self) -> Vec<u8> {
+        b"request-data-deletion".to_vec()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestDataDeletionResponse {
+    /// Token to be passed to `delete-my-data` to confirm the deletion
+    pub token: String,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteMyDataRequest {
+    pub token: String,
+    /// Auths of the ephemeral keys of the sender proof sets to be deleted together, signed over
+    /// `sender_proof_set_content(token)`
+    pub sender_proof_set_auths: Vec<Auth>,
+}
+
+impl DeleteMyDataRequest {
+    pub fn sender_proof_set_content(token: &str) -> Vec<u8> {
+        bincode::serialize(&("delete-sender-proof-set", token)).unwrap()
+    }
+}
+
+impl Signable for DeleteMyDataRequest {
+    fn content(&self) -> Vec<u8> {
+        let ephemeral_pubkeys = self
+            .sender_proof_set_auths
+            .iter()
+            .map(|auth| auth.pubkey)
+            .collect::<Vec<U256>>();
+        bincode::serialize(&("delete-my-data", &self.token, ephemeral_pubkeys)).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteMyDataResponse {
+    pub deleted_user_data_versions: u64,
+    pub deleted_entries: u64,
+    pub deleted_sender_proof_sets: u64,
+    /// Sender proof sets kept until the retention, since some of their transfers are not
+    /// acknowledged by the recipients yet
+    #[serde(default)]
+    pub skipped_sender_proof_sets: u64,
+}
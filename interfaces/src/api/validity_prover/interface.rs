@@ -12,6 +12,7 @@ use intmax2_zkp::{
 };
 use plonky2::{field::goldilocks_field::GoldilocksField, plonk::config::PoseidonGoldilocksConfig};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::api::error::ServerError;
 
@@ -19,26 +20,29 @@ type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
 const D: usize = 2;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DepositInfo {
+    #[schema(value_type = String)]
     pub deposit_hash: Bytes32,
     pub block_number: u32,
     pub deposit_index: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AccountInfo {
     pub account_id: Option<u64>,
     pub block_number: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TransitionProofTask {
     pub block_number: u32,
+    #[schema(value_type = Object)]
     pub prev_validity_pis: ValidityPublicInputs,
+    #[schema(value_type = Object)]
     pub validity_witness: ValidityWitness,
 }
 
@@ -11,6 +11,7 @@ use plonky2::{
 };
 use plonky2_keccak::utils::solidity_keccak256;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::api::error::ServerError;
 
@@ -19,7 +20,7 @@ type C = PoseidonGoldilocksConfig;
 const D: usize = 2;
 
 /// fee = constant + coefficient * amount
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Fee {
     pub token_index: u32,
@@ -27,19 +28,22 @@ pub struct Fee {
     pub coefficient: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct WithdrawalInfo {
     pub status: WithdrawalStatus,
     pub contract_withdrawal: ContractWithdrawal,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContractWithdrawal {
+    #[schema(value_type = String)]
     pub recipient: Address,
     pub token_index: u32,
+    #[schema(value_type = String)]
     pub amount: U256,
+    #[schema(value_type = String)]
     pub nullifier: Bytes32,
 }
 
@@ -54,7 +58,7 @@ impl ContractWithdrawal {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum WithdrawalStatus {
     Requested = 0,
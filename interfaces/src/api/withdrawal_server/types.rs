@@ -7,6 +7,7 @@ use plonky2::{
     plonk::{config::PoseidonGoldilocksConfig, proof::ProofWithPublicInputs},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use super::interface::{Fee, WithdrawalInfo};
 
@@ -14,33 +15,40 @@ type F = GoldilocksField;
 type C = PoseidonGoldilocksConfig;
 const D: usize = 2;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RequestWithdrawalRequest {
+    #[schema(value_type = String)]
     pub pubkey: U256,
+    #[schema(value_type = Object)]
     pub single_withdrawal_proof: ProofWithPublicInputs<F, C, D>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetFeeResponse {
     pub fees: Vec<Fee>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct GetWithdrawalInfoRequest {
+    #[param(value_type = String)]
     pub pubkey: U256,
+    #[param(value_type = Object)]
     pub signature: FlatG2,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct GetWithdrawalInfoByRecipientRequest {
+    #[param(value_type = String)]
     pub recipient: Address,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GetWithdrawalInfoResponse {
     pub withdrawal_info: Vec<WithdrawalInfo>,
@@ -14,6 +14,7 @@ use intmax2_zkp::{
 use super::{
     encryption::algorithm::{decrypt, encrypt},
     error::DataError,
+    versioning::{from_versioned_bytes, to_versioned_bytes, Versioned},
 };
 
 type Result<T> = std::result::Result<T, DataError>;
@@ -86,12 +87,11 @@ impl TryFrom<u8> for TokenType {
 
 impl DepositData {
     fn to_bytes(&self) -> Vec<u8> {
-        bincode::serialize(self).unwrap()
+        to_versioned_bytes(self)
     }
 
     fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let data = bincode::deserialize(bytes)?;
-        Ok(data)
+        from_versioned_bytes(bytes)
     }
 
     pub fn encrypt(&self, pubkey: U256) -> Vec<u8> {
@@ -133,9 +133,57 @@ impl DepositData {
     }
 }
 
+impl Versioned for DepositData {
+    const CURRENT_VERSION: u32 = 1;
+
+    fn migrate(version: u32, body: &[u8]) -> Result<Self> {
+        match version {
+            // v0 is the untagged legacy encoding, whose layout is identical to v1
+            0 => Ok(bincode::deserialize(body)?),
+            _ => Err(DataError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Frozen layout of v1, which is embedded in v1 of `WalletBackup`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DepositDataV1 {
+    deposit_salt: Salt,
+    depositor: Address,
+    pubkey_salt_hash: Bytes32,
+    amount: U256,
+    is_eligible: bool,
+    token_type: TokenType,
+    token_address: Address,
+    token_id: U256,
+    token_index: Option<u32>,
+}
+
+impl From<DepositDataV1> for DepositData {
+    fn from(v1: DepositDataV1) -> Self {
+        Self {
+            deposit_salt: v1.deposit_salt,
+            depositor: v1.depositor,
+            pubkey_salt_hash: v1.pubkey_salt_hash,
+            amount: v1.amount,
+            is_eligible: v1.is_eligible,
+            token_type: v1.token_type,
+            token_address: v1.token_address,
+            token_id: v1.token_id,
+            token_index: v1.token_index,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::TokenType;
+    use super::{DepositData, TokenType};
+    use crate::data::encryption::algorithm::encrypt;
+    use intmax2_zkp::{
+        common::{deposit::get_pubkey_salt_hash, salt::Salt, signature::key_set::KeySet},
+        ethereum_types::{address::Address, u256::U256, u32limb_trait::U32LimbTrait},
+    };
     use std::str::FromStr;
 
     #[test]
@@ -146,4 +194,47 @@ mod tests {
         let erc721 = TokenType::ERC721;
         assert_eq!(erc721.to_string(), "ERC721");
     }
+
+    #[test]
+    fn test_decrypt_legacy_deposit_data() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let deposit_salt = Salt::rand(&mut rng);
+        let deposit_data = DepositData {
+            deposit_salt,
+            depositor: Address::rand(&mut rng),
+            pubkey_salt_hash: get_pubkey_salt_hash(key.pubkey, deposit_salt),
+            amount: U256::rand(&mut rng),
+            is_eligible: true,
+            token_type: TokenType::ERC20,
+            token_address: Address::rand(&mut rng),
+            token_id: U256::rand(&mut rng),
+            token_index: Some(1),
+        };
+
+        // v0: bare bincode without version tag, spelled out field by field. The encoding of the
+        // intmax2-zkp types is pinned by its revision.
+        fn field<T: serde::Serialize>(value: &T) -> Vec<u8> {
+            bincode::serialize(value).unwrap()
+        }
+        let v0 = [
+            field(&deposit_data.deposit_salt),
+            field(&deposit_data.depositor),
+            field(&deposit_data.pubkey_salt_hash),
+            field(&deposit_data.amount),
+            b"\x01".to_vec(),             // is_eligible: true
+            b"\x01\x00\x00\x00".to_vec(), // token_type: ERC20
+            field(&deposit_data.token_address),
+            field(&deposit_data.token_id),
+            b"\x01\x01\x00\x00\x00".to_vec(), // token_index: Some(1)
+        ]
+        .concat();
+        assert_eq!(bincode::serialize(&deposit_data).unwrap(), v0);
+        let decrypted = DepositData::decrypt(&encrypt(key.pubkey, &v0), key).unwrap();
+        assert_eq!(decrypted.deposit_hash(), deposit_data.deposit_hash());
+
+        let current = deposit_data.encrypt(key.pubkey);
+        let decrypted = DepositData::decrypt(&current, key).unwrap();
+        assert_eq!(decrypted.deposit_hash(), deposit_data.deposit_hash());
+    }
 }
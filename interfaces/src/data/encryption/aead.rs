@@ -0,0 +1,186 @@
+//! Version 2 of the encryption scheme: ECIES over BN254 with AES-256-GCM.
+//!
+//! Layout: `version (1) | flags (1) | ephemeral pubkey (32) | nonce (12) | ciphertext | tag (16)`.
+//! The version, flags and ephemeral pubkey are authenticated as the associated data, so that the
+//! compression flag cannot be flipped without failing the tag check.
+
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
+use intmax2_zkp::{
+    common::signature::key_set::KeySet,
+    ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait},
+};
+use rand::Rng;
+
+use super::{
+    errors::ECIESError,
+    utils::{ecdh_x, kdf, U256_SIZE},
+};
+
+pub(crate) const NONCE_SIZE: usize = 12;
+pub(crate) const TAG_SIZE: usize = 16;
+pub(crate) const HEADER_SIZE: usize = 2 + U256_SIZE + NONCE_SIZE;
+
+/// The payload is compressed with raw DEFLATE before encryption
+pub(crate) const FLAG_DEFLATE: u8 = 1;
+
+// Compression level of DEFLATE, between 0 and 10
+const DEFLATE_LEVEL: u8 = 6;
+
+// Domain separation of the key derivation from version 1
+const KDF_INFO: &[u8] = b"intmax2-ecies-v2-aes-256-gcm";
+
+// Upper bound of the decompressed payload, to avoid decompression bombs
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+pub(crate) fn aead_encrypt(
+    key: &[u8; 32],
+    nonce: &[u8; NONCE_SIZE],
+    aad: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: data, aad })
+        .expect("encryption of in-memory data should not fail")
+}
+
+pub(crate) fn aead_decrypt(
+    key: &[u8; 32],
+    nonce: &[u8; NONCE_SIZE],
+    aad: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, ECIESError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: data, aad })
+        .map_err(|_| ECIESError::TagCheckDecryptFailed)
+}
+
+fn derive_key(pubkey: &U256, privkey: &ark_bn254::Fr) -> [u8; 32] {
+    let x = ecdh_x(pubkey, privkey);
+    let mut key = [0u8; 32];
+    kdf(x, KDF_INFO, &mut key);
+    key
+}
+
+/// Encrypt the data for `receiver_public_key`, compressing it first if `compress` is set.
+pub(crate) fn encrypt_v2(
+    version: u8,
+    receiver_public_key: U256,
+    data: &[u8],
+    compress: bool,
+) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let ephemeral_key = KeySet::rand(&mut rng);
+    let nonce: [u8; NONCE_SIZE] = rng.gen();
+    encrypt_v2_with(
+        version,
+        receiver_public_key,
+        data,
+        compress,
+        ephemeral_key,
+        nonce,
+    )
+}
+
+pub(crate) fn encrypt_v2_with(
+    version: u8,
+    receiver_public_key: U256,
+    data: &[u8],
+    compress: bool,
+    ephemeral_key: KeySet,
+    nonce: [u8; NONCE_SIZE],
+) -> Vec<u8> {
+    let (flags, payload) = if compress {
+        let compressed = miniz_oxide::deflate::compress_to_vec(data, DEFLATE_LEVEL);
+        // keep the raw data if compression does not help
+        if compressed.len() < data.len() {
+            (FLAG_DEFLATE, compressed)
+        } else {
+            (0, data.to_vec())
+        }
+    } else {
+        (0, data.to_vec())
+    };
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + payload.len() + TAG_SIZE);
+    out.push(version);
+    out.push(flags);
+    out.extend_from_slice(&ephemeral_key.pubkey.to_bytes_be());
+    let aad = out.clone();
+    out.extend_from_slice(&nonce);
+
+    let key = derive_key(&receiver_public_key, &ephemeral_key.privkey);
+    out.extend_from_slice(&aead_encrypt(&key, &nonce, &aad, &payload));
+    out
+}
+
+/// Decrypt data encrypted by `encrypt_v2`, including the version byte.
+pub(crate) fn decrypt_v2(key: KeySet, data: &[u8]) -> Result<Vec<u8>, ECIESError> {
+    if data.len() < HEADER_SIZE + TAG_SIZE {
+        return Err(ECIESError::EncryptedDataTooSmall);
+    }
+    let (aad, rest) = data.split_at(2 + U256_SIZE);
+    let (nonce, encrypted) = rest.split_at(NONCE_SIZE);
+    let flags = aad[1];
+    let ephemeral_pubkey = U256::from_bytes_be(&aad[2..]);
+
+    let symmetric_key = derive_key(&ephemeral_pubkey, &key.privkey);
+    let payload = aead_decrypt(&symmetric_key, nonce.try_into().unwrap(), aad, encrypted)?;
+
+    match flags {
+        0 => Ok(payload),
+        FLAG_DEFLATE => {
+            miniz_oxide::inflate::decompress_to_vec_with_limit(&payload, MAX_DECOMPRESSED_SIZE)
+                .map_err(|e| ECIESError::DecompressionFailed(format!("{:?}", e.status)))
+        }
+        _ => Err(ECIESError::UnknownFlags(flags)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aead_decrypt, aead_encrypt};
+
+    // Test vectors of AES-256-GCM, test cases 13 and 14 of the GCM specification (McGrew and
+    // Viega), and one with associated data.
+    #[test]
+    fn test_aead_vectors() {
+        let vectors: [(&[u8; 32], &[u8; 12], &[u8], &[u8], &str); 3] = [
+            (
+                &[0; 32],
+                &[0; 12],
+                b"",
+                b"",
+                "530f8afbc74536b9a963b4f1c4cb738b",
+            ),
+            (
+                &[0; 32],
+                &[0; 12],
+                b"",
+                &[0; 16],
+                "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919",
+            ),
+            (
+                &std::array::from_fn(|i| i as u8),
+                &std::array::from_fn(|i| i as u8),
+                b"aad",
+                b"hello intmax2",
+                "2f67ba77aac5ab75f92cf6f3832273eed5cb32298067fa42f6b450f78b",
+            ),
+        ];
+        for (key, nonce, aad, plaintext, expected) in vectors {
+            let encrypted = aead_encrypt(key, nonce, aad, plaintext);
+            assert_eq!(alloy_primitives::hex::encode(&encrypted), expected);
+            assert_eq!(
+                aead_decrypt(key, nonce, aad, &encrypted).unwrap(),
+                plaintext
+            );
+            // the associated data is authenticated
+            assert!(aead_decrypt(key, nonce, b"other", &encrypted).is_err());
+        }
+    }
+}
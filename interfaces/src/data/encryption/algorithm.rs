@@ -12,6 +12,7 @@ use intmax2_zkp::{
 use rand::Rng;
 
 use super::{
+    aead::{decrypt_v2, encrypt_v2},
     errors::ECIESError,
     message::EncryptedMessage,
     utils::{ecdh_x, hmac_sha256, kdf, sha256, U256_SIZE},
@@ -19,14 +20,49 @@ use super::{
 
 pub use alloy_primitives::bytes::BytesMut;
 
-const ENCRYPTION_VERSION: u8 = 1;
+/// Version of the encryption scheme, stored as the first byte of the encrypted data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionVersion {
+    /// ECIES with AES-128-CTR and HMAC-SHA256
+    V1 = 1,
+    /// ECIES with AES-256-GCM, and optional DEFLATE compression of the payload
+    V2 = 2,
+}
+
+/// Version used by `encrypt`. Data encrypted by older versions can still be decrypted.
+///
+/// Version 1 is written until the readers of the data, e.g. deployed wasm clients, can decrypt
+/// version 2. The `encryption-v2` feature switches to version 2.
+#[cfg(not(feature = "encryption-v2"))]
+pub const ENCRYPTION_VERSION: EncryptionVersion = EncryptionVersion::V1;
+#[cfg(feature = "encryption-v2")]
+pub const ENCRYPTION_VERSION: EncryptionVersion = EncryptionVersion::V2;
+
+/// Payloads of at least this size are compressed before encryption, from version 2
+pub const COMPRESSION_THRESHOLD: usize = 1024;
 
 pub fn encrypt(pubkey: U256, data: &[u8]) -> Vec<u8> {
+    encrypt_with_version(pubkey, data, ENCRYPTION_VERSION)
+}
+
+pub fn encrypt_with_version(pubkey: U256, data: &[u8], version: EncryptionVersion) -> Vec<u8> {
+    match version {
+        EncryptionVersion::V1 => encrypt_v1(pubkey, data),
+        EncryptionVersion::V2 => encrypt_v2(
+            EncryptionVersion::V2 as u8,
+            pubkey,
+            data,
+            data.len() >= COMPRESSION_THRESHOLD,
+        ),
+    }
+}
+
+fn encrypt_v1(pubkey: U256, data: &[u8]) -> Vec<u8> {
     let sender = EciesSender::new(pubkey);
     let mut encrypted_data = BytesMut::new();
     sender.encrypt_message(data, &mut encrypted_data);
 
-    let version = ENCRYPTION_VERSION;
+    let version = EncryptionVersion::V1 as u8;
     let mut version_data = BytesMut::new();
     version_data.extend_from_slice(&version.to_be_bytes());
     version_data.unsplit(encrypted_data);
@@ -35,9 +71,10 @@ pub fn encrypt(pubkey: U256, data: &[u8]) -> Vec<u8> {
 }
 
 pub fn decrypt(key: KeySet, encrypted_data: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let (version_data, encrypted_data) = encrypted_data.split_at(1);
-    let version = version_data[0];
-    if version != 1 {
+    let Some(&version) = encrypted_data.first() else {
+        anyhow::bail!("Empty encrypted data");
+    };
+    if version != EncryptionVersion::V1 as u8 && version != EncryptionVersion::V2 as u8 {
         anyhow::bail!("Unsupported version");
     }
 
@@ -45,7 +82,10 @@ pub fn decrypt(key: KeySet, encrypted_data: &[u8]) -> anyhow::Result<Vec<u8>> {
         anyhow::bail!("Invalid private key");
     }
 
-    let mut encrypted_data = encrypted_data.to_vec();
+    if version == EncryptionVersion::V2 as u8 {
+        return Ok(decrypt_v2(key, encrypted_data)?);
+    }
+    let mut encrypted_data = encrypted_data[1..].to_vec();
     let receiver = EciesReceiver::new(key);
     let decrypted_data = receiver.decrypt_message(&mut encrypted_data)?;
 
@@ -136,12 +176,14 @@ mod test {
         common::signature::key_set::KeySet,
         ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait},
     };
+    use num_bigint::BigUint;
     use rand::Rng;
 
     use super::{
-        decrypt, ecdh_x, encrypt, hmac_sha256, kdf, sha256, BytesMut, EciesReceiver, EciesSender,
-        U256_SIZE,
+        decrypt, ecdh_x, encrypt, encrypt_with_version, hmac_sha256, kdf, sha256, BytesMut,
+        EciesReceiver, EciesSender, EncryptionVersion, COMPRESSION_THRESHOLD, U256_SIZE,
     };
+    use crate::data::encryption::aead::{encrypt_v2_with, FLAG_DEFLATE, HEADER_SIZE, TAG_SIZE};
 
     #[test]
     fn test_ecies_encryption() {
@@ -245,7 +287,7 @@ mod test {
         let mut encrypted_data = BytesMut::new();
         sender.encrypt_message(data, &mut encrypted_data);
 
-        let version = 2u8;
+        let version = 3u8;
         let mut version_data = BytesMut::new();
         version_data.extend_from_slice(&version.to_be_bytes());
         version_data.unsplit(encrypted_data);
@@ -271,4 +313,79 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_decrypt_all_versions() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let small = b"hello world".to_vec();
+        let large = b"intmax2 ".repeat(COMPRESSION_THRESHOLD);
+        for data in [small, large] {
+            for version in [EncryptionVersion::V1, EncryptionVersion::V2] {
+                let encrypted = encrypt_with_version(key.pubkey, &data, version);
+                assert_eq!(encrypted[0], version as u8);
+                assert_eq!(decrypt(key, &encrypted).unwrap(), data);
+            }
+        }
+        // data encrypted for another key fails to decrypt
+        let other = KeySet::rand(&mut rng);
+        assert!(decrypt(other, &encrypt(key.pubkey, b"hello world")).is_err());
+    }
+
+    #[test]
+    fn test_v2_envelope() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let ephemeral_key = KeySet::rand(&mut rng);
+        let nonce = [7u8; 12];
+
+        // uncompressed: header, ciphertext of the same length as the data, and tag
+        let data = b"hello world";
+        let encrypted = encrypt_v2_with(2, key.pubkey, data, false, ephemeral_key, nonce);
+        assert_eq!(encrypted.len(), HEADER_SIZE + data.len() + TAG_SIZE);
+        assert_eq!(encrypted[1], 0);
+        assert_eq!(&encrypted[2..34], &ephemeral_key.pubkey.to_bytes_be()[..]);
+        assert_eq!(&encrypted[34..46], &nonce);
+        // deterministic for a fixed ephemeral key and nonce
+        assert_eq!(
+            encrypted,
+            encrypt_v2_with(2, key.pubkey, data, false, ephemeral_key, nonce)
+        );
+        assert_eq!(decrypt(key, &encrypted).unwrap(), data);
+
+        // compressed payloads are flagged and smaller than the data
+        let data = vec![0u8; 4 * COMPRESSION_THRESHOLD];
+        let encrypted = encrypt_v2_with(2, key.pubkey, &data, true, ephemeral_key, nonce);
+        assert_eq!(encrypted[1], FLAG_DEFLATE);
+        assert!(encrypted.len() < data.len());
+        assert_eq!(decrypt(key, &encrypted).unwrap(), data);
+
+        // the flags are authenticated
+        let mut tampered = encrypted.clone();
+        tampered[1] = 0;
+        assert!(decrypt(key, &tampered).is_err());
+    }
+
+    // Envelopes generated by an independent implementation of the scheme, for the receiver key
+    // 12345 with the ephemeral key 67890 and the nonce [7; 12]
+    #[test]
+    fn test_decrypt_v2_fixtures() {
+        let key = KeySet::new(BigUint::from(12345u32).into());
+        assert_eq!(
+            key.pubkey,
+            U256::from_hex("0x1936f7b07be20ac4b7faac53aba252c44112b369f437c12d75b8157882b390aa")
+                .unwrap()
+        );
+
+        let encrypted = include_bytes!("../../../test_data/encryption_v2.bin");
+        assert_eq!(encrypted[..2], [EncryptionVersion::V2 as u8, 0]);
+        assert_eq!(
+            decrypt(key, encrypted).unwrap(),
+            b"intmax2 encryption v2 fixture"
+        );
+
+        let encrypted = include_bytes!("../../../test_data/encryption_v2_deflate.bin");
+        assert_eq!(encrypted[..2], [EncryptionVersion::V2 as u8, FLAG_DEFLATE]);
+        assert_eq!(decrypt(key, encrypted).unwrap(), vec![0u8; 4096]);
+    }
 }
@@ -9,4 +9,10 @@ pub enum ECIESError {
     /// The encrypted data is not large enough for all fields
     #[error("encrypted data is not large enough for all fields")]
     EncryptedDataTooSmall,
+    /// The decrypted payload could not be decompressed
+    #[error("decompression failed: {0}")]
+    DecompressionFailed(String),
+    /// The header has flags that this version does not know
+    #[error("unknown flags: {0}")]
+    UnknownFlags(u8),
 }
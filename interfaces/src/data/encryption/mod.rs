@@ -1,3 +1,4 @@
+pub mod aead;
 pub mod algorithm;
 pub mod errors;
 pub mod message;
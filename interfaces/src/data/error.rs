@@ -11,6 +11,9 @@ pub enum DataError {
     #[error("Deserialization error: {0}")]
     DeserializeError(#[from] bincode::Error),
 
+    #[error("Unsupported data version: {0}")]
+    UnsupportedVersion(u32),
+
     #[error("Validation error: {0}")]
     ValidationError(String),
 }
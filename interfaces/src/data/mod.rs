@@ -2,8 +2,11 @@ pub mod deposit_data;
 pub mod encryption;
 pub mod error;
 pub mod meta_data;
+pub mod processed_uuids;
 pub mod proof_compression;
 pub mod sender_proof_set;
 pub mod transfer_data;
 pub mod tx_data;
 pub mod user_data;
+pub mod versioning;
+pub mod wallet_backup;
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::meta_data::MetaData;
+
+// Number of skipped uuids kept. Older ones are dropped.
+const MAX_SKIPPED_UUIDS: usize = 1000;
+
+/// Uuids of processed data of a single data type, together with their timestamps.
+///
+/// The store vault only returns data whose timestamp is not older than the lpt, so entries older
+/// than the lpt can be dropped without changing the result of `contains` for any data that can
+/// still be fetched. Only the entries near the lpt (e.g. processed out of order) are kept.
+///
+/// Data passed by the lpt without being incorporated (e.g. timed out) is recorded separately as
+/// skipped, and those entries are not compacted, so that it is not reported as included later.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessedUuids {
+    entries: BTreeMap<String, u64>,
+    skipped: BTreeMap<String, u64>,
+}
+
+impl ProcessedUuids {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, meta: &MetaData) {
+        self.skipped.remove(&meta.uuid);
+        self.entries.insert(meta.uuid.clone(), meta.timestamp);
+    }
+
+    /// Record data that the lpt passes without it being incorporated, keeping only the latest
+    /// `MAX_SKIPPED_UUIDS` records.
+    pub fn skip(&mut self, meta: &MetaData) {
+        if self.contains(&meta.uuid) {
+            return;
+        }
+        self.skipped.insert(meta.uuid.clone(), meta.timestamp);
+        if self.skipped.len() > MAX_SKIPPED_UUIDS {
+            let oldest = self
+                .skipped
+                .iter()
+                .min_by_key(|(_, timestamp)| **timestamp)
+                .map(|(uuid, _)| uuid.clone())
+                .unwrap();
+            self.skipped.remove(&oldest);
+        }
+    }
+
+    pub fn is_skipped(&self, uuid: &str) -> bool {
+        self.skipped.contains_key(uuid)
+    }
+
+    pub fn contains(&self, uuid: &str) -> bool {
+        self.entries.contains_key(uuid)
+    }
+
+    /// Returns true if the data is already processed or skipped, given the lpt of the same data
+    /// type. Data older than the lpt is considered processed even if its uuid has been compacted.
+    pub fn is_processed(&self, meta: &MetaData, lpt: u64) -> bool {
+        meta.timestamp < lpt || self.contains(&meta.uuid) || self.is_skipped(&meta.uuid)
+    }
+
+    /// Returns true if the data has been incorporated, given the lpt of the same data type.
+    /// Unlike `is_processed`, data skipped by the lpt is not included.
+    pub fn is_included(&self, meta: &MetaData, lpt: u64) -> bool {
+        self.is_processed(meta, lpt) && !self.is_skipped(&meta.uuid)
+    }
+
+    /// Drop entries that are older than the lpt.
+    pub fn compact(&mut self, lpt: u64) {
+        self.entries.retain(|_, timestamp| *timestamp >= lpt);
+    }
+
+    pub fn uuids(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn from_entries(entries: BTreeMap<String, u64>) -> Self {
+        Self {
+            entries,
+            skipped: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::meta_data::MetaData;
+
+    use super::ProcessedUuids;
+
+    fn meta(uuid: &str, timestamp: u64) -> MetaData {
+        MetaData {
+            uuid: uuid.to_string(),
+            timestamp,
+            block_number: None,
+        }
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut processed = ProcessedUuids::new();
+        processed.insert(&meta("a", 10));
+        processed.insert(&meta("b", 20));
+        processed.insert(&meta("c", 30));
+
+        processed.compact(20);
+        assert!(!processed.contains("a"));
+        assert!(processed.contains("b"));
+        assert!(processed.contains("c"));
+        assert_eq!(processed.len(), 2);
+
+        // compacted entries are still considered processed
+        assert!(processed.is_processed(&meta("a", 10), 20));
+        assert!(!processed.is_processed(&meta("d", 25), 20));
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut processed = ProcessedUuids::new();
+        processed.skip(&meta("a", 10));
+        processed.insert(&meta("b", 20));
+        processed.compact(30);
+
+        // skipped entries are processed but not included, and survive the compaction
+        assert!(processed.is_processed(&meta("a", 10), 5));
+        assert!(!processed.is_included(&meta("a", 10), 30));
+        assert!(processed.is_included(&meta("b", 20), 30));
+
+        // a skipped entry incorporated later is included
+        processed.insert(&meta("a", 10));
+        assert!(processed.is_included(&meta("a", 10), 30));
+        assert!(!processed.is_skipped("a"));
+    }
+}
@@ -119,6 +119,24 @@ impl CompressedSpentProof {
     }
 }
 
+/// Frozen layout of the compressed proofs embedded in the historical versions of the versioned
+/// data, so that changes of the live types do not alter those versions.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CompressedProofV1(#[serde_as(as = "Base64")] pub Vec<u8>);
+
+impl From<CompressedProofV1> for CompressedBalanceProof {
+    fn from(v1: CompressedProofV1) -> Self {
+        Self(v1.0)
+    }
+}
+
+impl From<CompressedProofV1> for CompressedSpentProof {
+    fn from(v1: CompressedProofV1) -> Self {
+        Self(v1.0)
+    }
+}
+
 fn serialize(
     vd: &VerifierCircuitData<F, C, D>,
     input: &ProofWithPublicInputs<F, C, D>,
@@ -10,7 +10,7 @@ use crate::utils::circuit_verifiers::CircuitVerifiers;
 use super::{
     encryption::algorithm::{decrypt, encrypt},
     error::DataError,
-    proof_compression::{CompressedBalanceProof, CompressedSpentProof},
+    proof_compression::{CompressedBalanceProof, CompressedProofV1, CompressedSpentProof},
 };
 
 type Result<T> = std::result::Result<T, DataError>;
@@ -24,11 +24,11 @@ pub struct SenderProofSet {
 }
 
 impl SenderProofSet {
-    fn to_bytes(&self) -> Vec<u8> {
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
         bincode::serialize(self).unwrap()
     }
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self> {
         let data = bincode::deserialize(bytes)?;
         Ok(data)
     }
@@ -71,3 +71,20 @@ impl SenderProofSet {
         Ok(())
     }
 }
+
+/// Frozen layout of `SenderProofSet` embedded in v1 of `TransferData`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SenderProofSetV1 {
+    pub spent_proof: CompressedProofV1,
+    pub prev_balance_proof: CompressedProofV1,
+}
+
+impl From<SenderProofSetV1> for SenderProofSet {
+    fn from(v1: SenderProofSetV1) -> Self {
+        Self {
+            spent_proof: v1.spent_proof.into(),
+            prev_balance_proof: v1.prev_balance_proof.into(),
+        }
+    }
+}
@@ -14,11 +14,15 @@ use intmax2_zkp::{
 use super::{
     encryption::algorithm::{decrypt, encrypt},
     error::DataError,
-    sender_proof_set::SenderProofSet,
+    sender_proof_set::{SenderProofSet, SenderProofSetV1},
+    versioning::{from_versioned_bytes, to_versioned_bytes, Versioned},
 };
 
 type Result<T> = std::result::Result<T, DataError>;
 
+/// Maximum length of the memo in bytes
+pub const MAX_MEMO_LEN: usize = 256;
+
 /// Backup data for receiving transfers
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -36,16 +40,19 @@ pub struct TransferData {
     pub transfer: Transfer,
     pub transfer_index: u32,
     pub transfer_merkle_proof: TransferMerkleProof,
+
+    // Optional memo set by the sender, e.g. a payment reference. Encrypted together with the
+    // rest of the data, so only the recipient can read it.
+    pub memo: Option<String>,
 }
 
 impl TransferData {
     fn to_bytes(&self) -> Vec<u8> {
-        bincode::serialize(self).unwrap()
+        to_versioned_bytes(self)
     }
 
     fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let data = bincode::deserialize(bytes)?;
-        Ok(data)
+        from_versioned_bytes(bytes)
     }
 
     pub fn encrypt(&self, pubkey: U256) -> Vec<u8> {
@@ -54,8 +61,13 @@ impl TransferData {
 
     pub fn decrypt(bytes: &[u8], key: KeySet) -> Result<Self> {
         let data = decrypt(key, bytes).map_err(|e| DataError::DecryptionError(e.to_string()))?;
-        let data = Self::from_bytes(&data)?;
+        let mut data = Self::from_bytes(&data)?;
         data.validate(key)?;
+        // The limit is enforced by the sender. A longer memo is truncated rather than rejecting
+        // the transfer, which the recipient could not receive otherwise.
+        if let Some(memo) = data.memo.as_mut() {
+            truncate_memo(memo);
+        }
         Ok(data)
     }
 
@@ -81,3 +93,219 @@ impl TransferData {
         self.sender_proof_set = Some(sender_proof_set);
     }
 }
+
+pub fn validate_memo(memo: Option<&str>) -> Result<()> {
+    if let Some(memo) = memo {
+        if memo.len() > MAX_MEMO_LEN {
+            return Err(DataError::ValidationError(format!(
+                "Memo is too long: {} > {} bytes",
+                memo.len(),
+                MAX_MEMO_LEN
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Truncate the memo to `MAX_MEMO_LEN` bytes at a char boundary
+pub fn truncate_memo(memo: &mut String) {
+    if memo.len() > MAX_MEMO_LEN {
+        let mut len = MAX_MEMO_LEN;
+        while !memo.is_char_boundary(len) {
+            len -= 1;
+        }
+        memo.truncate(len);
+    }
+}
+
+impl Versioned for TransferData {
+    const CURRENT_VERSION: u32 = 2;
+
+    fn migrate(version: u32, body: &[u8]) -> Result<Self> {
+        match version {
+            // v0 is the untagged legacy encoding, whose layout is identical to v1
+            0 | 1 => {
+                let v1: TransferDataV1 = bincode::deserialize(body)?;
+                Ok(v1.into())
+            }
+            _ => Err(DataError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Frozen layout of v1, which had no memo. The nested types of this crate are frozen as well, while
+/// the types of intmax2-zkp are pinned by its revision.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferDataV1 {
+    sender_proof_set_ephemeral_key: U256,
+    sender_proof_set: Option<SenderProofSetV1>,
+    sender: U256,
+    tx: Tx,
+    tx_index: u32,
+    tx_merkle_proof: TxMerkleProof,
+    tx_tree_root: Bytes32,
+    transfer: Transfer,
+    transfer_index: u32,
+    transfer_merkle_proof: TransferMerkleProof,
+}
+
+impl From<TransferDataV1> for TransferData {
+    fn from(v1: TransferDataV1) -> Self {
+        Self {
+            sender_proof_set_ephemeral_key: v1.sender_proof_set_ephemeral_key,
+            sender_proof_set: v1.sender_proof_set.map(Into::into),
+            sender: v1.sender,
+            tx: v1.tx,
+            tx_index: v1.tx_index,
+            tx_merkle_proof: v1.tx_merkle_proof,
+            tx_tree_root: v1.tx_tree_root,
+            transfer: v1.transfer,
+            transfer_index: v1.transfer_index,
+            transfer_merkle_proof: v1.transfer_merkle_proof,
+            memo: None,
+        }
+    }
+}
+
+/// Frozen layout of v2, which is embedded in v1 of `WalletBackup`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TransferDataV2 {
+    sender_proof_set_ephemeral_key: U256,
+    sender_proof_set: Option<SenderProofSetV1>,
+    sender: U256,
+    tx: Tx,
+    tx_index: u32,
+    tx_merkle_proof: TxMerkleProof,
+    tx_tree_root: Bytes32,
+    transfer: Transfer,
+    transfer_index: u32,
+    transfer_merkle_proof: TransferMerkleProof,
+    memo: Option<String>,
+}
+
+impl From<TransferDataV2> for TransferData {
+    fn from(v2: TransferDataV2) -> Self {
+        Self {
+            sender_proof_set_ephemeral_key: v2.sender_proof_set_ephemeral_key,
+            sender_proof_set: v2.sender_proof_set.map(Into::into),
+            sender: v2.sender,
+            tx: v2.tx,
+            tx_index: v2.tx_index,
+            tx_merkle_proof: v2.tx_merkle_proof,
+            tx_tree_root: v2.tx_tree_root,
+            transfer: v2.transfer,
+            transfer_index: v2.transfer_index,
+            transfer_merkle_proof: v2.transfer_merkle_proof,
+            memo: v2.memo,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::{
+        common::{
+            signature::key_set::KeySet,
+            transfer::Transfer,
+            trees::{transfer_tree::TransferTree, tx_tree::TxTree},
+            tx::Tx,
+        },
+        constants::{TRANSFER_TREE_HEIGHT, TX_TREE_HEIGHT},
+        ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _},
+    };
+
+    use crate::data::{encryption::algorithm::encrypt, versioning::VERSION_MAGIC};
+
+    use super::{TransferData, TransferDataV1};
+
+    fn transfer_data_v1(rng: &mut rand::rngs::ThreadRng) -> TransferDataV1 {
+        let transfer = Transfer::rand(rng);
+        let mut transfer_tree = TransferTree::new(TRANSFER_TREE_HEIGHT);
+        transfer_tree.push(transfer);
+        let tx = Tx {
+            transfer_tree_root: transfer_tree.get_root(),
+            nonce: 0,
+        };
+        let mut tx_tree = TxTree::new(TX_TREE_HEIGHT);
+        tx_tree.push(tx);
+        TransferDataV1 {
+            sender_proof_set_ephemeral_key: U256::rand(rng),
+            sender_proof_set: None,
+            sender: U256::rand(rng),
+            tx,
+            tx_index: 0,
+            tx_merkle_proof: tx_tree.prove(0),
+            tx_tree_root: tx_tree.get_root().into(),
+            transfer,
+            transfer_index: 0,
+            transfer_merkle_proof: transfer_tree.prove(0),
+        }
+    }
+
+    // Encoding of v1 spelled out field by field, so that a change of the field order or of the
+    // types of this crate is detected. The encoding of the intmax2-zkp types is pinned by its
+    // revision.
+    fn v1_fixture(v1: &TransferDataV1) -> Vec<u8> {
+        fn field<T: serde::Serialize>(value: &T) -> Vec<u8> {
+            bincode::serialize(value).unwrap()
+        }
+        [
+            field(&v1.sender_proof_set_ephemeral_key),
+            b"\x00".to_vec(), // sender_proof_set: None
+            field(&v1.sender),
+            field(&v1.tx),
+            b"\x00\x00\x00\x00".to_vec(), // tx_index: 0
+            field(&v1.tx_merkle_proof),
+            field(&v1.tx_tree_root),
+            field(&v1.transfer),
+            b"\x00\x00\x00\x00".to_vec(), // transfer_index: 0
+            field(&v1.transfer_merkle_proof),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_decrypt_memo_less_transfer_data() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let v1 = transfer_data_v1(&mut rng);
+        let body = v1_fixture(&v1);
+        assert_eq!(bincode::serialize(&v1).unwrap(), body);
+
+        // v0: bare bincode without version tag
+        let decrypted = TransferData::decrypt(&encrypt(key.pubkey, &body), key).unwrap();
+        assert_eq!(decrypted.sender, v1.sender);
+        assert!(decrypted.memo.is_none());
+
+        // v1: same layout with version tag
+        let mut tagged = VERSION_MAGIC.to_vec();
+        tagged.extend_from_slice(&1u32.to_be_bytes());
+        tagged.extend_from_slice(&body);
+        let decrypted = TransferData::decrypt(&encrypt(key.pubkey, &tagged), key).unwrap();
+        assert!(decrypted.memo.is_none());
+
+        // v2: the v1 layout followed by the memo
+        let mut tagged = VERSION_MAGIC.to_vec();
+        tagged.extend_from_slice(&2u32.to_be_bytes());
+        tagged.extend_from_slice(&body);
+        tagged.extend_from_slice(b"\x01\x0a\x00\x00\x00\x00\x00\x00\x00invoice-42");
+        let decrypted = TransferData::decrypt(&encrypt(key.pubkey, &tagged), key).unwrap();
+        assert_eq!(decrypted.memo.as_deref(), Some("invoice-42"));
+        assert_eq!(decrypted.to_bytes(), tagged);
+
+        // too long memo is truncated, and the transfer is still accepted
+        let mut data = decrypted;
+        data.memo = Some("a".repeat(super::MAX_MEMO_LEN + 1));
+        let decrypted = TransferData::decrypt(&data.encrypt(key.pubkey), key).unwrap();
+        assert_eq!(decrypted.memo, Some("a".repeat(super::MAX_MEMO_LEN)));
+        assert_eq!(decrypted.sender, data.sender);
+
+        // truncated at a char boundary
+        data.memo = Some("\u{3042}".repeat(super::MAX_MEMO_LEN));
+        let decrypted = TransferData::decrypt(&data.encrypt(key.pubkey), key).unwrap();
+        let memo = decrypted.memo.unwrap();
+        assert_eq!(memo, "\u{3042}".repeat(super::MAX_MEMO_LEN / 3));
+    }
+}
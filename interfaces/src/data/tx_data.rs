@@ -14,6 +14,7 @@ type Result<T> = std::result::Result<T, DataError>;
 use super::{
     encryption::algorithm::{decrypt, encrypt},
     error::DataError,
+    versioning::{from_versioned_bytes, to_versioned_bytes, Versioned},
 };
 
 // tx data for syncing sender's balance proof
@@ -32,12 +33,11 @@ pub struct TxData {
 
 impl TxData {
     fn to_bytes(&self) -> Vec<u8> {
-        bincode::serialize(self).unwrap()
+        to_versioned_bytes(self)
     }
 
     fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let data = bincode::deserialize(bytes)?;
-        Ok(data)
+        from_versioned_bytes(bytes)
     }
 
     pub fn encrypt(&self, pubkey: U256) -> Vec<u8> {
@@ -62,3 +62,38 @@ impl TxData {
         Ok(())
     }
 }
+
+impl Versioned for TxData {
+    const CURRENT_VERSION: u32 = 1;
+
+    fn migrate(version: u32, body: &[u8]) -> Result<Self> {
+        match version {
+            // v0 is the untagged legacy encoding, whose layout is identical to v1
+            0 => Ok(bincode::deserialize(body)?),
+            _ => Err(DataError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Frozen layout of v1, which is embedded in v1 of `WalletBackup`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TxDataV1 {
+    tx_index: u32,
+    tx_merkle_proof: TxMerkleProof,
+    tx_tree_root: Bytes32,
+    spent_witness: SpentWitness,
+    sender_proof_set_ephemeral_key: U256,
+}
+
+impl From<TxDataV1> for TxData {
+    fn from(v1: TxDataV1) -> Self {
+        Self {
+            tx_index: v1.tx_index,
+            tx_merkle_proof: v1.tx_merkle_proof,
+            tx_tree_root: v1.tx_tree_root,
+            spent_witness: v1.spent_witness,
+            sender_proof_set_ephemeral_key: v1.sender_proof_set_ephemeral_key,
+        }
+    }
+}
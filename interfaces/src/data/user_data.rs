@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +8,7 @@ use intmax2_zkp::{
     common::{
         private_state::{FullPrivateState, PrivateState},
         signature::key_set::KeySet,
+        transfer::Transfer,
         trees::asset_tree::AssetLeaf,
     },
     ethereum_types::u256::U256,
@@ -16,13 +19,31 @@ use super::{
     deposit_data::DepositData,
     encryption::algorithm::{decrypt, encrypt},
     error::DataError,
-    proof_compression::CompressedBalanceProof,
+    meta_data::MetaData,
+    processed_uuids::ProcessedUuids,
+    proof_compression::{CompressedBalanceProof, CompressedProofV1},
     transfer_data::TransferData,
     tx_data::TxData,
+    versioning::{from_versioned_bytes, to_versioned_bytes, Versioned},
 };
 
 type Result<T> = std::result::Result<T, DataError>;
 
+// Number of rejected transfers kept in the user data. Older ones are dropped.
+const MAX_REJECTED_TRANSFERS: usize = 100;
+
+/// An incoming transfer or own withdrawal that could not be incorporated into the balance,
+/// e.g. because the sender's balance proof for it is invalid.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedTransfer {
+    pub uuid: String,
+    pub timestamp: u64,
+    pub sender: U256,
+    pub transfer: Transfer,
+    pub reason: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserData {
@@ -39,10 +60,15 @@ pub struct UserData {
     pub tx_lpt: u64,
     pub withdrawal_lpt: u64,
 
-    pub processed_deposit_uuids: Vec<String>,
-    pub processed_transfer_uuids: Vec<String>,
-    pub processed_tx_uuids: Vec<String>,
-    pub processed_withdrawal_uuids: Vec<String>,
+    // Uuids of processed actions. Entries older than the corresponding lpt are compacted away,
+    // except for the ones skipped by the lpt.
+    pub processed_deposit_uuids: ProcessedUuids,
+    pub processed_transfer_uuids: ProcessedUuids,
+    pub processed_tx_uuids: ProcessedUuids,
+    pub processed_withdrawal_uuids: ProcessedUuids,
+
+    // The latest rejected transfers and withdrawals, at most `MAX_REJECTED_TRANSFERS`
+    pub rejected_transfers: Vec<RejectedTransfer>,
 }
 
 impl UserData {
@@ -58,13 +84,67 @@ impl UserData {
             tx_lpt: 0,
             withdrawal_lpt: 0,
 
-            processed_deposit_uuids: vec![],
-            processed_transfer_uuids: vec![],
-            processed_tx_uuids: vec![],
-            processed_withdrawal_uuids: vec![],
+            processed_deposit_uuids: ProcessedUuids::new(),
+            processed_transfer_uuids: ProcessedUuids::new(),
+            processed_tx_uuids: ProcessedUuids::new(),
+            processed_withdrawal_uuids: ProcessedUuids::new(),
+
+            rejected_transfers: Vec::new(),
+        }
+    }
+
+    /// Record a transfer or withdrawal that could not be incorporated, keeping only the latest
+    /// `MAX_REJECTED_TRANSFERS` records.
+    pub fn add_rejected_transfer(
+        &mut self,
+        meta: &MetaData,
+        sender: U256,
+        transfer: Transfer,
+        reason: &str,
+    ) {
+        if self.rejected_transfer(&meta.uuid).is_some() {
+            return;
+        }
+        self.rejected_transfers.push(RejectedTransfer {
+            uuid: meta.uuid.clone(),
+            timestamp: meta.timestamp,
+            sender,
+            transfer,
+            reason: reason.to_string(),
+        });
+        if self.rejected_transfers.len() > MAX_REJECTED_TRANSFERS {
+            self.rejected_transfers.sort_by_key(|r| r.timestamp);
+            let excess = self.rejected_transfers.len() - MAX_REJECTED_TRANSFERS;
+            self.rejected_transfers.drain(..excess);
         }
     }
 
+    pub fn rejected_transfer(&self, uuid: &str) -> Option<&RejectedTransfer> {
+        self.rejected_transfers.iter().find(|r| r.uuid == uuid)
+    }
+
+    // The lpts never move backward, since the uuids older than them have been compacted away and
+    // the data would be processed again.
+    pub fn set_deposit_lpt(&mut self, lpt: u64) {
+        self.deposit_lpt = self.deposit_lpt.max(lpt);
+        self.processed_deposit_uuids.compact(self.deposit_lpt);
+    }
+
+    pub fn set_transfer_lpt(&mut self, lpt: u64) {
+        self.transfer_lpt = self.transfer_lpt.max(lpt);
+        self.processed_transfer_uuids.compact(self.transfer_lpt);
+    }
+
+    pub fn set_tx_lpt(&mut self, lpt: u64) {
+        self.tx_lpt = self.tx_lpt.max(lpt);
+        self.processed_tx_uuids.compact(self.tx_lpt);
+    }
+
+    pub fn set_withdrawal_lpt(&mut self, lpt: u64) {
+        self.withdrawal_lpt = self.withdrawal_lpt.max(lpt);
+        self.processed_withdrawal_uuids.compact(self.withdrawal_lpt);
+    }
+
     pub fn block_number(&self) -> Result<u32> {
         let balance_proof = self
             .balance_proof
@@ -76,12 +156,11 @@ impl UserData {
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        bincode::serialize(&self).unwrap()
+        to_versioned_bytes(self)
     }
 
     fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let user_data = bincode::deserialize(bytes)?;
-        Ok(user_data)
+        from_versioned_bytes(bytes)
     }
 
     pub fn encrypt(&self, pubkey: U256) -> Vec<u8> {
@@ -114,6 +193,190 @@ impl UserData {
     }
 }
 
+impl Versioned for UserData {
+    const CURRENT_VERSION: u32 = 4;
+
+    fn migrate(version: u32, body: &[u8]) -> Result<Self> {
+        match version {
+            // v0 is the untagged legacy encoding, whose layout is identical to v1
+            0 | 1 => {
+                let v1: UserDataV1 = bincode::deserialize(body)?;
+                Ok(UserDataV2::from(v1).into())
+            }
+            2 => {
+                let v2: UserDataV2 = bincode::deserialize(body)?;
+                Ok(v2.into())
+            }
+            3 => {
+                let v3: UserDataV3 = bincode::deserialize(body)?;
+                Ok(v3.into())
+            }
+            _ => Err(DataError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Frozen layout of v3, which had no skipped uuids. It is also embedded in v1 of `WalletBackup`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UserDataV3 {
+    pubkey: U256,
+    full_private_state: FullPrivateState,
+    balance_proof: Option<CompressedProofV1>,
+    deposit_lpt: u64,
+    transfer_lpt: u64,
+    tx_lpt: u64,
+    withdrawal_lpt: u64,
+    processed_deposit_uuids: ProcessedUuidsV2,
+    processed_transfer_uuids: ProcessedUuidsV2,
+    processed_tx_uuids: ProcessedUuidsV2,
+    processed_withdrawal_uuids: ProcessedUuidsV2,
+    rejected_transfers: Vec<RejectedTransferV3>,
+}
+
+/// Frozen layout of `RejectedTransfer` in v3.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RejectedTransferV3 {
+    uuid: String,
+    timestamp: u64,
+    sender: U256,
+    transfer: Transfer,
+    reason: String,
+}
+
+impl From<UserDataV3> for UserData {
+    fn from(v3: UserDataV3) -> Self {
+        Self {
+            pubkey: v3.pubkey,
+            full_private_state: v3.full_private_state,
+            balance_proof: v3.balance_proof.map(Into::into),
+            deposit_lpt: v3.deposit_lpt,
+            transfer_lpt: v3.transfer_lpt,
+            tx_lpt: v3.tx_lpt,
+            withdrawal_lpt: v3.withdrawal_lpt,
+            processed_deposit_uuids: v3.processed_deposit_uuids.into(),
+            processed_transfer_uuids: v3.processed_transfer_uuids.into(),
+            processed_tx_uuids: v3.processed_tx_uuids.into(),
+            processed_withdrawal_uuids: v3.processed_withdrawal_uuids.into(),
+            rejected_transfers: v3
+                .rejected_transfers
+                .into_iter()
+                .map(|r| RejectedTransfer {
+                    uuid: r.uuid,
+                    timestamp: r.timestamp,
+                    sender: r.sender,
+                    transfer: r.transfer,
+                    reason: r.reason,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Frozen layout of v2, which had no rejected transfers. The nested types of this crate are frozen
+/// as well, while the types of intmax2-zkp are pinned by its revision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserDataV2 {
+    pubkey: U256,
+    full_private_state: FullPrivateState,
+    balance_proof: Option<CompressedProofV1>,
+    deposit_lpt: u64,
+    transfer_lpt: u64,
+    tx_lpt: u64,
+    withdrawal_lpt: u64,
+    processed_deposit_uuids: ProcessedUuidsV2,
+    processed_transfer_uuids: ProcessedUuidsV2,
+    processed_tx_uuids: ProcessedUuidsV2,
+    processed_withdrawal_uuids: ProcessedUuidsV2,
+}
+
+/// Frozen layout of `ProcessedUuids` in v2 and v3.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProcessedUuidsV2 {
+    entries: BTreeMap<String, u64>,
+}
+
+impl ProcessedUuidsV2 {
+    // The timestamps of the uuids of v1 are unknown. They are given the lpt, so they are kept
+    // until the lpt advances.
+    fn from_legacy(uuids: Vec<String>, lpt: u64) -> Self {
+        let entries = uuids.into_iter().map(|uuid| (uuid, lpt)).collect();
+        Self { entries }
+    }
+}
+
+impl From<ProcessedUuidsV2> for ProcessedUuids {
+    fn from(v2: ProcessedUuidsV2) -> Self {
+        Self::from_entries(v2.entries)
+    }
+}
+
+impl From<UserDataV2> for UserData {
+    fn from(v2: UserDataV2) -> Self {
+        Self {
+            pubkey: v2.pubkey,
+            full_private_state: v2.full_private_state,
+            balance_proof: v2.balance_proof.map(Into::into),
+            deposit_lpt: v2.deposit_lpt,
+            transfer_lpt: v2.transfer_lpt,
+            tx_lpt: v2.tx_lpt,
+            withdrawal_lpt: v2.withdrawal_lpt,
+            processed_deposit_uuids: v2.processed_deposit_uuids.into(),
+            processed_transfer_uuids: v2.processed_transfer_uuids.into(),
+            processed_tx_uuids: v2.processed_tx_uuids.into(),
+            processed_withdrawal_uuids: v2.processed_withdrawal_uuids.into(),
+            rejected_transfers: Vec::new(),
+        }
+    }
+}
+
+/// Frozen layout of v1, where processed uuids were kept as plain lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserDataV1 {
+    pubkey: U256,
+    full_private_state: FullPrivateState,
+    balance_proof: Option<CompressedProofV1>,
+    deposit_lpt: u64,
+    transfer_lpt: u64,
+    tx_lpt: u64,
+    withdrawal_lpt: u64,
+    processed_deposit_uuids: Vec<String>,
+    processed_transfer_uuids: Vec<String>,
+    processed_tx_uuids: Vec<String>,
+    processed_withdrawal_uuids: Vec<String>,
+}
+
+impl From<UserDataV1> for UserDataV2 {
+    fn from(v1: UserDataV1) -> Self {
+        Self {
+            pubkey: v1.pubkey,
+            full_private_state: v1.full_private_state,
+            balance_proof: v1.balance_proof,
+            deposit_lpt: v1.deposit_lpt,
+            transfer_lpt: v1.transfer_lpt,
+            tx_lpt: v1.tx_lpt,
+            withdrawal_lpt: v1.withdrawal_lpt,
+            processed_deposit_uuids: ProcessedUuidsV2::from_legacy(
+                v1.processed_deposit_uuids,
+                v1.deposit_lpt,
+            ),
+            processed_transfer_uuids: ProcessedUuidsV2::from_legacy(
+                v1.processed_transfer_uuids,
+                v1.transfer_lpt,
+            ),
+            processed_tx_uuids: ProcessedUuidsV2::from_legacy(v1.processed_tx_uuids, v1.tx_lpt),
+            processed_withdrawal_uuids: ProcessedUuidsV2::from_legacy(
+                v1.processed_withdrawal_uuids,
+                v1.withdrawal_lpt,
+            ),
+        }
+    }
+}
+
 /// Token index -> AssetLeaf
 pub struct Balances(pub HashMap<u32, AssetLeaf>);
 
@@ -157,3 +420,162 @@ impl Balances {
         is_insufficient
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::common::{private_state::FullPrivateState, signature::key_set::KeySet};
+
+    use crate::data::{
+        encryption::algorithm::encrypt, meta_data::MetaData, versioning::VERSION_MAGIC,
+    };
+
+    use super::{UserData, UserDataV1, UserDataV2};
+
+    // Encoding of the fields of `user_data_v1` that follow the pubkey and the full private state,
+    // as written by v0 and v1. The encoding of the intmax2-zkp types is pinned by its revision.
+    const V1_TAIL: &[u8] = b"\
+        \x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\
+        \x02\x00\x00\x00\x00\x00\x00\x00\
+        \x03\x00\x00\x00\x00\x00\x00\x00\
+        \x04\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00deposit\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00transfer\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00tx\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x0a\x00\x00\x00\x00\x00\x00\x00withdrawal";
+
+    // Same as `V1_TAIL` as written by v2, where each uuid has the lpt as its timestamp.
+    const V2_TAIL: &[u8] = b"\
+        \x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\
+        \x02\x00\x00\x00\x00\x00\x00\x00\
+        \x03\x00\x00\x00\x00\x00\x00\x00\
+        \x04\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00deposit\
+        \x01\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00transfer\
+        \x02\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00tx\
+        \x03\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x0a\x00\x00\x00\x00\x00\x00\x00withdrawal\
+        \x04\x00\x00\x00\x00\x00\x00\x00";
+
+    // Same as `V2_TAIL` as written by v3, followed by the empty rejected transfers.
+    const V3_TAIL_SUFFIX: &[u8] = b"\x00\x00\x00\x00\x00\x00\x00\x00";
+
+    // Same as `V2_TAIL` and `V3_TAIL_SUFFIX` as written by v4, where each of the processed uuids
+    // is followed by its empty skipped uuids.
+    const V4_TAIL: &[u8] = b"\
+        \x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\
+        \x02\x00\x00\x00\x00\x00\x00\x00\
+        \x03\x00\x00\x00\x00\x00\x00\x00\
+        \x04\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00deposit\
+        \x01\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00transfer\
+        \x02\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00tx\
+        \x03\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\
+        \x01\x00\x00\x00\x00\x00\x00\x00\x0a\x00\x00\x00\x00\x00\x00\x00withdrawal\
+        \x04\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00";
+
+    fn user_data_v1(key: KeySet) -> UserDataV1 {
+        UserDataV1 {
+            pubkey: key.pubkey,
+            full_private_state: FullPrivateState::new(),
+            balance_proof: None,
+            deposit_lpt: 1,
+            transfer_lpt: 2,
+            tx_lpt: 3,
+            withdrawal_lpt: 4,
+            processed_deposit_uuids: vec!["deposit".to_string()],
+            processed_transfer_uuids: vec!["transfer".to_string()],
+            processed_tx_uuids: vec!["tx".to_string()],
+            processed_withdrawal_uuids: vec!["withdrawal".to_string()],
+        }
+    }
+
+    fn tagged(version: u32, body: &[u8]) -> Vec<u8> {
+        let mut bytes = VERSION_MAGIC.to_vec();
+        bytes.extend_from_slice(&version.to_be_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn assert_migrated(user_data: &UserData) {
+        assert_eq!(user_data.deposit_lpt, 1);
+        assert_eq!(user_data.withdrawal_lpt, 4);
+        assert!(user_data.processed_deposit_uuids.contains("deposit"));
+        assert!(user_data.processed_transfer_uuids.contains("transfer"));
+        assert!(user_data.processed_tx_uuids.contains("tx"));
+        assert!(user_data.processed_withdrawal_uuids.contains("withdrawal"));
+    }
+
+    #[test]
+    fn test_decrypt_legacy_user_data() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let full_private_state = FullPrivateState::new();
+        let mut head = bincode::serialize(&key.pubkey).unwrap();
+        head.extend_from_slice(&bincode::serialize(&full_private_state).unwrap());
+
+        // v0: bare bincode without version tag
+        let v0 = [head.as_slice(), V1_TAIL].concat();
+        let decrypted = UserData::decrypt(&encrypt(key.pubkey, &v0), key).unwrap();
+        assert_migrated(&decrypted);
+
+        // v1: same layout as v0 with version tag
+        let decrypted = UserData::decrypt(&encrypt(key.pubkey, &tagged(1, &v0)), key).unwrap();
+        assert_migrated(&decrypted);
+
+        // v2: processed uuids with timestamps, without rejected transfers
+        let v2 = [head.as_slice(), V2_TAIL].concat();
+        let decrypted = UserData::decrypt(&encrypt(key.pubkey, &tagged(2, &v2)), key).unwrap();
+        assert_migrated(&decrypted);
+        assert!(decrypted.rejected_transfers.is_empty());
+
+        // v3: with rejected transfers, without skipped uuids
+        let v3 = [head.as_slice(), V2_TAIL, V3_TAIL_SUFFIX].concat();
+        let decrypted = UserData::decrypt(&encrypt(key.pubkey, &tagged(3, &v3)), key).unwrap();
+        assert_migrated(&decrypted);
+        assert!(!decrypted.processed_deposit_uuids.is_skipped("deposit"));
+
+        // v4: the current version
+        let v4 = [head.as_slice(), V4_TAIL].concat();
+        let decrypted = UserData::decrypt(&encrypt(key.pubkey, &tagged(4, &v4)), key).unwrap();
+        assert_migrated(&decrypted);
+
+        // the frozen layouts and the current one still encode to the fixtures
+        let v1 = user_data_v1(key);
+        assert!(bincode::serialize(&v1).unwrap().ends_with(V1_TAIL));
+        let v2 = UserDataV2::from(v1);
+        assert!(bincode::serialize(&v2).unwrap().ends_with(V2_TAIL));
+        let v4 = bincode::serialize(&UserData::from(v2)).unwrap();
+        assert!(v4.ends_with(V4_TAIL));
+    }
+
+    #[test]
+    fn test_lpt_compacts_processed_uuids() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let mut user_data: UserData = UserDataV2::from(user_data_v1(key)).into();
+        user_data.processed_deposit_uuids.insert(&MetaData {
+            uuid: "new_deposit".to_string(),
+            timestamp: 10,
+            block_number: None,
+        });
+        user_data.set_deposit_lpt(5);
+        assert!(!user_data.processed_deposit_uuids.contains("deposit"));
+        assert!(user_data.processed_deposit_uuids.contains("new_deposit"));
+
+        // the lpt does not move backward behind the compacted uuids
+        user_data.set_deposit_lpt(3);
+        assert_eq!(user_data.deposit_lpt, 5);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::error::DataError;
+
+type Result<T> = std::result::Result<T, DataError>;
+
+/// Prefix of versioned payloads. Legacy payloads are bare bincode, so they are distinguished from
+/// versioned ones by the absence of this prefix.
+pub const VERSION_MAGIC: [u8; 4] = [0xff, b'I', b'M', b'V'];
+
+const HEADER_LEN: usize = VERSION_MAGIC.len() + 4;
+
+/// The version of payloads that were written before the version tag was introduced.
+pub const LEGACY_VERSION: u32 = 0;
+
+/// Data types that are serialized with a version tag.
+///
+/// The serialized payload is `VERSION_MAGIC || version (u32 big endian) || bincode(data)`.
+/// Payloads without the magic prefix are treated as `LEGACY_VERSION`.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The version written by `to_versioned_bytes`.
+    const CURRENT_VERSION: u32;
+
+    /// Decode the body of a payload written with an older `version` and migrate it to the
+    /// current layout. `version` is always less than `CURRENT_VERSION`.
+    fn migrate(version: u32, body: &[u8]) -> Result<Self>;
+}
+
+pub fn to_versioned_bytes<T: Versioned>(data: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(&VERSION_MAGIC);
+    bytes.extend_from_slice(&T::CURRENT_VERSION.to_be_bytes());
+    bytes.extend_from_slice(&bincode::serialize(data).unwrap());
+    bytes
+}
+
+pub fn from_versioned_bytes<T: Versioned>(bytes: &[u8]) -> Result<T> {
+    let (version, body) = split_version(bytes);
+    if version > T::CURRENT_VERSION {
+        return Err(DataError::UnsupportedVersion(version));
+    }
+    if version == T::CURRENT_VERSION {
+        let data = bincode::deserialize(body)?;
+        return Ok(data);
+    }
+    T::migrate(version, body)
+}
+
+/// Returns the version of the payload and the body without the header.
+pub fn split_version(bytes: &[u8]) -> (u32, &[u8]) {
+    if bytes.len() < HEADER_LEN || bytes[..VERSION_MAGIC.len()] != VERSION_MAGIC {
+        return (LEGACY_VERSION, bytes);
+    }
+    let version = u32::from_be_bytes(
+        bytes[VERSION_MAGIC.len()..HEADER_LEN]
+            .try_into()
+            .expect("slice length is 4"),
+    );
+    (version, &bytes[HEADER_LEN..])
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::common::signature::key_set::KeySet;
+
+    use crate::data::{encryption::algorithm::encrypt, error::DataError, user_data::UserData};
+
+    use super::{split_version, to_versioned_bytes, Versioned, LEGACY_VERSION, VERSION_MAGIC};
+
+    #[test]
+    fn test_split_version() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let user_data = UserData::new(key.pubkey);
+
+        let legacy = bincode::serialize(&user_data).unwrap();
+        let (version, body) = split_version(&legacy);
+        assert_eq!(version, LEGACY_VERSION);
+        assert_eq!(body, &legacy[..]);
+
+        let versioned = to_versioned_bytes(&user_data);
+        let (version, body) = split_version(&versioned);
+        assert_eq!(version, UserData::CURRENT_VERSION);
+        assert_eq!(body, &legacy[..]);
+    }
+
+    #[test]
+    fn test_reject_future_version() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let mut bytes = to_versioned_bytes(&UserData::new(key.pubkey));
+        let future_version = UserData::CURRENT_VERSION + 1;
+        bytes[VERSION_MAGIC.len()..VERSION_MAGIC.len() + 4]
+            .copy_from_slice(&future_version.to_be_bytes());
+        let encrypted = encrypt(key.pubkey, &bytes);
+        let result = UserData::decrypt(&encrypted, key);
+        assert!(matches!(result, Err(DataError::UnsupportedVersion(v)) if v == future_version));
+    }
+}
@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+use intmax2_zkp::{common::signature::key_set::KeySet, ethereum_types::u256::U256};
+
+use super::{
+    deposit_data::{DepositData, DepositDataV1},
+    encryption::algorithm::{decrypt, encrypt},
+    error::DataError,
+    meta_data::MetaData,
+    sender_proof_set::{SenderProofSet, SenderProofSetV1},
+    transfer_data::{TransferData, TransferDataV2},
+    tx_data::{TxData, TxDataV1},
+    user_data::{UserData, UserDataV3},
+    versioning::{from_versioned_bytes, to_versioned_bytes, Versioned},
+};
+
+type Result<T> = std::result::Result<T, DataError>;
+
+/// Snapshot of everything the store vault server keeps for an account, in decrypted form.
+///
+/// The nested data is serialized as its own versioned payload (see `WalletBackupV2`), so a layout
+/// change of a nested type does not require a new version of `WalletBackup`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "WalletBackupV2", try_from = "WalletBackupV2")]
+pub struct WalletBackup {
+    pub pubkey: U256,
+
+    // unix timestamp when the backup was created
+    pub created_at: u64,
+
+    pub user_data: UserData,
+
+    pub deposits: Vec<(MetaData, DepositData)>,
+    pub transfers: Vec<(MetaData, TransferData)>,
+    pub txs: Vec<(MetaData, TxData)>,
+    pub withdrawals: Vec<(MetaData, TransferData)>,
+
+    // Sender proof sets referenced by the transfers, withdrawals and txs, keyed by the ephemeral
+    // private key
+    pub sender_proof_sets: Vec<(U256, SenderProofSet)>,
+}
+
+impl WalletBackup {
+    fn to_bytes(&self) -> Vec<u8> {
+        to_versioned_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        from_versioned_bytes(bytes)
+    }
+
+    pub fn encrypt(&self, pubkey: U256) -> Vec<u8> {
+        encrypt(pubkey, &self.to_bytes())
+    }
+
+    pub fn decrypt(bytes: &[u8], key: KeySet) -> Result<Self> {
+        let data = decrypt(key, bytes).map_err(|e| DataError::DecryptionError(e.to_string()))?;
+        let data = Self::from_bytes(&data)?;
+        data.validate(key)?;
+        Ok(data)
+    }
+
+    pub fn validate(&self, key: KeySet) -> Result<()> {
+        if self.pubkey != key.pubkey || self.user_data.pubkey != key.pubkey {
+            return Err(DataError::ValidationError(
+                "Backup pubkey mismatch".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Versioned for WalletBackup {
+    const CURRENT_VERSION: u32 = 2;
+
+    fn migrate(version: u32, body: &[u8]) -> Result<Self> {
+        match version {
+            1 => {
+                let v1: WalletBackupV1 = bincode::deserialize(body)?;
+                Ok(v1.into())
+            }
+            // there is no backup written before versioning
+            _ => Err(DataError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Layout of v2, where the user data and the records are embedded as their versioned payloads and
+/// decoded through the migrations of their types.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WalletBackupV2 {
+    pubkey: U256,
+    created_at: u64,
+    user_data: Vec<u8>,
+    deposits: Vec<(MetaData, Vec<u8>)>,
+    transfers: Vec<(MetaData, Vec<u8>)>,
+    txs: Vec<(MetaData, Vec<u8>)>,
+    withdrawals: Vec<(MetaData, Vec<u8>)>,
+    sender_proof_sets: Vec<(U256, Vec<u8>)>,
+}
+
+fn encode_records<T: Versioned>(records: Vec<(MetaData, T)>) -> Vec<(MetaData, Vec<u8>)> {
+    records
+        .into_iter()
+        .map(|(meta, data)| (meta, to_versioned_bytes(&data)))
+        .collect()
+}
+
+fn decode_records<T: Versioned>(records: Vec<(MetaData, Vec<u8>)>) -> Result<Vec<(MetaData, T)>> {
+    records
+        .into_iter()
+        .map(|(meta, bytes)| Ok((meta, from_versioned_bytes(&bytes)?)))
+        .collect()
+}
+
+impl From<WalletBackup> for WalletBackupV2 {
+    fn from(backup: WalletBackup) -> Self {
+        Self {
+            pubkey: backup.pubkey,
+            created_at: backup.created_at,
+            user_data: to_versioned_bytes(&backup.user_data),
+            deposits: encode_records(backup.deposits),
+            transfers: encode_records(backup.transfers),
+            txs: encode_records(backup.txs),
+            withdrawals: encode_records(backup.withdrawals),
+            sender_proof_sets: backup
+                .sender_proof_sets
+                .into_iter()
+                .map(|(key, data)| (key, data.to_bytes()))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<WalletBackupV2> for WalletBackup {
+    type Error = DataError;
+
+    fn try_from(v2: WalletBackupV2) -> Result<Self> {
+        Ok(Self {
+            pubkey: v2.pubkey,
+            created_at: v2.created_at,
+            user_data: from_versioned_bytes(&v2.user_data)?,
+            deposits: decode_records(v2.deposits)?,
+            transfers: decode_records(v2.transfers)?,
+            txs: decode_records(v2.txs)?,
+            withdrawals: decode_records(v2.withdrawals)?,
+            sender_proof_sets: v2
+                .sender_proof_sets
+                .into_iter()
+                .map(|(key, bytes)| Ok((key, SenderProofSet::from_bytes(&bytes)?)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Frozen layout of v1, which embedded the layouts of the nested types of that time. `MetaData`
+/// is part of the store vault API and is not frozen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WalletBackupV1 {
+    pubkey: U256,
+    created_at: u64,
+    user_data: UserDataV3,
+    deposits: Vec<(MetaData, DepositDataV1)>,
+    transfers: Vec<(MetaData, TransferDataV2)>,
+    txs: Vec<(MetaData, TxDataV1)>,
+    withdrawals: Vec<(MetaData, TransferDataV2)>,
+    sender_proof_sets: Vec<(U256, SenderProofSetV1)>,
+}
+
+fn migrate_records<T, U: From<T>>(records: Vec<(MetaData, T)>) -> Vec<(MetaData, U)> {
+    records
+        .into_iter()
+        .map(|(meta, data)| (meta, data.into()))
+        .collect()
+}
+
+impl From<WalletBackupV1> for WalletBackup {
+    fn from(v1: WalletBackupV1) -> Self {
+        Self {
+            pubkey: v1.pubkey,
+            created_at: v1.created_at,
+            user_data: v1.user_data.into(),
+            deposits: migrate_records(v1.deposits),
+            transfers: migrate_records(v1.transfers),
+            txs: migrate_records(v1.txs),
+            withdrawals: migrate_records(v1.withdrawals),
+            sender_proof_sets: v1
+                .sender_proof_sets
+                .into_iter()
+                .map(|(key, data)| (key, data.into()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::common::{private_state::FullPrivateState, signature::key_set::KeySet};
+
+    use crate::data::{
+        encryption::algorithm::encrypt, error::DataError, user_data::UserData,
+        versioning::VERSION_MAGIC,
+    };
+
+    use super::WalletBackup;
+
+    // Encoding of the empty user data of v3 that follows its pubkey and full private state,
+    // followed by the empty records of a v1 backup.
+    const V1_TAIL: &[u8] = &[0u8; 1 + 8 * 9 + 8 * 5];
+
+    fn empty_backup(key: KeySet) -> WalletBackup {
+        WalletBackup {
+            pubkey: key.pubkey,
+            created_at: 0,
+            user_data: UserData::new(key.pubkey),
+            deposits: vec![],
+            transfers: vec![],
+            txs: vec![],
+            withdrawals: vec![],
+            sender_proof_sets: vec![],
+        }
+    }
+
+    #[test]
+    fn test_backup_encryption() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let backup = empty_backup(key);
+        let decrypted = WalletBackup::decrypt(&backup.encrypt(key.pubkey), key).unwrap();
+        assert_eq!(decrypted.pubkey, key.pubkey);
+
+        // a backup of another account is rejected even if it is encrypted for this key
+        let other = KeySet::rand(&mut rng);
+        let result = WalletBackup::decrypt(&empty_backup(other).encrypt(key.pubkey), key);
+        assert!(matches!(result, Err(DataError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_decrypt_v1_backup() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let mut bytes = VERSION_MAGIC.to_vec();
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&bincode::serialize(&key.pubkey).unwrap());
+        bytes.extend_from_slice(&7u64.to_le_bytes());
+        bytes.extend_from_slice(&bincode::serialize(&key.pubkey).unwrap());
+        bytes.extend_from_slice(&bincode::serialize(&FullPrivateState::new()).unwrap());
+        bytes.extend_from_slice(V1_TAIL);
+
+        let decrypted = WalletBackup::decrypt(&encrypt(key.pubkey, &bytes), key).unwrap();
+        assert_eq!(decrypted.created_at, 7);
+        assert_eq!(decrypted.user_data.pubkey, key.pubkey);
+        assert!(decrypted.deposits.is_empty());
+        assert!(decrypted.sender_proof_sets.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use std::{fmt, str::FromStr};
+
+use bech32::{FromBase32 as _, ToBase32 as _, Variant};
+use intmax2_zkp::{
+    common::generic_address::GenericAddress,
+    ethereum_types::{address::Address, u256::U256, u32limb_trait::U32LimbTrait as _},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AddressError {
+    #[error("Invalid encoding: {0}")]
+    InvalidEncoding(String),
+
+    #[error("Unknown network prefix: {0}")]
+    UnknownPrefix(String),
+
+    #[error("Invalid length: expected 32 bytes, got {0}")]
+    InvalidLength(usize),
+
+    #[error("Network mismatch: expected {expected}, got {actual}")]
+    NetworkMismatch { expected: Network, actual: Network },
+}
+
+/// Network an intmax address belongs to. Addresses of different networks have different prefixes,
+/// so that an address of a test network is not accepted on prod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Network {
+    Local,
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl Network {
+    /// Human readable prefix of the bech32m encoding
+    pub fn hrp(&self) -> &'static str {
+        match self {
+            Self::Local => "intmaxlocal",
+            Self::Dev => "intmaxdev",
+            Self::Staging => "intmaxstaging",
+            Self::Prod => "intmax",
+        }
+    }
+
+    pub fn from_hrp(hrp: &str) -> Option<Self> {
+        [Self::Local, Self::Dev, Self::Staging, Self::Prod]
+            .into_iter()
+            .find(|network| network.hrp() == hrp)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Local => "local",
+            Self::Dev => "dev",
+            Self::Staging => "staging",
+            Self::Prod => "prod",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "dev" => Ok(Self::Dev),
+            "staging" => Ok(Self::Staging),
+            "prod" => Ok(Self::Prod),
+            _ => Err(format!("invalid network: {}", s)),
+        }
+    }
+}
+
+/// Checksummed encoding of an intmax pubkey, e.g. `intmax1...`
+///
+/// This is the bech32m encoding of the 32 bytes big endian pubkey with the prefix of the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntmaxAddress {
+    pub network: Network,
+    pub pubkey: U256,
+}
+
+impl IntmaxAddress {
+    pub fn new(network: Network, pubkey: U256) -> Self {
+        Self { network, pubkey }
+    }
+
+    pub fn encode(&self) -> String {
+        bech32::encode(
+            self.network.hrp(),
+            self.pubkey.to_bytes_be().to_base32(),
+            Variant::Bech32m,
+        )
+        .expect("hrp is valid")
+    }
+
+    pub fn decode(address: &str) -> Result<Self, AddressError> {
+        let (hrp, data, variant) =
+            bech32::decode(address).map_err(|e| AddressError::InvalidEncoding(e.to_string()))?;
+        if variant != Variant::Bech32m {
+            return Err(AddressError::InvalidEncoding(
+                "not a bech32m encoding".to_string(),
+            ));
+        }
+        let network = Network::from_hrp(&hrp).ok_or(AddressError::UnknownPrefix(hrp))?;
+        let bytes = Vec::<u8>::from_base32(&data)
+            .map_err(|e| AddressError::InvalidEncoding(e.to_string()))?;
+        if bytes.len() != 32 {
+            return Err(AddressError::InvalidLength(bytes.len()));
+        }
+        Ok(Self {
+            network,
+            pubkey: U256::from_bytes_be(&bytes),
+        })
+    }
+
+    /// Decode the address, checking that it belongs to `network`
+    pub fn decode_for(address: &str, network: Network) -> Result<Self, AddressError> {
+        let decoded = Self::decode(address)?;
+        if decoded.network != network {
+            return Err(AddressError::NetworkMismatch {
+                expected: network,
+                actual: decoded.network,
+            });
+        }
+        Ok(decoded)
+    }
+}
+
+impl fmt::Display for IntmaxAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+impl FromStr for IntmaxAddress {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::decode(s)
+    }
+}
+
+/// Format the recipient of a transfer, using the intmax address for pubkeys and the hex string for
+/// ethereum addresses.
+pub fn format_generic_address(address: &GenericAddress, network: Network) -> String {
+    if address.is_pubkey {
+        IntmaxAddress::new(network, address.to_pubkey().unwrap()).encode()
+    } else {
+        address.to_address().unwrap().to_hex()
+    }
+}
+
+/// Parse the recipient of a transfer, which is either an intmax address of `network`, a 32 bytes
+/// hex pubkey, or a 20 bytes hex ethereum address.
+pub fn parse_generic_address(
+    input: &str,
+    network: Network,
+) -> Result<GenericAddress, AddressError> {
+    let Some(hex) = input.strip_prefix("0x") else {
+        let address = IntmaxAddress::decode_for(input, network)?;
+        return Ok(GenericAddress::from_pubkey(address.pubkey));
+    };
+    match hex.len() {
+        64 => U256::from_hex(input)
+            .map(GenericAddress::from_pubkey)
+            .map_err(|e| AddressError::InvalidEncoding(e.to_string())),
+        40 => Address::from_hex(input)
+            .map(GenericAddress::from_address)
+            .map_err(|e| AddressError::InvalidEncoding(e.to_string())),
+        _ => Err(AddressError::InvalidLength(hex.len() / 2)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+
+    use super::{AddressError, IntmaxAddress, Network};
+
+    #[test]
+    fn test_intmax_address() {
+        let mut rng = rand::thread_rng();
+        let pubkey = U256::rand(&mut rng);
+        let address = IntmaxAddress::new(Network::Prod, pubkey);
+        let encoded = address.encode();
+        assert!(encoded.starts_with("intmax1"));
+        assert_eq!(IntmaxAddress::decode(&encoded).unwrap(), address);
+
+        // a single typo is detected by the checksum
+        let mut typo = encoded.clone().into_bytes();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+        let typo = String::from_utf8(typo).unwrap();
+        assert!(matches!(
+            IntmaxAddress::decode(&typo),
+            Err(AddressError::InvalidEncoding(_))
+        ));
+
+        // an address of another network is rejected
+        let dev = IntmaxAddress::new(Network::Dev, pubkey).encode();
+        assert!(dev.starts_with("intmaxdev1"));
+        assert!(matches!(
+            IntmaxAddress::decode_for(&dev, Network::Prod),
+            Err(AddressError::NetworkMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+use std::{fmt, str::FromStr};
+
+use intmax2_zkp::{
+    common::generic_address::GenericAddress,
+    ethereum_types::{address::Address, u256::U256, u32limb_trait::U32LimbTrait as _},
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{
+        deposit_data::TokenType,
+        transfer_data::{validate_memo, MAX_MEMO_LEN},
+    },
+    utils::address::{format_generic_address, IntmaxAddress, Network},
+};
+
+/// Scheme of the invoice URI
+pub const INVOICE_SCHEME: &str = "intmax2";
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvoiceError {
+    #[error("Invalid scheme: {0}")]
+    InvalidScheme(String),
+
+    #[error("Invalid recipient: {0}")]
+    InvalidRecipient(String),
+
+    #[error("Invalid parameter {0}: {1}")]
+    InvalidParameter(String, String),
+
+    #[error("Missing parameter: {0}")]
+    MissingParameter(String),
+
+    #[error("Unknown parameter: {0}")]
+    UnknownParameter(String),
+
+    #[error("Memo is longer than {0} bytes")]
+    MemoTooLong(usize),
+}
+
+type Result<T> = std::result::Result<T, InvoiceError>;
+
+/// Token requested by an invoice, either by its index in the liquidity contract or by its
+/// contract, which is resolved to the index by the payer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvoiceToken {
+    Index(u32),
+    Contract {
+        token_type: TokenType,
+        token_address: Address,
+        token_id: U256,
+    },
+}
+
+/// A request to pay `amount` of `token` to `recipient`, encoded as a URI
+///
+/// `intmax2:<recipient>?tokenIndex=<index>&amount=<amount>[&memo=<memo>][&expiry=<unix time>]`
+///
+/// The recipient is an intmax address or an ethereum address (hex, paid by a withdrawal). The
+/// intmax address carries the network, which must match that of the payer. A hex pubkey
+/// (32 bytes) is also accepted as the recipient when parsing. Instead of `tokenIndex`, the token
+/// may be given by `tokenType`, `tokenAddress` and `tokenId`. The amount is a decimal integer in
+/// the smallest unit of the token, and the memo is percent-encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invoice {
+    pub recipient: GenericAddress,
+    pub token: InvoiceToken,
+    pub amount: U256,
+    pub memo: Option<String>,
+    pub expiry: Option<u64>,
+}
+
+impl Invoice {
+    /// Encode the invoice, with the recipient pubkey as an intmax address of `network`
+    pub fn to_uri(&self, network: Network) -> String {
+        let recipient = format_generic_address(&self.recipient, network);
+        let mut params = Vec::new();
+        match &self.token {
+            InvoiceToken::Index(token_index) => {
+                params.push(format!("tokenIndex={}", token_index));
+            }
+            InvoiceToken::Contract {
+                token_type,
+                token_address,
+                token_id,
+            } => {
+                params.push(format!("tokenType={}", token_type));
+                params.push(format!("tokenAddress={}", token_address.to_hex()));
+                params.push(format!("tokenId={}", token_id));
+            }
+        }
+        params.push(format!("amount={}", self.amount));
+        if let Some(memo) = &self.memo {
+            params.push(format!("memo={}", percent_encode(memo)));
+        }
+        if let Some(expiry) = self.expiry {
+            params.push(format!("expiry={}", expiry));
+        }
+        format!("{}:{}?{}", INVOICE_SCHEME, recipient, params.join("&"))
+    }
+
+    /// Parse the invoice, rejecting an intmax address recipient of other than `network`
+    pub fn from_uri(uri: &str, network: Network) -> Result<Self> {
+        let rest = uri
+            .strip_prefix(INVOICE_SCHEME)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .ok_or_else(|| {
+                InvoiceError::InvalidScheme(uri.split(':').next().unwrap_or("").to_string())
+            })?;
+        let (recipient, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let recipient = parse_recipient(recipient, network)?;
+
+        let mut token_index = None;
+        let mut token_type = None;
+        let mut token_address = None;
+        let mut token_id = None;
+        let mut amount = None;
+        let mut memo = None;
+        let mut expiry = None;
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let invalid = |e: &dyn fmt::Display| {
+                InvoiceError::InvalidParameter(key.to_string(), e.to_string())
+            };
+            match key {
+                "tokenIndex" => token_index = Some(value.parse::<u32>().map_err(|e| invalid(&e))?),
+                "tokenType" => {
+                    token_type = Some(TokenType::from_str(value).map_err(|e| invalid(&e))?)
+                }
+                "tokenAddress" => {
+                    token_address = Some(Address::from_hex(value).map_err(|e| invalid(&e))?)
+                }
+                "tokenId" => token_id = Some(parse_decimal(value).map_err(|e| invalid(&e))?),
+                "amount" => amount = Some(parse_decimal(value).map_err(|e| invalid(&e))?),
+                "memo" => memo = Some(percent_decode(value).map_err(|e| invalid(&e))?),
+                "expiry" => expiry = Some(value.parse::<u64>().map_err(|e| invalid(&e))?),
+                _ => return Err(InvoiceError::UnknownParameter(key.to_string())),
+            }
+        }
+
+        let token = match (token_index, token_type, token_address) {
+            (Some(token_index), None, None) => InvoiceToken::Index(token_index),
+            (None, Some(token_type), token_address) => {
+                let token_address = match token_type {
+                    TokenType::NATIVE => token_address.unwrap_or_default(),
+                    _ => token_address
+                        .ok_or(InvoiceError::MissingParameter("tokenAddress".to_string()))?,
+                };
+                InvoiceToken::Contract {
+                    token_type,
+                    token_address,
+                    token_id: token_id.unwrap_or_default(),
+                }
+            }
+            (None, None, _) => {
+                return Err(InvoiceError::MissingParameter("tokenIndex".to_string()))
+            }
+            _ => {
+                return Err(InvoiceError::InvalidParameter(
+                    "tokenIndex".to_string(),
+                    "both token index and token contract are given".to_string(),
+                ))
+            }
+        };
+        let invoice = Self {
+            recipient,
+            token,
+            amount: amount.ok_or(InvoiceError::MissingParameter("amount".to_string()))?,
+            memo,
+            expiry,
+        };
+        invoice.validate()?;
+        Ok(invoice)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_memo(self.memo.as_deref()).map_err(|_| InvoiceError::MemoTooLong(MAX_MEMO_LEN))
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expiry.is_some_and(|expiry| expiry < now)
+    }
+}
+
+fn parse_recipient(recipient: &str, network: Network) -> Result<GenericAddress> {
+    let Some(hex) = recipient.strip_prefix("0x") else {
+        return IntmaxAddress::decode_for(recipient, network)
+            .map(|address| GenericAddress::from_pubkey(address.pubkey))
+            .map_err(|e| InvoiceError::InvalidRecipient(format!("{}: {}", recipient, e)));
+    };
+    match hex.len() {
+        64 => U256::from_hex(recipient)
+            .map(GenericAddress::from_pubkey)
+            .map_err(|_| InvoiceError::InvalidRecipient(recipient.to_string())),
+        40 => Address::from_hex(recipient)
+            .map(GenericAddress::from_address)
+            .map_err(|_| InvoiceError::InvalidRecipient(recipient.to_string())),
+        _ => Err(InvoiceError::InvalidRecipient(recipient.to_string())),
+    }
+}
+
+fn parse_decimal(value: &str) -> std::result::Result<U256, String> {
+    let value = BigUint::from_str(value).map_err(|e| e.to_string())?;
+    value.try_into().map_err(|_| "overflows u256".to_string())
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> std::result::Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .ok_or("truncated percent encoding".to_string())?;
+                let byte = u8::from_str_radix(hex, 16).map_err(|e| e.to_string())?;
+                decoded.push(byte);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::{
+        common::generic_address::GenericAddress,
+        ethereum_types::{address::Address, u256::U256, u32limb_trait::U32LimbTrait as _},
+    };
+
+    use crate::{
+        data::deposit_data::TokenType,
+        utils::address::{IntmaxAddress, Network},
+    };
+
+    use super::{Invoice, InvoiceError, InvoiceToken};
+
+    #[test]
+    fn test_invoice_uri_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let invoice = Invoice {
+            recipient: GenericAddress::from_pubkey(U256::rand(&mut rng)),
+            token: InvoiceToken::Index(1),
+            amount: 1_000_000u32.into(),
+            memo: Some("order #42 / ü".to_string()),
+            expiry: Some(1_700_000_000),
+        };
+        let uri = invoice.to_uri(Network::Prod);
+        assert!(uri.starts_with("intmax2:intmax1"));
+        assert_eq!(Invoice::from_uri(&uri, Network::Prod).unwrap(), invoice);
+
+        // hex pubkey of an older invoice
+        let pubkey = invoice.recipient.to_pubkey().unwrap();
+        let address = IntmaxAddress::new(Network::Prod, pubkey).encode();
+        let hex_uri = uri.replacen(&address, &pubkey.to_hex(), 1);
+        assert_eq!(Invoice::from_uri(&hex_uri, Network::Prod).unwrap(), invoice);
+        assert!(invoice.is_expired(1_700_000_001));
+
+        let invoice = Invoice {
+            recipient: GenericAddress::from_address(Address::rand(&mut rng)),
+            token: InvoiceToken::Contract {
+                token_type: TokenType::ERC20,
+                token_address: Address::rand(&mut rng),
+                token_id: U256::default(),
+            },
+            amount: 5u32.into(),
+            memo: None,
+            expiry: None,
+        };
+        let uri = invoice.to_uri(Network::Prod);
+        assert!(uri.starts_with("intmax2:0x"));
+        assert_eq!(Invoice::from_uri(&uri, Network::Prod).unwrap(), invoice);
+
+        // an intmax address of another network is rejected
+        let uri = Invoice {
+            recipient: GenericAddress::from_pubkey(U256::rand(&mut rng)),
+            ..invoice
+        }
+        .to_uri(Network::Dev);
+        assert!(matches!(
+            Invoice::from_uri(&uri, Network::Prod),
+            Err(InvoiceError::InvalidRecipient(_))
+        ));
+
+        assert!(matches!(
+            Invoice::from_uri("bitcoin:0x00?amount=1", Network::Prod),
+            Err(InvoiceError::InvalidScheme(_))
+        ));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod address;
 pub mod circuit_verifiers;
 pub mod digest;
+pub mod invoice;
+pub mod pow;
 pub mod signature;
+pub mod threshold_signature;
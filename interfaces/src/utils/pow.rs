@@ -0,0 +1,79 @@
+use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+use sha2::{Digest as _, Sha256};
+
+use crate::api::store_vault_server::interface::SaveDataEntry;
+
+/// Challenge of the proof of work of an entry written to the inbox of another user. It commits to
+/// the writer, so that a solved nonce cannot be reused by others.
+pub fn pow_challenge(writer: U256, entry: &SaveDataEntry) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"intmax2-store-vault-pow");
+    hasher.update(writer.to_bytes_be());
+    hasher.update([entry.data_type as u8]);
+    hasher.update(entry.pubkey.to_bytes_be());
+    hasher.update(Sha256::digest(&entry.encrypted_data));
+    hasher.finalize().into()
+}
+
+fn leading_zero_bits(challenge: &[u8; 32], nonce: u64) -> u32 {
+    let hash = Sha256::new()
+        .chain_update(challenge)
+        .chain_update(nonce.to_be_bytes())
+        .finalize();
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Returns true if the `pow_nonce` of the entry has at least `difficulty` leading zero bits.
+pub fn verify_pow(writer: U256, entry: &SaveDataEntry, difficulty: u32) -> bool {
+    match entry.pow_nonce {
+        Some(nonce) => leading_zero_bits(&pow_challenge(writer, entry), nonce) >= difficulty,
+        None => difficulty == 0,
+    }
+}
+
+/// Finds a nonce of the entry with `difficulty` leading zero bits, which takes about
+/// `2^difficulty` hashes.
+pub fn solve_pow(writer: U256, entry: &SaveDataEntry, difficulty: u32) -> u64 {
+    let challenge = pow_challenge(writer, entry);
+    (0..)
+        .find(|nonce| leading_zero_bits(&challenge, *nonce) >= difficulty)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+
+    use super::{solve_pow, verify_pow};
+    use crate::api::store_vault_server::interface::{DataType, SaveDataEntry};
+
+    fn u256(n: u8) -> U256 {
+        let mut bytes = [0u8; 32];
+        bytes[31] = n;
+        U256::from_bytes_be(&bytes)
+    }
+
+    #[test]
+    fn test_pow() {
+        let writer = u256(1);
+        let mut entry = SaveDataEntry {
+            data_type: DataType::Transfer,
+            pubkey: u256(2),
+            encrypted_data: vec![1, 2, 3],
+            pow_nonce: None,
+        };
+        assert!(!verify_pow(writer, &entry, 8));
+
+        entry.pow_nonce = Some(solve_pow(writer, &entry, 8));
+        assert!(verify_pow(writer, &entry, 8));
+        // bound to the writer
+        assert!(!verify_pow(u256(3), &entry, 8));
+    }
+}
@@ -11,45 +11,81 @@ use plonky2_bn254::{
 };
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Auth {
+    #[schema(value_type = String)]
     pub pubkey: U256,
     pub expiry: u64,
+    /// Random number which makes every signed request unique, so that the servers can reject
+    /// replays of a request until its expiry. Absent in the auths of clients older than the
+    /// nonce, which are signed without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    #[schema(value_type = Object)]
     pub signature: FlatG2,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct WithAuth<T> {
     pub inner: T,
     pub auth: Auth,
 }
 
+/// Body of a request that is authenticated either by `auth` or, if it is omitted, by a session
+/// token in the `Authorization` header. `WithAuth<T>` is accepted as is.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WithOptionalAuth<T> {
+    pub inner: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SignContent {
     pub pubkey: U256,
     pub content: Vec<u8>,
     pub expiry: u64,
+    #[serde(default)]
+    pub nonce: Option<u64>,
 }
 
-impl Auth {
-    pub fn sign(key: KeySet, time_to_expiry: u64, content: &[u8]) -> Self {
-        let expiry = current_time() + time_to_expiry;
-        let sign_content = SignContent {
-            pubkey: key.pubkey,
+impl SignContent {
+    pub fn new(pubkey: U256, time_to_expiry: u64, content: &[u8]) -> Self {
+        SignContent {
+            pubkey,
             content: content.to_vec(),
-            expiry,
+            expiry: current_time() + time_to_expiry,
+            nonce: Some(rand::random()),
+        }
+    }
+
+    /// The hash that is signed by `Auth`. Without a nonce, it is the hash of the legacy layout.
+    pub fn hash(&self) -> Bytes32 {
+        let serialized = match self.nonce {
+            Some(nonce) => {
+                bincode::serialize(&(self.pubkey, &self.content, self.expiry, nonce)).unwrap()
+            }
+            None => bincode::serialize(&(self.pubkey, &self.content, self.expiry)).unwrap(),
         };
-        let serialized = bincode::serialize(&sign_content).unwrap();
         let digest = sha2::Sha256::digest(&serialized);
-        let hash = Bytes32::from_bytes_be(&digest);
-        let signature = sign_message(key.privkey, hash).unwrap();
+        Bytes32::from_bytes_be(&digest)
+    }
+}
+
+impl Auth {
+    pub fn sign(key: KeySet, time_to_expiry: u64, content: &[u8]) -> Self {
+        let sign_content = SignContent::new(key.pubkey, time_to_expiry, content);
+        let signature = sign_message(key.privkey, sign_content.hash()).unwrap();
         Auth {
             pubkey: key.pubkey,
-            expiry,
+            expiry: sign_content.expiry,
+            nonce: sign_content.nonce,
             signature,
         }
     }
@@ -62,11 +98,9 @@ impl Auth {
             pubkey: self.pubkey,
             content: content.to_vec(),
             expiry: self.expiry,
+            nonce: self.nonce,
         };
-        let serialized = bincode::serialize(&sign_content).unwrap();
-        let digest = sha2::Sha256::digest(&serialized);
-        let hash = Bytes32::from_bytes_be(&digest);
-        verify_signature(self.signature.clone(), self.pubkey, hash)
+        verify_signature(self.signature.clone(), self.pubkey, sign_content.hash())
     }
 }
 
@@ -121,7 +155,7 @@ cfg_if::cfg_if! {
     }
 }
 
-fn hash_to_message_point(hash: Bytes32) -> G2Affine {
+pub(crate) fn hash_to_message_point(hash: Bytes32) -> G2Affine {
     let elements = hash
         .to_u32_vec()
         .iter()
@@ -130,7 +164,7 @@ fn hash_to_message_point(hash: Bytes32) -> G2Affine {
     G2Target::<GoldilocksField, 2>::hash_to_g2(&elements)
 }
 
-fn check_pairing(g1s: Vec<G1Affine>, g2s: Vec<G2Affine>) -> bool {
+pub(crate) fn check_pairing(g1s: Vec<G1Affine>, g2s: Vec<G2Affine>) -> bool {
     Bn254::multi_pairing(g1s, g2s).is_zero()
 }
 
@@ -158,5 +192,44 @@ mod test {
         let content = b"test";
         let auth = super::Auth::sign(key, 10, content);
         assert!(auth.verify(content).is_ok());
+
+        // the nonce is signed
+        let other = super::Auth::sign(key, 10, content);
+        assert_ne!(auth.nonce, other.nonce);
+        let tampered = super::Auth {
+            nonce: other.nonce,
+            ..auth.clone()
+        };
+        assert!(tampered.verify(content).is_err());
+        let stripped = super::Auth {
+            nonce: None,
+            ..auth
+        };
+        assert!(stripped.verify(content).is_err());
+    }
+
+    #[test]
+    fn test_legacy_auth_verify() {
+        let mut rnd = rand::thread_rng();
+        let key = KeySet::rand(&mut rnd);
+        let content = b"test";
+        // signed by a client older than the nonce
+        let sign_content = super::SignContent {
+            nonce: None,
+            ..super::SignContent::new(key.pubkey, 10, content)
+        };
+        let auth = super::Auth {
+            pubkey: key.pubkey,
+            expiry: sign_content.expiry,
+            nonce: None,
+            signature: sign_message(key.privkey, sign_content.hash()).unwrap(),
+        };
+        assert!(auth.verify(content).is_ok());
+
+        // the nonce is omitted from the json, and parsed as none if missing
+        let json = serde_json::to_value(&auth).unwrap();
+        assert!(json.get("nonce").is_none());
+        let parsed: super::Auth = serde_json::from_value(json).unwrap();
+        assert!(parsed.verify(content).is_ok());
     }
 }
@@ -0,0 +1,359 @@
+use ark_bn254::{Fq, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr as _;
+use ark_ff::{BigInteger as _, Field as _, PrimeField, UniformRand as _};
+use intmax2_zkp::{
+    common::{
+        block_builder::{BlockProposal, UserSignature},
+        signature::{
+            flatten::FlatG2,
+            key_set::KeySet,
+            sign::{hash_to_weight, tx_tree_root_and_expiry_to_message_point},
+        },
+    },
+    ethereum_types::{bytes32::Bytes32, u256::U256, u32limb_trait::U32LimbTrait},
+};
+use num_traits::identities::Zero as _;
+use plonky2_bn254::fields::recover::RecoverFromX as _;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::signature::{check_pairing, hash_to_message_point, Auth, SignContent};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ThresholdSignatureError {
+    #[error("Invalid threshold: {threshold} of {num_shares}")]
+    InvalidThreshold { threshold: u32, num_shares: u32 },
+
+    #[error("Not enough partial signatures: {0} < {1}")]
+    NotEnoughPartialSignatures(usize, u32),
+
+    #[error("Combined signature is invalid")]
+    InvalidCombinedSignature,
+}
+
+type Result<T> = std::result::Result<T, ThresholdSignatureError>;
+
+/// A share of the private key of an account controlled by `threshold` of `num_shares` holders.
+///
+/// The shares are points `(index, f(index))` of a random polynomial `f` of degree
+/// `threshold - 1` with `f(0)` being the private key, so any `threshold` shares determine the
+/// key and fewer shares reveal nothing about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdKeyShare {
+    // pubkey of the shared account
+    pub pubkey: U256,
+    pub threshold: u32,
+    pub num_shares: u32,
+    // 1-indexed x coordinate of the share
+    pub index: u32,
+    pub share: U256,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialSignature {
+    pub index: u32,
+    pub signature: FlatG2,
+}
+
+/// Generate a new account and split its private key into shares (trusted dealer).
+pub fn generate_threshold_key<R: Rng>(
+    rng: &mut R,
+    threshold: u32,
+    num_shares: u32,
+) -> Result<Vec<ThresholdKeyShare>> {
+    let key = KeySet::rand(rng);
+    split_key(rng, key, threshold, num_shares)
+}
+
+/// Split the private key of an existing account into shares.
+///
+/// Only the signatures of the account are threshold controlled. The user data and the received
+/// transfers are encrypted to the same key, so whoever syncs the account still needs the key.
+pub fn split_key<R: Rng>(
+    rng: &mut R,
+    key: KeySet,
+    threshold: u32,
+    num_shares: u32,
+) -> Result<Vec<ThresholdKeyShare>> {
+    if threshold == 0 || threshold > num_shares {
+        return Err(ThresholdSignatureError::InvalidThreshold {
+            threshold,
+            num_shares,
+        });
+    }
+    let mut coefficients = vec![key.privkey];
+    for _ in 1..threshold {
+        coefficients.push(Fr::rand(rng));
+    }
+    let shares = (1..=num_shares)
+        .map(|index| {
+            let x = Fr::from(index as u64);
+            // Horner's method
+            let share = coefficients
+                .iter()
+                .rev()
+                .fold(Fr::zero(), |acc, c| acc * x + c);
+            ThresholdKeyShare {
+                pubkey: key.pubkey,
+                threshold,
+                num_shares,
+                index,
+                share: fr_to_u256(share),
+            }
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// Partial signature of the hash, to be combined into a signature compatible with
+/// `sign_message`.
+pub fn partial_sign_message(share: &ThresholdKeyShare, hash: Bytes32) -> PartialSignature {
+    partial_sign_point(share, hash_to_message_point(hash), Fr::from(1u64))
+}
+
+/// Partial signature of the block proposal, to be combined into the same signature as
+/// `BlockProposal::sign` with the full key.
+pub fn partial_sign_proposal(
+    share: &ThresholdKeyShare,
+    proposal: &BlockProposal,
+) -> PartialSignature {
+    let (message_point, weight) = proposal_message(share.pubkey, proposal);
+    partial_sign_point(share, message_point, weight)
+}
+
+pub fn combine_message_signature(
+    pubkey: U256,
+    threshold: u32,
+    hash: Bytes32,
+    partial_signatures: &[PartialSignature],
+) -> Result<FlatG2> {
+    let signature = combine_partial_signatures(threshold, partial_signatures)?;
+    super::signature::verify_signature(signature.clone(), pubkey, hash)
+        .map_err(|_| ThresholdSignatureError::InvalidCombinedSignature)?;
+    Ok(signature)
+}
+
+/// Combine partial signatures of the sign content into an `Auth` for requests of the account.
+pub fn combine_auth(
+    threshold: u32,
+    sign_content: &SignContent,
+    partial_signatures: &[PartialSignature],
+) -> Result<Auth> {
+    let signature = combine_message_signature(
+        sign_content.pubkey,
+        threshold,
+        sign_content.hash(),
+        partial_signatures,
+    )?;
+    Ok(Auth {
+        pubkey: sign_content.pubkey,
+        expiry: sign_content.expiry,
+        nonce: sign_content.nonce,
+        signature,
+    })
+}
+
+pub fn combine_proposal_signature(
+    pubkey: U256,
+    threshold: u32,
+    proposal: &BlockProposal,
+    partial_signatures: &[PartialSignature],
+) -> Result<UserSignature> {
+    let signature = combine_partial_signatures(threshold, partial_signatures)?;
+    let (message_point, weight) = proposal_message(pubkey, proposal);
+    let pubkey_x: Fq = pubkey.into();
+    let pubkey_g1 = G1Affine::recover_from_x(pubkey_x);
+    let weighted_pubkey: G1Affine = (pubkey_g1 * weight).into();
+    if !check_pairing(
+        vec![-G1Affine::generator(), weighted_pubkey],
+        vec![G2Affine::from(signature.clone()), message_point],
+    ) {
+        return Err(ThresholdSignatureError::InvalidCombinedSignature);
+    }
+    Ok(UserSignature { pubkey, signature })
+}
+
+/// Interpolate the signature at 0 from the partial signatures of the first `threshold` distinct
+/// shares.
+pub fn combine_partial_signatures(
+    threshold: u32,
+    partial_signatures: &[PartialSignature],
+) -> Result<FlatG2> {
+    let mut selected: Vec<&PartialSignature> = Vec::new();
+    for partial in partial_signatures {
+        if selected.len() == threshold as usize {
+            break;
+        }
+        if partial.index != 0 && !selected.iter().any(|p| p.index == partial.index) {
+            selected.push(partial);
+        }
+    }
+    if selected.len() < threshold as usize || threshold == 0 {
+        return Err(ThresholdSignatureError::NotEnoughPartialSignatures(
+            selected.len(),
+            threshold,
+        ));
+    }
+    let xs = selected
+        .iter()
+        .map(|p| Fr::from(p.index as u64))
+        .collect::<Vec<_>>();
+    let signature = selected
+        .iter()
+        .zip(xs.iter())
+        .fold(G2Affine::zero(), |acc, (partial, x_i)| {
+            // Lagrange coefficient at 0
+            let lambda = xs
+                .iter()
+                .filter(|x_j| *x_j != x_i)
+                .fold(Fr::from(1u64), |acc, x_j| {
+                    acc * x_j * (*x_j - x_i).inverse().unwrap()
+                });
+            let point: G2Affine = partial.signature.clone().into();
+            let weighted: G2Affine = (point * lambda).into();
+            (acc + weighted).into()
+        });
+    Ok(FlatG2::from(signature))
+}
+
+fn partial_sign_point(
+    share: &ThresholdKeyShare,
+    message_point: G2Affine,
+    weight: Fr,
+) -> PartialSignature {
+    let signature: G2Affine = (message_point * (u256_to_fr(share.share) * weight)).into();
+    PartialSignature {
+        index: share.index,
+        signature: FlatG2::from(signature),
+    }
+}
+
+fn proposal_message(pubkey: U256, proposal: &BlockProposal) -> (G2Affine, Fr) {
+    let weight = hash_to_weight(pubkey, proposal.pubkeys_hash);
+    let message_point =
+        tx_tree_root_and_expiry_to_message_point(proposal.tx_tree_root, proposal.expiry.into());
+    (message_point, u256_to_fr(weight))
+}
+
+fn fr_to_u256(x: Fr) -> U256 {
+    U256::from_bytes_be(&x.into_bigint().to_bytes_be())
+}
+
+fn u256_to_fr(x: U256) -> Fr {
+    Fr::from_be_bytes_mod_order(&x.to_bytes_be())
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::G2Affine;
+    use intmax2_zkp::{
+        common::{
+            block_builder::BlockProposal,
+            signature::{key_set::KeySet, utils::get_pubkey_hash},
+            trees::tx_tree::TxTree,
+            tx::Tx,
+        },
+        constants::{NUM_SENDERS_IN_BLOCK, TX_TREE_HEIGHT},
+        ethereum_types::{bytes32::Bytes32, u256::U256, u32limb_trait::U32LimbTrait as _},
+    };
+
+    use crate::utils::signature::verify_signature;
+
+    use super::{
+        combine_message_signature, combine_partial_signatures, combine_proposal_signature,
+        generate_threshold_key, partial_sign_message, partial_sign_proposal, split_key,
+        ThresholdSignatureError,
+    };
+
+    #[test]
+    fn test_threshold_sign() {
+        let mut rng = rand::thread_rng();
+        let shares = generate_threshold_key(&mut rng, 2, 3).unwrap();
+        let pubkey = shares[0].pubkey;
+        let hash = Bytes32::rand(&mut rng);
+        let partials = shares
+            .iter()
+            .map(|share| partial_sign_message(share, hash))
+            .collect::<Vec<_>>();
+
+        // any 2 of 3 shares produce the same valid signature
+        let signature =
+            combine_message_signature(pubkey, 2, hash, &[partials[0].clone(), partials[1].clone()])
+                .unwrap();
+        let other =
+            combine_message_signature(pubkey, 2, hash, &[partials[2].clone(), partials[0].clone()])
+                .unwrap();
+        assert_eq!(G2Affine::from(signature.clone()), G2Affine::from(other));
+        assert!(verify_signature(signature, pubkey, hash).is_ok());
+
+        let result = combine_partial_signatures(2, &[partials[1].clone(), partials[1].clone()]);
+        assert!(matches!(
+            result,
+            Err(ThresholdSignatureError::NotEnoughPartialSignatures(1, 2))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_sign_proposal() {
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let shares = split_key(&mut rng, key, 2, 3).unwrap();
+
+        // proposal built the same way as the block builder does
+        let tx = Tx::rand(&mut rng);
+        let other = KeySet::rand(&mut rng).pubkey;
+        let mut txs = vec![(key.pubkey, tx), (other, Tx::rand(&mut rng))];
+        txs.sort_by(|a, b| b.0.cmp(&a.0));
+        txs.resize(NUM_SENDERS_IN_BLOCK, (U256::dummy_pubkey(), Tx::default()));
+        let pubkeys = txs.iter().map(|(pubkey, _)| *pubkey).collect::<Vec<_>>();
+        let mut tx_tree = TxTree::new(TX_TREE_HEIGHT);
+        for (_, tx) in txs.iter() {
+            tx_tree.push(*tx);
+        }
+        let tx_index = txs.iter().position(|(p, _)| *p == key.pubkey).unwrap() as u32;
+        let proposal = BlockProposal {
+            tx_tree_root: tx_tree.get_root().into(),
+            expiry: 0,
+            tx_index,
+            tx_merkle_proof: tx_tree.prove(tx_index as u64),
+            pubkeys_hash: get_pubkey_hash(&pubkeys),
+            pubkeys,
+        };
+        assert!(proposal.verify(tx).is_ok());
+
+        let partials = shares
+            .iter()
+            .map(|share| partial_sign_proposal(share, &proposal))
+            .collect::<Vec<_>>();
+        let signature = combine_proposal_signature(
+            key.pubkey,
+            2,
+            &proposal,
+            &[partials[2].clone(), partials[1].clone()],
+        )
+        .unwrap();
+
+        // same signature as the full key, accepted by the check of `post_signature`
+        let expected = proposal.sign(key);
+        assert_eq!(signature.pubkey, expected.pubkey);
+        assert_eq!(
+            G2Affine::from(signature.signature.clone()),
+            G2Affine::from(expected.signature)
+        );
+        assert!(signature
+            .verify(
+                proposal.tx_tree_root,
+                proposal.expiry,
+                proposal.pubkeys_hash
+            )
+            .is_ok());
+
+        // a single share is not enough
+        assert!(matches!(
+            combine_proposal_signature(key.pubkey, 2, &proposal, &partials[..1]),
+            Err(ThresholdSignatureError::NotEnoughPartialSignatures(1, 2))
+        ));
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use actix_web::{http::header::AUTHORIZATION, HttpRequest};
+use hmac::{Hmac, Mac};
+use intmax2_interfaces::{
+    api::{
+        error::ErrorCode,
+        session::{CreateSessionRequest, CreateSessionResponse, SessionAudience},
+    },
+    utils::signature::{current_time, Auth, Signable, WithAuth},
+};
+use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+use sha2::{Digest as _, Sha256};
+
+use crate::error::ApiError;
+
+/// Signed requests must expire within this many seconds, which bounds how long their nonces are
+/// remembered.
+pub const MAX_AUTH_TTL: u64 = 300; // 5 minutes
+const DEFAULT_SESSION_TTL: u64 = 900; // 15 minutes
+const DEFAULT_MAX_NONCES: usize = 1_000_000;
+const DEFAULT_MAX_NONCES_PER_PUBKEY: usize = 10_000;
+
+/// Nonces of the signed requests seen until their expiry, to reject replays of a captured request.
+/// The nonces are kept in memory, so a request replayed to another instance of the server is not
+/// detected. The legacy auths without a nonce are tracked separately by their signatures, which
+/// are unique to the signed content and expiry.
+///
+/// A pubkey can't hold more than `max_nonces_per_pubkey` nonces at once. When the cache is full,
+/// the nonce closest to its expiry is evicted instead of rejecting the request, so a flood of
+/// requests signed by many keys doesn't lock out the other users, at the cost of a replay of the
+/// evicted request being accepted until it expires.
+#[derive(Debug)]
+pub struct ReplayCache {
+    max_nonces: usize,
+    max_nonces_per_pubkey: usize,
+    nonces: Mutex<Nonces>,
+    legacy_nonces: Mutex<Nonces>,
+}
+
+#[derive(Debug, Default)]
+struct Nonces {
+    // (expiry, pubkey, nonce), in the order of eviction
+    by_expiry: BTreeSet<(u64, String, u64)>,
+    // (pubkey, nonce) -> expiry
+    expiries: HashMap<(String, u64), u64>,
+    counts: HashMap<String, usize>,
+}
+
+impl Nonces {
+    fn remove_first(&mut self) {
+        let Some((_, pubkey, nonce)) = self.by_expiry.pop_first() else {
+            return;
+        };
+        self.expiries.remove(&(pubkey.clone(), nonce));
+        if let Some(count) = self.counts.get_mut(&pubkey) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&pubkey);
+            }
+        }
+    }
+}
+
+impl ReplayCache {
+    pub fn new(max_nonces: usize, max_nonces_per_pubkey: usize) -> Self {
+        Self {
+            max_nonces,
+            max_nonces_per_pubkey,
+            nonces: Mutex::new(Nonces::default()),
+            legacy_nonces: Mutex::new(Nonces::default()),
+        }
+    }
+
+    /// Records the nonce of the auth, failing if it has been seen before.
+    pub fn check(&self, auth: &Auth, now: u64) -> Result<(), ApiError> {
+        if auth.expiry > now + MAX_AUTH_TTL {
+            return Err(ApiError::unauthorized(format!(
+                "Auth must expire within {} seconds",
+                MAX_AUTH_TTL
+            )));
+        }
+        let (mut nonces, nonce) = match auth.nonce {
+            Some(nonce) => (self.nonces.lock().unwrap(), nonce),
+            None => (self.legacy_nonces.lock().unwrap(), signature_nonce(auth)),
+        };
+        while nonces
+            .by_expiry
+            .first()
+            .is_some_and(|(expiry, _, _)| *expiry < now)
+        {
+            nonces.remove_first();
+        }
+        let pubkey = auth.pubkey.to_hex();
+        let key = (pubkey.clone(), nonce);
+        if nonces.expiries.contains_key(&key) {
+            return Err(ApiError::new(
+                ErrorCode::ReplayedRequest,
+                "Request has already been received",
+            ));
+        }
+        if nonces.counts.get(&pubkey).copied().unwrap_or(0) >= self.max_nonces_per_pubkey {
+            return Err(ApiError::new(
+                ErrorCode::TooManyRequests,
+                "Too many signed requests by the pubkey",
+            ));
+        }
+        if nonces.expiries.len() >= self.max_nonces {
+            nonces.remove_first();
+        }
+        nonces
+            .by_expiry
+            .insert((auth.expiry, pubkey.clone(), nonce));
+        nonces.expiries.insert(key, auth.expiry);
+        *nonces.counts.entry(pubkey).or_default() += 1;
+        Ok(())
+    }
+}
+
+/// Stands in for the nonce of a legacy auth, derived from its signature
+fn signature_nonce(auth: &Auth) -> u64 {
+    let digest = Sha256::digest(serde_json::to_vec(&auth.signature).unwrap());
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+/// Issuer of session tokens, which are HMACs of the pubkey, the expiry and the audience. The
+/// tokens are stateless, so every instance sharing the secret accepts them.
+pub struct SessionIssuer {
+    audience: SessionAudience,
+    secret: Vec<u8>,
+    ttl: u64,
+}
+
+impl SessionIssuer {
+    pub fn new(audience: SessionAudience, secret: &[u8], ttl: u64) -> Self {
+        Self {
+            audience,
+            secret: secret.to_vec(),
+            ttl,
+        }
+    }
+
+    fn mac(&self, pubkey: &str, expires_at: u64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).unwrap();
+        mac.update(format!("{}:{}:{}", self.audience.as_str(), pubkey, expires_at).as_bytes());
+        mac
+    }
+
+    pub fn issue(&self, pubkey: U256, now: u64) -> CreateSessionResponse {
+        let pubkey = pubkey.to_hex();
+        let expires_at = now + self.ttl;
+        let tag = self.mac(&pubkey, expires_at).finalize().into_bytes();
+        CreateSessionResponse {
+            token: format!("{}.{}.{}", pubkey, expires_at, hex::encode(tag)),
+            expires_at,
+        }
+    }
+
+    /// Returns the pubkey of the token.
+    pub fn verify(&self, token: &str, now: u64) -> Result<U256, ApiError> {
+        let invalid = || ApiError::unauthorized("Invalid session token");
+        let mut parts = token.split('.');
+        let (Some(pubkey), Some(expires_at), Some(tag), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let expires_at = expires_at.parse::<u64>().map_err(|_| invalid())?;
+        let tag = hex::decode(tag).map_err(|_| invalid())?;
+        self.mac(pubkey, expires_at)
+            .verify_slice(&tag)
+            .map_err(|_| invalid())?;
+        if expires_at < now {
+            return Err(ApiError::unauthorized("Session token expired"));
+        }
+        U256::from_hex(pubkey).map_err(|_| invalid())
+    }
+}
+
+/// Authentication of the requests to a server, by a signature with a fresh nonce or by a session
+/// token.
+pub struct Authenticator {
+    replay_cache: ReplayCache,
+    sessions: SessionIssuer,
+    accept_legacy_auth: bool,
+}
+
+impl Authenticator {
+    /// `session_secret` should be shared by all instances of the server. If None, a random one is
+    /// used, so the sessions are only accepted by this instance until it restarts.
+    /// `accept_legacy_auth` accepts the auths without a nonce of the clients released before it,
+    /// during the rollout of the clients.
+    pub fn new(
+        audience: SessionAudience,
+        session_secret: Option<&str>,
+        session_ttl: Option<u64>,
+        accept_legacy_auth: bool,
+    ) -> Self {
+        let secret = match session_secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                log::warn!("SESSION_SECRET is not set, using a random secret");
+                rand::random::<[u8; 32]>().to_vec()
+            }
+        };
+        Self {
+            replay_cache: ReplayCache::new(DEFAULT_MAX_NONCES, DEFAULT_MAX_NONCES_PER_PUBKEY),
+            sessions: SessionIssuer::new(
+                audience,
+                &secret,
+                session_ttl.unwrap_or(DEFAULT_SESSION_TTL),
+            ),
+            accept_legacy_auth,
+        }
+    }
+
+    /// Verifies the signature of the request and its nonce, and returns the pubkey.
+    pub fn verify_signed<T: Signable>(&self, inner: &T, auth: &Auth) -> Result<U256, ApiError> {
+        if auth.nonce.is_none() && !self.accept_legacy_auth {
+            return Err(ApiError::unauthorized(
+                "Auth without a nonce is not accepted, update the client",
+            ));
+        }
+        inner.verify(auth).map_err(ApiError::unauthorized)?;
+        self.replay_cache.check(auth, current_time())?;
+        Ok(auth.pubkey)
+    }
+
+    /// Authenticates the request by `auth` if given, or by the session token in the
+    /// `Authorization` header otherwise, and returns the pubkey.
+    pub fn authenticate<T: Signable>(
+        &self,
+        req: &HttpRequest,
+        inner: &T,
+        auth: Option<&Auth>,
+    ) -> Result<U256, ApiError> {
+        match auth {
+            Some(auth) => self.verify_signed(inner, auth),
+            None => self.verify_session(req),
+        }
+    }
+
+    /// Verifies the session token in the `Authorization` header, and returns the pubkey.
+    pub fn verify_session(&self, req: &HttpRequest) -> Result<U256, ApiError> {
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::unauthorized("Session token is not given"))?;
+        self.sessions.verify(token, current_time())
+    }
+
+    pub fn create_session(
+        &self,
+        request: &WithAuth<CreateSessionRequest>,
+    ) -> Result<CreateSessionResponse, ApiError> {
+        if request.inner.audience != self.sessions.audience {
+            return Err(ApiError::bad_request(format!(
+                "Session for {} cannot be created by {}",
+                request.inner.audience.as_str(),
+                self.sessions.audience.as_str()
+            )));
+        }
+        let pubkey = self.verify_signed(&request.inner, &request.auth)?;
+        Ok(self.sessions.issue(pubkey, current_time()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::{
+        api::{
+            error::ErrorCode,
+            session::{CreateSessionRequest, SessionAudience},
+        },
+        utils::signature::{current_time, sign_message, Auth, SignContent, Signable as _},
+    };
+    use intmax2_zkp::common::signature::key_set::KeySet;
+
+    use super::{Authenticator, ReplayCache, SessionIssuer, MAX_AUTH_TTL};
+
+    // signed as by the clients released before the nonce
+    fn legacy_auth(key: KeySet, content: &[u8]) -> Auth {
+        let sign_content = SignContent {
+            nonce: None,
+            ..SignContent::new(key.pubkey, 10, content)
+        };
+        Auth {
+            pubkey: key.pubkey,
+            expiry: sign_content.expiry,
+            nonce: None,
+            signature: sign_message(key.privkey, sign_content.hash()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_replay_cache() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let now = current_time();
+        let cache = ReplayCache::new(2, 2);
+        let auth = Auth::sign(key, 10, b"content");
+        cache.check(&auth, now).unwrap();
+        let err = cache.check(&auth, now).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ReplayedRequest);
+
+        // a pubkey can't hold more nonces than the limit until they expire
+        cache.check(&Auth::sign(key, 10, b"content"), now).unwrap();
+        let err = cache
+            .check(&Auth::sign(key, 10, b"content"), now)
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::TooManyRequests);
+        cache
+            .check(&Auth::sign(key, 10, b"content"), now + 20)
+            .unwrap();
+
+        let long_lived = Auth::sign(key, MAX_AUTH_TTL + 10, b"content");
+        assert!(cache.check(&long_lived, now).is_err());
+    }
+
+    #[test]
+    fn test_replay_cache_full() {
+        let now = current_time();
+        let cache = ReplayCache::new(2, 2);
+        let victim = KeySet::rand(&mut rand::thread_rng());
+        let victim_auth = Auth::sign(victim, 60, b"content");
+        cache.check(&victim_auth, now).unwrap();
+
+        // requests of other pubkeys are accepted while the cache is full, evicting the nonce
+        // closest to its expiry
+        let attacker = KeySet::rand(&mut rand::thread_rng());
+        let first = Auth::sign(attacker, 10, b"content");
+        cache.check(&first, now).unwrap();
+        let other = KeySet::rand(&mut rand::thread_rng());
+        cache
+            .check(&Auth::sign(other, 30, b"content"), now)
+            .unwrap();
+        cache
+            .check(&Auth::sign(victim, 60, b"content"), now)
+            .unwrap();
+        let err = cache.check(&victim_auth, now).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ReplayedRequest);
+        // the evicted nonce is forgotten
+        cache.check(&first, now).unwrap();
+    }
+
+    #[test]
+    fn test_legacy_auth() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let request = CreateSessionRequest {
+            audience: SessionAudience::StoreVaultServer,
+        };
+        let auth = legacy_auth(key, &request.content());
+
+        let strict = Authenticator::new(SessionAudience::StoreVaultServer, Some("s"), None, false);
+        assert!(strict.verify_signed(&request, &auth).is_err());
+
+        // accepted during the rollout, and its replays are rejected by the signature
+        let lenient = Authenticator::new(SessionAudience::StoreVaultServer, Some("s"), None, true);
+        assert_eq!(lenient.verify_signed(&request, &auth).unwrap(), key.pubkey);
+        let err = lenient.verify_signed(&request, &auth).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ReplayedRequest);
+        // tracked apart from the nonces of the current clients
+        lenient
+            .verify_signed(&request, &request.clone().sign(key, 10).auth)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_session_token() {
+        let key = KeySet::rand(&mut rand::thread_rng());
+        let issuer = SessionIssuer::new(SessionAudience::StoreVaultServer, b"secret", 60);
+        let session = issuer.issue(key.pubkey, 100);
+        assert_eq!(session.expires_at, 160);
+        assert_eq!(issuer.verify(&session.token, 150).unwrap(), key.pubkey);
+        assert!(issuer.verify(&session.token, 161).is_err());
+
+        // tokens of other audiences or secrets are rejected
+        let other = SessionIssuer::new(SessionAudience::WithdrawalServer, b"secret", 60);
+        assert!(other.verify(&session.token, 150).is_err());
+        let other = SessionIssuer::new(SessionAudience::StoreVaultServer, b"other", 60);
+        assert!(other.verify(&session.token, 150).is_err());
+        let parts = session.token.split('.').collect::<Vec<_>>();
+        let tampered = format!("{}.{}.{}", parts[0], 999, parts[2]);
+        assert!(issuer.verify(&tampered, 150).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{future::Future, pin::Pin};
+
+use actix_web::{
+    body::BoxBody,
+    dev::Payload,
+    http::header::{ACCEPT, CONTENT_TYPE, VARY},
+    web::BytesMut,
+    FromRequest, HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use futures_util::StreamExt as _;
+use intmax2_interfaces::api::{encoding::WireFormat, error::ErrorCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::ApiError;
+
+/// Default limit of the request body size
+pub const DEFAULT_BODY_LIMIT: usize = 35_000_000;
+
+/// Limit of the request body size of `Encoded`, set as app data.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodedConfig {
+    pub limit: usize,
+}
+
+impl Default for EncodedConfig {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_BODY_LIMIT,
+        }
+    }
+}
+
+/// Replacement of `Json` that also supports bincode.
+///
+/// As an extractor, the body is decoded according to the Content-Type. As a responder, the body is
+/// encoded in the format preferred by the Accept header of the request, which is JSON unless
+/// bincode is requested explicitly.
+#[derive(Debug)]
+pub struct Encoded<T>(pub T);
+
+impl<T> Encoded<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Encoded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Encoded<T> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let format = WireFormat::from_content_type(
+            req.headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok()),
+        );
+        let limit = req
+            .app_data::<EncodedConfig>()
+            .copied()
+            .unwrap_or_default()
+            .limit;
+        let mut payload = payload.take();
+        Box::pin(async move {
+            let mut body = BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk.map_err(ApiError::bad_request)?;
+                if body.len() + chunk.len() > limit {
+                    return Err(ApiError::new(
+                        ErrorCode::PayloadTooLarge,
+                        format!("request body exceeds {} bytes", limit),
+                    )
+                    .into());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            let value = format.decode(&body).map_err(ApiError::bad_request)?;
+            Ok(Encoded(value))
+        })
+    }
+}
+
+impl<T: Serialize> Responder for Encoded<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let format =
+            WireFormat::from_accept(req.headers().get(ACCEPT).and_then(|v| v.to_str().ok()));
+        match format.encode(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(format.content_type())
+                .insert_header((VARY, "Accept"))
+                .body(body),
+            Err(e) => ApiError::internal(e).error_response(),
+        }
+    }
+}
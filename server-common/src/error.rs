@@ -0,0 +1,97 @@
+use actix_web::{
+    error::{JsonPayloadError, PayloadError},
+    http::StatusCode,
+    web::JsonConfig,
+    HttpResponse, ResponseError,
+};
+use intmax2_interfaces::api::error::{ErrorCode, ErrorResponse};
+use serde_qs::actix::QsQueryConfig;
+
+/// Error of the handlers of all servers, which is returned as an `ErrorResponse`
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn bad_request(message: impl ToString) -> Self {
+        Self::new(ErrorCode::BadRequest, message)
+    }
+
+    pub fn unauthorized(message: impl ToString) -> Self {
+        Self::new(ErrorCode::Unauthorized, message)
+    }
+
+    pub fn not_found(message: impl ToString) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn internal(message: impl ToString) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn unavailable(message: impl ToString) -> Self {
+        Self::new(ErrorCode::Unavailable, message)
+    }
+
+    pub fn to_response(&self) -> ErrorResponse {
+        ErrorResponse {
+            details: self.details.clone(),
+            ..ErrorResponse::new(self.code, self.message.clone())
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.status_code().is_server_error() {
+            log::error!("{}", self);
+        }
+        HttpResponse::build(self.status_code()).json(self.to_response())
+    }
+}
+
+/// `JsonConfig` whose errors are returned as an `ErrorResponse`
+pub fn json_config() -> JsonConfig {
+    JsonConfig::default().error_handler(|e, _| {
+        let error = match &e {
+            JsonPayloadError::Overflow { .. }
+            | JsonPayloadError::OverflowKnownLength { .. }
+            | JsonPayloadError::Payload(PayloadError::Overflow) => {
+                ApiError::new(ErrorCode::PayloadTooLarge, &e)
+            }
+            _ => ApiError::bad_request(&e),
+        };
+        error.into()
+    })
+}
+
+/// `QsQueryConfig` whose errors are returned as an `ErrorResponse`
+pub fn qs_query_config() -> QsQueryConfig {
+    QsQueryConfig::default().error_handler(|e, _| ApiError::bad_request(e).into())
+}
+
+/// Default service of the servers, returning an `ErrorResponse` for unknown routes
+pub async fn not_found() -> HttpResponse {
+    ApiError::not_found("No route found").error_response()
+}
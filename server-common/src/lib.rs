@@ -1,4 +1,8 @@
 #![feature(once_cell_try)]
 
+pub mod auth;
+pub mod encoding;
+pub mod error;
 pub mod health_check;
 pub mod logger;
+pub mod openapi;
@@ -0,0 +1,159 @@
+use actix_web::{web, HttpResponse, Resource};
+use utoipa::openapi::OpenApi;
+
+/// Well-known path at which every server publishes its OpenAPI document
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Defines the scope of a server together with a function returning its OpenAPI document,
+/// from the same list of handlers. A handler without `#[utoipa::path]` fails to compile, so a
+/// route can not be added without being documented.
+///
+/// The paths in `#[utoipa::path]` are relative to the scope, like the ones of the actix macros.
+#[macro_export]
+macro_rules! api_scope {
+    ($scope:ident, $spec:ident, $prefix:literal, [$($handler:ident),* $(,)?]) => {
+        pub fn $scope() -> actix_web::Scope {
+            actix_web::web::scope($prefix)$(.service($handler))*
+        }
+
+        pub fn $spec() -> utoipa::openapi::OpenApi {
+            #[derive(utoipa::OpenApi)]
+            #[openapi(paths($($handler),*))]
+            struct ApiDoc;
+
+            $crate::openapi::with_prefix(<ApiDoc as utoipa::OpenApi>::openapi(), $prefix)
+        }
+    };
+}
+
+/// Prefixes all the paths of `spec` with the path of the scope they are served under
+pub fn with_prefix(mut spec: OpenApi, prefix: &str) -> OpenApi {
+    let paths = std::mem::take(&mut spec.paths.paths);
+    spec.paths.paths = paths
+        .into_iter()
+        .map(|(path, item)| (format!("{}{}", prefix, path), item))
+        .collect();
+    spec
+}
+
+/// Serves `spec` at `OPENAPI_PATH`
+pub fn openapi_service(spec: OpenApi) -> Resource {
+    let json = spec
+        .to_pretty_json()
+        .expect("failed to serialize openapi spec");
+    web::resource(OPENAPI_PATH).route(web::get().to(move || {
+        let json = json.clone();
+        async move {
+            HttpResponse::Ok()
+                .content_type("application/json")
+                .body(json)
+        }
+    }))
+}
+
+/// Returns the `(method, path)` of every operation of `spec`, sorted
+pub fn operations(spec: &OpenApi) -> Vec<(String, String)> {
+    let mut operations = Vec::new();
+    for (path, item) in spec.paths.paths.iter() {
+        if item.get.is_some() {
+            operations.push(("GET".to_string(), path.clone()));
+        }
+        if item.post.is_some() {
+            operations.push(("POST".to_string(), path.clone()));
+        }
+        if item.put.is_some() {
+            operations.push(("PUT".to_string(), path.clone()));
+        }
+        if item.delete.is_some() {
+            operations.push(("DELETE".to_string(), path.clone()));
+        }
+    }
+    operations.sort();
+    operations
+}
+
+/// Returns the names of the schemas referenced in `spec` that are not defined in its components
+pub fn unresolved_refs(spec: &OpenApi) -> Vec<String> {
+    fn collect(value: &serde_json::Value, refs: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", serde_json::Value::String(r)) => refs.push(r.clone()),
+                        _ => collect(value, refs),
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|v| collect(v, refs)),
+            _ => {}
+        }
+    }
+    let value = serde_json::to_value(spec).expect("failed to serialize openapi spec");
+    let mut refs = Vec::new();
+    collect(&value, &mut refs);
+    let schemas = spec
+        .components
+        .as_ref()
+        .map(|c| c.schemas.clone())
+        .unwrap_or_default();
+    let mut unresolved: Vec<String> = refs
+        .into_iter()
+        .filter_map(|r| {
+            let name = r.strip_prefix("#/components/schemas/").unwrap_or(&r);
+            (!schemas.contains_key(name)).then(|| name.to_string())
+        })
+        .collect();
+    unresolved.sort();
+    unresolved.dedup();
+    unresolved
+}
+
+/// Checks that the `spec` of a server matches its routes:
+/// - the operations of `spec` are exactly `expected`, which is kept next to the server's tests,
+///   so that adding, removing or renaming a route requires updating the test
+/// - every operation is routed by `app` (neither 404 nor 405), so that the path in
+///   `#[utoipa::path]` can not drift from the one of the actix macro
+/// - every schema referenced by the operations is defined
+///
+/// `app` should be built without app data, so that the handlers fail before doing anything.
+#[cfg(any(test, feature = "test-utils"))]
+pub async fn assert_spec_matches_routes<S, B>(app: &S, spec: &OpenApi, expected: &[(&str, &str)])
+where
+    S: actix_web::dev::Service<
+        actix_http::Request,
+        Response = actix_web::dev::ServiceResponse<B>,
+        Error = actix_web::Error,
+    >,
+{
+    let mut expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|(method, path)| (method.to_string(), path.to_string()))
+        .collect();
+    expected.sort();
+    let operations = operations(spec);
+    assert_eq!(operations, expected, "openapi operations do not match");
+
+    for (method, path) in operations {
+        let method = actix_web::http::Method::from_bytes(method.as_bytes()).unwrap();
+        let req = actix_web::test::TestRequest::default()
+            .method(method.clone())
+            .uri(&path)
+            .to_request();
+        let status = actix_web::test::call_service(app, req).await.status();
+        assert!(
+            status != actix_web::http::StatusCode::NOT_FOUND
+                && status != actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+            "{} {} in the openapi spec is not routed: {}",
+            method,
+            path,
+            status
+        );
+    }
+
+    let unresolved = unresolved_refs(spec);
+    assert!(
+        unresolved.is_empty(),
+        "openapi spec references undefined schemas: {:?}",
+        unresolved
+    );
+}
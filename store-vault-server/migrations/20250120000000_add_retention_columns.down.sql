@@ -0,0 +1,10 @@
+DROP INDEX IF EXISTS idx_encrypted_data_type_acked_at;
+DROP INDEX IF EXISTS idx_encrypted_data_type_timestamp;
+DROP INDEX IF EXISTS idx_encrypted_sender_proof_set_acked_at;
+DROP INDEX IF EXISTS idx_encrypted_sender_proof_set_timestamp;
+
+ALTER TABLE encrypted_data DROP COLUMN IF EXISTS acked_at;
+
+ALTER TABLE encrypted_sender_proof_set
+    DROP COLUMN IF EXISTS acked_at,
+    DROP COLUMN IF EXISTS timestamp;
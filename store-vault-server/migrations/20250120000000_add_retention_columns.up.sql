@@ -0,0 +1,10 @@
+ALTER TABLE encrypted_sender_proof_set
+    ADD COLUMN timestamp BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    ADD COLUMN acked_at BIGINT;
+
+ALTER TABLE encrypted_data ADD COLUMN acked_at BIGINT;
+
+CREATE INDEX idx_encrypted_sender_proof_set_timestamp ON encrypted_sender_proof_set(timestamp);
+CREATE INDEX idx_encrypted_sender_proof_set_acked_at ON encrypted_sender_proof_set(acked_at);
+CREATE INDEX idx_encrypted_data_type_timestamp ON encrypted_data(data_type, timestamp);
+CREATE INDEX idx_encrypted_data_type_acked_at ON encrypted_data(data_type, acked_at);
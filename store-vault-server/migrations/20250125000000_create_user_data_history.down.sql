@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS encrypted_user_data_history;
+
+ALTER TABLE encrypted_user_data DROP COLUMN IF EXISTS version;
@@ -0,0 +1,10 @@
+ALTER TABLE encrypted_user_data ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+
+CREATE TABLE IF NOT EXISTS encrypted_user_data_history (
+    pubkey VARCHAR(66) NOT NULL,
+    version BIGINT NOT NULL,
+    encrypted_data BYTEA NOT NULL,
+    digest BYTEA NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (pubkey, version)
+);
@@ -0,0 +1,23 @@
+-- Only the blobs of the postgres blob storage are restored. The blobs in a filesystem or S3
+-- blob storage have to be copied into the blobs table before reverting.
+UPDATE encrypted_user_data t SET encrypted_data = b.data
+FROM blobs b WHERE t.encrypted_data IS NULL AND t.digest = b.digest;
+UPDATE encrypted_user_data_history t SET encrypted_data = b.data
+FROM blobs b WHERE t.encrypted_data IS NULL AND t.digest = b.digest;
+UPDATE encrypted_sender_proof_set t SET encrypted_data = b.data
+FROM blobs b WHERE t.encrypted_data IS NULL AND t.digest = b.digest;
+
+DROP INDEX IF EXISTS idx_encrypted_sender_proof_set_digest;
+ALTER TABLE encrypted_sender_proof_set DROP COLUMN IF EXISTS size;
+ALTER TABLE encrypted_sender_proof_set DROP COLUMN IF EXISTS digest;
+ALTER TABLE encrypted_sender_proof_set ALTER COLUMN encrypted_data SET NOT NULL;
+
+DROP INDEX IF EXISTS idx_encrypted_user_data_history_digest;
+ALTER TABLE encrypted_user_data_history DROP COLUMN IF EXISTS size;
+ALTER TABLE encrypted_user_data_history ALTER COLUMN encrypted_data SET NOT NULL;
+
+DROP INDEX IF EXISTS idx_encrypted_user_data_digest;
+ALTER TABLE encrypted_user_data DROP COLUMN IF EXISTS size;
+ALTER TABLE encrypted_user_data ALTER COLUMN encrypted_data SET NOT NULL;
+
+DROP TABLE IF EXISTS blobs;
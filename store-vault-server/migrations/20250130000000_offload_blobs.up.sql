@@ -0,0 +1,27 @@
+-- Blobs of the postgres blob storage, addressed by the sha256 digest of their content
+CREATE TABLE IF NOT EXISTS blobs (
+    digest BYTEA PRIMARY KEY,
+    data BYTEA NOT NULL
+);
+
+-- The blobs are moved out of these tables by the server on startup, leaving encrypted_data NULL
+ALTER TABLE encrypted_user_data ALTER COLUMN encrypted_data DROP NOT NULL;
+ALTER TABLE encrypted_user_data ADD COLUMN size BIGINT;
+UPDATE encrypted_user_data SET size = octet_length(encrypted_data);
+ALTER TABLE encrypted_user_data ALTER COLUMN size SET NOT NULL;
+CREATE INDEX idx_encrypted_user_data_digest ON encrypted_user_data(digest);
+
+ALTER TABLE encrypted_user_data_history ALTER COLUMN encrypted_data DROP NOT NULL;
+ALTER TABLE encrypted_user_data_history ADD COLUMN size BIGINT;
+UPDATE encrypted_user_data_history SET size = octet_length(encrypted_data);
+ALTER TABLE encrypted_user_data_history ALTER COLUMN size SET NOT NULL;
+CREATE INDEX idx_encrypted_user_data_history_digest ON encrypted_user_data_history(digest);
+
+ALTER TABLE encrypted_sender_proof_set ALTER COLUMN encrypted_data DROP NOT NULL;
+ALTER TABLE encrypted_sender_proof_set ADD COLUMN digest BYTEA;
+ALTER TABLE encrypted_sender_proof_set ADD COLUMN size BIGINT;
+UPDATE encrypted_sender_proof_set
+SET digest = sha256(encrypted_data), size = octet_length(encrypted_data);
+ALTER TABLE encrypted_sender_proof_set ALTER COLUMN digest SET NOT NULL;
+ALTER TABLE encrypted_sender_proof_set ALTER COLUMN size SET NOT NULL;
+CREATE INDEX idx_encrypted_sender_proof_set_digest ON encrypted_sender_proof_set(digest);
@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS encrypted_data_notify ON encrypted_data;
+DROP FUNCTION IF EXISTS notify_encrypted_data();
@@ -0,0 +1,17 @@
+-- Notifies the subscribers of the recipient when an entry is saved. The payload is small enough
+-- for the 8000 bytes limit of pg_notify.
+CREATE OR REPLACE FUNCTION notify_encrypted_data() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('encrypted_data', json_build_object(
+        'pubkey', NEW.pubkey,
+        'uuid', NEW.uuid,
+        'data_type', NEW.data_type,
+        'timestamp', NEW.timestamp
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER encrypted_data_notify
+AFTER INSERT ON encrypted_data
+FOR EACH ROW EXECUTE FUNCTION notify_encrypted_data();
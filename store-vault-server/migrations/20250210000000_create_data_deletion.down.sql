@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS deleted_accounts;
+DROP TABLE IF EXISTS data_deletion_requests;
@@ -0,0 +1,13 @@
+-- Tokens issued to confirm the deletion of the data of a user
+CREATE TABLE IF NOT EXISTS data_deletion_requests (
+    pubkey VARCHAR(66) PRIMARY KEY,
+    token TEXT NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+-- Tombstones of the users whose data has been deleted, so that the clients can tell a deleted
+-- account from a new one
+CREATE TABLE IF NOT EXISTS deleted_accounts (
+    pubkey VARCHAR(66) PRIMARY KEY,
+    deleted_at BIGINT NOT NULL
+);
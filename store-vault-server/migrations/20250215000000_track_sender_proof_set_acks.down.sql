@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS sender_proof_set_acks;
+ALTER TABLE encrypted_sender_proof_set DROP COLUMN IF EXISTS num_transfers;
@@ -0,0 +1,13 @@
+-- Number of transfers sharing the sender proof set. The set is acknowledged once all of them
+-- are, and never if unknown.
+ALTER TABLE encrypted_sender_proof_set ADD COLUMN num_transfers INTEGER;
+
+-- The first acknowledgement of any recipient no longer acknowledges the whole set
+UPDATE encrypted_sender_proof_set SET acked_at = NULL;
+
+CREATE TABLE IF NOT EXISTS sender_proof_set_acks (
+    pubkey VARCHAR(66) NOT NULL REFERENCES encrypted_sender_proof_set(pubkey) ON DELETE CASCADE,
+    transfer_index INTEGER NOT NULL,
+    acked_at BIGINT NOT NULL,
+    PRIMARY KEY (pubkey, transfer_index)
+);
@@ -1,154 +1,562 @@
-use crate::api::state::State;
+use crate::{api::state::State, app::notifier::event_stream};
 use actix_web::{
-    error::ErrorUnauthorized,
+    get,
+    http::header::{CACHE_CONTROL, CONTENT_TYPE},
     post,
     web::{Data, Json},
-    Error,
+    Error, HttpRequest, HttpResponse,
 };
 use intmax2_interfaces::{
-    api::store_vault_server::types::{
-        GetDataAllAfterRequest, GetDataAllAfterResponse, GetSenderProofSetRequest,
-        GetSenderProofSetResponse, GetUserDataRequest, GetUserDataResponse, SaveDataBatchRequest,
-        SaveDataBatchResponse, SaveSenderProofSetRequest, SaveUserDataRequest,
+    api::{
+        error::ErrorResponse,
+        session::{CreateSessionRequest, CreateSessionResponse},
+        store_vault_server::types::{
+            AcknowledgeDataRequest, AcknowledgeSenderProofSetRequest, DataNotification,
+            DeleteMyDataRequest, DeleteMyDataResponse, GetDataAllAfterRequest,
+            GetDataAllAfterResponse, GetDataBatchRequest, GetDataBatchResponse,
+            GetGcMetricsResponse, GetSenderProofSetRequest, GetSenderProofSetResponse,
+            GetUserDataRequest, GetUserDataResponse, GetUserDataVersionRequest,
+            GetUserDataVersionResponse, ListUserDataVersionsRequest, ListUserDataVersionsResponse,
+            RequestDataDeletionRequest, RequestDataDeletionResponse, SaveDataBatchRequest,
+            SaveDataBatchResponse, SaveSenderProofSetRequest, SaveUserDataRequest,
+            SubscribeRequest,
+        },
     },
-    utils::signature::{Signable, WithAuth},
+    utils::signature::{WithAuth, WithOptionalAuth},
 };
+use server_common::{api_scope, error::ApiError};
 
+const MAX_BATCH_SIZE: usize = 1000;
+// smaller than MAX_BATCH_SIZE since the entries are returned with their data
+const MAX_GET_DATA_BATCH_SIZE: usize = 100;
+
+#[utoipa::path(
+    post,
+    path = "/create-session",
+    request_body = WithAuth<CreateSessionRequest>,
+    responses(
+        (status = 200, body = CreateSessionResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/create-session")]
+pub async fn create_session(
+    state: Data<State>,
+    request: Json<WithAuth<CreateSessionRequest>>,
+) -> Result<Json<CreateSessionResponse>, Error> {
+    let session = state.authenticator.create_session(&request)?;
+    Ok(Json(session))
+}
+
+#[utoipa::path(
+    post,
+    path = "/save-user-data",
+    request_body = WithOptionalAuth<SaveUserDataRequest>,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/save-user-data")]
 pub async fn save_user_data(
     state: Data<State>,
-    request: Json<WithAuth<SaveUserDataRequest>>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<SaveUserDataRequest>>,
 ) -> Result<Json<()>, Error> {
-    request
-        .inner
-        .verify(&request.auth)
-        .map_err(ErrorUnauthorized)?;
-    let pubkey = request.auth.pubkey;
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
     let request = &request.inner;
     state
         .store_vault_server
         .save_user_data(pubkey, request.prev_digest, &request.data)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/get-user-data",
+    request_body = WithOptionalAuth<GetUserDataRequest>,
+    responses(
+        (status = 200, body = GetUserDataResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/get-user-data")]
 pub async fn get_user_data(
     state: Data<State>,
-    request: Json<WithAuth<GetUserDataRequest>>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<GetUserDataRequest>>,
 ) -> Result<Json<GetUserDataResponse>, Error> {
-    request
-        .inner
-        .verify(&request.auth)
-        .map_err(ErrorUnauthorized)?;
-    let pubkey = request.auth.pubkey;
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
     let data = state
         .store_vault_server
         .get_user_data(pubkey)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(GetUserDataResponse { data }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/list-user-data-versions",
+    request_body = WithOptionalAuth<ListUserDataVersionsRequest>,
+    responses(
+        (status = 200, body = ListUserDataVersionsResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/list-user-data-versions")]
+pub async fn list_user_data_versions(
+    state: Data<State>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<ListUserDataVersionsRequest>>,
+) -> Result<Json<ListUserDataVersionsResponse>, Error> {
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
+    let versions = state
+        .store_vault_server
+        .list_user_data_versions(pubkey)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(ListUserDataVersionsResponse { versions }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/get-user-data-version",
+    request_body = WithOptionalAuth<GetUserDataVersionRequest>,
+    responses(
+        (status = 200, body = GetUserDataVersionResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/get-user-data-version")]
+pub async fn get_user_data_version(
+    state: Data<State>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<GetUserDataVersionRequest>>,
+) -> Result<Json<GetUserDataVersionResponse>, Error> {
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
+    let data = state
+        .store_vault_server
+        .get_user_data_version(pubkey, request.inner.version)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(GetUserDataVersionResponse { data }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/save-sender-proof-set",
+    request_body = WithAuth<SaveSenderProofSetRequest>,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/save-sender-proof-set")]
 pub async fn save_sender_proof_set(
     state: Data<State>,
     request: Json<WithAuth<SaveSenderProofSetRequest>>,
 ) -> Result<Json<()>, Error> {
-    request
-        .inner
-        .verify(&request.auth)
-        .map_err(ErrorUnauthorized)?;
+    let pubkey = state
+        .authenticator
+        .verify_signed(&request.inner, &request.auth)?;
     let data = &request.inner.data;
-    let pubkey = request.auth.pubkey;
     state
         .store_vault_server
-        .save_sender_proof_set(pubkey, data)
+        .save_sender_proof_set(pubkey, data, request.inner.num_transfers)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/get-sender-proof-set",
+    request_body = WithAuth<GetSenderProofSetRequest>,
+    responses(
+        (status = 200, body = GetSenderProofSetResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/get-sender-proof-set")]
 pub async fn get_sender_proof_set(
     state: Data<State>,
     request: Json<WithAuth<GetSenderProofSetRequest>>,
 ) -> Result<Json<GetSenderProofSetResponse>, Error> {
-    request
-        .inner
-        .verify(&request.auth)
-        .map_err(ErrorUnauthorized)?;
-    let pubkey = request.auth.pubkey;
+    let pubkey = state
+        .authenticator
+        .verify_signed(&request.inner, &request.auth)?;
     let data = state
         .store_vault_server
         .get_sender_proof_set(pubkey)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(GetSenderProofSetResponse { data }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/acknowledge-sender-proof-set",
+    request_body = WithAuth<AcknowledgeSenderProofSetRequest>,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/acknowledge-sender-proof-set")]
+pub async fn acknowledge_sender_proof_set(
+    state: Data<State>,
+    request: Json<WithAuth<AcknowledgeSenderProofSetRequest>>,
+) -> Result<Json<()>, Error> {
+    let pubkey = state
+        .authenticator
+        .verify_signed(&request.inner, &request.auth)?;
+    state
+        .store_vault_server
+        .acknowledge_sender_proof_set(pubkey, request.inner.transfer_index)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/save-data-batch",
+    request_body = WithOptionalAuth<SaveDataBatchRequest>,
+    responses(
+        (status = 200, body = SaveDataBatchResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/save-data-batch")]
 pub async fn batch_save_data(
     state: Data<State>,
-    request: Json<WithAuth<SaveDataBatchRequest>>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<SaveDataBatchRequest>>,
 ) -> Result<Json<SaveDataBatchResponse>, Error> {
-    request
-        .inner
-        .verify(&request.auth)
-        .map_err(ErrorUnauthorized)?;
-    let pubkey = request.auth.pubkey;
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
     let entries = &request.inner.data;
-
-    const MAX_BATCH_SIZE: usize = 1000;
     if entries.len() > MAX_BATCH_SIZE {
-        return Err(actix_web::error::ErrorBadRequest(format!(
+        return Err(ApiError::bad_request(format!(
             "Batch size exceeds maximum limit of {}",
             MAX_BATCH_SIZE
-        )));
+        ))
+        .into());
     }
 
     for entry in entries {
         if entry.data_type.need_auth() && entry.pubkey != pubkey {
-            return Err(ErrorUnauthorized(format!(
+            return Err(ApiError::unauthorized(format!(
                 "Data type {:?} requires auth but given pubkey is different",
                 entry.data_type,
-            )));
+            ))
+            .into());
         }
     }
+    let ip = if state.trust_forwarded_for {
+        req.connection_info()
+            .realip_remote_addr()
+            .map(|ip| ip.to_string())
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    };
+    state
+        .spam_filter
+        .check(
+            pubkey,
+            ip.as_deref(),
+            entries,
+            chrono::Utc::now().timestamp() as u64,
+        )
+        .map_err(ApiError::from)?;
     let uuids = state
         .store_vault_server
         .batch_save_data(entries)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(SaveDataBatchResponse { uuids }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/get-data-all-after",
+    request_body = WithOptionalAuth<GetDataAllAfterRequest>,
+    responses(
+        (status = 200, body = GetDataAllAfterResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/get-data-all-after")]
 pub async fn get_data_all_after(
     state: Data<State>,
-    request: Json<WithAuth<GetDataAllAfterRequest>>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<GetDataAllAfterRequest>>,
 ) -> Result<Json<GetDataAllAfterResponse>, Error> {
-    request
-        .inner
-        .verify(&request.auth)
-        .map_err(ErrorUnauthorized)?;
-    let pubkey = request.auth.pubkey;
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
     let request = &request.inner;
     let data = state
         .store_vault_server
         .get_data_all_after(request.data_type, pubkey, request.timestamp)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(GetDataAllAfterResponse { data }))
 }
 
-pub fn store_vault_server_scope() -> actix_web::Scope {
-    actix_web::web::scope("/store-vault-server")
-        .service(save_user_data)
-        .service(get_user_data)
-        .service(save_sender_proof_set)
-        .service(get_sender_proof_set)
-        .service(batch_save_data)
-        .service(get_data_all_after)
+#[utoipa::path(
+    post,
+    path = "/get-data-batch",
+    request_body = WithOptionalAuth<GetDataBatchRequest>,
+    responses(
+        (status = 200, body = GetDataBatchResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/get-data-batch")]
+pub async fn get_data_batch(
+    state: Data<State>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<GetDataBatchRequest>>,
+) -> Result<Json<GetDataBatchResponse>, Error> {
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
+    let request = &request.inner;
+    if request.uuids.len() > MAX_GET_DATA_BATCH_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "Batch size exceeds maximum limit of {}",
+            MAX_GET_DATA_BATCH_SIZE
+        ))
+        .into());
+    }
+    let data = state
+        .store_vault_server
+        .get_data_batch(request.data_type, pubkey, &request.uuids)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(GetDataBatchResponse { data }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/acknowledge-data",
+    request_body = WithOptionalAuth<AcknowledgeDataRequest>,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/acknowledge-data")]
+pub async fn acknowledge_data(
+    state: Data<State>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<AcknowledgeDataRequest>>,
+) -> Result<Json<()>, Error> {
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
+    let request = &request.inner;
+    if request.uuids.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "Batch size exceeds maximum limit of {}",
+            MAX_BATCH_SIZE
+        ))
+        .into());
+    }
+    state
+        .store_vault_server
+        .acknowledge_data(request.data_type, pubkey, &request.uuids)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/request-data-deletion",
+    request_body = WithAuth<RequestDataDeletionRequest>,
+    responses(
+        (status = 200, body = RequestDataDeletionResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/request-data-deletion")]
+pub async fn request_data_deletion(
+    state: Data<State>,
+    request: Json<WithAuth<RequestDataDeletionRequest>>,
+) -> Result<Json<RequestDataDeletionResponse>, Error> {
+    let pubkey = state
+        .authenticator
+        .verify_signed(&request.inner, &request.auth)?;
+    let (token, expires_at) = state
+        .store_vault_server
+        .request_data_deletion(pubkey)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(RequestDataDeletionResponse { token, expires_at }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/delete-my-data",
+    request_body = WithAuth<DeleteMyDataRequest>,
+    responses(
+        (status = 200, body = DeleteMyDataResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/delete-my-data")]
+pub async fn delete_my_data(
+    state: Data<State>,
+    request: Json<WithAuth<DeleteMyDataRequest>>,
+) -> Result<Json<DeleteMyDataResponse>, Error> {
+    let pubkey = state
+        .authenticator
+        .verify_signed(&request.inner, &request.auth)?;
+    let request = &request.inner;
+    if request.sender_proof_set_auths.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "Batch size exceeds maximum limit of {}",
+            MAX_BATCH_SIZE
+        ))
+        .into());
+    }
+    // the sender proof sets are deleted only if the user holds their ephemeral keys
+    let content = DeleteMyDataRequest::sender_proof_set_content(&request.token);
+    for auth in request.sender_proof_set_auths.iter() {
+        auth.verify(&content).map_err(ApiError::unauthorized)?;
+    }
+    let ephemeral_pubkeys = request
+        .sender_proof_set_auths
+        .iter()
+        .map(|auth| auth.pubkey)
+        .collect::<Vec<_>>();
+    let deleted = state
+        .store_vault_server
+        .delete_account_data(pubkey, &request.token, &ephemeral_pubkeys)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(DeleteMyDataResponse {
+        deleted_user_data_versions: deleted.user_data_versions,
+        deleted_entries: deleted.entries,
+        deleted_sender_proof_sets: deleted.sender_proof_sets,
+        skipped_sender_proof_sets: deleted.skipped_sender_proof_sets,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/subscribe",
+    request_body = WithOptionalAuth<SubscribeRequest>,
+    responses(
+        (
+            status = 200,
+            description = "Server-sent events whose `data` events are notifications of new entries",
+            body = DataNotification,
+            content_type = "text/event-stream"
+        ),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/subscribe")]
+pub async fn subscribe(
+    state: Data<State>,
+    req: HttpRequest,
+    request: Json<WithOptionalAuth<SubscribeRequest>>,
+) -> Result<HttpResponse, Error> {
+    let pubkey = state
+        .authenticator
+        .authenticate(&req, &request.inner, request.auth.as_ref())?;
+    let receiver = state.notifier.subscribe(pubkey).map_err(ApiError::from)?;
+    let data_types = request.into_inner().inner.data_types;
+    Ok(HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, "text/event-stream"))
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .streaming(event_stream(receiver, data_types)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/gc-metrics",
+    responses(
+        (status = 200, body = GetGcMetricsResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[get("/gc-metrics")]
+pub async fn get_gc_metrics(state: Data<State>) -> Result<Json<GetGcMetricsResponse>, Error> {
+    Ok(Json(state.store_vault_server.gc_metrics()))
+}
+
+api_scope!(
+    store_vault_server_scope,
+    store_vault_server_openapi,
+    "/store-vault-server",
+    [
+        create_session,
+        save_user_data,
+        get_user_data,
+        list_user_data_versions,
+        get_user_data_version,
+        save_sender_proof_set,
+        get_sender_proof_set,
+        acknowledge_sender_proof_set,
+        batch_save_data,
+        get_data_all_after,
+        get_data_batch,
+        acknowledge_data,
+        request_data_deletion,
+        delete_my_data,
+        subscribe,
+        get_gc_metrics,
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use server_common::openapi::assert_spec_matches_routes;
+
+    use super::{store_vault_server_openapi, store_vault_server_scope};
+
+    #[actix_web::test]
+    async fn test_openapi_spec_matches_routes() {
+        let app = test::init_service(App::new().service(store_vault_server_scope())).await;
+        assert_spec_matches_routes(
+            &app,
+            &store_vault_server_openapi(),
+            &[
+                ("GET", "/store-vault-server/gc-metrics"),
+                ("POST", "/store-vault-server/acknowledge-data"),
+                ("POST", "/store-vault-server/acknowledge-sender-proof-set"),
+                ("POST", "/store-vault-server/create-session"),
+                ("POST", "/store-vault-server/delete-my-data"),
+                ("POST", "/store-vault-server/get-data-all-after"),
+                ("POST", "/store-vault-server/get-data-batch"),
+                ("POST", "/store-vault-server/get-sender-proof-set"),
+                ("POST", "/store-vault-server/get-user-data"),
+                ("POST", "/store-vault-server/get-user-data-version"),
+                ("POST", "/store-vault-server/list-user-data-versions"),
+                ("POST", "/store-vault-server/request-data-deletion"),
+                ("POST", "/store-vault-server/save-data-batch"),
+                ("POST", "/store-vault-server/save-sender-proof-set"),
+                ("POST", "/store-vault-server/save-user-data"),
+                ("POST", "/store-vault-server/subscribe"),
+            ],
+        )
+        .await;
+    }
 }
@@ -1,11 +1,33 @@
-use crate::app::store_vault_server::StoreVaultServer;
+use std::sync::Arc;
+
+use server_common::auth::Authenticator;
+
+use crate::app::{
+    notifier::Notifier, spam_filter::SpamFilter, store_vault_server::StoreVaultServer,
+};
 
 pub struct State {
     pub store_vault_server: StoreVaultServer,
+    pub spam_filter: SpamFilter,
+    pub notifier: Arc<Notifier>,
+    pub authenticator: Authenticator,
+    pub trust_forwarded_for: bool,
 }
 
 impl State {
-    pub fn new(store_vault_server: StoreVaultServer) -> Self {
-        Self { store_vault_server }
+    pub fn new(
+        store_vault_server: StoreVaultServer,
+        spam_filter: SpamFilter,
+        notifier: Arc<Notifier>,
+        authenticator: Authenticator,
+        trust_forwarded_for: bool,
+    ) -> Self {
+        Self {
+            store_vault_server,
+            spam_filter,
+            notifier,
+            authenticator,
+            trust_forwarded_for,
+        }
     }
 }
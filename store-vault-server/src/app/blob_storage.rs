@@ -0,0 +1,251 @@
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use intmax2_interfaces::utils::digest::get_digest;
+use intmax2_zkp::ethereum_types::{bytes32::Bytes32, u32limb_trait::U32LimbTrait as _};
+use object_store::{
+    aws::AmazonS3Builder, local::LocalFileSystem, path::Path, ObjectStore, PutPayload,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::EnvVar;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlobStorageError {
+    #[error("Database error: {0}")]
+    DBError(#[from] sqlx::Error),
+
+    #[error("Object store error: {0}")]
+    ObjectStoreError(#[from] object_store::Error),
+
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Digest mismatch of blob {0}")]
+    DigestMismatch(Bytes32),
+
+    #[error("Config error: {0}")]
+    ConfigError(String),
+}
+
+type Result<T> = std::result::Result<T, BlobStorageError>;
+
+/// Storage of the encrypted blobs, addressed by the sha256 digest of their content. Only the
+/// digests are kept in the tables of the store vault server. Shared by the workers of the http
+/// server, hence `Send + Sync`.
+#[async_trait(?Send)]
+pub trait BlobStorage: Send + Sync {
+    /// Saves the blob. Saving the same blob twice is not an error.
+    async fn put(&self, digest: Bytes32, data: &[u8]) -> Result<()>;
+
+    /// Returns the blob after checking its digest.
+    async fn get(&self, digest: Bytes32) -> Result<Option<Vec<u8>>>;
+
+    /// Deletes the blob. Deleting a missing blob is not an error.
+    async fn delete(&self, digest: Bytes32) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobStorageType {
+    Postgres,
+    Filesystem,
+    S3,
+}
+
+pub fn blob_storage_from_env(env: &EnvVar, pool: PgPool) -> Result<Arc<dyn BlobStorage>> {
+    let storage: Arc<dyn BlobStorage> =
+        match env.blob_storage.unwrap_or(BlobStorageType::Postgres) {
+            BlobStorageType::Postgres => Arc::new(PostgresBlobStorage::new(pool)),
+            BlobStorageType::Filesystem => {
+                let dir = env.blob_storage_dir.as_ref().ok_or_else(|| {
+                    BlobStorageError::ConfigError("BLOB_STORAGE_DIR is not set".to_string())
+                })?;
+                Arc::new(ObjectBlobStorage::filesystem(dir.into())?)
+            }
+            BlobStorageType::S3 => {
+                let bucket = env.s3_bucket.as_ref().ok_or_else(|| {
+                    BlobStorageError::ConfigError("S3_BUCKET is not set".to_string())
+                })?;
+                let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+                if let Some(endpoint) = &env.s3_endpoint {
+                    // S3 compatible servers such as MinIO are accessed in path style
+                    builder = builder
+                        .with_endpoint(endpoint)
+                        .with_virtual_hosted_style_request(false)
+                        .with_allow_http(endpoint.starts_with("http://"));
+                }
+                Arc::new(ObjectBlobStorage::new(Arc::new(builder.build()?)))
+            }
+        };
+    Ok(storage)
+}
+
+fn verify_digest(digest: Bytes32, data: Vec<u8>) -> Result<Vec<u8>> {
+    if get_digest(&data) != digest {
+        return Err(BlobStorageError::DigestMismatch(digest));
+    }
+    Ok(data)
+}
+
+/// Blobs in the `blobs` table of the same database
+pub struct PostgresBlobStorage {
+    pool: PgPool,
+}
+
+impl PostgresBlobStorage {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait(?Send)]
+impl BlobStorage for PostgresBlobStorage {
+    async fn put(&self, digest: Bytes32, data: &[u8]) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO blobs (digest, data) VALUES ($1, $2)
+            ON CONFLICT (digest) DO NOTHING
+            "#,
+            digest.to_bytes_be(),
+            data
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(&self, digest: Bytes32) -> Result<Option<Vec<u8>>> {
+        let data = sqlx::query_scalar!(
+            r#"
+            SELECT data FROM blobs WHERE digest = $1
+            "#,
+            digest.to_bytes_be()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        data.map(|data| verify_digest(digest, data)).transpose()
+    }
+
+    async fn delete(&self, digest: Bytes32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM blobs WHERE digest = $1
+            "#,
+            digest.to_bytes_be()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Blobs in an object store, i.e. a local directory or an S3 compatible bucket
+pub struct ObjectBlobStorage {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl ObjectBlobStorage {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    pub fn filesystem(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self::new(Arc::new(LocalFileSystem::new_with_prefix(dir)?)))
+    }
+
+    fn path(digest: Bytes32) -> Path {
+        let hex = digest.to_hex();
+        let hex = hex.trim_start_matches("0x");
+        // fan out to avoid too many entries in a single directory
+        Path::from(format!("{}/{}", &hex[..2], hex))
+    }
+}
+
+#[async_trait(?Send)]
+impl BlobStorage for ObjectBlobStorage {
+    async fn put(&self, digest: Bytes32, data: &[u8]) -> Result<()> {
+        self.store
+            .put(&Self::path(digest), PutPayload::from(data.to_vec()))
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, digest: Bytes32) -> Result<Option<Vec<u8>>> {
+        match self.store.get(&Self::path(digest)).await {
+            Ok(result) => {
+                let data = result.bytes().await?.to_vec();
+                verify_digest(digest, data).map(Some)
+            }
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, digest: Bytes32) -> Result<()> {
+        match self.store.delete(&Self::path(digest)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use intmax2_interfaces::utils::digest::get_digest;
+    use object_store::{aws::AmazonS3Builder, memory::InMemory};
+
+    use super::{BlobStorage, ObjectBlobStorage};
+
+    async fn assert_roundtrip(storage: &dyn BlobStorage) {
+        let data = b"encrypted blob".to_vec();
+        let digest = get_digest(&data);
+        assert_eq!(storage.get(digest).await.unwrap(), None);
+
+        storage.put(digest, &data).await.unwrap();
+        storage.put(digest, &data).await.unwrap();
+        assert_eq!(storage.get(digest).await.unwrap(), Some(data));
+
+        storage.delete(digest).await.unwrap();
+        storage.delete(digest).await.unwrap();
+        assert_eq!(storage.get(digest).await.unwrap(), None);
+
+        // a blob whose content does not match the digest is rejected
+        let other = get_digest(b"other");
+        storage.put(other, b"tampered").await.unwrap();
+        assert!(storage.get(other).await.is_err());
+        storage.delete(other).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_object_blob_storage() {
+        assert_roundtrip(&ObjectBlobStorage::new(Arc::new(InMemory::new()))).await;
+
+        let dir = std::env::temp_dir().join(format!("blob-storage-{}", uuid::Uuid::new_v4()));
+        assert_roundtrip(&ObjectBlobStorage::filesystem(dir.clone()).unwrap()).await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Requires an S3 compatible server, e.g.
+    // `docker run -p 9000:9000 -e MINIO_ROOT_USER=minioadmin -e MINIO_ROOT_PASSWORD=minioadmin
+    // minio/minio server /data` with a bucket `store-vault-test`
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_blob_storage() {
+        let store = AmazonS3Builder::new()
+            .with_endpoint("http://localhost:9000")
+            .with_allow_http(true)
+            .with_virtual_hosted_style_request(false)
+            .with_region("us-east-1")
+            .with_access_key_id("minioadmin")
+            .with_secret_access_key("minioadmin")
+            .with_bucket_name("store-vault-test")
+            .build()
+            .unwrap();
+        assert_roundtrip(&ObjectBlobStorage::new(Arc::new(store))).await;
+    }
+}
@@ -0,0 +1,99 @@
+use intmax2_interfaces::api::error::ErrorCode;
+use intmax2_zkp::ethereum_types::bytes32::Bytes32;
+use server_common::error::ApiError;
+
+use super::blob_storage::BlobStorageError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreVaultError {
+    #[error("Database error: {0}")]
+    DBError(#[from] sqlx::Error),
+
+    #[error("Blob storage error: {0}")]
+    BlobStorageError(#[from] BlobStorageError),
+
+    #[error("Blob {0} not found in the blob storage")]
+    BlobNotFound(Bytes32),
+
+    #[error("Inline blob does not match its digest {0}")]
+    BlobDigestMismatch(Bytes32),
+
+    #[error("Prev digest mismatch {0} != {1}")]
+    DigestMismatch(Bytes32, Bytes32),
+
+    #[error("User data not found though prev_digest is provided")]
+    UserDataNotFound,
+
+    #[error("User data already exists but prev_digest is not provided")]
+    UserDataAlreadyExists,
+
+    #[error("Sender proof set not found")]
+    SenderProofSetNotFound,
+
+    #[error("Transfer index {0} is out of the {1} transfers of the sender proof set")]
+    InvalidTransferIndex(u32, u32),
+
+    #[error("Entry size {0} exceeds the limit of {1} bytes")]
+    EntryTooLarge(usize, usize),
+
+    #[error("Too many entries written for {0}, retry after {1} seconds")]
+    RateLimited(String, u64),
+
+    #[error("Too many subscriptions, the limit is {0}")]
+    TooManySubscriptions(usize),
+
+    #[error("Proof of work of difficulty {0} is required for entries written to other users")]
+    ProofOfWorkRequired(u32),
+
+    #[error("Invalid or expired confirmation token")]
+    InvalidConfirmationToken,
+
+    #[error("Data of the account was deleted at {0}")]
+    AccountDeleted(u64),
+}
+
+impl From<StoreVaultError> for ApiError {
+    fn from(e: StoreVaultError) -> Self {
+        match &e {
+            StoreVaultError::DBError(_) | StoreVaultError::BlobStorageError(_) => {
+                ApiError::unavailable(e)
+            }
+            StoreVaultError::BlobNotFound(_) | StoreVaultError::BlobDigestMismatch(_) => {
+                ApiError::internal(e)
+            }
+            StoreVaultError::DigestMismatch(current, given) => {
+                ApiError::new(ErrorCode::DigestMismatch, &e).with_details(serde_json::json!({
+                    "currentDigest": current,
+                    "prevDigest": given,
+                }))
+            }
+            StoreVaultError::UserDataNotFound | StoreVaultError::UserDataAlreadyExists => {
+                ApiError::new(ErrorCode::DigestMismatch, e)
+            }
+            StoreVaultError::SenderProofSetNotFound => ApiError::not_found(e),
+            StoreVaultError::InvalidTransferIndex(_, _) => ApiError::bad_request(e),
+            StoreVaultError::EntryTooLarge(size, max_size) => {
+                ApiError::new(ErrorCode::PayloadTooLarge, &e).with_details(serde_json::json!({
+                    "size": size,
+                    "maxSize": max_size,
+                }))
+            }
+            StoreVaultError::RateLimited(_, retry_after) => {
+                ApiError::new(ErrorCode::TooManyRequests, &e)
+                    .with_details(serde_json::json!({ "retryAfter": retry_after }))
+            }
+            StoreVaultError::TooManySubscriptions(_) => ApiError::unavailable(e),
+            StoreVaultError::ProofOfWorkRequired(difficulty) => {
+                ApiError::new(ErrorCode::ProofOfWorkRequired, &e)
+                    .with_details(serde_json::json!({ "difficulty": difficulty }))
+            }
+            StoreVaultError::InvalidConfirmationToken => {
+                ApiError::new(ErrorCode::InvalidConfirmationToken, e)
+            }
+            StoreVaultError::AccountDeleted(deleted_at) => {
+                ApiError::new(ErrorCode::AccountDeleted, &e)
+                    .with_details(serde_json::json!({ "deletedAt": deleted_at }))
+            }
+        }
+    }
+}
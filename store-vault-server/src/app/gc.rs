@@ -0,0 +1,234 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use intmax2_interfaces::api::store_vault_server::{
+    interface::DataType,
+    types::{GcTargetMetrics, GetGcMetricsResponse},
+};
+
+use crate::EnvVar;
+
+const DEFAULT_GC_INTERVAL: u64 = 3600; // 1 hour
+const DEFAULT_GC_BATCH_SIZE: u64 = 1000;
+
+/// Kind of the entries which are garbage collected independently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GcTarget {
+    Data(DataType),
+    SenderProofSet,
+}
+
+impl GcTarget {
+    pub const ALL: [GcTarget; 5] = [
+        GcTarget::Data(DataType::Deposit),
+        GcTarget::Data(DataType::Transfer),
+        GcTarget::Data(DataType::Withdrawal),
+        GcTarget::Data(DataType::Tx),
+        GcTarget::SenderProofSet,
+    ];
+}
+
+impl fmt::Display for GcTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GcTarget::Data(data_type) => write!(f, "{}", data_type),
+            GcTarget::SenderProofSet => write!(f, "sender_proof_set"),
+        }
+    }
+}
+
+/// Retention of the entries of a `GcTarget` in seconds. An entry is deleted when either of the
+/// periods has passed, and `None` disables the corresponding rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Period since the entry was saved
+    pub retention: Option<u64>,
+    /// Period since the entry was acknowledged by its owner
+    pub acked_retention: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn keeps_forever(&self) -> bool {
+        self.retention.is_none() && self.acked_retention.is_none()
+    }
+
+    /// Returns the thresholds of `timestamp` and `acked_at` before which the entries are
+    /// deleted at `now`.
+    pub fn cutoffs(&self, now: u64) -> (Option<i64>, Option<i64>) {
+        let cutoff = |period: Option<u64>| period.map(|p| now.saturating_sub(p) as i64);
+        (cutoff(self.retention), cutoff(self.acked_retention))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GcConfig {
+    pub interval: u64,
+    pub batch_size: u64,
+    pub policies: Vec<(GcTarget, RetentionPolicy)>,
+}
+
+impl GcConfig {
+    pub fn from_env(env: &EnvVar) -> Self {
+        let policies = GcTarget::ALL
+            .iter()
+            .map(|&target| {
+                let policy = match target {
+                    GcTarget::Data(DataType::Deposit) => RetentionPolicy {
+                        retention: env.deposit_retention,
+                        acked_retention: env.deposit_acked_retention,
+                    },
+                    GcTarget::Data(DataType::Transfer) => RetentionPolicy {
+                        retention: env.transfer_retention,
+                        acked_retention: env.transfer_acked_retention,
+                    },
+                    GcTarget::Data(DataType::Withdrawal) => RetentionPolicy {
+                        retention: env.withdrawal_retention,
+                        acked_retention: env.withdrawal_acked_retention,
+                    },
+                    GcTarget::Data(DataType::Tx) => RetentionPolicy {
+                        retention: env.tx_retention,
+                        acked_retention: env.tx_acked_retention,
+                    },
+                    GcTarget::SenderProofSet => RetentionPolicy {
+                        retention: env.sender_proof_set_retention,
+                        acked_retention: env.sender_proof_set_acked_retention,
+                    },
+                };
+                (target, policy)
+            })
+            .filter(|(_, policy)| !policy.keeps_forever())
+            .collect();
+        Self {
+            interval: env.gc_interval.unwrap_or(DEFAULT_GC_INTERVAL),
+            batch_size: env.gc_batch_size.unwrap_or(DEFAULT_GC_BATCH_SIZE),
+            policies,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.policies.is_empty()
+    }
+}
+
+/// Rows and bytes of encrypted data deleted by a GC run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reclaimed {
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+impl std::ops::AddAssign for Reclaimed {
+    fn add_assign(&mut self, rhs: Self) {
+        self.rows += rhs.rows;
+        self.bytes += rhs.bytes;
+    }
+}
+
+#[derive(Debug, Default)]
+struct TargetCounters {
+    deleted_rows: AtomicU64,
+    reclaimed_bytes: AtomicU64,
+}
+
+/// Cumulative counters of the GC job since the server started
+#[derive(Debug, Default)]
+pub struct GcMetrics {
+    runs: AtomicU64,
+    failures: AtomicU64,
+    last_run_at: AtomicU64,
+    targets: [TargetCounters; GcTarget::ALL.len()],
+}
+
+impl GcMetrics {
+    fn counters(&self, target: GcTarget) -> &TargetCounters {
+        let index = GcTarget::ALL.iter().position(|t| *t == target).unwrap();
+        &self.targets[index]
+    }
+
+    pub fn record_reclaimed(&self, target: GcTarget, reclaimed: Reclaimed) {
+        let counters = self.counters(target);
+        counters
+            .deleted_rows
+            .fetch_add(reclaimed.rows, Ordering::Relaxed);
+        counters
+            .reclaimed_bytes
+            .fetch_add(reclaimed.bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_run(&self, now: u64, success: bool) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        self.last_run_at.store(now, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, gc_enabled: bool) -> GetGcMetricsResponse {
+        let last_run_at = self.last_run_at.load(Ordering::Relaxed);
+        GetGcMetricsResponse {
+            gc_enabled,
+            runs: self.runs.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            last_run_at: (last_run_at != 0).then_some(last_run_at),
+            targets: GcTarget::ALL
+                .iter()
+                .map(|&target| {
+                    let counters = self.counters(target);
+                    GcTargetMetrics {
+                        target: target.to_string(),
+                        deleted_rows: counters.deleted_rows.load(Ordering::Relaxed),
+                        reclaimed_bytes: counters.reclaimed_bytes.load(Ordering::Relaxed),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::api::store_vault_server::interface::DataType;
+
+    use super::{GcMetrics, GcTarget, Reclaimed, RetentionPolicy};
+
+    #[test]
+    fn test_retention_cutoffs() {
+        let policy = RetentionPolicy {
+            retention: Some(100),
+            acked_retention: None,
+        };
+        assert_eq!(policy.cutoffs(1000), (Some(900), None));
+        assert_eq!(policy.cutoffs(50), (Some(0), None));
+        assert!(RetentionPolicy::default().keeps_forever());
+    }
+
+    #[test]
+    fn test_gc_metrics() {
+        let metrics = GcMetrics::default();
+        metrics.record_reclaimed(
+            GcTarget::Data(DataType::Transfer),
+            Reclaimed { rows: 2, bytes: 10 },
+        );
+        metrics.record_reclaimed(GcTarget::SenderProofSet, Reclaimed { rows: 1, bytes: 5 });
+        metrics.record_run(42, true);
+
+        let snapshot = metrics.snapshot(true);
+        assert!(snapshot.gc_enabled);
+        assert_eq!(snapshot.runs, 1);
+        assert_eq!(snapshot.last_run_at, Some(42));
+        let transfer = snapshot
+            .targets
+            .iter()
+            .find(|t| t.target == "transfer")
+            .unwrap();
+        assert_eq!((transfer.deleted_rows, transfer.reclaimed_bytes), (2, 10));
+        let sender_proof_set = snapshot
+            .targets
+            .iter()
+            .find(|t| t.target == "sender_proof_set")
+            .unwrap();
+        assert_eq!(sender_proof_set.reclaimed_bytes, 5);
+    }
+}
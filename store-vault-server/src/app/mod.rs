@@ -1 +1,6 @@
+pub mod blob_storage;
+pub mod error;
+pub mod gc;
+pub mod notifier;
+pub mod spam_filter;
 pub mod store_vault_server;
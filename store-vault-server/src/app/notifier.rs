@@ -0,0 +1,210 @@
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix_web::web::Bytes;
+use futures_util::{stream, Stream};
+use hashbrown::HashMap;
+use intmax2_interfaces::api::store_vault_server::{interface::DataType, types::DataNotification};
+use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+
+use super::error::StoreVaultError;
+
+/// Channel of the notifications sent by the trigger on `encrypted_data`
+const CHANNEL: &str = "encrypted_data";
+const SUBSCRIPTION_BUFFER_SIZE: usize = 256;
+const KEEP_ALIVE_INTERVAL: u64 = 15; // seconds
+const RECONNECT_INTERVAL: u64 = 5; // seconds
+
+#[derive(Debug, Deserialize)]
+struct Payload {
+    pubkey: String,
+    uuid: String,
+    data_type: i32,
+    timestamp: i64,
+}
+
+fn data_type_from_i32(data_type: i32) -> Option<DataType> {
+    [
+        DataType::Deposit,
+        DataType::Transfer,
+        DataType::Withdrawal,
+        DataType::Tx,
+    ]
+    .into_iter()
+    .find(|t| *t as i32 == data_type)
+}
+
+/// Dispatches the entries saved for a pubkey to its subscribers. The entries are notified via
+/// postgres, so that the subscribers of every instance of the server are notified.
+#[derive(Debug)]
+pub struct Notifier {
+    max_subscriptions: usize,
+    subscribers: Mutex<HashMap<String, broadcast::Sender<DataNotification>>>,
+}
+
+impl Notifier {
+    pub fn new(max_subscriptions: usize) -> Self {
+        Self {
+            max_subscriptions,
+            subscribers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn subscribe(
+        &self,
+        pubkey: U256,
+    ) -> Result<broadcast::Receiver<DataNotification>, StoreVaultError> {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|_, sender| sender.receiver_count() > 0);
+        let count = subscribers
+            .values()
+            .map(|sender| sender.receiver_count())
+            .sum::<usize>();
+        if count >= self.max_subscriptions {
+            return Err(StoreVaultError::TooManySubscriptions(
+                self.max_subscriptions,
+            ));
+        }
+        let receiver = subscribers
+            .entry(pubkey.to_hex())
+            .or_insert_with(|| broadcast::channel(SUBSCRIPTION_BUFFER_SIZE).0)
+            .subscribe();
+        Ok(receiver)
+    }
+
+    fn notify(&self, pubkey: &str, notification: DataNotification) {
+        if let Some(sender) = self.subscribers.lock().unwrap().get(pubkey) {
+            // fails only if all subscribers have gone
+            let _ = sender.send(notification);
+        }
+    }
+
+    /// Closes all subscriptions, so that the subscribers catch up on the missed notifications by
+    /// polling.
+    fn close_all(&self) {
+        self.subscribers.lock().unwrap().clear();
+    }
+
+    async fn listen(&self, database_url: &str) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect(database_url).await?;
+        listener.listen(CHANNEL).await?;
+        loop {
+            // None if the connection was lost and has been re-established
+            let Some(notification) = listener.try_recv().await? else {
+                log::warn!("Connection of the notifier was lost, closing all subscriptions");
+                self.close_all();
+                continue;
+            };
+            let payload = match serde_json::from_str::<Payload>(notification.payload()) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    log::error!("Invalid notification {}: {}", notification.payload(), e);
+                    continue;
+                }
+            };
+            let Some(data_type) = data_type_from_i32(payload.data_type) else {
+                log::error!("Invalid data type in notification: {}", payload.data_type);
+                continue;
+            };
+            self.notify(
+                &payload.pubkey,
+                DataNotification {
+                    data_type,
+                    uuid: payload.uuid,
+                    timestamp: payload.timestamp as u64,
+                },
+            );
+        }
+    }
+
+    pub fn listen_job(self: &Arc<Self>, database_url: String) {
+        let notifier = self.clone();
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = notifier.listen(&database_url).await {
+                    log::error!("Error in notifier: {}", e);
+                }
+                notifier.close_all();
+                tokio::time::sleep(Duration::from_secs(RECONNECT_INTERVAL)).await;
+            }
+        });
+    }
+}
+
+/// Server-sent events of the notifications of `data_types`, or of all if empty. The stream ends
+/// when the subscriber misses a notification, so that it catches up by polling.
+pub fn event_stream(
+    receiver: broadcast::Receiver<DataNotification>,
+    data_types: Vec<DataType>,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    stream::unfold(receiver, move |mut receiver| {
+        let data_types = data_types.clone();
+        async move {
+            loop {
+                tokio::select! {
+                    result = receiver.recv() => {
+                        let notification = result.ok()?;
+                        if data_types.is_empty() || data_types.contains(&notification.data_type) {
+                            let event = format!(
+                                "event: data\ndata: {}\n\n",
+                                serde_json::to_string(&notification).unwrap()
+                            );
+                            return Some((Ok(Bytes::from(event)), receiver));
+                        }
+                    }
+                    _ = tokio::time::sleep(Duration::from_secs(KEEP_ALIVE_INTERVAL)) => {
+                        return Some((Ok(Bytes::from_static(b": keep-alive\n\n")), receiver));
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt as _;
+    use intmax2_interfaces::api::store_vault_server::{
+        interface::DataType, types::DataNotification,
+    };
+    use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+
+    use super::{event_stream, Notifier};
+
+    fn notification(data_type: DataType, uuid: &str) -> DataNotification {
+        DataNotification {
+            data_type,
+            uuid: uuid.to_string(),
+            timestamp: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let notifier = Notifier::new(1);
+        let pubkey = U256::from_bytes_be(&[1; 32]);
+        let receiver = notifier.subscribe(pubkey).unwrap();
+        assert!(notifier.subscribe(pubkey).is_err());
+
+        let mut events = Box::pin(event_stream(receiver, vec![DataType::Transfer]));
+        notifier.notify(&pubkey.to_hex(), notification(DataType::Deposit, "a"));
+        notifier.notify(&pubkey.to_hex(), notification(DataType::Transfer, "b"));
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(
+            event,
+            "event: data\ndata: {\"dataType\":\"transfer\",\"uuid\":\"b\",\"timestamp\":1}\n\n"
+        );
+
+        // the stream ends when closed
+        notifier.close_all();
+        assert!(events.next().await.is_none());
+        drop(events);
+        notifier.subscribe(pubkey).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::Mutex;
+
+use hashbrown::HashMap;
+use intmax2_interfaces::{
+    api::store_vault_server::interface::SaveDataEntry, utils::pow::verify_pow,
+};
+use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+
+use crate::EnvVar;
+
+use super::error::StoreVaultError;
+
+const DEFAULT_MAX_ENTRY_SIZE: usize = 1_048_576; // 1 MiB
+const DEFAULT_RATE_LIMIT_WINDOW: u64 = 60; // 1 minute
+
+#[derive(Debug, Clone)]
+pub struct SpamFilterConfig {
+    pub max_entry_size: usize,
+    pub rate_limit_window: u64,
+    /// Entries written to the inboxes of other users per window, counted per writer and per IP
+    pub max_entries_per_source: Option<u32>,
+    /// Entries written by other users per window, counted per recipient
+    pub max_entries_per_recipient: Option<u32>,
+    /// Leading zero bits of the proof of work required for entries written to other users
+    pub pow_difficulty: Option<u32>,
+}
+
+impl SpamFilterConfig {
+    pub fn from_env(env: &EnvVar) -> Self {
+        Self {
+            max_entry_size: env.max_entry_size.unwrap_or(DEFAULT_MAX_ENTRY_SIZE),
+            rate_limit_window: env.rate_limit_window.unwrap_or(DEFAULT_RATE_LIMIT_WINDOW),
+            max_entries_per_source: env.max_entries_per_source,
+            max_entries_per_recipient: env.max_entries_per_recipient,
+            pow_difficulty: env.pow_difficulty.filter(|d| *d > 0),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    window_start: u64,
+    counts: HashMap<String, u32>,
+}
+
+/// Protects the inboxes from the entries which are written without the auth of the recipient,
+/// i.e. deposits and transfers. The entries written by a user to its own inbox are only subject
+/// to the size cap.
+#[derive(Debug)]
+pub struct SpamFilter {
+    config: SpamFilterConfig,
+    counters: Mutex<Counters>,
+}
+
+impl SpamFilter {
+    pub fn new(config: SpamFilterConfig) -> Self {
+        Self {
+            config,
+            counters: Mutex::new(Counters::default()),
+        }
+    }
+
+    /// Checks the entries of a batch signed by `writer` and sent from `ip`, and counts them
+    /// against the rate limits if accepted.
+    pub fn check(
+        &self,
+        writer: U256,
+        ip: Option<&str>,
+        entries: &[SaveDataEntry],
+        now: u64,
+    ) -> Result<(), StoreVaultError> {
+        for entry in entries {
+            if entry.encrypted_data.len() > self.config.max_entry_size {
+                return Err(StoreVaultError::EntryTooLarge(
+                    entry.encrypted_data.len(),
+                    self.config.max_entry_size,
+                ));
+            }
+        }
+        let foreign = entries
+            .iter()
+            .filter(|entry| entry.pubkey != writer)
+            .collect::<Vec<_>>();
+        if foreign.is_empty() {
+            return Ok(());
+        }
+        if let Some(difficulty) = self.config.pow_difficulty {
+            if !foreign
+                .iter()
+                .all(|entry| verify_pow(writer, entry, difficulty))
+            {
+                return Err(StoreVaultError::ProofOfWorkRequired(difficulty));
+            }
+        }
+
+        let mut increments: Vec<(String, u32, u32)> = Vec::new();
+        if let Some(limit) = self.config.max_entries_per_source {
+            let n = foreign.len() as u32;
+            increments.push((format!("writer:{}", writer.to_hex()), n, limit));
+            if let Some(ip) = ip {
+                increments.push((format!("ip:{}", ip), n, limit));
+            }
+        }
+        if let Some(limit) = self.config.max_entries_per_recipient {
+            let mut per_recipient: HashMap<String, u32> = HashMap::new();
+            for entry in foreign.iter() {
+                *per_recipient
+                    .entry(format!("recipient:{}", entry.pubkey.to_hex()))
+                    .or_default() += 1;
+            }
+            for (key, n) in per_recipient {
+                increments.push((key, n, limit));
+            }
+        }
+        if increments.is_empty() {
+            return Ok(());
+        }
+
+        let mut counters = self.counters.lock().unwrap();
+        if now >= counters.window_start + self.config.rate_limit_window {
+            counters.window_start = now;
+            counters.counts.clear();
+        }
+        for (key, n, limit) in increments.iter() {
+            let count = counters.counts.get(key).copied().unwrap_or(0);
+            if count + n > *limit {
+                let retry_after = counters.window_start + self.config.rate_limit_window - now;
+                return Err(StoreVaultError::RateLimited(key.clone(), retry_after));
+            }
+        }
+        for (key, n, _) in increments {
+            *counters.counts.entry(key).or_default() += n;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use intmax2_interfaces::{
+        api::store_vault_server::interface::{DataType, SaveDataEntry},
+        utils::pow::solve_pow,
+    };
+    use intmax2_zkp::ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait as _};
+
+    use super::{SpamFilter, SpamFilterConfig};
+    use crate::app::error::StoreVaultError;
+
+    fn u256(n: u8) -> U256 {
+        let mut bytes = [0u8; 32];
+        bytes[31] = n;
+        U256::from_bytes_be(&bytes)
+    }
+
+    fn entry(pubkey: U256) -> SaveDataEntry {
+        SaveDataEntry {
+            data_type: DataType::Transfer,
+            pubkey,
+            encrypted_data: vec![0; 10],
+            pow_nonce: None,
+        }
+    }
+
+    fn config() -> SpamFilterConfig {
+        SpamFilterConfig {
+            max_entry_size: 100,
+            rate_limit_window: 60,
+            max_entries_per_source: None,
+            max_entries_per_recipient: None,
+            pow_difficulty: None,
+        }
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let filter = SpamFilter::new(SpamFilterConfig {
+            max_entries_per_source: Some(3),
+            max_entries_per_recipient: Some(2),
+            ..config()
+        });
+        let (writer, alice, bob) = (u256(1), u256(2), u256(3));
+
+        filter
+            .check(writer, Some("ip"), &[entry(alice), entry(alice)], 0)
+            .unwrap();
+        // the recipient limit is reached
+        let result = filter.check(u256(4), None, &[entry(alice)], 10);
+        assert!(matches!(result, Err(StoreVaultError::RateLimited(_, 50))));
+        // the source limit is reached even via another key
+        filter
+            .check(u256(5), Some("ip"), &[entry(bob)], 10)
+            .unwrap();
+        assert!(filter
+            .check(u256(6), Some("ip"), &[entry(bob)], 10)
+            .is_err());
+        // own entries are not limited
+        filter
+            .check(writer, Some("ip"), &[entry(writer), entry(writer)], 10)
+            .unwrap();
+        // limits are reset in the next window
+        filter
+            .check(writer, Some("ip"), &[entry(alice)], 60)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_size_and_pow() {
+        let filter = SpamFilter::new(SpamFilterConfig {
+            pow_difficulty: Some(8),
+            ..config()
+        });
+        let (writer, alice) = (u256(1), u256(2));
+
+        let mut large = entry(writer);
+        large.encrypted_data = vec![0; 101];
+        assert!(matches!(
+            filter.check(writer, None, &[large], 0),
+            Err(StoreVaultError::EntryTooLarge(101, 100))
+        ));
+
+        let mut transfer = entry(alice);
+        assert!(matches!(
+            filter.check(writer, None, &[transfer.clone()], 0),
+            Err(StoreVaultError::ProofOfWorkRequired(8))
+        ));
+        transfer.pow_nonce = Some(solve_pow(writer, &transfer, 8));
+        filter.check(writer, None, &[transfer], 0).unwrap();
+    }
+}
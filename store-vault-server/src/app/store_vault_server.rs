@@ -1,10 +1,9 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use anyhow::{anyhow, Ok, Result};
 use intmax2_interfaces::{
     api::store_vault_server::{
         interface::{DataType, SaveDataEntry},
-        types::DataWithMetaData,
+        types::{DataWithMetaData, GetGcMetricsResponse, UserDataVersion},
     },
     data::meta_data::MetaData,
     utils::digest::get_digest,
@@ -16,8 +15,35 @@ use uuid::Uuid;
 
 use crate::EnvVar;
 
+use super::{
+    blob_storage::{blob_storage_from_env, BlobStorage},
+    error::StoreVaultError,
+    gc::{GcConfig, GcMetrics, GcTarget, Reclaimed},
+};
+
+type Result<T> = std::result::Result<T, StoreVaultError>;
+
+const DEFAULT_USER_DATA_HISTORY_SIZE: u32 = 10;
+const OFFLOAD_BATCH_SIZE: i64 = 100;
+const DATA_DELETION_TOKEN_TTL: i64 = 600; // 10 minutes
+
+/// Numbers of the rows removed by `StoreVaultServer::delete_account_data`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeletedAccountData {
+    pub user_data_versions: u64,
+    pub entries: u64,
+    pub sender_proof_sets: u64,
+    /// Sender proof sets left to the retention since not all of their transfers are acknowledged
+    pub skipped_sender_proof_sets: u64,
+}
+
+#[derive(Clone)]
 pub struct StoreVaultServer {
     pool: PgPool,
+    blob_storage: Arc<dyn BlobStorage>,
+    user_data_history_size: u32,
+    gc_config: GcConfig,
+    gc_metrics: Arc<GcMetrics>,
 }
 
 impl StoreVaultServer {
@@ -27,8 +53,17 @@ impl StoreVaultServer {
             .idle_timeout(Duration::from_secs(env.database_timeout))
             .connect(&env.database_url)
             .await?;
+        let blob_storage = blob_storage_from_env(env, pool.clone())?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            blob_storage,
+            user_data_history_size: env
+                .user_data_history_size
+                .unwrap_or(DEFAULT_USER_DATA_HISTORY_SIZE),
+            gc_config: GcConfig::from_env(env),
+            gc_metrics: Arc::new(GcMetrics::default()),
+        })
     }
 
     pub async fn save_user_data(
@@ -38,60 +73,151 @@ impl StoreVaultServer {
         encrypted_data: &[u8],
     ) -> Result<()> {
         let mut tx = self.pool.begin().await?;
-        let result = self.get_user_data_and_digest(&mut tx, pubkey).await?;
+        let current_digest = self.get_user_data_digest(&mut tx, pubkey).await?;
         // validation
         if let Some(prev_digest) = prev_digest {
-            if let Some((_, digest)) = result {
+            if let Some(digest) = current_digest {
                 if digest != prev_digest {
-                    return Err(anyhow!(
-                        "Prev digest mismatch {} != {}",
-                        digest,
-                        prev_digest
-                    ));
+                    return Err(StoreVaultError::DigestMismatch(digest, prev_digest));
                 }
             } else {
-                return Err(anyhow!(
-                    "User data not found though prev_digest is provided"
-                ));
+                return Err(StoreVaultError::UserDataNotFound);
             }
-        } else if result.is_some() {
-            return Err(anyhow!(
-                "User data already exists but prev_digest is not provided"
-            ));
+        } else if current_digest.is_some() {
+            return Err(StoreVaultError::UserDataAlreadyExists);
         }
-        let pubkey_hex = pubkey.to_hex();
         let digest = get_digest(encrypted_data);
+        // the blob is saved before the row referring to it
+        self.blob_storage.put(digest, encrypted_data).await?;
+
+        let pubkey_hex = pubkey.to_hex();
+        let mut released = Vec::new();
+        if self.user_data_history_size > 0 {
+            // keep the current version before overwriting it
+            sqlx::query!(
+                r#"
+                INSERT INTO encrypted_user_data_history
+                (pubkey, version, encrypted_data, digest, size, timestamp)
+                SELECT pubkey, version, encrypted_data, digest, size, timestamp
+                FROM encrypted_user_data WHERE pubkey = $1
+                ON CONFLICT (pubkey, version) DO NOTHING
+                "#,
+                pubkey_hex
+            )
+            .execute(tx.as_mut())
+            .await?;
+        } else {
+            released.extend(current_digest);
+        }
         let digest_serialized = digest.to_bytes_be();
-        sqlx::query!(
+        let version = sqlx::query_scalar!(
             r#"
-            INSERT INTO encrypted_user_data (pubkey, encrypted_data, digest, timestamp)
-            VALUES ($1, $2, $3, $4)
-            ON CONFLICT (pubkey) DO UPDATE SET encrypted_data = EXCLUDED.encrypted_data,
-            digest = EXCLUDED.digest, timestamp = EXCLUDED.timestamp
+            INSERT INTO encrypted_user_data (pubkey, encrypted_data, digest, size, timestamp, version)
+            VALUES ($1, NULL, $2, $3, $4, 1)
+            ON CONFLICT (pubkey) DO UPDATE SET encrypted_data = NULL,
+            digest = EXCLUDED.digest, size = EXCLUDED.size, timestamp = EXCLUDED.timestamp,
+            version = encrypted_user_data.version + 1
+            RETURNING version
             "#,
             pubkey_hex,
-            encrypted_data,
             digest_serialized,
+            encrypted_data.len() as i64,
             chrono::Utc::now().timestamp() as i64
         )
-        .execute(tx.as_mut())
+        .fetch_one(tx.as_mut())
+        .await?;
+        let pruned = sqlx::query_scalar!(
+            r#"
+            DELETE FROM encrypted_user_data_history WHERE pubkey = $1 AND version < $2
+            RETURNING digest
+            "#,
+            pubkey_hex,
+            version - self.user_data_history_size as i64
+        )
+        .fetch_all(tx.as_mut())
         .await?;
         tx.commit().await?;
+        released.extend(pruned.iter().map(|digest| Bytes32::from_bytes_be(digest)));
+        self.release_blobs(released).await;
         Ok(())
     }
 
-    pub async fn get_user_data(&self, pubkey: U256) -> Result<Option<Vec<u8>>> {
-        let mut tx = self.pool.begin().await?;
-        let result = self.get_user_data_and_digest(&mut tx, pubkey).await?;
-        tx.commit().await?;
-        Ok(result.map(|(data, _)| data))
+    /// Returns the current and the prior versions of the user data, the newest first.
+    pub async fn list_user_data_versions(&self, pubkey: U256) -> Result<Vec<UserDataVersion>> {
+        let pubkey_hex = pubkey.to_hex();
+        let current = sqlx::query!(
+            r#"
+            SELECT version, digest, timestamp, size
+            FROM encrypted_user_data WHERE pubkey = $1
+            "#,
+            pubkey_hex
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let history = sqlx::query!(
+            r#"
+            SELECT version, digest, timestamp, size
+            FROM encrypted_user_data_history WHERE pubkey = $1
+            ORDER BY version DESC
+            "#,
+            pubkey_hex
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut versions = Vec::new();
+        if let Some(r) = current {
+            versions.push(UserDataVersion {
+                version: r.version as u64,
+                digest: Bytes32::from_bytes_be(&r.digest),
+                timestamp: r.timestamp as u64,
+                size: r.size as u64,
+            });
+        }
+        versions.extend(history.into_iter().map(|r| UserDataVersion {
+            version: r.version as u64,
+            digest: Bytes32::from_bytes_be(&r.digest),
+            timestamp: r.timestamp as u64,
+            size: r.size as u64,
+        }));
+        Ok(versions)
     }
 
-    async fn get_user_data_and_digest(
+    pub async fn get_user_data_version(
         &self,
-        tx: &mut sqlx::Transaction<'_, Postgres>,
         pubkey: U256,
-    ) -> Result<Option<(Vec<u8>, Bytes32)>> {
+        version: u64,
+    ) -> Result<Option<Vec<u8>>> {
+        let pubkey_hex = pubkey.to_hex();
+        let current = sqlx::query!(
+            r#"
+            SELECT encrypted_data, digest FROM encrypted_user_data
+            WHERE pubkey = $1 AND version = $2
+            "#,
+            pubkey_hex,
+            version as i64
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(r) = current {
+            return self.load_blob(r.encrypted_data, &r.digest).await.map(Some);
+        }
+        let prior = sqlx::query!(
+            r#"
+            SELECT encrypted_data, digest FROM encrypted_user_data_history
+            WHERE pubkey = $1 AND version = $2
+            "#,
+            pubkey_hex,
+            version as i64
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        match prior {
+            Some(r) => self.load_blob(r.encrypted_data, &r.digest).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_user_data(&self, pubkey: U256) -> Result<Option<Vec<u8>>> {
         let pubkey_hex = pubkey.to_hex();
         let record = sqlx::query!(
             r#"
@@ -99,25 +225,66 @@ impl StoreVaultServer {
             "#,
             pubkey_hex
         )
+        .fetch_optional(&self.pool)
+        .await?;
+        match record {
+            Some(r) => self.load_blob(r.encrypted_data, &r.digest).await.map(Some),
+            None => {
+                // distinguish a deleted account from a new one, whose sync starts from scratch
+                let deleted_at = sqlx::query_scalar!(
+                    r#"
+                    SELECT deleted_at FROM deleted_accounts WHERE pubkey = $1
+                    "#,
+                    pubkey_hex
+                )
+                .fetch_optional(&self.pool)
+                .await?;
+                match deleted_at {
+                    Some(deleted_at) => Err(StoreVaultError::AccountDeleted(deleted_at as u64)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    async fn get_user_data_digest(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        pubkey: U256,
+    ) -> Result<Option<Bytes32>> {
+        let pubkey_hex = pubkey.to_hex();
+        let digest = sqlx::query_scalar!(
+            r#"
+            SELECT digest FROM encrypted_user_data WHERE pubkey = $1
+            "#,
+            pubkey_hex
+        )
         .fetch_optional(tx.as_mut())
         .await?;
-        Ok(record.map(|r| (r.encrypted_data, Bytes32::from_bytes_be(&r.digest))))
+        Ok(digest.map(|digest| Bytes32::from_bytes_be(&digest)))
     }
 
     pub async fn save_sender_proof_set(
         &self,
         ephemeral_pubkey: U256,
         encrypted_data: &[u8],
+        num_transfers: Option<u32>,
     ) -> Result<()> {
         let pubkey_hex = ephemeral_pubkey.to_hex();
+        let digest = get_digest(encrypted_data);
+        self.blob_storage.put(digest, encrypted_data).await?;
         sqlx::query!(
             r#"
-            INSERT INTO encrypted_sender_proof_set (pubkey, encrypted_data)
-            VALUES ($1, $2)
+            INSERT INTO encrypted_sender_proof_set
+            (pubkey, encrypted_data, digest, size, timestamp, num_transfers)
+            VALUES ($1, NULL, $2, $3, $4, $5)
             ON CONFLICT (pubkey) DO NOTHING
             "#,
             pubkey_hex,
-            encrypted_data
+            digest.to_bytes_be(),
+            encrypted_data.len() as i64,
+            chrono::Utc::now().timestamp(),
+            num_transfers.map(|n| n as i32)
         )
         .execute(&self.pool)
         .await?;
@@ -128,16 +295,71 @@ impl StoreVaultServer {
         let pubkey_hex = ephemeral_pubkey.to_hex();
         let record = sqlx::query!(
             r#"
-            SELECT encrypted_data FROM encrypted_sender_proof_set WHERE pubkey = $1
+            SELECT encrypted_data, digest FROM encrypted_sender_proof_set WHERE pubkey = $1
             "#,
             pubkey_hex
         )
         .fetch_optional(&self.pool)
         .await?;
-        if record.is_none() {
-            return Err(anyhow!("Sender proof set not found"));
+        let record = record.ok_or(StoreVaultError::SenderProofSetNotFound)?;
+        self.load_blob(record.encrypted_data, &record.digest).await
+    }
+
+    /// Records the acknowledgement of a transfer of the sender proof set. The set is acknowledged
+    /// once all of its transfers are, so that no recipient loses it before having processed its
+    /// transfer. Acknowledgements of a set saved without the number of transfers are ignored.
+    pub async fn acknowledge_sender_proof_set(
+        &self,
+        ephemeral_pubkey: U256,
+        transfer_index: u32,
+    ) -> Result<()> {
+        let pubkey_hex = ephemeral_pubkey.to_hex();
+        let mut tx = self.pool.begin().await?;
+        // the row lock serializes the acknowledgements of the same set
+        let num_transfers = sqlx::query_scalar!(
+            r#"
+            SELECT num_transfers FROM encrypted_sender_proof_set WHERE pubkey = $1 FOR UPDATE
+            "#,
+            pubkey_hex
+        )
+        .fetch_optional(tx.as_mut())
+        .await?
+        .ok_or(StoreVaultError::SenderProofSetNotFound)?;
+        let Some(num_transfers) = num_transfers else {
+            return Ok(());
+        };
+        if transfer_index >= num_transfers as u32 {
+            return Err(StoreVaultError::InvalidTransferIndex(
+                transfer_index,
+                num_transfers as u32,
+            ));
         }
-        Ok(record.unwrap().encrypted_data)
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+            INSERT INTO sender_proof_set_acks (pubkey, transfer_index, acked_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (pubkey, transfer_index) DO NOTHING
+            "#,
+            pubkey_hex,
+            transfer_index as i32,
+            now
+        )
+        .execute(tx.as_mut())
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE encrypted_sender_proof_set SET acked_at = $2
+            WHERE pubkey = $1 AND acked_at IS NULL
+            AND (SELECT COUNT(*) FROM sender_proof_set_acks WHERE pubkey = $1) >= num_transfers
+            "#,
+            pubkey_hex,
+            now
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        Ok(())
     }
 
     pub async fn batch_save_data(&self, entries: &[SaveDataEntry]) -> Result<Vec<String>> {
@@ -216,6 +438,475 @@ impl StoreVaultServer {
 
         Ok(result)
     }
+
+    /// Returns the entries of the user with the given uuids. Unknown uuids and entries of other
+    /// users are ignored.
+    pub async fn get_data_batch(
+        &self,
+        data_type: DataType,
+        pubkey: U256,
+        uuids: &[String],
+    ) -> Result<Vec<DataWithMetaData>> {
+        let pubkey_hex = pubkey.to_hex();
+        let records = sqlx::query!(
+            r#"
+            SELECT uuid, timestamp, encrypted_data
+            FROM encrypted_data
+            WHERE data_type = $1 AND pubkey = $2 AND uuid = ANY($3)
+            ORDER BY timestamp ASC
+            "#,
+            data_type as i32,
+            pubkey_hex,
+            uuids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let result = records
+            .into_iter()
+            .map(|r| DataWithMetaData {
+                meta: MetaData {
+                    uuid: r.uuid,
+                    timestamp: r.timestamp as u64,
+                    block_number: None,
+                },
+                data: r.encrypted_data,
+            })
+            .collect();
+        Ok(result)
+    }
+
+    /// Marks the entries of the user as acknowledged, and returns the number of entries newly
+    /// acknowledged. Unknown uuids and entries of other users are ignored.
+    pub async fn acknowledge_data(
+        &self,
+        data_type: DataType,
+        pubkey: U256,
+        uuids: &[String],
+    ) -> Result<u64> {
+        let pubkey_hex = pubkey.to_hex();
+        let result = sqlx::query!(
+            r#"
+            UPDATE encrypted_data SET acked_at = $4
+            WHERE data_type = $1 AND pubkey = $2 AND uuid = ANY($3) AND acked_at IS NULL
+            "#,
+            data_type as i32,
+            pubkey_hex,
+            uuids,
+            chrono::Utc::now().timestamp()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Issues a token to confirm the deletion of the data of the user, replacing the previous one.
+    pub async fn request_data_deletion(&self, pubkey: U256) -> Result<(String, u64)> {
+        let pubkey_hex = pubkey.to_hex();
+        let token = Uuid::new_v4().to_string();
+        let expires_at = chrono::Utc::now().timestamp() + DATA_DELETION_TOKEN_TTL;
+        sqlx::query!(
+            r#"
+            INSERT INTO data_deletion_requests (pubkey, token, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (pubkey) DO UPDATE SET token = EXCLUDED.token,
+            expires_at = EXCLUDED.expires_at
+            "#,
+            pubkey_hex,
+            token,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok((token, expires_at as u64))
+    }
+
+    /// Deletes the user data with its history and all entries of the user, together with the
+    /// acknowledged sender proof sets of `ephemeral_pubkeys`, and leaves a tombstone of the
+    /// account. The sender proof sets which are not acknowledged yet may still be needed by the
+    /// recipients to receive the transfers, so they are left to the retention. The token is
+    /// consumed, so it cannot be used twice.
+    pub async fn delete_account_data(
+        &self,
+        pubkey: U256,
+        token: &str,
+        ephemeral_pubkeys: &[U256],
+    ) -> Result<DeletedAccountData> {
+        let pubkey_hex = pubkey.to_hex();
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        let confirmed = sqlx::query!(
+            r#"
+            DELETE FROM data_deletion_requests
+            WHERE pubkey = $1 AND token = $2 AND expires_at > $3
+            "#,
+            pubkey_hex,
+            token,
+            now
+        )
+        .execute(tx.as_mut())
+        .await?;
+        if confirmed.rows_affected() == 0 {
+            return Err(StoreVaultError::InvalidConfirmationToken);
+        }
+
+        let mut released = Vec::new();
+        let current = sqlx::query_scalar!(
+            r#"
+            DELETE FROM encrypted_user_data WHERE pubkey = $1
+            RETURNING digest
+            "#,
+            pubkey_hex
+        )
+        .fetch_all(tx.as_mut())
+        .await?;
+        let history = sqlx::query_scalar!(
+            r#"
+            DELETE FROM encrypted_user_data_history WHERE pubkey = $1
+            RETURNING digest
+            "#,
+            pubkey_hex
+        )
+        .fetch_all(tx.as_mut())
+        .await?;
+        let user_data_versions = (current.len() + history.len()) as u64;
+        released.extend(current.into_iter().chain(history));
+
+        let entries = sqlx::query!(
+            r#"
+            DELETE FROM encrypted_data WHERE pubkey = $1
+            "#,
+            pubkey_hex
+        )
+        .execute(tx.as_mut())
+        .await?;
+
+        let ephemeral_pubkeys = ephemeral_pubkeys
+            .iter()
+            .map(|pubkey| pubkey.to_hex())
+            .collect::<Vec<_>>();
+        let sender_proof_sets = sqlx::query_scalar!(
+            r#"
+            DELETE FROM encrypted_sender_proof_set
+            WHERE pubkey = ANY($1) AND acked_at IS NOT NULL
+            RETURNING digest
+            "#,
+            &ephemeral_pubkeys
+        )
+        .fetch_all(tx.as_mut())
+        .await?;
+        let deleted_sender_proof_sets = sender_proof_sets.len() as u64;
+        released.extend(sender_proof_sets);
+        let skipped_sender_proof_sets = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM encrypted_sender_proof_set WHERE pubkey = ANY($1)
+            "#,
+            &ephemeral_pubkeys
+        )
+        .fetch_one(tx.as_mut())
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO deleted_accounts (pubkey, deleted_at) VALUES ($1, $2)
+            ON CONFLICT (pubkey) DO UPDATE SET deleted_at = EXCLUDED.deleted_at
+            "#,
+            pubkey_hex,
+            now
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+
+        self.release_blobs(
+            released
+                .iter()
+                .map(|digest| Bytes32::from_bytes_be(digest))
+                .collect(),
+        )
+        .await;
+        Ok(DeletedAccountData {
+            user_data_versions,
+            entries: entries.rows_affected(),
+            sender_proof_sets: deleted_sender_proof_sets,
+            skipped_sender_proof_sets: skipped_sender_proof_sets as u64,
+        })
+    }
+
+    pub fn gc_metrics(&self) -> GetGcMetricsResponse {
+        self.gc_metrics.snapshot(self.gc_config.is_enabled())
+    }
+
+    /// Deletes the entries whose retention has passed, in batches of `gc_batch_size` rows.
+    pub async fn gc(&self) -> Result<()> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let result = self.gc_inner(now).await;
+        self.gc_metrics.record_run(now, result.is_ok());
+        result
+    }
+
+    async fn gc_inner(&self, now: u64) -> Result<()> {
+        for (target, policy) in self.gc_config.policies.iter() {
+            let (created_before, acked_before) = policy.cutoffs(now);
+            let mut total = Reclaimed::default();
+            loop {
+                let reclaimed = self
+                    .delete_expired(*target, created_before, acked_before)
+                    .await?;
+                self.gc_metrics.record_reclaimed(*target, reclaimed);
+                total += reclaimed;
+                if reclaimed.rows < self.gc_config.batch_size {
+                    break;
+                }
+            }
+            if total.rows > 0 {
+                log::info!(
+                    "GC deleted {} {} entries, reclaimed {} bytes",
+                    total.rows,
+                    target,
+                    total.bytes
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_expired(
+        &self,
+        target: GcTarget,
+        created_before: Option<i64>,
+        acked_before: Option<i64>,
+    ) -> Result<Reclaimed> {
+        let limit = self.gc_config.batch_size as i64;
+        let (rows, bytes) = match target {
+            GcTarget::Data(data_type) => {
+                let record = sqlx::query!(
+                    r#"
+                    WITH deleted AS (
+                        DELETE FROM encrypted_data WHERE uuid IN (
+                            SELECT uuid FROM encrypted_data
+                            WHERE data_type = $1
+                            AND (timestamp < $2 OR acked_at < $3)
+                            LIMIT $4
+                        )
+                        RETURNING octet_length(encrypted_data) AS size
+                    )
+                    SELECT COUNT(*) AS "rows!", COALESCE(SUM(size), 0)::BIGINT AS "bytes!"
+                    FROM deleted
+                    "#,
+                    data_type as i32,
+                    created_before,
+                    acked_before,
+                    limit
+                )
+                .fetch_one(&self.pool)
+                .await?;
+                (record.rows, record.bytes)
+            }
+            GcTarget::SenderProofSet => {
+                let records = sqlx::query!(
+                    r#"
+                    DELETE FROM encrypted_sender_proof_set WHERE pubkey IN (
+                        SELECT pubkey FROM encrypted_sender_proof_set
+                        WHERE timestamp < $1 OR acked_at < $2
+                        LIMIT $3
+                    )
+                    RETURNING digest, size
+                    "#,
+                    created_before,
+                    acked_before,
+                    limit
+                )
+                .fetch_all(&self.pool)
+                .await?;
+                let rows = records.len() as i64;
+                let bytes = records.iter().map(|r| r.size).sum::<i64>();
+                self.release_blobs(
+                    records
+                        .iter()
+                        .map(|r| Bytes32::from_bytes_be(&r.digest))
+                        .collect(),
+                )
+                .await;
+                (rows, bytes)
+            }
+        };
+        Ok(Reclaimed {
+            rows: rows as u64,
+            bytes: bytes as u64,
+        })
+    }
+
+    /// Returns the blob inlined in the row by the versions before the blob storage, or the blob
+    /// in the blob storage otherwise.
+    async fn load_blob(&self, inline: Option<Vec<u8>>, digest: &[u8]) -> Result<Vec<u8>> {
+        if let Some(data) = inline {
+            return Ok(data);
+        }
+        let digest = Bytes32::from_bytes_be(digest);
+        self.blob_storage
+            .get(digest)
+            .await?
+            .ok_or(StoreVaultError::BlobNotFound(digest))
+    }
+
+    /// Deletes the blobs which are no longer referred to by any row. Failures are only logged,
+    /// since a leftover blob does no harm other than taking space.
+    async fn release_blobs(&self, digests: Vec<Bytes32>) {
+        for digest in digests {
+            if let Err(e) = self.release_blob(digest).await {
+                log::warn!("Failed to release blob {}: {}", digest, e);
+            }
+        }
+    }
+
+    async fn release_blob(&self, digest: Bytes32) -> Result<()> {
+        let digest_serialized = digest.to_bytes_be();
+        let referred = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM encrypted_user_data WHERE digest = $1)
+            OR EXISTS (SELECT 1 FROM encrypted_user_data_history WHERE digest = $1)
+            OR EXISTS (SELECT 1 FROM encrypted_sender_proof_set WHERE digest = $1)
+            AS "referred!"
+            "#,
+            digest_serialized
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !referred {
+            self.blob_storage.delete(digest).await?;
+        }
+        Ok(())
+    }
+
+    /// Moves the blobs inlined in the rows by the versions before the blob storage to the blob
+    /// storage, in batches of `OFFLOAD_BATCH_SIZE` rows. Returns the number of moved blobs.
+    pub async fn offload_inline_blobs(&self) -> Result<u64> {
+        let mut total = 0;
+        loop {
+            let records = sqlx::query!(
+                r#"
+                SELECT pubkey, version, encrypted_data AS "encrypted_data!", digest
+                FROM encrypted_user_data WHERE encrypted_data IS NOT NULL
+                LIMIT $1
+                "#,
+                OFFLOAD_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            for r in records.iter() {
+                self.offload_blob(&r.digest, &r.encrypted_data).await?;
+                sqlx::query!(
+                    r#"
+                    UPDATE encrypted_user_data SET encrypted_data = NULL
+                    WHERE pubkey = $1 AND version = $2
+                    "#,
+                    r.pubkey,
+                    r.version
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            total += records.len() as u64;
+            if (records.len() as i64) < OFFLOAD_BATCH_SIZE {
+                break;
+            }
+        }
+        loop {
+            let records = sqlx::query!(
+                r#"
+                SELECT pubkey, version, encrypted_data AS "encrypted_data!", digest
+                FROM encrypted_user_data_history WHERE encrypted_data IS NOT NULL
+                LIMIT $1
+                "#,
+                OFFLOAD_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            for r in records.iter() {
+                self.offload_blob(&r.digest, &r.encrypted_data).await?;
+                sqlx::query!(
+                    r#"
+                    UPDATE encrypted_user_data_history SET encrypted_data = NULL
+                    WHERE pubkey = $1 AND version = $2
+                    "#,
+                    r.pubkey,
+                    r.version
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            total += records.len() as u64;
+            if (records.len() as i64) < OFFLOAD_BATCH_SIZE {
+                break;
+            }
+        }
+        loop {
+            let records = sqlx::query!(
+                r#"
+                SELECT pubkey, encrypted_data AS "encrypted_data!", digest
+                FROM encrypted_sender_proof_set WHERE encrypted_data IS NOT NULL
+                LIMIT $1
+                "#,
+                OFFLOAD_BATCH_SIZE
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            for r in records.iter() {
+                self.offload_blob(&r.digest, &r.encrypted_data).await?;
+                sqlx::query!(
+                    r#"
+                    UPDATE encrypted_sender_proof_set SET encrypted_data = NULL
+                    WHERE pubkey = $1
+                    "#,
+                    r.pubkey
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            total += records.len() as u64;
+            if (records.len() as i64) < OFFLOAD_BATCH_SIZE {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    async fn offload_blob(&self, digest: &[u8], data: &[u8]) -> Result<()> {
+        let digest = Bytes32::from_bytes_be(digest);
+        if get_digest(data) != digest {
+            return Err(StoreVaultError::BlobDigestMismatch(digest));
+        }
+        self.blob_storage.put(digest, data).await?;
+        Ok(())
+    }
+
+    pub fn offload_inline_blobs_job(&self) {
+        let server = self.clone();
+        actix_web::rt::spawn(async move {
+            match server.offload_inline_blobs().await {
+                Ok(0) => {}
+                Ok(n) => log::info!("Moved {} inline blobs to the blob storage", n),
+                Err(e) => log::error!("Error in moving inline blobs: {}", e),
+            }
+        });
+    }
+
+    pub fn gc_job(&self) {
+        if !self.gc_config.is_enabled() {
+            log::info!("GC is disabled since no retention is configured");
+            return;
+        }
+        let server = self.clone();
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = server.gc().await {
+                    log::error!("Error in GC: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(server.gc_config.interval)).await;
+            }
+        });
+    }
 }
 
 #[cfg(test)]
@@ -224,7 +915,10 @@ mod tests {
     use intmax2_interfaces::{data::user_data::UserData, utils::digest::get_digest};
     use intmax2_zkp::common::signature::key_set::KeySet;
 
-    use crate::{app::store_vault_server::StoreVaultServer, EnvVar};
+    use crate::{
+        app::{error::StoreVaultError, store_vault_server::StoreVaultServer},
+        EnvVar,
+    };
 
     #[tokio::test]
     async fn test_get_and_save() -> anyhow::Result<()> {
@@ -249,10 +943,83 @@ mod tests {
         assert_eq!(digest, digest2);
 
         user_data.deposit_lpt = 1;
-        let encrypted = user_data.encrypt(key.pubkey);
+        let encrypted2 = user_data.encrypt(key.pubkey);
         store_vault_server
-            .save_user_data(key.pubkey, Some(digest), &encrypted)
+            .save_user_data(key.pubkey, Some(digest), &encrypted2)
+            .await?;
+
+        // the first version is kept in the history
+        let versions = store_vault_server
+            .list_user_data_versions(key.pubkey)
+            .await?;
+        assert_eq!(
+            versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(versions[1].digest, digest);
+        let prior = store_vault_server
+            .get_user_data_version(key.pubkey, 1)
+            .await?;
+        assert_eq!(prior, Some(encrypted));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_data() -> anyhow::Result<()> {
+        dotenv::dotenv().ok();
+        let env: EnvVar = envy::from_env()?;
+        let store_vault_server = StoreVaultServer::new(&env).await?;
+        let mut rng = rand::thread_rng();
+        let key = KeySet::rand(&mut rng);
+        let encrypted = UserData::new(key.pubkey).encrypt(key.pubkey);
+        store_vault_server
+            .save_user_data(key.pubkey, None, &encrypted)
+            .await?;
+
+        // one sender proof set acknowledged by its only recipient and one still pending
+        let acked = KeySet::rand(&mut rng).pubkey;
+        store_vault_server
+            .save_sender_proof_set(acked, &[1, 2, 3], Some(1))
+            .await?;
+        store_vault_server
+            .acknowledge_sender_proof_set(acked, 0)
+            .await?;
+        let pending = KeySet::rand(&mut rng).pubkey;
+        store_vault_server
+            .save_sender_proof_set(pending, &[4, 5, 6], Some(1))
+            .await?;
+
+        let (token, _) = store_vault_server.request_data_deletion(key.pubkey).await?;
+        assert!(matches!(
+            store_vault_server
+                .delete_account_data(key.pubkey, "invalid", &[])
+                .await,
+            Err(StoreVaultError::InvalidConfirmationToken)
+        ));
+        let deleted = store_vault_server
+            .delete_account_data(key.pubkey, &token, &[acked, pending])
             .await?;
+        assert_eq!(deleted.user_data_versions, 1);
+        assert_eq!(deleted.sender_proof_sets, 1);
+        assert_eq!(deleted.skipped_sender_proof_sets, 1);
+        assert!(store_vault_server
+            .get_sender_proof_set(acked)
+            .await
+            .is_err());
+        assert!(store_vault_server
+            .get_sender_proof_set(pending)
+            .await
+            .is_ok());
+        assert!(matches!(
+            store_vault_server.get_user_data(key.pubkey).await,
+            Err(StoreVaultError::AccountDeleted(_))
+        ));
+        // the token is consumed
+        assert!(store_vault_server
+            .delete_account_data(key.pubkey, &token, &[])
+            .await
+            .is_err());
 
         Ok(())
     }
@@ -1,3 +1,4 @@
+use app::blob_storage::BlobStorageType;
 use serde::Deserialize;
 
 pub mod api;
@@ -9,4 +10,47 @@ pub struct EnvVar {
     pub database_url: String,
     pub database_max_connections: u32,
     pub database_timeout: u64,
+
+    // Storage of the user data and the sender proof sets. `S3_*` are for an S3 compatible object
+    // store, and the credentials are read from `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`.
+    pub blob_storage: Option<BlobStorageType>,
+    pub blob_storage_dir: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_endpoint: Option<String>,
+
+    /// Number of prior versions of the user data kept per pubkey
+    pub user_data_history_size: Option<u32>,
+
+    // Garbage collection. Retentions are in seconds, and unset ones keep the entries forever.
+    pub gc_interval: Option<u64>,
+    pub gc_batch_size: Option<u64>,
+    pub deposit_retention: Option<u64>,
+    pub deposit_acked_retention: Option<u64>,
+    pub transfer_retention: Option<u64>,
+    pub transfer_acked_retention: Option<u64>,
+    pub withdrawal_retention: Option<u64>,
+    pub withdrawal_acked_retention: Option<u64>,
+    pub tx_retention: Option<u64>,
+    pub tx_acked_retention: Option<u64>,
+    pub sender_proof_set_retention: Option<u64>,
+    pub sender_proof_set_acked_retention: Option<u64>,
+
+    // Spam protection of the entries written to the inboxes of other users
+    pub max_entry_size: Option<usize>,
+    pub rate_limit_window: Option<u64>,
+    pub max_entries_per_source: Option<u32>,
+    pub max_entries_per_recipient: Option<u32>,
+    pub pow_difficulty: Option<u32>,
+    /// Use the client IP in `X-Forwarded-For` for the rate limits, if behind a trusted proxy
+    pub trust_forwarded_for: Option<bool>,
+
+    /// Maximum number of concurrent subscriptions to the notifications of new entries
+    pub max_subscriptions: Option<usize>,
+
+    // Session tokens. The secret should be shared by all instances, and the ttl is in seconds.
+    pub session_secret: Option<String>,
+    pub session_ttl: Option<u64>,
+
+    /// Accepts the signed requests without a nonce of the older clients, until they are updated
+    pub accept_legacy_auth: Option<bool>,
 }
@@ -1,21 +1,37 @@
 use actix_cors::Cors;
 use actix_web::{
     middleware::Logger,
-    web::{Data, JsonConfig},
+    web::{self, Data},
     App, HttpServer,
 };
+use intmax2_interfaces::api::session::SessionAudience;
 use server_common::{
+    auth::Authenticator,
+    error::{json_config, not_found},
     health_check::{health_check, set_name_and_version},
     logger::init_logger,
+    openapi::openapi_service,
+};
+use std::{
+    io::{self},
+    sync::Arc,
 };
-use std::io::{self};
 use store_vault_server::{
-    api::{routes::store_vault_server_scope, state::State},
-    app::store_vault_server::StoreVaultServer,
+    api::{
+        routes::{store_vault_server_openapi, store_vault_server_scope},
+        state::State,
+    },
+    app::{
+        notifier::Notifier,
+        spam_filter::{SpamFilter, SpamFilterConfig},
+        store_vault_server::StoreVaultServer,
+    },
     // middleware::authorization::authorization_middleware,
     EnvVar,
 };
 
+const DEFAULT_MAX_SUBSCRIPTIONS: usize = 10_000;
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     set_name_and_version(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
@@ -35,7 +51,24 @@ async fn main() -> std::io::Result<()> {
             format!("Failed to initialize store_vault_server: {}", e),
         )
     })?;
-    let state = Data::new(State::new(store_vault_server));
+    store_vault_server.offload_inline_blobs_job();
+    store_vault_server.gc_job();
+    let notifier = Arc::new(Notifier::new(
+        env.max_subscriptions.unwrap_or(DEFAULT_MAX_SUBSCRIPTIONS),
+    ));
+    notifier.listen_job(env.database_url.clone());
+    let state = Data::new(State::new(
+        store_vault_server,
+        SpamFilter::new(SpamFilterConfig::from_env(&env)),
+        notifier,
+        Authenticator::new(
+            SessionAudience::StoreVaultServer,
+            env.session_secret.as_deref(),
+            env.session_ttl,
+            env.accept_legacy_auth.unwrap_or(false),
+        ),
+        env.trust_forwarded_for.unwrap_or(false),
+    ));
 
     HttpServer::new(move || {
         let cors = Cors::permissive();
@@ -43,10 +76,12 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .wrap(Logger::new("Request: %r | Status: %s | Duration: %Ts"))
             // .wrap(from_fn(authorization_middleware))
-            .app_data(JsonConfig::default().limit(35_000_000))
+            .app_data(json_config().limit(35_000_000))
             .app_data(state.clone())
             .service(health_check)
             .service(store_vault_server_scope())
+            .service(openapi_service(store_vault_server_openapi()))
+            .default_service(web::to(not_found))
     })
     .bind(format!("0.0.0.0:{}", env.port))?
     .run()
@@ -7,19 +7,39 @@ use intmax2_interfaces::api::validity_prover::types::{
     AssignResponse, CompleteRequest, HeartBeatRequest,
 };
 
+use intmax2_interfaces::api::error::ErrorResponse;
+use server_common::{api_scope, error::ApiError};
+
 use crate::api::state::State;
 
+#[utoipa::path(
+    post,
+    path = "/assign",
+    responses(
+        (status = 200, body = AssignResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/assign")]
 pub async fn assign_task(data: Data<State>) -> Result<Json<AssignResponse>, Error> {
     let assigned_task = data.coordinator.assign_task().await.map_err(|e| {
         log::error!("Failed to assign task: {:?}", e);
-        actix_web::error::ErrorInternalServerError(e)
+        ApiError::internal(e)
     })?;
     Ok(Json(AssignResponse {
         task: assigned_task,
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/complete",
+    request_body = CompleteRequest,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/complete")]
 pub async fn complete_task(
     data: Data<State>,
@@ -30,11 +50,20 @@ pub async fn complete_task(
         .await
         .map_err(|e| {
             log::error!("Failed to complete task: {:?}", e);
-            actix_web::error::ErrorInternalServerError(e)
+            ApiError::internal(e)
         })?;
     Ok(Json(()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/heartbeat",
+    request_body = HeartBeatRequest,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/heartbeat")]
 pub async fn heartbeat(
     data: Data<State>,
@@ -45,14 +74,14 @@ pub async fn heartbeat(
         .await
         .map_err(|e| {
             log::error!("Failed to heartbeat: {:?}", e);
-            actix_web::error::ErrorInternalServerError(e)
+            ApiError::internal(e)
         })?;
     Ok(Json(()))
 }
 
-pub fn coordinator_scope() -> actix_web::Scope {
-    actix_web::web::scope("/coordinator")
-        .service(assign_task)
-        .service(complete_task)
-        .service(heartbeat)
-}
+api_scope!(
+    coordinator_scope,
+    coordinator_openapi,
+    "/coordinator",
+    [assign_task, complete_task, heartbeat,]
+);
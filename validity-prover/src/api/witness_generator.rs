@@ -1,72 +1,117 @@
 use crate::api::state::State;
-use actix_web::{
-    get,
-    web::{Data, Json},
-    Error,
-};
-use intmax2_interfaces::api::validity_prover::types::{
-    GetAccountInfoQuery, GetAccountInfoResponse, GetBlockMerkleProofQuery,
-    GetBlockMerkleProofResponse, GetBlockNumberByTxTreeRootQuery,
-    GetBlockNumberByTxTreeRootResponse, GetBlockNumberResponse, GetDepositInfoQuery,
-    GetDepositInfoResponse, GetDepositMerkleProofQuery, GetDepositMerkleProofResponse,
-    GetNextDepositIndexResponse, GetSenderLeavesQuery, GetSenderLeavesResponse,
-    GetUpdateWitnessQuery, GetUpdateWitnessResponse, GetValidityPisQuery, GetValidityPisResponse,
+use actix_web::{get, web::Data, Error};
+use intmax2_interfaces::api::{
+    error::ErrorResponse,
+    validity_prover::types::{
+        GetAccountInfoQuery, GetAccountInfoResponse, GetBlockMerkleProofQuery,
+        GetBlockMerkleProofResponse, GetBlockNumberByTxTreeRootQuery,
+        GetBlockNumberByTxTreeRootResponse, GetBlockNumberResponse, GetDepositInfoQuery,
+        GetDepositInfoResponse, GetDepositMerkleProofQuery, GetDepositMerkleProofResponse,
+        GetNextDepositIndexResponse, GetSenderLeavesQuery, GetSenderLeavesResponse,
+        GetUpdateWitnessQuery, GetUpdateWitnessResponse, GetValidityPisQuery,
+        GetValidityPisResponse,
+    },
 };
 use serde_qs::actix::QsQuery;
+use server_common::{api_scope, encoding::Encoded, error::ApiError};
 
+#[utoipa::path(
+    get,
+    path = "/block-number",
+    responses(
+        (status = 200, body = GetBlockNumberResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/block-number")]
-pub async fn get_block_number(state: Data<State>) -> Result<Json<GetBlockNumberResponse>, Error> {
+pub async fn get_block_number(
+    state: Data<State>,
+) -> Result<Encoded<GetBlockNumberResponse>, Error> {
     let block_number = state
         .witness_generator
         .get_last_block_number()
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetBlockNumberResponse { block_number }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetBlockNumberResponse { block_number }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/validity-proof-block-number",
+    responses(
+        (status = 200, body = GetBlockNumberResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/validity-proof-block-number")]
 pub async fn get_validity_proof_block_number(
     state: Data<State>,
-) -> Result<Json<GetBlockNumberResponse>, Error> {
+) -> Result<Encoded<GetBlockNumberResponse>, Error> {
     let block_number = state
         .witness_generator
         .get_latest_validity_proof_block_number()
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetBlockNumberResponse { block_number }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetBlockNumberResponse { block_number }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/next-deposit-index",
+    responses(
+        (status = 200, body = GetNextDepositIndexResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/next-deposit-index")]
 pub async fn get_next_deposit_index(
     state: Data<State>,
-) -> Result<Json<GetNextDepositIndexResponse>, Error> {
+) -> Result<Encoded<GetNextDepositIndexResponse>, Error> {
     let deposit_index = state
         .witness_generator
         .get_next_deposit_index()
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetNextDepositIndexResponse { deposit_index }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetNextDepositIndexResponse { deposit_index }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-account-info",
+    params(GetAccountInfoQuery),
+    responses(
+        (status = 200, body = GetAccountInfoResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-account-info")]
 pub async fn get_account_info(
     state: Data<State>,
     query: QsQuery<GetAccountInfoQuery>,
-) -> Result<Json<GetAccountInfoResponse>, Error> {
+) -> Result<Encoded<GetAccountInfoResponse>, Error> {
     let query = query.into_inner();
     let account_info = state
         .witness_generator
         .get_account_info(query.pubkey)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetAccountInfoResponse { account_info }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetAccountInfoResponse { account_info }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-update-witness",
+    params(GetUpdateWitnessQuery),
+    responses(
+        (status = 200, body = GetUpdateWitnessResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-update-witness")]
 pub async fn get_update_witness(
     state: Data<State>,
     query: QsQuery<GetUpdateWitnessQuery>,
-) -> Result<Json<GetUpdateWitnessResponse>, Error> {
+) -> Result<Encoded<GetUpdateWitnessResponse>, Error> {
     let query = query.into_inner();
     let update_witness = state
         .witness_generator
@@ -77,107 +122,207 @@ pub async fn get_update_witness(
             query.is_prev_account_tree,
         )
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetUpdateWitnessResponse { update_witness }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetUpdateWitnessResponse { update_witness }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-deposit-info",
+    params(GetDepositInfoQuery),
+    responses(
+        (status = 200, body = GetDepositInfoResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-deposit-info")]
 pub async fn get_deposit_info(
     state: Data<State>,
     query: QsQuery<GetDepositInfoQuery>,
-) -> Result<Json<GetDepositInfoResponse>, Error> {
+) -> Result<Encoded<GetDepositInfoResponse>, Error> {
     let query = query.into_inner();
     let deposit_info = state
         .witness_generator
         .get_deposit_info(query.deposit_hash)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetDepositInfoResponse { deposit_info }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetDepositInfoResponse { deposit_info }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-block-number-by-tx-tree-root",
+    params(GetBlockNumberByTxTreeRootQuery),
+    responses(
+        (status = 200, body = GetBlockNumberByTxTreeRootResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-block-number-by-tx-tree-root")]
 pub async fn get_block_number_by_tx_tree_root(
     state: Data<State>,
     query: QsQuery<GetBlockNumberByTxTreeRootQuery>,
-) -> Result<Json<GetBlockNumberByTxTreeRootResponse>, Error> {
+) -> Result<Encoded<GetBlockNumberByTxTreeRootResponse>, Error> {
     let query = query.into_inner();
     let block_number = state
         .witness_generator
         .get_block_number_by_tx_tree_root(query.tx_tree_root)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetBlockNumberByTxTreeRootResponse { block_number }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetBlockNumberByTxTreeRootResponse { block_number }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-validity-pis",
+    params(GetValidityPisQuery),
+    responses(
+        (status = 200, body = GetValidityPisResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-validity-pis")]
 pub async fn get_validity_pis(
     state: Data<State>,
     query: QsQuery<GetValidityPisQuery>,
-) -> Result<Json<GetValidityPisResponse>, Error> {
+) -> Result<Encoded<GetValidityPisResponse>, Error> {
     let query = query.into_inner();
     let validity_pis = state
         .witness_generator
         .get_validity_pis(query.block_number)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetValidityPisResponse { validity_pis }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetValidityPisResponse { validity_pis }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-sender-leaves",
+    params(GetSenderLeavesQuery),
+    responses(
+        (status = 200, body = GetSenderLeavesResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-sender-leaves")]
 pub async fn get_sender_leaves(
     state: Data<State>,
     query: QsQuery<GetSenderLeavesQuery>,
-) -> Result<Json<GetSenderLeavesResponse>, Error> {
+) -> Result<Encoded<GetSenderLeavesResponse>, Error> {
     let query = query.into_inner();
     let sender_leaves = state
         .witness_generator
         .get_sender_leaves(query.block_number)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetSenderLeavesResponse { sender_leaves }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetSenderLeavesResponse { sender_leaves }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-block-merkle-proof",
+    params(GetBlockMerkleProofQuery),
+    responses(
+        (status = 200, body = GetBlockMerkleProofResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-block-merkle-proof")]
 pub async fn get_block_merkle_proof(
     state: Data<State>,
     query: QsQuery<GetBlockMerkleProofQuery>,
-) -> Result<Json<GetBlockMerkleProofResponse>, Error> {
+) -> Result<Encoded<GetBlockMerkleProofResponse>, Error> {
     let query = query.into_inner();
     let block_merkle_proof = state
         .witness_generator
         .get_block_merkle_proof(query.root_block_number, query.leaf_block_number)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetBlockMerkleProofResponse { block_merkle_proof }))
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetBlockMerkleProofResponse { block_merkle_proof }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-deposit-merkle-proof",
+    params(GetDepositMerkleProofQuery),
+    responses(
+        (status = 200, body = GetDepositMerkleProofResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-deposit-merkle-proof")]
 pub async fn get_deposit_merkle_proof(
     state: Data<State>,
     query: QsQuery<GetDepositMerkleProofQuery>,
-) -> Result<Json<GetDepositMerkleProofResponse>, Error> {
+) -> Result<Encoded<GetDepositMerkleProofResponse>, Error> {
     let query = query.into_inner();
     let deposit_merkle_proof = state
         .witness_generator
         .get_deposit_merkle_proof(query.block_number, query.deposit_index)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(Json(GetDepositMerkleProofResponse {
+        .map_err(ApiError::from)?;
+    Ok(Encoded(GetDepositMerkleProofResponse {
         deposit_merkle_proof,
     }))
 }
 
-pub fn validity_prover_scope() -> actix_web::Scope {
-    actix_web::web::scope("/validity-prover")
-        .service(get_block_number)
-        .service(get_validity_proof_block_number)
-        .service(get_next_deposit_index)
-        .service(get_account_info)
-        .service(get_update_witness)
-        .service(get_deposit_info)
-        .service(get_block_number_by_tx_tree_root)
-        .service(get_validity_pis)
-        .service(get_sender_leaves)
-        .service(get_block_merkle_proof)
-        .service(get_deposit_merkle_proof)
+api_scope!(
+    validity_prover_scope,
+    validity_prover_openapi,
+    "/validity-prover",
+    [
+        get_block_number,
+        get_validity_proof_block_number,
+        get_next_deposit_index,
+        get_account_info,
+        get_update_witness,
+        get_deposit_info,
+        get_block_number_by_tx_tree_root,
+        get_validity_pis,
+        get_sender_leaves,
+        get_block_merkle_proof,
+        get_deposit_merkle_proof,
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use server_common::openapi::assert_spec_matches_routes;
+
+    use super::{validity_prover_openapi, validity_prover_scope};
+    use crate::api::coordinator::{coordinator_openapi, coordinator_scope};
+
+    #[actix_web::test]
+    async fn test_openapi_spec_matches_routes() {
+        let mut spec = validity_prover_openapi();
+        spec.merge(coordinator_openapi());
+        let app = test::init_service(
+            App::new()
+                .service(validity_prover_scope())
+                .service(coordinator_scope()),
+        )
+        .await;
+        assert_spec_matches_routes(
+            &app,
+            &spec,
+            &[
+                ("GET", "/validity-prover/block-number"),
+                ("GET", "/validity-prover/get-account-info"),
+                ("GET", "/validity-prover/get-block-merkle-proof"),
+                ("GET", "/validity-prover/get-block-number-by-tx-tree-root"),
+                ("GET", "/validity-prover/get-deposit-info"),
+                ("GET", "/validity-prover/get-deposit-merkle-proof"),
+                ("GET", "/validity-prover/get-sender-leaves"),
+                ("GET", "/validity-prover/get-update-witness"),
+                ("GET", "/validity-prover/get-validity-pis"),
+                ("GET", "/validity-prover/next-deposit-index"),
+                ("GET", "/validity-prover/validity-proof-block-number"),
+                ("POST", "/coordinator/assign"),
+                ("POST", "/coordinator/complete"),
+                ("POST", "/coordinator/heartbeat"),
+            ],
+        )
+        .await;
+    }
 }
@@ -1,5 +1,7 @@
 use intmax2_client_sdk::external_api::contract::error::BlockchainError;
+use intmax2_interfaces::api::error::ErrorCode;
 use intmax2_zkp::ethereum_types::bytes32::Bytes32;
+use server_common::error::ApiError;
 
 use crate::trees::merkle_tree::error::MerkleTreeError;
 
@@ -72,6 +74,27 @@ pub enum ValidityProverError {
     InputError(String),
 }
 
+impl From<ValidityProverError> for ApiError {
+    fn from(e: ValidityProverError) -> Self {
+        let code = match &e {
+            ValidityProverError::DBError(_)
+            | ValidityProverError::ObserverError(ObserverError::DBError(_)) => {
+                ErrorCode::Unavailable
+            }
+            ValidityProverError::ValidityProofNotFound(_)
+            | ValidityProverError::BlockTreeNotFound(_)
+            | ValidityProverError::AccountTreeNotFound(_)
+            | ValidityProverError::DepositTreeRootNotFound(_)
+            | ValidityProverError::ObserverError(ObserverError::BlockNotFound(_)) => {
+                ErrorCode::NotFound
+            }
+            ValidityProverError::InputError(_) => ErrorCode::BadRequest,
+            _ => ErrorCode::Internal,
+        };
+        ApiError::new(code, e)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ProverCoordinatorError {
     #[error("Database error: {0}")]
@@ -1,13 +1,23 @@
 use actix_cors::Cors;
-use actix_web::{middleware::Logger, web::Data, App, HttpServer};
+use actix_web::{
+    middleware::Logger,
+    web::{self, Data},
+    App, HttpServer,
+};
 use server_common::{
+    error::{json_config, not_found, qs_query_config},
     health_check::{health_check, set_name_and_version},
     logger::init_logger,
+    openapi::openapi_service,
 };
 use std::io::{self};
 
 use validity_prover::{
-    api::{coordinator::coordinator_scope, state::State, witness_generator::validity_prover_scope},
+    api::{
+        coordinator::{coordinator_openapi, coordinator_scope},
+        state::State,
+        witness_generator::{validity_prover_openapi, validity_prover_scope},
+    },
     Env,
 };
 
@@ -34,6 +44,8 @@ async fn main() -> std::io::Result<()> {
     state.job();
 
     let data = Data::new(state.clone());
+    let mut spec = validity_prover_openapi();
+    spec.merge(coordinator_openapi());
 
     HttpServer::new(move || {
         let cors = Cors::permissive();
@@ -41,9 +53,13 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .wrap(Logger::new("Request: %r | Status: %s | Duration: %Ts"))
             .app_data(data.clone())
+            .app_data(json_config())
+            .app_data(qs_query_config())
             .service(health_check)
             .service(validity_prover_scope())
             .service(coordinator_scope())
+            .service(openapi_service(spec.clone()))
+            .default_service(web::to(not_found))
     })
     .bind(format!("0.0.0.0:{}", env.port))?
     .run()
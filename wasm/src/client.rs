@@ -5,10 +5,12 @@ use intmax2_client_sdk::{
         block_builder::BlockBuilderClient,
         contract::{liquidity_contract::LiquidityContract, rollup_contract::RollupContract},
         store_vault_server::StoreVaultServerClient,
+        utils::transport::HttpTransport,
         validity_prover::ValidityProverClient,
         withdrawal_server::WithdrawalServerClient,
     },
 };
+use intmax2_interfaces::api::encoding::WireFormat;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::wasm_bindgen;
 
@@ -77,6 +79,17 @@ pub struct Config {
 
     /// Scroll block number when the rollup contract was deployed
     pub rollup_contract_deployed_block_number: u64,
+
+    /// Use bincode instead of JSON for the balance prover and the validity prover. False by
+    /// default, and can be set after construction.
+    #[serde(default)]
+    pub binary_wire_encoding: bool,
+
+    /// Authenticate the requests to the store vault server and the withdrawal server by session
+    /// tokens instead of signing each of them. False by default, and can be set after
+    /// construction.
+    #[serde(default)]
+    pub use_sessions: bool,
 }
 
 #[wasm_bindgen]
@@ -124,22 +137,38 @@ impl Config {
             l2_chain_id,
             rollup_contract_address,
             rollup_contract_deployed_block_number,
+            binary_wire_encoding: false,
+            use_sessions: false,
         }
     }
 }
 
 pub fn get_client(config: &Config) -> Client<BB, S, V, B, W> {
-    let block_builder = BB::new();
-    let store_vault_server = S::new(&config.store_vault_server_url);
-    let balance_prover: BalanceProverClient = B::new(&config.balance_prover_url);
-    let validity_prover = V::new(&config.validity_prover_url);
-    let withdrawal_server = W::new(&config.withdrawal_server_url);
+    let transport = HttpTransport::default();
+    let block_builder = BB::with_transport(transport.clone());
+    let mut store_vault_server =
+        S::with_transport(&config.store_vault_server_url, transport.clone());
+    let prover_transport = if config.binary_wire_encoding {
+        transport.with_wire_format(WireFormat::Bincode)
+    } else {
+        transport.clone()
+    };
+    let balance_prover = B::with_transport(&config.balance_prover_url, prover_transport.clone());
+    let validity_prover = V::with_transport(&config.validity_prover_url, prover_transport);
+    let mut withdrawal_server = W::with_transport(&config.withdrawal_server_url, transport);
+    if config.use_sessions {
+        store_vault_server = store_vault_server.with_sessions();
+        withdrawal_server = withdrawal_server.with_sessions();
+    }
 
     let client_config = ClientConfig {
         deposit_timeout: config.deposit_timeout,
         tx_timeout: config.tx_timeout,
         block_builder_request_interval: config.block_builder_request_interval,
         block_builder_request_limit: config.block_builder_request_limit,
+        block_builder_query_wait_time: config.block_builder_query_wait_time,
+        block_builder_query_interval: config.block_builder_query_interval,
+        block_builder_query_limit: config.block_builder_query_limit,
     };
 
     let liquidity_contract = LiquidityContract::new(
@@ -1,13 +1,19 @@
-use intmax2_interfaces::api::withdrawal_server::interface::ContractWithdrawal;
+use intmax2_interfaces::{
+    api::withdrawal_server::interface::ContractWithdrawal,
+    data::deposit_data::TokenType,
+    utils::invoice::{Invoice, InvoiceToken},
+};
 use intmax2_zkp::{
     common::{
         generic_address::GenericAddress, transfer::Transfer, tx::Tx,
         withdrawal::get_withdrawal_nullifier,
     },
-    ethereum_types::{address::Address, u256::U256, u32limb_trait::U32LimbTrait},
+    ethereum_types::{address::Address, u32limb_trait::U32LimbTrait},
 };
 use wasm_bindgen::{prelude::wasm_bindgen, JsError};
 
+use crate::utils::parse_pubkey;
+
 use super::utils::{parse_address, parse_bytes32, parse_poseidon_hashout, parse_salt, parse_u256};
 
 #[derive(Debug, Clone)]
@@ -15,7 +21,8 @@ use super::utils::{parse_address, parse_bytes32, parse_poseidon_hashout, parse_s
 pub struct JsGenericAddress {
     /// true if pubkey, false if ethereum address
     pub is_pubkey: bool,
-    /// hex string of 32 bytes (pubkey) or 20 bytes (ethereum address)
+    /// hex string of 32 bytes (pubkey) or 20 bytes (ethereum address). A pubkey may also be given
+    /// as an intmax address.
     pub data: String,
 }
 
@@ -32,8 +39,7 @@ impl JsGenericAddress {
 
     pub fn to_generic_address(&self) -> Result<GenericAddress, JsError> {
         if self.is_pubkey {
-            let pubkey =
-                U256::from_hex(&self.data).map_err(|_| JsError::new("Failed to parse pubkey"))?;
+            let pubkey = parse_pubkey(&self.data)?;
             Ok(GenericAddress::from_pubkey(pubkey))
         } else {
             let address = Address::from_hex(&self.data)
@@ -49,7 +55,7 @@ impl JsGenericAddress {
     pub fn new(is_pubkey: bool, data: String) -> Result<Self, JsError> {
         // validation
         if is_pubkey {
-            U256::from_hex(&data).map_err(|_| JsError::new("Invalid pubkey"))?;
+            parse_pubkey(&data).map_err(|_| JsError::new("Invalid pubkey"))?;
         } else {
             Address::from_hex(&data).map_err(|_| JsError::new("Invalid address"))?;
         }
@@ -198,3 +204,101 @@ impl JsContractWithdrawal {
         Ok(hash)
     }
 }
+
+#[derive(Debug, Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct JsInvoice {
+    pub recipient: JsGenericAddress,
+    /// Either token_index, or token_type, token_address and token_id are set
+    pub token_index: Option<u32>,
+    pub token_type: Option<u8>,
+    pub token_address: Option<String>, // hex string
+    pub token_id: Option<String>,      // 10 base string
+    pub amount: String,                // 10 base string
+    pub memo: Option<String>,
+    pub expiry: Option<u64>, // unix timestamp in seconds
+}
+
+#[wasm_bindgen]
+impl JsInvoice {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        recipient: JsGenericAddress,
+        token_index: u32,
+        amount: String,
+        memo: Option<String>,
+        expiry: Option<u64>,
+    ) -> Self {
+        Self {
+            recipient,
+            token_index: Some(token_index),
+            token_type: None,
+            token_address: None,
+            token_id: None,
+            amount,
+            memo,
+            expiry,
+        }
+    }
+}
+
+impl JsInvoice {
+    pub fn from_invoice(invoice: &Invoice) -> Self {
+        let (token_index, token_type, token_address, token_id) = match &invoice.token {
+            InvoiceToken::Index(token_index) => (Some(*token_index), None, None, None),
+            InvoiceToken::Contract {
+                token_type,
+                token_address,
+                token_id,
+            } => (
+                None,
+                Some(*token_type as u8),
+                Some(token_address.to_hex()),
+                Some(token_id.to_string()),
+            ),
+        };
+        Self {
+            recipient: JsGenericAddress::from_generic_address(&invoice.recipient),
+            token_index,
+            token_type,
+            token_address,
+            token_id,
+            amount: invoice.amount.to_string(),
+            memo: invoice.memo.clone(),
+            expiry: invoice.expiry,
+        }
+    }
+
+    pub fn to_invoice(&self) -> Result<Invoice, JsError> {
+        let token = match (self.token_index, self.token_type) {
+            (Some(token_index), None) => InvoiceToken::Index(token_index),
+            (None, Some(token_type)) => InvoiceToken::Contract {
+                token_type: TokenType::try_from(token_type).map_err(|e| JsError::new(&e))?,
+                token_address: self
+                    .token_address
+                    .as_deref()
+                    .map(parse_address)
+                    .transpose()?
+                    .unwrap_or_default(),
+                token_id: self
+                    .token_id
+                    .as_deref()
+                    .map(parse_u256)
+                    .transpose()?
+                    .unwrap_or_default(),
+            },
+            _ => return Err(JsError::new("Either token index or token type must be set")),
+        };
+        let invoice = Invoice {
+            recipient: self.recipient.to_generic_address()?,
+            token,
+            amount: parse_u256(&self.amount)?,
+            memo: self.memo.clone(),
+            expiry: self.expiry,
+        };
+        invoice
+            .validate()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(invoice)
+    }
+}
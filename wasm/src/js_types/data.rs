@@ -36,6 +36,8 @@ impl JsDepositData {
 pub struct JsTransferData {
     pub sender: String,
     pub transfer: JsTransfer,
+    /// The memo set by the sender, if any
+    pub memo: Option<String>,
 }
 
 impl JsTransferData {
@@ -43,6 +45,7 @@ impl JsTransferData {
         Self {
             sender: transfer_data.sender.to_hex(),
             transfer: JsTransfer::from_transfer(&transfer_data.transfer),
+            memo: transfer_data.memo.clone(),
         }
     }
 }
@@ -182,10 +185,10 @@ impl JsUserData {
             transfer_lpt: user_data.transfer_lpt,
             tx_lpt: user_data.tx_lpt,
             withdrawal_lpt: user_data.withdrawal_lpt,
-            processed_deposit_uuids: user_data.processed_deposit_uuids.clone(),
-            processed_transfer_uuids: user_data.processed_transfer_uuids.clone(),
-            processed_tx_uuids: user_data.processed_tx_uuids.clone(),
-            processed_withdrawal_uuids: user_data.processed_withdrawal_uuids.clone(),
+            processed_deposit_uuids: user_data.processed_deposit_uuids.uuids(),
+            processed_transfer_uuids: user_data.processed_transfer_uuids.uuids(),
+            processed_tx_uuids: user_data.processed_tx_uuids.uuids(),
+            processed_withdrawal_uuids: user_data.processed_withdrawal_uuids.uuids(),
         }
     }
 }
@@ -4,10 +4,16 @@ use intmax2_client_sdk::{
     client::key_from_eth::generate_intmax_account_from_eth_key as inner_generate_intmax_account_from_eth_key,
     external_api::utils::time::sleep_for,
 };
-use intmax2_interfaces::data::{
-    deposit_data::{DepositData, TokenType},
-    transfer_data::TransferData,
-    tx_data::TxData,
+use intmax2_interfaces::{
+    data::{
+        deposit_data::{DepositData, TokenType},
+        transfer_data::TransferData,
+        tx_data::TxData,
+    },
+    utils::{
+        address::{IntmaxAddress, Network},
+        invoice::Invoice,
+    },
 };
 use intmax2_zkp::{
     common::transfer::Transfer,
@@ -15,13 +21,14 @@ use intmax2_zkp::{
     ethereum_types::{u256::U256, u32limb_trait::U32LimbTrait},
 };
 use js_types::{
-    common::JsTransfer,
+    common::{JsInvoice, JsTransfer},
     data::{JsDepositData, JsDepositResult, JsTransferData, JsTxData, JsTxResult, JsUserData},
     utils::{parse_address, parse_u256},
     wrapper::{JsBlockProposal, JsTxRequestMemo},
 };
 use num_bigint::BigUint;
-use utils::{parse_h256, parse_h256_as_u256, str_privkey_to_keyset};
+use std::str::FromStr as _;
+use utils::{parse_h256, parse_h256_as_u256, parse_pubkey, str_privkey_to_keyset};
 use wasm_bindgen::{prelude::wasm_bindgen, JsError};
 
 pub mod client;
@@ -50,6 +57,23 @@ pub async fn generate_intmax_account_from_eth_key(
     })
 }
 
+/// Encode the pubkey (32bytes hex string) as a checksummed intmax address of the network
+/// ("local", "dev", "staging" or "prod").
+#[wasm_bindgen]
+pub fn encode_intmax_address(pubkey: &str, network: &str) -> Result<String, JsError> {
+    let pubkey = parse_h256_as_u256(pubkey)?;
+    let network = Network::from_str(network).map_err(|e| JsError::new(&e))?;
+    Ok(IntmaxAddress::new(network, pubkey).encode())
+}
+
+/// Decode the intmax address into the pubkey (32bytes hex string), verifying its checksum.
+#[wasm_bindgen]
+pub fn decode_intmax_address(address: &str) -> Result<String, JsError> {
+    let address = IntmaxAddress::decode(address)
+        .map_err(|e| JsError::new(&format!("failed to decode intmax address: {}", e)))?;
+    Ok(address.pubkey.to_hex())
+}
+
 /// Function to take a backup before calling the deposit function of the liquidity contract.
 ///
 /// You can also get the pubkey_salt_hash from the return value.
@@ -65,7 +89,7 @@ pub async fn prepare_deposit(
 ) -> Result<JsDepositResult, JsError> {
     init_logger();
     let depositor = parse_address(depositor)?;
-    let recipient = parse_h256_as_u256(recipient)?;
+    let recipient = parse_pubkey(recipient)?;
     let amount = parse_u256(amount)?;
     let token_type = TokenType::try_from(token_type).map_err(|e| JsError::new(&e))?;
     let token_address = parse_address(token_address)?;
@@ -86,12 +110,16 @@ pub async fn prepare_deposit(
 }
 
 /// Function to send a tx request to the block builder. The return value contains information to take a backup.
+///
+/// `memos` is optional. If given, it must have an entry for each transfer, where an empty string
+/// means no memo.
 #[wasm_bindgen]
 pub async fn send_tx_request(
     config: &Config,
     block_builder_url: &str,
     private_key: &str,
     transfers: Vec<JsTransfer>,
+    memos: Option<Vec<String>>,
 ) -> Result<JsTxRequestMemo, JsError> {
     init_logger();
     if transfers.len() > NUM_TRANSFERS_IN_TX {
@@ -105,10 +133,15 @@ pub async fn send_tx_request(
         .iter()
         .map(|transfer| transfer.to_transfer())
         .collect::<Result<Vec<_>, JsError>>()?;
+    let memos = memos
+        .unwrap_or_default()
+        .into_iter()
+        .map(|memo| if memo.is_empty() { None } else { Some(memo) })
+        .collect();
 
     let client = get_client(config);
     let memo = client
-        .send_tx_request(block_builder_url, key, transfers)
+        .send_tx_request(block_builder_url, key, transfers, memos)
         .await
         .map_err(|e| JsError::new(&format!("failed to send tx request {}", e)))?;
 
@@ -161,6 +194,48 @@ pub async fn finalize_tx(
     Ok(JsTxResult::from_tx_result(&tx_result))
 }
 
+/// Encode the invoice as a URI, which can be shared as text or a QR code. A pubkey recipient is
+/// encoded as an intmax address of the network.
+#[wasm_bindgen]
+pub fn create_invoice(invoice: &JsInvoice, network: &str) -> Result<String, JsError> {
+    let invoice = invoice.to_invoice()?;
+    let network = Network::from_str(network).map_err(|e| JsError::new(&e))?;
+    Ok(invoice.to_uri(network))
+}
+
+/// Parse an invoice URI, rejecting an intmax address recipient of another network.
+#[wasm_bindgen]
+pub fn parse_invoice(uri: &str, network: &str) -> Result<JsInvoice, JsError> {
+    let network = Network::from_str(network).map_err(|e| JsError::new(&e))?;
+    let invoice = Invoice::from_uri(uri, network)
+        .map_err(|e| JsError::new(&format!("failed to parse invoice: {}", e)))?;
+    Ok(JsInvoice::from_invoice(&invoice))
+}
+
+/// Pay the invoice given as a URI by a single transfer.
+///
+/// This goes through send_tx_request, query_proposal and finalize_tx at once.
+#[wasm_bindgen]
+pub async fn pay_invoice(
+    config: &Config,
+    block_builder_url: &str,
+    private_key: &str,
+    uri: &str,
+    network: &str,
+) -> Result<JsTxResult, JsError> {
+    init_logger();
+    let key = str_privkey_to_keyset(private_key)?;
+    let network = Network::from_str(network).map_err(|e| JsError::new(&e))?;
+    let invoice = Invoice::from_uri(uri, network)
+        .map_err(|e| JsError::new(&format!("failed to parse invoice: {}", e)))?;
+    let client = get_client(config);
+    let tx_result = client
+        .pay_invoice(block_builder_url, key, &invoice)
+        .await
+        .map_err(|e| JsError::new(&format!("failed to pay invoice: {}", e)))?;
+    Ok(JsTxResult::from_tx_result(&tx_result))
+}
+
 /// Batch function of query_proposal and finalize_tx.
 #[wasm_bindgen]
 pub async fn query_and_finalize(
@@ -1,4 +1,5 @@
 use ethers::types::H256;
+use intmax2_interfaces::utils::address::IntmaxAddress;
 use intmax2_zkp::{
     common::signature::key_set::KeySet,
     ethereum_types::{bytes32::Bytes32, u256::U256, u32limb_trait::U32LimbTrait},
@@ -30,3 +31,13 @@ pub fn parse_h256_as_u256(s: &str) -> Result<U256, JsError> {
     let x = parse_h256(s)?;
     Ok(h256_to_bytes32(x).into())
 }
+
+/// Parse a pubkey given as a 32 bytes hex string or an intmax address of any network.
+pub fn parse_pubkey(s: &str) -> Result<U256, JsError> {
+    if s.starts_with("0x") {
+        return parse_h256_as_u256(s);
+    }
+    let address = IntmaxAddress::decode(s)
+        .map_err(|e| JsError::new(&format!("failed to parse intmax address: {}", e)))?;
+    Ok(address.pubkey)
+}
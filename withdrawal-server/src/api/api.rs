@@ -1,18 +1,54 @@
 use crate::api::state::State;
 use actix_web::{
-    get, post,
+    get,
+    http::header::AUTHORIZATION,
+    post,
     web::{Data, Json},
-    Error, Scope,
+    Error, HttpRequest,
 };
-use intmax2_interfaces::api::withdrawal_server::{
-    interface::Fee,
-    types::{
-        GetFeeResponse, GetWithdrawalInfoByRecipientRequest, GetWithdrawalInfoRequest,
-        GetWithdrawalInfoResponse, RequestWithdrawalRequest,
+use intmax2_interfaces::{
+    api::{
+        error::ErrorResponse,
+        session::{CreateSessionRequest, CreateSessionResponse},
+        withdrawal_server::{
+            interface::Fee,
+            types::{
+                GetFeeResponse, GetWithdrawalInfoByRecipientRequest, GetWithdrawalInfoRequest,
+                GetWithdrawalInfoResponse, RequestWithdrawalRequest,
+            },
+        },
     },
+    utils::signature::WithAuth,
 };
 use serde_qs::actix::QsQuery;
+use server_common::{api_scope, error::ApiError};
 
+#[utoipa::path(
+    post,
+    path = "/create-session",
+    request_body = WithAuth<CreateSessionRequest>,
+    responses(
+        (status = 200, body = CreateSessionResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
+#[post("/create-session")]
+pub async fn create_session(
+    state: Data<State>,
+    request: Json<WithAuth<CreateSessionRequest>>,
+) -> Result<Json<CreateSessionResponse>, Error> {
+    let session = state.authenticator.create_session(&request)?;
+    Ok(Json(session))
+}
+
+#[utoipa::path(
+    get,
+    path = "/fee",
+    responses(
+        (status = 200, body = GetFeeResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/fee")]
 pub async fn get_fee() -> Result<Json<GetFeeResponse>, Error> {
     let fees = vec![Fee {
@@ -23,6 +59,15 @@ pub async fn get_fee() -> Result<Json<GetFeeResponse>, Error> {
     Ok(Json(GetFeeResponse { fees }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/request-withdrawal",
+    request_body = RequestWithdrawalRequest,
+    responses(
+        (status = 200),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[post("/request-withdrawal")]
 pub async fn request_withdrawal(
     state: Data<State>,
@@ -32,24 +77,51 @@ pub async fn request_withdrawal(
         .withdrawal_server
         .request_withdrawal(request.pubkey, &request.single_withdrawal_proof)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-withdrawal-info",
+    params(GetWithdrawalInfoRequest),
+    responses(
+        (status = 200, body = GetWithdrawalInfoResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-withdrawal-info")]
 pub async fn get_withdrawal_info(
     state: Data<State>,
+    req: HttpRequest,
     query: QsQuery<GetWithdrawalInfoRequest>,
 ) -> Result<Json<GetWithdrawalInfoResponse>, Error> {
+    // the session token is checked if given. Requests without it are still accepted for the
+    // clients which do not create sessions.
+    if req.headers().contains_key(AUTHORIZATION) {
+        let pubkey = state.authenticator.verify_session(&req)?;
+        if pubkey != query.pubkey {
+            return Err(ApiError::unauthorized("Session is not of the given pubkey").into());
+        }
+    }
     let withdrawal_info = state
         .withdrawal_server
         .get_withdrawal_info(query.pubkey, query.signature.clone())
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
 
     Ok(Json(GetWithdrawalInfoResponse { withdrawal_info }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/get-withdrawal-info-by-recipient",
+    params(GetWithdrawalInfoByRecipientRequest),
+    responses(
+        (status = 200, body = GetWithdrawalInfoResponse),
+        (status = "default", body = ErrorResponse)
+    )
+)]
 #[get("/get-withdrawal-info-by-recipient")]
 pub async fn get_withdrawal_info_by_recipient(
     state: Data<State>,
@@ -59,14 +131,44 @@ pub async fn get_withdrawal_info_by_recipient(
         .withdrawal_server
         .get_withdrawal_info_by_recipient(query.recipient)
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(ApiError::from)?;
     Ok(Json(GetWithdrawalInfoResponse { withdrawal_info }))
 }
 
-pub fn withdrawal_server_scope() -> Scope {
-    actix_web::web::scope("/withdrawal-server")
-        .service(request_withdrawal)
-        .service(get_fee)
-        .service(get_withdrawal_info)
-        .service(get_withdrawal_info_by_recipient)
+api_scope!(
+    withdrawal_server_scope,
+    withdrawal_server_openapi,
+    "/withdrawal-server",
+    [
+        create_session,
+        request_withdrawal,
+        get_fee,
+        get_withdrawal_info,
+        get_withdrawal_info_by_recipient,
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use server_common::openapi::assert_spec_matches_routes;
+
+    use super::{withdrawal_server_openapi, withdrawal_server_scope};
+
+    #[actix_web::test]
+    async fn test_openapi_spec_matches_routes() {
+        let app = test::init_service(App::new().service(withdrawal_server_scope())).await;
+        assert_spec_matches_routes(
+            &app,
+            &withdrawal_server_openapi(),
+            &[
+                ("GET", "/withdrawal-server/fee"),
+                ("POST", "/withdrawal-server/create-session"),
+                ("GET", "/withdrawal-server/get-withdrawal-info"),
+                ("GET", "/withdrawal-server/get-withdrawal-info-by-recipient"),
+                ("POST", "/withdrawal-server/request-withdrawal"),
+            ],
+        )
+        .await;
+    }
 }
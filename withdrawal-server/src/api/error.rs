@@ -1,3 +1,6 @@
+use intmax2_interfaces::api::error::ErrorCode;
+use server_common::error::ApiError;
+
 #[derive(Debug, thiserror::Error)]
 pub enum WithdrawalServerError {
     #[error("Database error {0}")]
@@ -9,3 +12,16 @@ pub enum WithdrawalServerError {
     #[error("Serialization error {0}")]
     SerializationError(String),
 }
+
+impl From<WithdrawalServerError> for ApiError {
+    fn from(e: WithdrawalServerError) -> Self {
+        let code = match &e {
+            WithdrawalServerError::DBError(_) => ErrorCode::Unavailable,
+            WithdrawalServerError::SingleWithdrawalVerificationError => {
+                ErrorCode::ProofVerificationFailed
+            }
+            WithdrawalServerError::SerializationError(_) => ErrorCode::Internal,
+        };
+        ApiError::new(code, e)
+    }
+}
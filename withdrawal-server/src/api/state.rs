@@ -1,5 +1,8 @@
 use std::sync::Arc;
 
+use intmax2_interfaces::api::session::SessionAudience;
+use server_common::auth::Authenticator;
+
 use crate::Env;
 
 use super::withdrawal_server::WithdrawalServer;
@@ -7,6 +10,7 @@ use super::withdrawal_server::WithdrawalServer;
 #[derive(Clone)]
 pub struct State {
     pub withdrawal_server: Arc<WithdrawalServer>,
+    pub authenticator: Arc<Authenticator>,
 }
 
 impl State {
@@ -17,8 +21,15 @@ impl State {
             env.database_timeout,
         )
         .await?;
+        let authenticator = Authenticator::new(
+            SessionAudience::WithdrawalServer,
+            env.session_secret.as_deref(),
+            env.session_ttl,
+            env.accept_legacy_auth.unwrap_or(false),
+        );
         Ok(State {
             withdrawal_server: Arc::new(withdrawal_server),
+            authenticator: Arc::new(authenticator),
         })
     }
 }
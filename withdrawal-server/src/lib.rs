@@ -8,4 +8,11 @@ pub struct Env {
     pub database_url: String,
     pub database_max_connections: u32,
     pub database_timeout: u64,
+
+    // Session tokens. The secret should be shared by all instances, and the ttl is in seconds.
+    pub session_secret: Option<String>,
+    pub session_ttl: Option<u64>,
+
+    /// Accepts the signed requests without a nonce of the older clients, until they are updated
+    pub accept_legacy_auth: Option<bool>,
 }
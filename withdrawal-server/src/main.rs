@@ -1,13 +1,22 @@
 use std::io;
 
 use actix_cors::Cors;
-use actix_web::{middleware::Logger, web::Data, App, HttpServer};
+use actix_web::{
+    middleware::Logger,
+    web::{self, Data},
+    App, HttpServer,
+};
 use server_common::{
+    error::{json_config, not_found, qs_query_config},
     health_check::{health_check, set_name_and_version},
     logger::init_logger,
+    openapi::openapi_service,
 };
 use withdrawal_server::{
-    api::{api::withdrawal_server_scope, state::State},
+    api::{
+        api::{withdrawal_server_openapi, withdrawal_server_scope},
+        state::State,
+    },
     Env,
 };
 
@@ -30,8 +39,12 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .wrap(Logger::new("Request: %r | Status: %s | Duration: %Ts"))
             .app_data(state.clone())
+            .app_data(json_config())
+            .app_data(qs_query_config())
             .service(health_check)
             .service(withdrawal_server_scope())
+            .service(openapi_service(withdrawal_server_openapi()))
+            .default_service(web::to(not_found))
     })
     .bind(format!("0.0.0.0:{}", env.port))?
     .run()